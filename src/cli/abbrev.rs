@@ -46,7 +46,7 @@ pub const PROJECT_COMMANDS: &[&str] = &[
 
 /// Sessions subcommands
 pub const SESSIONS_COMMANDS: &[&str] = &[
-    "list", "show", "modify", "delete", "report"
+    "list", "show", "modify", "delete", "report", "import"
 ];
 
 /// Queue subcommands
@@ -1,9 +1,23 @@
 use clap::{Parser, Subcommand};
 use rusqlite::Connection;
 use chrono::{Local, TimeZone};
-use crate::db::DbConnection;
-use crate::repo::{ProjectRepo, TaskRepo, StackRepo, SessionRepo, AnnotationRepo, TemplateRepo, ViewRepo, ExternalRepo};
+use crate::db::{DbConnection, ArchiveManager, ActiveLedger, LedgerSource, LedgerTarget, DEFAULT_LEDGER};
+use crate::repo::{ProjectRepo, TaskRepo, StackRepo, SessionRepo, AnnotationRepo, TemplateRepo, ViewRepo, ExternalRepo, DependencyRepo};
 use crate::cli::parser::{parse_task_args, join_description};
+use crate::cli::commands_backup::{handle_backup, handle_restore};
+use crate::cli::commands_archive::handle_archive;
+use crate::cli::commands_doctor::handle_doctor;
+use crate::cli::commands_sync::handle_sync;
+use crate::cli::commands_history::handle_history;
+use crate::cli::commands_undo::handle_undo;
+use crate::cli::commands_search::handle_search;
+use crate::cli::commands_events::{handle_events_verify, handle_events_snapshot, handle_events_log};
+use crate::cli::commands_trash::{handle_trash_list, handle_trash_restore, handle_trash_purge};
+use crate::cli::commands_config::{load_config, handle_config_get, handle_config_set, handle_config_unset, handle_config_list};
+use crate::cli::commands_ledger::{handle_ledger_list, handle_ledger_switch, handle_ledger_add, format_active_ledger};
+use crate::cli::explain::print_filter_explanation;
+use crate::cli::commands_context::{handle_context, active_context, active_context_filter, with_active_context, active_context_task_ids, format_active_context};
+use crate::cli::commands_transfer::{handle_export, handle_import, handle_sessions_import};
 use crate::cli::commands_sessions::{handle_task_sessions_list_with_filter, handle_task_sessions_show_with_filter, handle_sessions_modify, handle_sessions_delete, handle_sessions_report};
 use crate::cli::output::{format_task_list_table, format_task_summary, TaskListOptions};
 use crate::cli::error::{user_error, validate_task_id, validate_project_name, parse_task_id_spec, parse_task_id_list};
@@ -11,6 +25,7 @@ use crate::utils::{parse_date_expr, parse_duration, fuzzy};
 use crate::filter::{parse_filter, filter_tasks};
 use crate::respawn::respawn_task;
 use crate::cli::abbrev;
+use crate::config::Config;
 use std::collections::HashMap;
 use anyhow::{Context, Result};
 
@@ -19,10 +34,49 @@ use anyhow::{Context, Result};
 #[command(about = "Task and Time Ledger - A powerful command-line task and time tracking tool")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 pub struct Cli {
+    /// Ledger to use for this command (profile name or path to a .db file)
+    #[arg(long, global = true, value_name = "NAME|PATH")]
+    pub ledger: Option<String>,
+
+    /// Ignore the active context for this command
+    #[arg(long, global = true)]
+    pub no_context: bool,
+
+    /// Explain how the filter is understood instead of running the command
+    /// (list, modify, finish, sessions list, sessions report)
+    #[arg(long, global = true)]
+    pub explain: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// The global flags that change how a command selects tasks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalFlags {
+    /// `--no-context`: ignore the active context
+    pub no_context: bool,
+    /// `--explain`: explain the filter instead of running the command
+    pub explain: bool,
+}
+
+impl GlobalFlags {
+    pub fn from_cli(cli: &Cli) -> Self {
+        GlobalFlags { no_context: cli.no_context, explain: cli.explain }
+    }
+
+    /// Also honour `--explain` and `--no-context` written among a filter command's
+    /// trailing arguments, which clap reads as filter words. Removes them from `args`.
+    fn with_filter_args(mut self, args: &mut Vec<String>) -> Self {
+        args.retain(|arg| match arg.as_str() {
+            "--explain" => { self.explain = true; false }
+            "--no-context" => { self.no_context = true; false }
+            _ => true,
+        });
+        self
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Project management commands
@@ -41,6 +95,7 @@ The task description is all text that doesn't match field patterns. Field syntax
   allocation=<dur>   - Set time allocation (e.g., \"2h\", \"30m\", \"1d\")
   template=<name>    - Use template
   respawn=<pattern>  - Set respawn rule (see RESPAWN PATTERNS below)
+  depends=<ids>      - Depend on other tasks (e.g., \"depends=12,15\"); blocked until they are done
   +<tag>             - Add tag
   -<tag>             - Remove tag
   uda.<key>=<value>  - Set user-defined attribute
@@ -97,14 +152,37 @@ FILTER SYNTAX:
     due!=none            - Tasks that have a due date
     scheduled=<expr>     - Match by scheduled date
     wait=<expr>          - Match by wait date
-    kanban=<status>      - Match by kanban status (proposed, stalled, queued, external, done)
+    created=<expr>       - Match by creation time
+    modified=<expr>      - Match by last modification time
+    end=<expr>           - Match by completion or close time
+    lastactive=<expr>    - Match by the end of the most recent session
+    alloc=<duration>     - Match by time allocation (none: no allocation)
+    logged=<duration>    - Match by time logged in sessions
+    remaining=<duration> - Match by allocation minus logged time (remaining<0: over budget)
+    priority=<score>     - Match by priority score (pending tasks only), e.g. priority>8
+    queue=<position>     - Match by queue position, 0 at the top (any, none: queued or not)
+    kanban=<status>      - Match by kanban status (proposed, stalled, blocked, queued, external, done)
+    depends=<ids>        - Match tasks that depend on any of the tasks (any, none: has dependencies or not)
     desc=<pattern>       - Match description containing pattern (case-insensitive)
     description=<pattern> - Alias for desc=
+    desc~/<regex>/[i]    - Match description against a regular expression (i: ignore case)
+    note=<pattern>       - Match tasks with an annotation containing pattern
+    note~/<regex>/[i]    - Match tasks with an annotation matching a regular expression
+    uda.<key>=<value>    - Match a user-defined attribute (numbers and dates compare as such)
+    uda.<key>~<pattern>  - Match a user-defined attribute containing pattern, or a /regex/
 
   Tag filters:
     +<tag>               - Tasks with tag
     -<tag>               - Tasks without tag
 
+  Virtual tags (derived, read-only; +TAG or -TAG; modify can still remove a stored tag with -TAG):
+    OVERDUE, DUETODAY, DUEWEEK - Pending and due before now, today, or within 7 days
+    ACTIVE, QUEUED, EXTERNAL   - Open session, in the queue, sent to an external party
+    RESPAWNING, TEMPLATED      - Has a respawn rule, created from a template
+    ANNOTATED, UDA             - Has annotations, has user-defined attributes
+    OVERBUDGET                 - More time logged than allocated
+    BLOCKED, BLOCKING          - Waits on a pending task, a pending task waits on it
+
   Derived filters:
     waiting              - Tasks with wait_ts in the future
 
@@ -121,7 +199,10 @@ FILTER SYNTAX:
     desc=bug status=pending
     due=tomorrow kanban=queued
 
-DATE EXPRESSIONS (for due=, scheduled=, wait=):
+  Add --explain to print how the filter is understood instead of the tasks: the parsed
+  tree with match counts, resolved dates, expanded projects and terms that match nothing.
+
+DATE EXPRESSIONS (for due=, scheduled=, wait=, created=, modified=, end=, lastactive=):
   Relative: tomorrow, +3d, -1w, +2m, +1y
   Absolute: 2024-01-15, 2024-01-15 14:30
   Time-only: 09:00, 14:30
@@ -149,6 +230,26 @@ EXAMPLES:
         #[arg(long)]
         full: bool,
     },
+    /// Search task descriptions and annotations
+    #[command(long_about = "Full-text search over task descriptions and annotation notes, most relevant first. A task matches when every word appears in its description or its annotations; tasks with every word in a single text rank first. Words match regardless of case and word ending (bug matches bugs). Deleted tasks are left out.
+
+For patterns and regular expressions within a filter, use desc~ and note~ (see 'tatl list --help').
+
+EXAMPLES:
+  tatl search login bug
+  tatl search invoice --limit 5
+  tatl search sent to finance --json")]
+    Search {
+        /// Words to search for
+        #[arg(required = true)]
+        words: Vec<String>,
+        /// Maximum number of tasks to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
     /// Show detailed summary of task(s)
     #[command(long_about = "Show detailed information about one or more tasks.
 
@@ -168,6 +269,33 @@ EXAMPLES:
         /// Task ID, ID range (e.g., \"1-5\"), ID list (e.g., \"1,3,5\"), or filter expression. Examples: \"10\", \"1-5\", \"1,3,5\", \"project=work +urgent\"
         target: String,
     },
+    /// Show the change history of task(s)
+    #[command(long_about = "Show the event timeline of one or more tasks: creation, attribute changes with before and after values, status changes, tags, annotations, queue moves and sessions.
+
+TARGET SYNTAX:
+  Single ID:       10
+  ID range:        1-5
+  ID list:         1,3,5
+  Filter:          project=work +urgent (same filter syntax as 'tatl list')
+
+--since limits the timeline to events at or after a date expression (e.g. -7d, 2026-01-01). --json prints each task with its events, decoded field changes and raw payloads.
+
+EXAMPLES:
+  tatl history 10
+  tatl history 10 --since -7d
+  tatl history project=work +urgent
+  tatl history 1-5 --json")]
+    History {
+        /// Task ID, ID range, ID list, or filter expression
+        #[arg(required = true)]
+        target: Vec<String>,
+        /// Only show events at or after this date expression
+        #[arg(long, allow_hyphen_values = true)]
+        since: Option<String>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
     /// Modify tasks
     #[command(long_about = "Modify one or more tasks. Target can be a task ID, ID range (e.g., \"1-5\"), ID list (e.g., \"1,3,5\"), or filter expression.
 
@@ -180,6 +308,7 @@ MODIFICATION SYNTAX:
     allocation=<dur>      - Set time allocation (e.g., \"2h\", \"30m\", use \"allocation=none\" to clear)
     template=<name>       - Set template (use \"template=none\" to clear)
     respawn=<pattern>     - Set respawn rule (use \"respawn=none\" to clear, see RESPAWN PATTERNS)
+    depends=<ids>         - Replace the tasks this one depends on (use \"depends=none\" to clear)
     uda.<key>=<value>     - Set user-defined attribute (use \"uda.<key>=none\" to clear)
 
   Tag modifications:
@@ -392,8 +521,8 @@ TARGET SYNTAX:
         #[arg(long)]
         interactive: bool,
     },
-    /// Permanently delete task(s)
-    #[command(long_about = "Permanently delete one or more tasks. This action cannot be undone. All associated sessions, annotations, and events are also deleted.
+    /// Move task(s) to the trash
+    #[command(long_about = "Move one or more tasks to the trash. Trashed tasks keep their sessions, annotations and history but are hidden from lists, filters, reports and the queue. Use 'tatl trash restore' to bring a task back and 'tatl trash purge' to remove it permanently.
 
 TARGET SYNTAX:
   Task ID:           10
@@ -403,13 +532,24 @@ TARGET SYNTAX:
     Delete {
         /// Task ID, ID range, ID list, or filter. Examples: \"10\", \"1-5\", \"1,3,5\", \"project=work status=completed\"
         target: String,
-        /// Delete all matching tasks without confirmation
+        /// Trash all matching tasks without confirmation
         #[arg(short = 'y', long)]
         yes: bool,
         /// Confirm each task one by one
         #[arg(long)]
         interactive: bool,
     },
+    /// Trash bin commands
+    #[command(long_about = "List, restore or permanently remove deleted tasks. 'tatl delete' moves tasks to the trash; they stay there until purged.
+
+EXAMPLES:
+  tatl trash list
+  tatl trash restore 12
+  tatl trash purge --older-than 90d")]
+    Trash {
+        #[command(subcommand)]
+        subcommand: TrashCommands,
+    },
     /// Add task to end of clock stack
     #[command(long_about = "Add one or more tasks to the end of the queue. Tasks are added in the order specified. Does not start timing.")]
     Enqueue {
@@ -487,11 +627,401 @@ PERIOD:
 
 EXAMPLES:
   tatl report
-  tatl report --period=month")]
+  tatl report --period=month
+  tatl report --period=year --include-archive")]
     Report {
         /// Time period for statistics (week, month, year)
         #[arg(long, default_value = "week")]
         period: String,
+        /// Also count sessions and finished tasks moved to the archive with 'tatl archive'
+        #[arg(long)]
+        include_archive: bool,
+    },
+    /// Export the full ledger as JSON
+    #[command(long_about = "Export every table in the ledger (tasks, projects, tags, annotations, queue, sessions, task and audit events, templates, views, externals) as a single versioned JSON document.
+
+The document can be re-imported with 'tatl import' into an empty or existing ledger. Related rows reference tasks by uuid, so the export can be edited to restore only part of a ledger.
+
+FORMATS:
+  tatl (default)   Full ledger document
+  taskwarrior      Task objects readable by Taskwarrior ('task import')
+
+EXAMPLES:
+  tatl export > ledger.json
+  tatl export --output ~/backups/ledger.json
+  tatl export --to taskwarrior > tasks.json")]
+    Export {
+        /// Write the export to this file instead of stdout
+        #[arg(short = 'o', long)]
+        output: Option<String>,
+        /// Output format: tatl or taskwarrior
+        #[arg(long, default_value = "tatl")]
+        to: String,
+    },
+    /// Import a JSON ledger export
+    #[command(long_about = "Import a JSON document produced by 'tatl export'.
+
+Tasks are matched by uuid: tasks that already exist are left untouched and their related rows are skipped. Projects, stacks, templates and views are matched by name. The import runs in a single transaction.
+
+FORMATS:
+  tatl (default)   Full ledger document from 'tatl export'
+  taskwarrior      Output of 'task export'. Projects, tags, annotations, due/scheduled/wait
+                   and recur (as respawn) are mapped; other attributes become UDAs.
+
+EXAMPLES:
+  tatl import ledger.json
+  cat ledger.json | tatl import -
+  tatl import --from taskwarrior tasks.json")]
+    Import {
+        /// File to import (use - for stdin)
+        file: String,
+        /// Input format: tatl or taskwarrior
+        #[arg(long, default_value = "tatl")]
+        from: String,
+    },
+    /// Snapshot the ledger to ~/.tatl/backups
+    #[command(long_about = "Write a consistent copy of the ledger using SQLite's online backup API. Safe to run while the ledger is in use.
+
+Snapshots are named ledger-YYYYMMDD-HHMMSS.db and stored in ~/.tatl/backups. The oldest manual snapshots are removed so that at most --keep remain (default: backup.keep, 10). Snapshots are also taken automatically before schema migrations, restores, archiving, doctor --fix and sync; each of those kinds is rotated separately to backup.keep.
+
+EXAMPLES:
+  tatl backup
+  tatl backup --keep 30
+  tatl backup --list")]
+    Backup {
+        /// List existing snapshots instead of creating one
+        #[arg(long)]
+        list: bool,
+        /// Number of manual snapshots to keep after rotation (default: backup.keep)
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    /// Restore the ledger from a snapshot
+    #[command(long_about = "Replace the ledger with a snapshot. The snapshot's schema_version is checked first: snapshots from a newer tatl are rejected, and older snapshots are migrated the next time the ledger is opened. The current ledger is snapshotted before it is replaced.
+
+SNAPSHOT:
+  A file path, a snapshot name from 'tatl backup --list', or 'latest'.
+
+EXAMPLES:
+  tatl restore latest
+  tatl restore ledger-20260114-093000.db
+  tatl restore ~/Dropbox/ledger-copy.db --yes")]
+    Restore {
+        /// Snapshot path, name, or 'latest'
+        snapshot: String,
+        /// Restore without confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Move old finished tasks to the archive ledger
+    #[command(long_about = "Move completed, closed and deleted tasks last modified before a date into the archive ledger, together with their tags, sessions, annotations, externals and events. Archived tasks no longer slow down lists and filters.
+
+The archive is a ledger file next to the active one (ledger.db -> ledger.archive.db). 'tatl report' and 'tatl sessions report' can include it with --include-archive. Dependencies between archived tasks move with them; a dependency on a task that stays is dropped and recorded in the dependent task's history. The ledger is snapshotted before tasks are moved.
+
+EXAMPLES:
+  tatl archive --before 2025-01-01 --dry-run
+  tatl archive --before 2025-01-01
+  tatl archive --before -365d --yes")]
+    Archive {
+        /// Archive tasks last modified before this date
+        #[arg(long, allow_hyphen_values = true)]
+        before: String,
+        /// Show how many tasks would be archived without moving them
+        #[arg(long)]
+        dry_run: bool,
+        /// Archive without confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Check the ledger for inconsistencies
+    #[command(long_about = "Scan the ledger and report inconsistencies:
+
+  stack_item_not_pending     Queued task that is completed, closed or deleted (error)
+  session_inverted           Session that ends before it starts (error)
+  session_overlap            Closed sessions that overlap (warning)
+  external_on_deleted_task   Deleted task still out with an external party (warning)
+  project_empty              Active project with no tasks, including sub-projects (info)
+  respawn_invalid            Respawn rule that cannot be parsed (error)
+  udas_invalid               UDA data that is not a JSON object of strings (error)
+  tag_reserved               Stored tag named like a virtual tag, e.g. ACTIVE (warning)
+
+Each finding lists its fix. With --fix, the ledger is snapshotted to ~/.tatl/backups and the fixes for errors and warnings are applied in one transaction; add --include-info to also apply the fixes for info findings (archiving empty projects). Overlaps where one session lies entirely inside another have no automatic fix.
+
+Exits with status 1 when errors or warnings remain, so it can be run from cron. --json prints a machine-readable report.
+
+EXAMPLES:
+  tatl doctor
+  tatl doctor --fix
+  tatl doctor --fix --include-info
+  tatl doctor --json")]
+    Doctor {
+        /// Apply the suggested fixes for errors and warnings
+        #[arg(long)]
+        fix: bool,
+        /// With --fix, also apply the fixes for info findings
+        #[arg(long, requires = "fix")]
+        include_info: bool,
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check or replay the task event log
+    #[command(long_about = "Work with the task_events log, the immutable history behind 'tatl history'.
+
+  verify     Replay every event of each task and compare the result with the stored task and its tags
+  snapshot   Show what tasks looked like at a past moment
+  log        Audit trail of tasks, sessions, externals and projects
+
+EXAMPLES:
+  tatl events verify
+  tatl events verify project=work --json
+  tatl events snapshot --at 2026-01-01
+  tatl events snapshot --at -7d 12
+  tatl events log --entity session --since -7d")]
+    Events {
+        #[command(subcommand)]
+        subcommand: EventsCommands,
+    },
+    /// Undo the most recent change
+    #[command(long_about = "Reverse the most recent command that changed the ledger. Repeat to step further back.
+
+Undoable commands: modify (including tag changes), finish, close, reopen, delete, trash restore, enqueue, dequeue, on, off, offon, onoff, annotate, send, collect, sessions modify/delete and projects rename/archive/unarchive. The last 100 commands are kept.
+
+History is never removed: undo restores the changed rows and appends events describing the reversal, plus an 'undone' event on every affected task (see 'tatl history').
+
+Tasks the command created (such as the next instance of a respawning task) are moved to the trash with their history. Undo refuses, without changing anything, when a command that cannot be undone (sync, import, doctor --fix...) has since changed the same rows.
+
+EXAMPLES:
+  tatl undo
+  tatl undo --list
+  tatl undo -y")]
+    Undo {
+        /// List recent undoable commands instead of undoing
+        #[arg(long)]
+        list: bool,
+        /// Undo without confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Merge another ledger file in both directions
+    #[command(long_about = "Sync the active ledger with another ledger file, e.g. a copy carried between devices with Syncthing or a USB stick. No server is involved: both files are changed so that they end up with the same tasks.
+
+Tasks are matched by uuid. Tasks that exist on only one side are copied with their tags, annotations, sessions, externals and history. For tasks on both sides:
+  - each attribute (description, status, project, dates, alloc, template, respawn, UDAs) takes the value whose latest change in the event log is newer
+  - tags are merged; a tag removed on one side is removed on the other when the removal is newer
+  - annotations, sessions and externals are merged; a session that was stopped on one side is stopped on the other
+  - event histories are merged
+
+Queues stay per-ledger. Moving a task to the trash is synced like any status change. A task purged or archived here is not copied back from the other side (nor removed there); deleted sessions and annotations are not propagated and come back. Nothing is written to either ledger unless the whole sync succeeds.
+
+Both ledgers are snapshotted to ~/.tatl/backups before they are changed. --dry-run reports what would change without writing either file.
+
+OTHER:
+  A path to a ledger file, or the name of a ledger profile.
+
+EXAMPLES:
+  tatl sync /media/usb/ledger.db
+  tatl sync ~/Sync/tatl/ledger.db --dry-run
+  tatl sync laptop")]
+    Sync {
+        /// Ledger file path or profile name
+        other: String,
+        /// Report changes without writing either ledger
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Ledger profile commands
+    #[command(long_about = "Manage named ledgers (profiles), e.g. separate ledgers for work, personal and client time.
+
+Ledgers are defined in ~/.tatl/rc as ledger.<name>=<path>. The 'default' ledger is data.location (or ~/.tatl/ledger.db).
+
+The active ledger is chosen by, in order:
+  1. --ledger <name|path> on the command line
+  2. the TATL_LEDGER environment variable (name or path)
+  3. 'tatl ledger switch <name>' (stored as ledger.active in ~/.tatl/rc)
+  4. the default ledger
+
+EXAMPLES:
+  tatl ledger add work ~/work/tatl.db
+  tatl ledger switch work
+  tatl ledger list
+  tatl --ledger personal report
+  TATL_LEDGER=client-x tatl on 12")]
+    Ledger {
+        #[command(subcommand)]
+        subcommand: LedgerCommands,
+    },
+    /// Filter context commands
+    #[command(long_about = "Define named filters and make one the active context. While a context is active it is ANDed into the filter of list, modify, finish, sessions list, sessions report and report. Tasks given by ID are not restricted.
+
+The active context is stored in the ledger, so each ledger has its own. Use --no-context to ignore it for a single command.
+
+USAGE:
+  tatl context                         Show the active context
+  tatl context define <name> <filter>  Define or redefine a context
+  tatl context <name>                  Make a context active
+  tatl context none                    Clear the active context
+  tatl context list                    List contexts (* marks the active one)
+  tatl context delete <name>           Delete a context
+
+EXAMPLES:
+  tatl context define work project=work or +oncall
+  tatl context work
+  tatl list +urgent
+  tatl --no-context list +urgent
+  tatl context none")]
+    Context {
+        /// Subcommand or context name, followed by its arguments
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Configuration commands
+    #[command(long_about = "Read and change settings in ~/.tatl/rc. Every key has a documented default; values are validated and errors name the offending key.
+
+KEYS:
+  backup.keep              Snapshots of each kind kept in ~/.tatl/backups
+  session.micro_seconds    Micro-session threshold in seconds
+  urgency.*                Priority coefficients (see 'tatl config list')
+  color.status.<status>    Colors for the status column
+  color.kanban.<status>    Colors for the kanban column
+  list.columns, list.sort, list.hide, list.virtual_tags
+                           Defaults for 'tatl list'
+  date.format, datetime.format
+                           strftime formats for displayed dates
+
+Colors: none, black, red, green, yellow, blue, magenta, cyan, white, and bright_<color>.
+
+EXAMPLES:
+  tatl config list
+  tatl config get session.micro_seconds
+  tatl config set list.sort -priority
+  tatl config set color.kanban.queued cyan
+  tatl config unset list.sort")]
+    Config {
+        #[command(subcommand)]
+        subcommand: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the value of a key
+    Get {
+        /// Configuration key
+        key: String,
+    },
+    /// Set a key in ~/.tatl/rc
+    Set {
+        /// Configuration key
+        key: String,
+        /// New value (validated)
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Remove a key from ~/.tatl/rc, restoring its default
+    Unset {
+        /// Configuration key
+        key: String,
+    },
+    /// List all keys with their values and descriptions
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum LedgerCommands {
+    /// List ledgers and show which one is active
+    List,
+    /// Make a ledger the active one for future commands
+    #[command(long_about = "Make a named ledger the active one. Use 'default' to go back to data.location. TATL_LEDGER and --ledger still take precedence.")]
+    Switch {
+        /// Ledger name
+        name: String,
+    },
+    /// Register a named ledger
+    #[command(long_about = "Add a ledger.<name>=<path> entry to ~/.tatl/rc. The database is created the first time the ledger is used.")]
+    Add {
+        /// Ledger name (letters, digits, '-' and '_')
+        name: String,
+        /// Path to the database file
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EventsCommands {
+    /// Compare tasks with their replayed event history
+    #[command(long_about = "Replay the events of each task (all tasks, including trashed ones, when no target is given) and report every attribute or tag that differs from the stored task. Exits with status 1 when any task diverges.
+
+Tasks created by older versions of tatl may diverge on attributes set when they were added, because their created events only recorded description and project.")]
+    Verify {
+        /// Task ID, ID range, ID list, or filter
+        target: Vec<String>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Chronological log of task, session, external and project events
+    #[command(long_about = "Show the audit trail of the whole ledger: task events plus changes to sessions (added, modified, deleted, merged or purged micro-sessions), externals (sent, returned) and projects (renamed, merged, archived, unarchived). Use it to see timesheet edits made after the fact.
+
+EXAMPLES:
+  tatl events log --entity session --since -7d
+  tatl events log --entity session --id 42
+  tatl events log --entity project --json")]
+    Log {
+        /// Only events of this kind of record: task, session, external or project
+        #[arg(long)]
+        entity: Option<String>,
+        /// Only events of the record with this id (requires --entity)
+        #[arg(long)]
+        id: Option<i64>,
+        /// Only events at or after a date expression (e.g. -7d, 2026-01-01)
+        #[arg(long, allow_hyphen_values = true)]
+        since: Option<String>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show tasks as they were at a past moment
+    #[command(long_about = "Rebuild tasks from the event log as of a date expression. Without a target, lists every task that existed and was not in the trash at that moment; with a target, shows all attributes of the matching tasks. Filters match the current state of tasks.")]
+    Snapshot {
+        /// Moment to rebuild (date expression, e.g. 2026-01-01, -7d)
+        #[arg(long, allow_hyphen_values = true)]
+        at: String,
+        /// Task ID, ID range, ID list, or filter
+        target: Vec<String>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// List tasks in the trash
+    List {
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Restore tasks from the trash
+    #[command(long_about = "Take tasks out of the trash. Each task gets back the status it had when it was deleted. Tasks are not put back in the queue.")]
+    Restore {
+        /// Task ID, ID range or ID list. Examples: \"10\", \"1-5\", \"1,3,5\"
+        target: String,
+    },
+    /// Permanently remove old tasks from the trash
+    #[command(long_about = "Permanently delete tasks that have been in the trash for at least the given duration, together with their sessions, annotations and history. Without --older-than the whole trash is emptied. This cannot be undone.
+
+EXAMPLES:
+  tatl trash purge --older-than 90d
+  tatl trash purge -y                # empty the trash")]
+    Purge {
+        /// Only purge tasks deleted at least this long ago (e.g. 30d, 90d)
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+        /// Purge without confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
 }
 
@@ -537,7 +1067,7 @@ pub enum ProjectCommands {
         name: String,
     },
     /// Show task counts by kanban status per project
-    #[command(long_about = "Generate a report showing task counts grouped by project and kanban status (proposed, stalled, queued, external, done).")]
+    #[command(long_about = "Generate a report showing task counts grouped by project and kanban status (proposed, stalled, blocked, queued, external, done).")]
     Report,
 }
 
@@ -622,12 +1152,52 @@ REPORT SYNTAX:
     tatl sessions report
     tatl sessions report -7d
     tatl sessions report -7d..now project=work
-    tatl sessions report 2024-01-01..2024-01-31 +urgent")]
+    tatl sessions report 2024-01-01..2024-01-31 +urgent
+    tatl sessions report --include-archive 2024-01-01..2024-12-31")]
     Report {
+        /// Also count sessions of tasks moved to the archive with 'tatl archive'
+        #[arg(long)]
+        include_archive: bool,
         /// Report arguments. Date interval: -7d, -7d..now, <start>..<end>. Task filters: project=<name>, +tag, task=<id>. Examples: \"-7d\", \"-7d..now\", \"-7d project=work\"
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Import sessions from Timewarrior or CSV time logs
+    #[command(long_about = "Import time intervals from Timewarrior data files, 'timew export' JSON, or CSV exports from other trackers as closed sessions.
+
+Each interval is attached to a task matched by description and project (case-insensitive); tasks are created when no match exists. A dry-run summary is shown before anything is committed.
+
+TIMEWARRIOR:
+  The first tag of each interval is used as the task description and the remaining tags become task tags. Annotations become task annotations linked to the session. Open intervals are skipped.
+
+CSV:
+  A header row is required. Recognised columns: start, end or duration (1h30m, 1:30, 1.5), description (or task/activity), project, tags, note, task_id.
+
+OVERLAPS:
+  skip    Do not import intervals that overlap existing sessions (default)
+  clip    Import only the parts that do not overlap
+  force   Import as-is, creating overlapping sessions
+
+EXAMPLES:
+  tatl sessions import ~/.timewarrior/data/2026-01.data
+  tatl sessions import hours.csv --overlap clip
+  tatl sessions import hours.csv --dry-run")]
+    Import {
+        /// File to import (use - for stdin)
+        file: String,
+        /// Input format: timewarrior or csv (default: detected from the file)
+        #[arg(long)]
+        from: Option<String>,
+        /// Overlap strategy: skip, clip or force
+        #[arg(long, default_value = "skip")]
+        overlap: String,
+        /// Show the summary without importing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Import without confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
 }
 
 
@@ -661,7 +1231,7 @@ fn split_on_pipe(args: &[String]) -> Vec<Vec<String>> {
 }
 
 /// Execute a piped command with the task ID from the previous command.
-fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
+fn execute_piped_command(task_id: i64, segment: &[String], flags: GlobalFlags, config: &Config, db: &LedgerTarget) -> Result<i64> {
     if segment.is_empty() {
         anyhow::bail!("Empty pipe segment");
     }
@@ -674,10 +1244,10 @@ fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
             // Special case: a prior stage (e.g., `finish` with no explicit target) can return 0
             // to mean "operate on queue[0]". For `on`, that should start timing queue[0].
             if task_id == 0 {
-                handle_on(None, rest.to_vec())?;
+                handle_on(None, rest.to_vec(), config, db)?;
                 Ok(0)
             } else {
-                handle_task_on(task_id.to_string(), rest.to_vec())?;
+                handle_task_on(task_id.to_string(), rest.to_vec(), config, db)?;
                 Ok(task_id)
             }
         }
@@ -685,24 +1255,24 @@ fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
             // Run onoff for the specific task
             let mut onoff_args = rest.to_vec();
             onoff_args.push(task_id.to_string());
-            handle_onoff(onoff_args, false)?;
+            handle_onoff(onoff_args, false, config, db)?;
             Ok(task_id)
         }
         "enqueue" => {
-            handle_task_enqueue(task_id.to_string())?;
+            handle_task_enqueue(task_id.to_string(), db)?;
             Ok(task_id)
         }
         "finish" => {
-            handle_task_finish(Some(task_id.to_string()), None, false, false)?;
+            handle_task_finish(Some(task_id.to_string()), None, false, false, flags, config, db)?;
             Ok(task_id)
         }
         "close" => {
-            handle_task_close(task_id.to_string(), false, false)?;
+            handle_task_close(task_id.to_string(), false, false, config, db)?;
             Ok(task_id)
         }
         "annotate" => {
             let note_args = rest.to_vec();
-            handle_annotation_add(Some(task_id.to_string()), note_args)?;
+            handle_annotation_add(Some(task_id.to_string()), note_args, db)?;
             Ok(task_id)
         }
         "send" => {
@@ -711,16 +1281,16 @@ fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
             }
             let recipient = rest[0].clone();
             let request = rest[1..].to_vec();
-            handle_send(task_id.to_string(), recipient, request)?;
+            handle_send(task_id.to_string(), recipient, request, db)?;
             Ok(task_id)
         }
         "collect" => {
-            handle_collect(task_id.to_string())?;
+            handle_collect(task_id.to_string(), db)?;
             Ok(task_id)
         }
         "off" => {
             // Stop timing for the task from previous command
-            let conn = DbConnection::connect()
+            let conn = DbConnection::connect_to(db)
                 .context("Failed to connect to database")?;
             
             // Check if there's an open session for this task
@@ -740,7 +1310,7 @@ fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
                     // Ensure end_ts is after start_ts (handle micro-sessions)
                     let end_ts = std::cmp::max(end_ts, session.start_ts + 1);
                     
-                    if let Err(e) = SessionRepo::close_open(&conn, end_ts) {
+                    if let Err(e) = SessionRepo::close_open(&conn, end_ts, config) {
                         // If closing fails (e.g., session was already closed/purged), 
                         // just continue - idempotent behavior
                         eprintln!("Warning: Could not close session: {}", e);
@@ -758,7 +1328,7 @@ fn execute_piped_command(task_id: i64, segment: &[String]) -> Result<i64> {
         }
         "dequeue" => {
             // Remove task from queue (uses task_id from previous command)
-            handle_dequeue(Some(task_id.to_string()))?;
+            handle_dequeue(Some(task_id.to_string()), db)?;
             Ok(task_id)
         }
         _ => {
@@ -788,6 +1358,10 @@ pub fn run() -> Result<()> {
         }
     }
     
+    // Global flags written before the command are set aside so that abbreviations and
+    // implicit commands still see the command first; clap parses them with the rest
+    let global_args = split_leading_global_flags(&mut args);
+
     // Expand command abbreviations before processing
     args = match abbrev::expand_command_abbreviations(args) {
         Ok(expanded) => expanded,
@@ -849,6 +1423,7 @@ pub fn run() -> Result<()> {
         let first_segment = &pipe_segments[0];
 
         let clap_args = std::iter::once("tatl".to_string())
+            .chain(global_args.iter().cloned())
             .chain(first_segment.iter().cloned())
             .collect::<Vec<_>>();
         let cli = match Cli::try_parse_from(clap_args) {
@@ -858,22 +1433,38 @@ pub fn run() -> Result<()> {
                 return Ok(());
             }
         };
+        let (config, mut db) = apply_global_options(&cli);
+        let flags = GlobalFlags::from_cli(&cli);
+        if flags.explain {
+            user_error("--explain cannot be used with ':' command chains");
+        }
+        if is_undoable(&cli.command) {
+            db.begin_undo(&args.join(" "))?;
+        }
 
         // Execute first command and capture task ID for piping
         let task_id = match cli.command {
             Commands::Add { args: add_args, yes } => {
-                handle_task_add(add_args, yes)?
+                handle_task_add(add_args, yes, &db)?
             }
-            Commands::Modify { target, args: mod_args, yes, interactive } => {
-                handle_task_modify(target.clone(), mod_args, yes, interactive)?;
+            Commands::Modify { target, args: mut mod_args, yes, interactive } => {
+                let flags = flags.with_filter_args(&mut mod_args);
+                if flags.explain {
+                    user_error("--explain cannot be used with ':' command chains");
+                }
+                handle_task_modify(target.clone(), mod_args, yes, interactive, flags, &config, &db)?;
                 // Extract task ID from target (only works with single task ID)
                 validate_task_id(&target)
                     .map_err(|_| anyhow::anyhow!("Pipe operator with modify requires a single task ID as target"))?
             }
-            Commands::Finish { target, time_args, yes, interactive } => {
+            Commands::Finish { target, mut time_args, yes, interactive } => {
+                let flags = flags.with_filter_args(&mut time_args);
+                if flags.explain {
+                    user_error("--explain cannot be used with ':' command chains");
+                }
                 let end_time = if time_args.is_empty() { None } else { Some(time_args.join(" ")) };
                 let finish_target = target.clone();
-                handle_task_finish(target, end_time, yes, interactive)?;
+                handle_task_finish(target, end_time, yes, interactive, flags, &config, &db)?;
 
                 if let Some(t) = finish_target {
                     validate_task_id(&t).unwrap_or(0)
@@ -889,19 +1480,19 @@ pub fn run() -> Result<()> {
                 if task_ids.is_empty() {
                     anyhow::bail!("No task IDs provided to enqueue");
                 }
-                handle_task_enqueue(task_id_str)?;
+                handle_task_enqueue(task_id_str, &db)?;
                 task_ids[0] // Return first task ID for piping
             }
             Commands::Close { target, yes, interactive } => {
                 let target_str = target.clone()
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with close requires a task ID as target"))?;
-                handle_task_close_optional(target, yes, interactive)?;
+                handle_task_close_optional(target, yes, interactive, &config, &db)?;
                 // Extract task ID from target (only works with single task ID)
                 validate_task_id(&target_str)
                     .map_err(|_| anyhow::anyhow!("Pipe operator with close requires a single task ID as target"))?
             }
             Commands::Reopen { target, yes, interactive } => {
-                handle_task_reopen(target.clone(), yes, interactive)?;
+                handle_task_reopen(target.clone(), yes, interactive, &config, &db)?;
                 // Extract task ID from target (only works with single task ID)
                 validate_task_id(&target)
                     .map_err(|_| anyhow::anyhow!("Pipe operator with reopen requires a single task ID as target"))?
@@ -912,38 +1503,38 @@ pub fn run() -> Result<()> {
                 }
                 let target_str = target.or(task)
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with annotate requires a task ID as target"))?;
-                handle_annotation_add(Some(target_str.clone()), note)?;
+                handle_annotation_add(Some(target_str.clone()), note, &db)?;
                 // Extract task ID from target (only works with single task ID)
                 validate_task_id(&target_str)
                     .map_err(|_| anyhow::anyhow!("Pipe operator with annotate requires a single task ID as target"))?
             }
             Commands::Send { task_id: task_id_str, recipient, request } => {
-                handle_send(task_id_str.clone(), recipient, request)?;
+                handle_send(task_id_str.clone(), recipient, request, &db)?;
                 validate_task_id(&task_id_str)
                     .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?
             }
             Commands::Collect { task_id: task_id_str } => {
-                handle_collect(task_id_str.clone())?;
+                handle_collect(task_id_str.clone(), &db)?;
                 validate_task_id(&task_id_str)
                     .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?
             }
             Commands::On { task_id: task_id_opt, time_args } => {
-                let conn = DbConnection::connect()
+                let conn = DbConnection::connect_to(&db)
                     .context("Failed to connect to database")?;
                 // For piping, we need a task ID - can't use queue[0]
                 let task_id_str = task_id_opt
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with 'on' requires a task ID"))?;
-                handle_task_on(task_id_str.clone(), time_args)?;
+                handle_task_on(task_id_str.clone(), time_args, &config, &db)?;
                 validate_task_id(&task_id_str)
                     .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?
             }
             Commands::Dequeue { task_id: task_id_opt } => {
-                let conn = DbConnection::connect()
+                let conn = DbConnection::connect_to(&db)
                     .context("Failed to connect to database")?;
                 // For piping, we need a task ID - can't use queue[0]
                 let task_id_str = task_id_opt
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with 'dequeue' requires a task ID"))?;
-                handle_dequeue(Some(task_id_str.clone()))?;
+                handle_dequeue(Some(task_id_str.clone()), &db)?;
                 validate_task_id(&task_id_str)
                     .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?
             }
@@ -954,7 +1545,7 @@ pub fn run() -> Result<()> {
                 let mut task_id_opt: Option<i64> = None;
                 for arg in &args {
                     if let Ok(id) = arg.parse::<i64>() {
-                        let conn = DbConnection::connect()
+                        let conn = DbConnection::connect_to(&db)
                             .context("Failed to connect to database")?;
                         if TaskRepo::get_by_id(&conn, id)?.is_some() {
                             task_id_opt = Some(id);
@@ -964,7 +1555,7 @@ pub fn run() -> Result<()> {
                 }
                 let task_id = task_id_opt
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with 'onoff' requires a task ID in arguments (e.g., 'onoff 09:00..12:00 5')"))?;
-                handle_onoff(args, yes)?;
+                handle_onoff(args, yes, &config, &db)?;
                 task_id
             }
             Commands::Offon { time_args, yes } => {
@@ -973,12 +1564,12 @@ pub fn run() -> Result<()> {
                 let task_id_opt = time_args.last()
                     .and_then(|arg| arg.parse::<i64>().ok())
                     .and_then(|id| {
-                        let conn = DbConnection::connect().ok()?;
+                        let conn = DbConnection::connect_to(&db).ok()?;
                         TaskRepo::get_by_id(&conn, id).ok()?.map(|_| id)
                     });
                 let task_id = task_id_opt
                     .ok_or_else(|| anyhow::anyhow!("Pipe operator with 'offon' requires a task ID in arguments (e.g., 'offon 14:30 5')"))?;
-                handle_offon(time_args, yes)?;
+                handle_offon(time_args, yes, &config, &db)?;
                 task_id
             }
             _ => {
@@ -989,7 +1580,7 @@ pub fn run() -> Result<()> {
         // Execute pipe segments in sequence
         let mut current_task_id = task_id;
         for segment in &pipe_segments[1..] {
-            current_task_id = execute_piped_command(current_task_id, segment)?;
+            current_task_id = execute_piped_command(current_task_id, segment, flags, &config, &db)?;
         }
 
         return Ok(());
@@ -997,6 +1588,7 @@ pub fn run() -> Result<()> {
 
     // Normal (non-piped) command flow
     let clap_args = std::iter::once("tatl".to_string())
+        .chain(global_args.iter().cloned())
         .chain(args.iter().cloned())
         .collect::<Vec<_>>();
     let cli = match Cli::try_parse_from(clap_args) {
@@ -1006,31 +1598,105 @@ pub fn run() -> Result<()> {
             return Ok(());
         }
     };
+    let (config, mut db) = apply_global_options(&cli);
+    if cli.explain && !is_explainable(&cli.command) {
+        user_error("--explain works with commands that take a filter: list, modify, finish, sessions list and sessions report");
+    }
+    if is_undoable(&cli.command) {
+        db.begin_undo(&args.join(" "))?;
+    }
     
-    handle_command(cli)
+    handle_command(cli, &config, &db)
+}
+
+/// Remove the global flags written before the command (`tatl --ledger work list`) from
+/// `args` and return them, so they can be handed back to clap after preprocessing
+fn split_leading_global_flags(args: &mut Vec<String>) -> Vec<String> {
+    let mut n = 0;
+    while n < args.len() {
+        n += match args[n].as_str() {
+            "--no-context" | "--explain" => 1,
+            "--ledger" => 2,
+            arg if arg.starts_with("--ledger=") => 1,
+            _ => break,
+        };
+    }
+    let rest = args.split_off(n.min(args.len()));
+    std::mem::replace(args, rest)
+}
+
+/// Load the configuration and select the ledger. The config and ledger commands must
+/// keep working with a broken rc file or ledger selection so they can be repaired, so
+/// they get the defaults here and read the rc file and selection themselves.
+fn apply_global_options(cli: &Cli) -> (Config, LedgerTarget) {
+    if matches!(cli.command, Commands::Config { .. } | Commands::Ledger { .. }) {
+        let ledger = ActiveLedger {
+            name: DEFAULT_LEDGER.to_string(),
+            path: DbConnection::default_path(),
+            source: LedgerSource::Default,
+        };
+        let config = Config::default();
+        let target = LedgerTarget { ledger, undo_op: None, snapshot_keep: config.backup.keep };
+        return (config, target);
+    }
+    let config = load_config();
+    let target = LedgerTarget::resolve(cli.ledger.as_deref(), &config)
+        .unwrap_or_else(|e| user_error(&e.to_string()));
+    (config, target)
+}
+
+/// Commands whose changes are journaled so that `tatl undo` can reverse them
+fn is_undoable(command: &Commands) -> bool {
+    match command {
+        Commands::Modify { .. } | Commands::Finish { .. } | Commands::Close { .. } | Commands::Reopen { .. }
+        | Commands::Enqueue { .. } | Commands::Dequeue { .. } | Commands::On { .. } | Commands::Off { .. }
+        | Commands::Offon { .. } | Commands::Onoff { .. } | Commands::Annotate { .. }
+        | Commands::Send { .. } | Commands::Collect { .. } | Commands::Delete { .. } => true,
+        Commands::Trash { subcommand } => matches!(subcommand, TrashCommands::Restore { .. }),
+        Commands::Sessions { subcommand, .. } => matches!(subcommand,
+            SessionsCommands::Modify { .. } | SessionsCommands::Delete { .. }),
+        Commands::Projects { subcommand } => matches!(subcommand,
+            ProjectCommands::Rename { .. } | ProjectCommands::Archive { .. } | ProjectCommands::Unarchive { .. }),
+        _ => false,
+    }
 }
 
-fn handle_command(cli: Cli) -> Result<()> {
+/// Commands that explain their filter with `--explain`
+fn is_explainable(command: &Commands) -> bool {
+    match command {
+        Commands::List { .. } | Commands::Modify { .. } | Commands::Finish { .. } => true,
+        Commands::Sessions { subcommand, .. } => matches!(subcommand,
+            SessionsCommands::List { .. } | SessionsCommands::Report { .. }),
+        _ => false,
+    }
+}
+
+fn handle_command(cli: Cli, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let flags = GlobalFlags::from_cli(&cli);
+    let ledger_flag = cli.ledger.clone();
     match cli.command {
-        Commands::Projects { subcommand } => handle_projects(subcommand),
-        Commands::Add { args, yes } => { handle_task_add(args, yes)?; Ok(()) }
-        Commands::List { filter, json, relative, full } => {
-            handle_task_list(filter, json, relative, full)
+        Commands::Projects { subcommand } => handle_projects(subcommand, db),
+        Commands::Add { args, yes } => { handle_task_add(args, yes, db)?; Ok(()) }
+        Commands::List { mut filter, json, relative, full } => {
+            let flags = flags.with_filter_args(&mut filter);
+            handle_task_list(filter, json, relative, full, flags, config, db)
         },
-        Commands::Show { target } => handle_task_summary(target),
-        Commands::Modify { target, args, yes, interactive } => {
-            handle_task_modify(target, args, yes, interactive)
-        }
-        Commands::On { task_id, time_args } => handle_on(task_id, time_args),
-        Commands::Off { time_args } => handle_off(time_args),
-        Commands::Offon { time_args, yes } => handle_offon(time_args, yes),
-        Commands::Onoff { args, yes } => handle_onoff(args, yes),
-        Commands::Dequeue { task_id } => handle_dequeue(task_id),
+        Commands::Show { target } => handle_task_summary(target, config, db),
+        Commands::History { target, since, json } => handle_history(target, since, json, config, db),
+        Commands::Modify { target, mut args, yes, interactive } => {
+            let flags = flags.with_filter_args(&mut args);
+            handle_task_modify(target, args, yes, interactive, flags, config, db)
+        }
+        Commands::On { task_id, time_args } => handle_on(task_id, time_args, config, db),
+        Commands::Off { time_args } => handle_off(time_args, config, db),
+        Commands::Offon { time_args, yes } => handle_offon(time_args, yes, config, db),
+        Commands::Onoff { args, yes } => handle_onoff(args, yes, config, db),
+        Commands::Dequeue { task_id } => handle_dequeue(task_id, db),
         Commands::Annotate { target, note, task, yes, interactive, delete } => {
             if let Some(annotation_id) = delete {
                 let target = target.or(task)
                     .unwrap_or_else(|| user_error("Task ID is required to delete an annotation."));
-                handle_annotation_delete(target, annotation_id)
+                handle_annotation_delete(target, annotation_id, db)
             } else {
                 if target.is_none() && task.is_none() && note.is_empty() {
                     let help_args = vec!["tatl".to_string(), "annotate".to_string(), "--help".to_string()];
@@ -1041,88 +1707,125 @@ fn handle_command(cli: Cli) -> Result<()> {
                 if let Some(target_token) = target {
                     if task.is_some() {
                         note_args.insert(0, target_token);
-                        handle_annotation_add(task, note_args)
+                        handle_annotation_add(task, note_args, db)
                     } else if let Ok(task_id) = validate_task_id(&target_token) {
-                        let conn = DbConnection::connect()
+                        let conn = DbConnection::connect_to(db)
                             .context("Failed to connect to database")?;
                         if TaskRepo::get_by_id(&conn, task_id)?.is_some() {
-                            handle_annotation_add(Some(target_token), note_args)
+                            handle_annotation_add(Some(target_token), note_args, db)
                         } else {
                             let open_session = SessionRepo::get_open(&conn)?;
                             if open_session.is_some() {
                                 note_args.insert(0, target_token);
-                                handle_annotation_add(None, note_args)
+                                handle_annotation_add(None, note_args, db)
                             } else {
                                 user_error(&format!("Task {} not found", task_id));
                             }
                         }
                     } else if looks_like_filter(&target_token) {
-                        handle_annotation_add_with_filter(target_token, note_args, yes, interactive)
+                        handle_annotation_add_with_filter(target_token, note_args, yes, interactive, config, db)
                     } else {
                         note_args.insert(0, target_token);
-                        handle_annotation_add(None, note_args)
+                        handle_annotation_add(None, note_args, db)
                     }
                 } else {
-                    handle_annotation_add(task, note_args)
+                    handle_annotation_add(task, note_args, db)
                 }
             }
         }
-        Commands::Finish { target, time_args, yes, interactive } => {
+        Commands::Finish { target, mut time_args, yes, interactive } => {
+            let flags = flags.with_filter_args(&mut time_args);
             // Convert time_args to optional end time
             let end_time = if time_args.is_empty() { None } else { Some(time_args.join(" ")) };
-            handle_task_finish(target, end_time, yes, interactive)
+            handle_task_finish(target, end_time, yes, interactive, flags, config, db)
         }
         Commands::Close { target, yes, interactive } => {
-            handle_task_close_optional(target, yes, interactive)
+            handle_task_close_optional(target, yes, interactive, config, db)
         }
         Commands::Reopen { target, yes, interactive } => {
-            handle_task_reopen(target, yes, interactive)
+            handle_task_reopen(target, yes, interactive, config, db)
         }
+        Commands::Events { subcommand } => match subcommand {
+            EventsCommands::Verify { target, json } => handle_events_verify(target, json, config, db),
+            EventsCommands::Snapshot { at, target, json } => handle_events_snapshot(at, target, json, config, db),
+            EventsCommands::Log { entity, id, since, json } => handle_events_log(entity, id, since, json, config, db),
+        },
+        Commands::Trash { subcommand } => match subcommand {
+            TrashCommands::List { json } => handle_trash_list(json, config, db),
+            TrashCommands::Restore { target } => handle_trash_restore(target, db),
+            TrashCommands::Purge { older_than, yes } => handle_trash_purge(older_than, yes, db),
+        },
         Commands::Delete { target, yes, interactive } => {
-            handle_task_delete(target, yes, interactive)
+            handle_task_delete(target, yes, interactive, config, db)
         }
         Commands::Enqueue { task_id } => {
-            handle_task_enqueue(task_id)
+            handle_task_enqueue(task_id, db)
         }
         Commands::Send { task_id, recipient, request } => {
-            handle_send(task_id, recipient, request)
+            handle_send(task_id, recipient, request, db)
         },
         Commands::Collect { task_id } => {
-            handle_collect(task_id)
+            handle_collect(task_id, db)
         },
         Commands::Externals { filter } => {
-            handle_externals(filter)
+            handle_externals(filter, config, db)
         },
         Commands::Sessions { subcommand, task } => {
             match subcommand {
-                SessionsCommands::List { filter, json } => {
+                SessionsCommands::List { mut filter, json } => {
+                    let flags = flags.with_filter_args(&mut filter);
                     // If filter arguments provided, use them; otherwise fall back to --task flag for backward compatibility
                     if !filter.is_empty() {
-                        handle_task_sessions_list_with_filter(filter, json)
+                        handle_task_sessions_list_with_filter(filter, json, flags, config, db)
                     } else if let Some(task_str) = task {
                         // Backward compatibility: support --task flag
-                        handle_task_sessions_list_with_filter(vec![task_str], json)
+                        handle_task_sessions_list_with_filter(vec![task_str], json, flags, config, db)
                     } else {
-                        handle_task_sessions_list_with_filter(vec![], json)
+                        handle_task_sessions_list_with_filter(vec![], json, flags, config, db)
                     }
                 }
                 SessionsCommands::Show => {
-                    handle_task_sessions_show_with_filter(task)
+                    handle_task_sessions_show_with_filter(task, config, db)
                 }
                 SessionsCommands::Modify { session_id, args, yes, force } => {
-                    handle_sessions_modify(session_id, args, yes, force)
+                    handle_sessions_modify(session_id, args, yes, force, db)
                 }
                 SessionsCommands::Delete { session_id, yes } => {
-                    handle_sessions_delete(session_id, yes)
+                    handle_sessions_delete(session_id, yes, db)
                 }
-                SessionsCommands::Report { args } => {
-                    handle_sessions_report(args)
+                SessionsCommands::Report { include_archive, mut args } => {
+                    let flags = flags.with_filter_args(&mut args);
+                    handle_sessions_report(args, include_archive, flags, config, db)
+                }
+                SessionsCommands::Import { file, from, overlap, dry_run, yes } => {
+                    handle_sessions_import(file, from, overlap, dry_run, yes, config, db)
                 }
                 }
             }
-        Commands::Report { period } => {
-            handle_report(period)
-        }
+        Commands::Report { period, include_archive } => {
+            handle_report(period, include_archive, flags.no_context, config, db)
+        }
+        Commands::Export { output, to } => handle_export(output, to, db),
+        Commands::Import { file, from } => handle_import(file, from, db),
+        Commands::Backup { list, keep } => handle_backup(list, keep, config, db),
+        Commands::Restore { snapshot, yes } => handle_restore(snapshot, yes, config, db),
+        Commands::Archive { before, dry_run, yes } => handle_archive(before, dry_run, yes, config, db),
+        Commands::Doctor { fix, include_info, json } => handle_doctor(fix, include_info, json, config, db),
+        Commands::Sync { other, dry_run } => handle_sync(other, dry_run, config, db),
+        Commands::Undo { list, yes } => handle_undo(list, yes, config, db),
+        Commands::Search { words, limit, json } => handle_search(words, limit, json, db),
+        Commands::Ledger { subcommand } => match subcommand {
+            LedgerCommands::List => handle_ledger_list(ledger_flag.as_deref()),
+            LedgerCommands::Switch { name } => handle_ledger_switch(name),
+            LedgerCommands::Add { name, path } => handle_ledger_add(name, path),
+        },
+        Commands::Context { args } => handle_context(args, db),
+        Commands::Config { subcommand } => match subcommand {
+            ConfigCommands::Get { key } => handle_config_get(key),
+            ConfigCommands::Set { key, value } => handle_config_set(key, value),
+            ConfigCommands::Unset { key } => handle_config_unset(key),
+            ConfigCommands::List => handle_config_list(),
+        },
     }
 }
 
@@ -1193,8 +1896,8 @@ fn project_not_found_error(conn: &Connection, project_name: &str) -> ! {
     }
 }
 
-fn handle_projects(cmd: ProjectCommands) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_projects(cmd: ProjectCommands, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     match cmd {
@@ -1300,11 +2003,11 @@ fn handle_projects(cmd: ProjectCommands) -> Result<()> {
 }
 
 /// Handle the report command
-fn handle_report(period: String) -> Result<()> {
+fn handle_report(period: String, include_archive: bool, no_context: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
     use crate::models::TaskStatus;
     use chrono::{Datelike, Duration, NaiveTime};
 
-    let conn = DbConnection::connect()
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
 
     let now = Local::now();
@@ -1348,9 +2051,17 @@ fn handle_report(period: String) -> Result<()> {
         }
     };
 
-    // Get queue (tasks in stack)
+    // The active context limits every section to its tasks
+    let context = active_context(&conn, no_context)?;
+    let context_task_ids = active_context_task_ids(&conn, no_context, config)?;
+    let in_context = |task_id: i64| context_task_ids.as_ref().is_none_or(|ids| ids.contains(&task_id));
+
+    // Get queue (tasks in stack), keeping positions in the full queue
     let stack = StackRepo::get_or_create_default(&conn)?;
     let stack_items = StackRepo::get_items(&conn, stack.id.unwrap())?;
+    let queue: Vec<_> = stack_items.iter().enumerate()
+        .filter(|(_, item)| in_context(item.task_id))
+        .collect();
 
     // Get open session for detecting active task
     let open_session = SessionRepo::get_open(&conn)?;
@@ -1360,17 +2071,21 @@ fn handle_report(period: String) -> Result<()> {
     println!("═══════════════════════════════════════════════════════════════════════════");
     println!("                           TATL DASHBOARD");
     println!("═══════════════════════════════════════════════════════════════════════════");
+    println!("{}", format_active_ledger(&db.ledger));
+    if let Some(context) = &context {
+        println!("{}", format_active_context(context));
+    }
     println!();
 
     // SECTION 1: Queue
-    println!("📋 QUEUE ({} tasks)", stack_items.len());
+    println!("📋 QUEUE ({} tasks)", queue.len());
     println!("───────────────────────────────────────────────────────────────────────────");
 
-    if stack_items.is_empty() {
+    if queue.is_empty() {
         println!("  (no tasks in queue)");
         } else {
         println!(" #  ID   Description                              Project    Priority");
-        for (pos, item) in stack_items.iter().take(5).enumerate() {
+        for &(pos, item) in queue.iter().take(5) {
             if let Ok(Some(task)) = TaskRepo::get_by_id(&conn, item.task_id) {
                 let project = if let Some(pid) = task.project_id {
                     ProjectRepo::get_by_id(&conn, pid).ok().flatten()
@@ -1379,7 +2094,7 @@ fn handle_report(period: String) -> Result<()> {
                 } else {
                     String::new()
                 };
-                let priority = crate::cli::priority::calculate_priority(&task, &conn)
+                let priority = crate::utils::priority::calculate_priority(&task, &conn, config)
                     .unwrap_or(0.0);
                 let indicator = if active_task_id == Some(item.task_id) { "▶" } else { " " };
                 let desc: String = task.description.chars().take(40).collect();
@@ -1387,8 +2102,8 @@ fn handle_report(period: String) -> Result<()> {
                     indicator, pos, item.task_id, desc, project, priority);
             }
         }
-        if stack_items.len() > 5 {
-            println!("    ... and {} more", stack_items.len() - 5);
+        if queue.len() > 5 {
+            println!("    ... and {} more", queue.len() - 5);
         }
     }
     println!();
@@ -1397,6 +2112,7 @@ fn handle_report(period: String) -> Result<()> {
     let today_sessions = SessionRepo::list_all(&conn)?
         .into_iter()
         .filter(|s| s.start_ts >= today_start_ts || s.is_open())
+        .filter(|s| in_context(s.task_id))
         .collect::<Vec<_>>();
 
     // Helper to get session duration, using current time for open sessions
@@ -1456,29 +2172,60 @@ fn handle_report(period: String) -> Result<()> {
     let period_sessions = SessionRepo::list_all(&conn)?
         .into_iter()
         .filter(|s| s.start_ts >= period_start_ts)
+        .filter(|s| in_context(s.task_id))
         .collect::<Vec<_>>();
 
-    let period_total_secs: i64 = period_sessions.iter()
+    let mut period_total_secs: i64 = period_sessions.iter()
         .map(get_session_duration)
         .sum();
 
     // Count completed tasks in period
-    let all_tasks = TaskRepo::list_all(&conn)?;
-    let completed_in_period = all_tasks.iter()
+    let all_tasks: Vec<_> = TaskRepo::list_all(&conn)?
+        .into_iter()
+        .filter(|(t, _)| t.id.is_some_and(in_context))
+        .collect();
+    let mut completed_in_period = all_tasks.iter()
         .filter(|(t, _)| {
             t.status == TaskStatus::Completed || t.status == TaskStatus::Closed
         })
         .count();
 
     // Calculate time by project
-    let mut time_by_project: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
-    for session in &period_sessions {
-        let project = TaskRepo::get_by_id(&conn, session.task_id).ok().flatten()
+    let project_name = |conn: &Connection, task_id: i64| {
+        TaskRepo::get_by_id(conn, task_id).ok().flatten()
             .and_then(|t| t.project_id)
-            .and_then(|pid| ProjectRepo::get_by_id(&conn, pid).ok().flatten())
+            .and_then(|pid| ProjectRepo::get_by_id(conn, pid).ok().flatten())
             .map(|p| p.name)
-            .unwrap_or_else(|| "(no project)".to_string());
-        *time_by_project.entry(project).or_insert(0) += get_session_duration(session);
+            .unwrap_or_else(|| "(no project)".to_string())
+    };
+    let mut time_by_project: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for session in &period_sessions {
+        *time_by_project.entry(project_name(&conn, session.task_id)).or_insert(0) += get_session_duration(session);
+    }
+
+    // Archived tasks are finished, so they only add closed sessions and tasks finished in the period
+    if include_archive {
+        let archive = ArchiveManager::open(&db.archive_path())
+            .unwrap_or_else(|e| user_error(&format!("{:#}", e)));
+        let archive_context_ids: Option<std::collections::HashSet<i64>> = match active_context_filter(&conn, no_context)? {
+            Some(context) => Some(filter_tasks(&archive, &context, config)?.iter().filter_map(|(t, _)| t.id).collect()),
+            None => None,
+        };
+        let in_archive_context = |task_id: i64| archive_context_ids.as_ref().is_none_or(|ids| ids.contains(&task_id));
+        for session in SessionRepo::list_all(&archive)? {
+            if session.start_ts >= period_start_ts && in_archive_context(session.task_id) {
+                let secs = get_session_duration(&session);
+                period_total_secs += secs;
+                *time_by_project.entry(project_name(&archive, session.task_id)).or_insert(0) += secs;
+            }
+        }
+        completed_in_period += TaskRepo::list_all(&archive)?.iter()
+            .filter(|(t, _)| {
+                (t.status == TaskStatus::Completed || t.status == TaskStatus::Closed)
+                    && t.modified_ts >= period_start_ts
+                    && t.id.is_some_and(in_archive_context)
+            })
+            .count();
     }
 
     println!("📊 {}", period_label);
@@ -1617,11 +2364,12 @@ fn handle_projects_report(conn: &Connection) -> Result<()> {
     let stack_task_ids: std::collections::HashSet<i64> = stack_items.iter().map(|i| i.task_id).collect();
 
     // Build project hierarchy with counts
-    // New kanban stages: proposed, stalled, queued, external, done
+    // New kanban stages: proposed, stalled, blocked, queued, external, done
     #[derive(Default)]
     struct ProjectStats {
         proposed: i64,
         stalled: i64,
+        blocked: i64,
         queued: i64,
         external: i64,
         done: i64,
@@ -1629,7 +2377,7 @@ fn handle_projects_report(conn: &Connection) -> Result<()> {
 
     impl ProjectStats {
         fn total(&self) -> i64 {
-            self.proposed + self.stalled + self.queued + self.external + self.done
+            self.proposed + self.stalled + self.blocked + self.queued + self.external + self.done
         }
     }
 
@@ -1645,6 +2393,8 @@ fn handle_projects_report(conn: &Connection) -> Result<()> {
             "done"
         } else if ExternalRepo::has_active_externals(conn, task_id)? {
             "external"
+        } else if DependencyRepo::is_blocked(conn, task_id)? {
+            "blocked"
         } else if stack_task_ids.contains(&task_id) {
             // All queued tasks (including position 0 and timing) are "queued"
             "queued"
@@ -1678,6 +2428,7 @@ fn handle_projects_report(conn: &Connection) -> Result<()> {
         match kanban {
             "proposed" => stats.proposed += 1,
             "stalled" => stats.stalled += 1,
+            "blocked" => stats.blocked += 1,
             "queued" => stats.queued += 1,
             "external" => stats.external += 1,
             "done" => stats.done += 1,
@@ -1690,46 +2441,48 @@ fn handle_projects_report(conn: &Connection) -> Result<()> {
     for stats in project_stats.values() {
         total_stats.proposed += stats.proposed;
         total_stats.stalled += stats.stalled;
+        total_stats.blocked += stats.blocked;
         total_stats.queued += stats.queued;
         total_stats.external += stats.external;
         total_stats.done += stats.done;
     }
     total_stats.proposed += no_project_stats.proposed;
     total_stats.stalled += no_project_stats.stalled;
+    total_stats.blocked += no_project_stats.blocked;
     total_stats.queued += no_project_stats.queued;
     total_stats.external += no_project_stats.external;
     total_stats.done += no_project_stats.done;
 
     // Print report
     let pw = 25; // project width
-    println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
-        "Project", "Proposed", "Stalled", "Queued", "External", "Done", "Total", pw = pw);
-    println!("{} {} {} {} {} {} {}",
-        "─".repeat(pw), "─".repeat(8), "─".repeat(8), "─".repeat(8),
+    println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
+        "Project", "Proposed", "Stalled", "Blocked", "Queued", "External", "Done", "Total", pw = pw);
+    println!("{} {} {} {} {} {} {} {}",
+        "─".repeat(pw), "─".repeat(8), "─".repeat(8), "─".repeat(8), "─".repeat(8),
         "─".repeat(8), "─".repeat(6), "─".repeat(6));
 
     for (name, stats) in &project_stats {
-        println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
+        println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
             truncate_str(name, pw),
-            stats.proposed, stats.stalled, stats.queued,
+            stats.proposed, stats.stalled, stats.blocked, stats.queued,
             stats.external, stats.done, stats.total(),
             pw = pw);
     }
 
     if no_project_stats.total() > 0 {
-        println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
+        println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
             "(no project)",
-            no_project_stats.proposed, no_project_stats.stalled, no_project_stats.queued,
+            no_project_stats.proposed, no_project_stats.stalled, no_project_stats.blocked, no_project_stats.queued,
             no_project_stats.external, no_project_stats.done, no_project_stats.total(),
             pw = pw);
     }
 
-    println!("{} {} {} {} {} {} {}",
-        "─".repeat(pw), "─".repeat(8), "─".repeat(8), "─".repeat(8),
+    println!("{} {} {} {} {} {} {} {}",
+        "─".repeat(pw), "─".repeat(8), "─".repeat(8), "─".repeat(8), "─".repeat(8),
         "─".repeat(8), "─".repeat(6), "─".repeat(6));
-    println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
+    println!("{:<pw$} {:>8} {:>8} {:>8} {:>8} {:>8} {:>6} {:>6}",
         "TOTAL",
-        total_stats.proposed, total_stats.stalled, total_stats.queued,
+        total_stats.proposed, total_stats.stalled, total_stats.blocked, total_stats.queued,
         total_stats.external, total_stats.done, total_stats.total(),
         pw = pw);
 
@@ -1744,14 +2497,15 @@ fn truncate_str(s: &str, max_len: usize) -> String {
     }
 }
 
-fn handle_send(task_id_str: String, recipient: String, request: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()?;
+fn handle_send(task_id_str: String, recipient: String, request: Vec<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)?;
     let task_id = validate_task_id(&task_id_str)
         .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?;
     
     // Verify task exists
     let task = TaskRepo::get_by_id(&conn, task_id)?
         .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Check if task is already sent to this recipient
     let existing_externals = ExternalRepo::get_active_for_task(&conn, task_id)?;
@@ -1781,8 +2535,8 @@ fn handle_send(task_id_str: String, recipient: String, request: Vec<String>) ->
     Ok(())
 }
 
-fn handle_collect(task_id_str: String) -> Result<()> {
-    let conn = DbConnection::connect()?;
+fn handle_collect(task_id_str: String, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)?;
     let task_id = validate_task_id(&task_id_str)
         .map_err(|e| anyhow::anyhow!("Invalid task ID: {}", e))?;
     
@@ -1804,8 +2558,8 @@ fn handle_collect(task_id_str: String) -> Result<()> {
     Ok(())
 }
 
-fn handle_externals(filter: Option<String>) -> Result<()> {
-    let conn = DbConnection::connect()?;
+fn handle_externals(filter: Option<String>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)?;
     
     let externals = if let Some(filter_str) = filter {
         // Try parsing as task ID first
@@ -1843,7 +2597,7 @@ fn handle_externals(filter: Option<String>) -> Result<()> {
             };
             
             for (idx, external) in externals_list.iter().enumerate() {
-                let sent_date = crate::cli::output::format_date(external.sent_ts);
+                let sent_date = crate::cli::output::format_date(external.sent_ts, config);
                 if idx == 0 {
                     println!("{:<6} {:<40} {:<20} {:<30}", task_id, desc, external.recipient, sent_date);
                 } else {
@@ -1856,7 +2610,7 @@ fn handle_externals(filter: Option<String>) -> Result<()> {
     Ok(())
 }
 
-fn handle_task_add(args: Vec<String>, auto_yes: bool) -> Result<i64> {
+fn handle_task_add(args: Vec<String>, auto_yes: bool, db: &LedgerTarget) -> Result<i64> {
     if args.is_empty() {
         user_error("Task description is required");
     }
@@ -1876,7 +2630,7 @@ fn handle_task_add(args: Vec<String>, auto_yes: bool) -> Result<i64> {
     
     let description = join_description(&parsed.description);
     
-    let conn = DbConnection::connect()
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Resolve project (handle clearing with project=none or project=)
@@ -1955,6 +2709,15 @@ fn handle_task_add(args: Vec<String>, auto_yes: bool) -> Result<i64> {
         None
     };
     
+    // Tasks the new task depends on must exist before it is created
+    let depends_on = parsed.depends.as_deref().map(parse_depends_value).unwrap_or_default();
+    for depends_on_id in &depends_on {
+        match TaskRepo::get_by_id(&conn, *depends_on_id)? {
+            Some(task) if task.status != crate::models::TaskStatus::Deleted => {}
+            _ => user_error(&format!("Task {} not found", depends_on_id)),
+        }
+    }
+    
     // Load template if specified and merge attributes
     let (final_project_id, final_due_ts, final_scheduled_ts, final_wait_ts, final_alloc_secs, final_udas, final_tags) = 
         if let Some(template_name) = &parsed.template {
@@ -2012,7 +2775,12 @@ fn handle_task_add(args: Vec<String>, auto_yes: bool) -> Result<i64> {
     .context("Failed to create task")?;
     
     let task_id = task.id.unwrap();
+    if !depends_on.is_empty() {
+        DependencyRepo::set(&conn, task_id, &depends_on)
+            .context("Failed to record dependencies")?;
+    }
     println!("Created task {}: {}", task_id, description);
+    print_blockers(&conn, task_id)?;
     
     Ok(task_id)
 }
@@ -2025,10 +2793,12 @@ struct ListRequest {
     color_column: Option<String>,
     fill_column: Option<String>,
     save_alias: Option<String>,
+    option_tokens: Vec<String>, // Display option tokens as given, for --explain
 }
 
 fn parse_list_request(tokens: Vec<String>) -> ListRequest {
     let mut filter_tokens = Vec::new();
+    let mut option_tokens = Vec::new();
     let mut sort_columns = Vec::new();
     let mut group_columns = Vec::new();
     let mut hide_columns = Vec::new();
@@ -2057,7 +2827,9 @@ fn parse_list_request(tokens: Vec<String>) -> ListRequest {
             }
         } else {
             filter_tokens.push(token);
+            continue;
         }
+        option_tokens.push(token);
     }
     
     ListRequest {
@@ -2068,6 +2840,7 @@ fn parse_list_request(tokens: Vec<String>) -> ListRequest {
         color_column,
         fill_column,
         save_alias,
+        option_tokens,
     }
 }
 
@@ -2077,14 +2850,17 @@ fn is_view_name_token(token: &str) -> bool {
 
 fn looks_like_filter(token: &str) -> bool {
     token.contains('=') || token.contains('>') || token.contains('<')
-        || token.starts_with('+') || token.starts_with('-') || token == "waiting"
+        || token.starts_with('+') || token.starts_with('-') || token.starts_with("uda.") || token == "waiting"
 }
 
-fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full: bool, flags: GlobalFlags, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let mut request = parse_list_request(filter_args);
+    let mut set_aside: Vec<(String, String)> = request.option_tokens.iter()
+        .map(|token| (token.clone(), "display option".to_string()))
+        .collect();
     
     if request.sort_columns.is_empty()
         && request.group_columns.is_empty()
@@ -2092,6 +2868,7 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
         && is_view_name_token(&request.filter_tokens[0])
     {
         if let Some(view) = ViewRepo::get_by_name(&conn, "tasks", &request.filter_tokens[0])? {
+            set_aside.push((view.name.clone(), "saved view, replaced by its filter and display options".to_string()));
             request.filter_tokens = view.filter_tokens;
             request.sort_columns = view.sort_columns;
             request.group_columns = view.group_columns;
@@ -2101,6 +2878,10 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
         }
     }
     
+    if flags.explain {
+        return print_filter_explanation(&conn, &request.filter_tokens, &set_aside, flags.no_context, config);
+    }
+    
     if let Some(alias) = request.save_alias.clone() {
         ViewRepo::upsert(
             &conn,
@@ -2116,10 +2897,14 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
         println!("Saved view '{}'.", alias);
     }
     
-    // Parse filter if provided
+    // Parse filter if provided; the active context restricts filters but not task IDs
     let tasks = if request.filter_tokens.is_empty() {
-        TaskRepo::list_all(&conn)
-            .context("Failed to list tasks")?
+        match active_context_filter(&conn, flags.no_context)? {
+            Some(context) => filter_tasks(&conn, &context, config)
+                .context("Failed to filter tasks")?,
+            None => TaskRepo::list_all(&conn)
+                .context("Failed to list tasks")?,
+        }
     } else if request.filter_tokens.len() == 1 {
         // Single argument - try to parse as ID spec (range/list) first
         match parse_task_id_spec(&request.filter_tokens[0]) {
@@ -2137,7 +2922,8 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
                 // Not an ID spec - try as filter
                 let filter_expr = parse_filter(request.filter_tokens)
                     .map_err(|e| anyhow::anyhow!("Filter parse error: {}", e))?;
-                filter_tasks(&conn, &filter_expr)
+                let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+                filter_tasks(&conn, &filter_expr, config)
                     .context("Failed to filter tasks")?
             }
         }
@@ -2145,12 +2931,20 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
         // Multiple arguments - treat as filter
         let filter_expr = parse_filter(request.filter_tokens)
             .map_err(|e| anyhow::anyhow!("Filter parse error: {}", e))?;
-        filter_tasks(&conn, &filter_expr)
+        let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+        filter_tasks(&conn, &filter_expr, config)
             .context("Failed to filter tasks")?
     };
+    let context_note = match active_context(&conn, flags.no_context)? {
+        Some(context) if !json => Some(format_active_context(&context)),
+        _ => None,
+    };
     
     if tasks.is_empty() {
         println!("No tasks found.");
+        if let Some(note) = context_note {
+            println!("{}", note);
+        }
         return Ok(());
     }
     
@@ -2166,33 +2960,51 @@ fn handle_task_list(filter_args: Vec<String>, json: bool, relative: bool, full:
                 "scheduled_ts": task.scheduled_ts,
                 "wait_ts": task.wait_ts,
                 "tags": tags,
+                "depends": DependencyRepo::get_dependencies(&conn, task.id.unwrap_or(0)).unwrap_or_default(),
                 "udas": task.udas,
             })
         }).collect();
         println!("{}", serde_json::to_string_pretty(&json_tasks)?);
     } else {
         // Human-readable table output
+        let list_config = &config.list;
         let options = TaskListOptions {
             use_relative_time: relative,
-            sort_columns: request.sort_columns,
+            sort_columns: if request.sort_columns.is_empty() { list_config.sort.clone() } else { request.sort_columns },
             group_columns: request.group_columns,
-            hide_columns: request.hide_columns,
+            hide_columns: if request.hide_columns.is_empty() { list_config.hide.clone() } else { request.hide_columns },
             color_column: request.color_column,
             fill_column: request.fill_column,
             full_width: full,
+            columns: list_config.columns.clone(),
+            virtual_tags: list_config.virtual_tags,
         };
-        let table = format_task_list_table(&conn, &tasks, &options)?;
+        let table = format_task_list_table(&conn, &tasks, &options, config)?;
         print!("{}", table);
+        if let Some(note) = context_note {
+            println!("{}", note);
+        }
     }
     
     Ok(())
 }
 
 /// Handle task modify with optional --on flag
-fn handle_task_modify(id_or_filter: String, args: Vec<String>, yes: bool, interactive: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_modify(id_or_filter: String, args: Vec<String>, yes: bool, interactive: bool, flags: GlobalFlags, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
+    if flags.explain {
+        // Only the target selects tasks; everything after it changes them
+        let set_aside: Vec<(String, String)> = args.iter().map(|arg| {
+            let role = if looks_like_filter(arg) { "modification" } else { "part of the new description" };
+            (arg.clone(), role.to_string())
+        }).collect();
+        print_filter_explanation(&conn, &[id_or_filter], &set_aside, flags.no_context, config)?;
+        println!("No tasks were modified (--explain).");
+        return Ok(());
+    }
+    
     // Try to parse as task ID spec (single ID, range, or list) first
     let task_ids: Vec<i64> = match parse_task_id_spec(&id_or_filter) {
         Ok(ids) => {
@@ -2214,7 +3026,8 @@ fn handle_task_modify(id_or_filter: String, args: Vec<String>, yes: bool, intera
                         Ok(expr) => expr,
                         Err(e) => user_error(&format!("Filter parse error: {}", e)),
                     };
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     if matching_tasks.is_empty() {
@@ -2229,6 +3042,8 @@ fn handle_task_modify(id_or_filter: String, args: Vec<String>, yes: bool, intera
         }
     };
     
+    reject_trashed(&conn, &task_ids)?;
+
     // Handle multiple tasks with confirmation
     if task_ids.len() > 1 {
         if !yes && !interactive {
@@ -2428,6 +3243,13 @@ fn modify_single_task(conn: &Connection, task_id: i64, args: &[String], auto_cre
         }
     }
     
+    // Dependencies are checked for missing tasks and cycles before anything changes
+    if let Some(value) = &parsed.depends {
+        if let Err(e) = DependencyRepo::set(conn, task_id, &parse_depends_value(value)) {
+            user_error(&e.to_string());
+        }
+    }
+    
     // Apply modifications
     TaskRepo::modify(
         &conn,
@@ -2448,6 +3270,9 @@ fn modify_single_task(conn: &Connection, task_id: i64, args: &[String], auto_cre
     .with_context(|| format!("Failed to modify task {}", task_id))?;
     
     println!("Modified task {}", task_id);
+    if parsed.depends.is_some() {
+        print_blockers(conn, task_id)?;
+    }
     
     // If respawn was set (and not clearing), show description of what will happen
     if let Some(resp_str) = &parsed.respawn {
@@ -2462,9 +3287,47 @@ fn modify_single_task(conn: &Connection, task_id: i64, args: &[String], auto_cre
     Ok(())
 }
 
+/// Task IDs of a `depends=` value; `depends=none` (or `depends=`) clears the dependencies
+fn parse_depends_value(value: &str) -> Vec<i64> {
+    if value == "none" {
+        return Vec::new();
+    }
+    match parse_task_id_spec(value) {
+        Ok(ids) => ids,
+        Err(e) => user_error(&format!("Invalid depends value '{}': {}. Use task IDs, e.g. depends=12,15", value, e)),
+    }
+}
 
-fn handle_task_enqueue(task_id_str: String) -> Result<()> {
-    let conn = DbConnection::connect()
+/// Note the pending tasks a task is waiting on, if any
+fn print_blockers(conn: &Connection, task_id: i64) -> Result<()> {
+    let blockers = DependencyRepo::get_blockers(conn, task_id)?;
+    if !blockers.is_empty() {
+        println!("Task {} is blocked by task(s) {}", task_id,
+            blockers.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "));
+    }
+    Ok(())
+}
+
+/// After a task is finished or closed, list the dependents it no longer holds up
+fn print_unblocked_dependents(conn: &Connection, task_id: i64) -> Result<()> {
+    for dependent_id in DependencyRepo::get_unblocked_dependents(conn, task_id)? {
+        if let Some(task) = TaskRepo::get_by_id(conn, dependent_id)? {
+            println!("Unblocked task {}: {}", dependent_id, task.description);
+        }
+    }
+    Ok(())
+}
+
+/// Refuse to act on tasks in the trash
+fn reject_trashed(conn: &Connection, task_ids: &[i64]) -> Result<()> {
+    for &task_id in task_ids {
+        TaskRepo::ensure_not_trashed(conn, task_id)?;
+    }
+    Ok(())
+}
+
+fn handle_task_enqueue(task_id_str: String, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Parse comma-separated list of IDs (preserves order)
@@ -2494,43 +3357,58 @@ fn handle_task_enqueue(task_id_str: String) -> Result<()> {
         user_error("No valid tasks to enqueue");
     }
     
+    reject_trashed(&conn, &valid_ids)?;
+
     // Enqueue all tasks in order
     let stack = StackRepo::get_or_create_default(&conn)?;
     let stack_id = stack.id.unwrap();
+    let was_empty = StackRepo::get_items(&conn, stack_id)?.is_empty();
     
     for task_id in valid_ids {
         StackRepo::enqueue(&conn, stack_id, task_id)
             .context(format!("Failed to enqueue task {}", task_id))?;
         println!("Enqueued task {}", task_id);
     }
+    if was_empty {
+        warn_if_top_blocked(&conn, stack_id)?;
+    }
     
     Ok(())
 }
 
+/// Warn when the task at the top of the queue waits on pending tasks
+fn warn_if_top_blocked(conn: &Connection, stack_id: i64) -> Result<()> {
+    if let Some((top, blockers)) = StackRepo::top_blockers(conn, stack_id)? {
+        eprintln!("Warning: Task {} at the top of the queue is blocked by task(s) {}. Finish those first, or remove the dependency with 'tatl modify {} depends=none'.",
+            top, blockers.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "), top);
+    }
+    Ok(())
+}
+
 /// Handle `tatl on [<task_id>] [<time>]` - Start timing
-fn handle_on(task_id_opt: Option<String>, mut time_args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_on(task_id_opt: Option<String>, mut time_args: Vec<String>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     if let Some(task_id_str) = task_id_opt {
         // Check if it's a valid task ID (numeric) or if it's actually a time expression
         if let Ok(_task_id) = task_id_str.parse::<i64>() {
             // Valid task ID - use it
-            handle_task_on(task_id_str, time_args)
+            handle_task_on(task_id_str, time_args, config, db)
         } else {
             // Not a valid task ID - treat as time expression, use queue[0]
             time_args.insert(0, task_id_str);
-            handle_on_queue_top(&conn, time_args)
+            handle_on_queue_top(&conn, time_args, config)
         }
     } else {
         // Use queue[0]
-        handle_on_queue_top(&conn, time_args)
+        handle_on_queue_top(&conn, time_args, config)
     }
 }
 
 /// Handle `tatl off [<time>]` - Stop timing
-fn handle_off(time_args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_off(time_args: Vec<String>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Check if session is running
@@ -2557,7 +3435,7 @@ fn handle_off(time_args: Vec<String>) -> Result<()> {
     }
     
     // Close session
-    let closed = SessionRepo::close_open(&conn, end_ts)
+    let closed = SessionRepo::close_open(&conn, end_ts, config)
         .context("Failed to close session")?;
     
     if let Some(session) = closed {
@@ -2574,8 +3452,8 @@ fn handle_off(time_args: Vec<String>) -> Result<()> {
 /// 
 /// When a session is running: Stops it at <stop> and starts a new one (at <start> or now)
 /// When no session is running: Operates on history (finds and modifies overlapping sessions)
-fn handle_offon(time_args: Vec<String>, mut yes: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_offon(time_args: Vec<String>, mut yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Extract -y or --yes from args (CLAP can miss them with trailing_var_arg)
@@ -2596,15 +3474,15 @@ fn handle_offon(time_args: Vec<String>, mut yes: bool) -> Result<()> {
     
     if current_session.is_some() {
         // Current session mode: stop and resume
-        handle_offon_current_session(&conn, filtered_args)
+        handle_offon_current_session(&conn, filtered_args, config)
     } else {
         // History mode: find and modify overlapping sessions
-        handle_offon_history(&conn, filtered_args, yes)
+        handle_offon_history(&conn, filtered_args, yes, config)
     }
 }
 
 /// Handle offon when a session is currently running
-fn handle_offon_current_session(conn: &Connection, time_args: Vec<String>) -> Result<()> {
+fn handle_offon_current_session(conn: &Connection, time_args: Vec<String>, config: &Config) -> Result<()> {
     if time_args.is_empty() {
         user_error("Time expression required. Usage: tatl offon <stop> or tatl offon <stop>..<start>");
     }
@@ -2622,7 +3500,7 @@ fn handle_offon_current_session(conn: &Connection, time_args: Vec<String>) -> Re
     let tx = conn.unchecked_transaction()?;
     
     // Close current session at stop_ts
-    SessionRepo::close_open(&tx, stop_ts)
+    SessionRepo::close_open(&tx, stop_ts, config)
         .context("Failed to close session")?;
     
     let current_task_id = current_session.task_id;
@@ -2643,7 +3521,7 @@ fn handle_offon_current_session(conn: &Connection, time_args: Vec<String>) -> Re
     };
     
     // Start new session
-    SessionRepo::create(&tx, resume_task_id, resume_ts)
+    SessionRepo::create(&tx, resume_task_id, resume_ts, config)
         .context("Failed to start new session")?;
     
     tx.commit()?;
@@ -2664,7 +3542,7 @@ fn handle_offon_current_session(conn: &Connection, time_args: Vec<String>) -> Re
 }
 
 /// Handle offon in history mode (no current session)
-fn handle_offon_history(conn: &Connection, time_args: Vec<String>, yes: bool) -> Result<()> {
+fn handle_offon_history(conn: &Connection, time_args: Vec<String>, yes: bool, config: &Config) -> Result<()> {
     if time_args.is_empty() {
         user_error("Time expression required. Usage: tatl offon <time> or tatl offon <stop>..<start>");
     }
@@ -2723,7 +3601,7 @@ fn handle_offon_history(conn: &Connection, time_args: Vec<String>, yes: bool) ->
     let tx = conn.unchecked_transaction()?;
     
     for session in overlapping {
-        modify_session_for_removal(&tx, &session, remove_start, remove_end)?;
+        modify_session_for_removal(&tx, &session, remove_start, remove_end, config)?;
     }
     
     tx.commit()?;
@@ -2734,8 +3612,8 @@ fn handle_offon_history(conn: &Connection, time_args: Vec<String>, yes: bool) ->
 }
 
 /// Handle `tatl onoff <start>..<end> [<task_id>] [note:<text>]` - Add historical session
-fn handle_onoff(args: Vec<String>, mut yes: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_onoff(args: Vec<String>, mut yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     if args.is_empty() {
@@ -2776,6 +3654,7 @@ fn handle_onoff(args: Vec<String>, mut yes: bool) -> Result<()> {
     // Validate task exists
     let task = TaskRepo::get_by_id(&conn, task_id)?
         .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Validate start < end
     if start_ts >= end_ts {
@@ -2825,7 +3704,7 @@ fn handle_onoff(args: Vec<String>, mut yes: bool) -> Result<()> {
         let tx = conn.unchecked_transaction()?;
         
         for session in overlapping {
-            modify_session_for_removal(&tx, &session, start_ts, end_ts)?;
+            modify_session_for_removal(&tx, &session, start_ts, end_ts, config)?;
         }
         
         // Create the new session
@@ -2925,7 +3804,7 @@ fn parse_onoff_args(args: &[String]) -> Result<(i64, i64, Option<i64>, Option<St
 
 /// Find all sessions overlapping with the given interval
 fn find_overlapping_sessions(conn: &Connection, start: i64, end: i64) -> Result<Vec<crate::models::Session>> {
-    let all_sessions = SessionRepo::list_all(conn)?;
+    let all_sessions = SessionRepo::list_including_trashed(conn)?;
     
     let overlapping: Vec<_> = all_sessions.into_iter()
         .filter(|s| {
@@ -2941,7 +3820,7 @@ fn find_overlapping_sessions(conn: &Connection, start: i64, end: i64) -> Result<
 }
 
 /// Modify a session to remove the specified interval
-fn modify_session_for_removal(conn: &Connection, session: &crate::models::Session, remove_start: i64, remove_end: i64) -> Result<()> {
+fn modify_session_for_removal(conn: &Connection, session: &crate::models::Session, remove_start: i64, remove_end: i64, config: &Config) -> Result<()> {
     let s_start = session.start_ts;
     let is_open = session.end_ts.is_none();
     let s_end = session.end_ts.unwrap_or(i64::MAX);
@@ -2958,7 +3837,7 @@ fn modify_session_for_removal(conn: &Connection, session: &crate::models::Sessio
         // Second part: remove_end to s_end
         if is_open {
             // Original was open - second part should remain open
-            SessionRepo::create(conn, session.task_id, remove_end)?;
+            SessionRepo::create(conn, session.task_id, remove_end, config)?;
             } else {
             SessionRepo::create_closed(conn, session.task_id, remove_end, s_end)?;
         }
@@ -2977,7 +3856,7 @@ fn modify_session_for_removal(conn: &Connection, session: &crate::models::Sessio
         SessionRepo::update_times(conn, session_id, s_start, Some(remove_start))?;
         if is_open {
             // Original was open - second part should remain open
-            SessionRepo::create(conn, session.task_id, remove_start)?;
+            SessionRepo::create(conn, session.task_id, remove_start, config)?;
                 } else {
             SessionRepo::create_closed(conn, session.task_id, remove_start, s_end)?;
                 }
@@ -3065,8 +3944,8 @@ trait Pipe: Sized {
 impl<T> Pipe for T {}
 
 /// Handle `tatl dequeue [<task_id>]` - Remove from queue without finishing
-fn handle_dequeue(task_id_opt: Option<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_dequeue(task_id_opt: Option<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let stack = StackRepo::get_or_create_default(&conn)?;
@@ -3106,7 +3985,7 @@ fn handle_dequeue(task_id_opt: Option<String>) -> Result<()> {
 }
 
 /// Start timing queue[0]
-fn handle_on_queue_top(conn: &Connection, args: Vec<String>) -> Result<()> {
+fn handle_on_queue_top(conn: &Connection, args: Vec<String>, config: &Config) -> Result<()> {
     // Get stack and check if it's empty
     let stack = StackRepo::get_or_create_default(conn)?;
     let stack_id = stack.id.unwrap();
@@ -3163,7 +4042,7 @@ fn handle_on_queue_top(conn: &Connection, args: Vec<String>) -> Result<()> {
         check_and_amend_overlaps(conn, start_ts)?;
         
         // Create open session
-        SessionRepo::create(conn, task_id, start_ts)
+        SessionRepo::create(conn, task_id, start_ts, config)
             .context("Failed to start session")?;
         
         // Get task description for better message
@@ -3176,8 +4055,8 @@ fn handle_on_queue_top(conn: &Connection, args: Vec<String>) -> Result<()> {
 }
 
 /// Start timing a specific task (pushes to queue[0] and starts timing)
-fn handle_task_on(task_id_str: String, args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_on(task_id_str: String, args: Vec<String>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Parse task ID
@@ -3190,6 +4069,7 @@ fn handle_task_on(task_id_str: String, args: Vec<String>) -> Result<()> {
     if TaskRepo::get_by_id(&conn, task_id)?.is_none() {
         user_error(&format!("Task {} not found", task_id));
     }
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Parse arguments - check for interval syntax (start..end)
     let arg_str = args.join(" ");
@@ -3248,7 +4128,7 @@ fn handle_task_on(task_id_str: String, args: Vec<String>) -> Result<()> {
     
     // If session is running, close it at the effective start time
     if existing_session.is_some() {
-        SessionRepo::close_open(&tx, effective_start_ts)
+        SessionRepo::close_open(&tx, effective_start_ts, config)
             .context("Failed to close existing session")?;
     }
     
@@ -3261,6 +4141,7 @@ fn handle_task_on(task_id_str: String, args: Vec<String>) -> Result<()> {
     let stack = StackRepo::get_or_create_default(&tx)?;
     StackRepo::push_to_top(&tx, stack.id.unwrap(), task_id)
         .context("Failed to push task to stack")?;
+    warn_if_top_blocked(&tx, stack.id.unwrap())?;
     
     // Create session (closed if interval, open otherwise)
     if let Some(end_ts) = end_ts_opt {
@@ -3269,7 +4150,7 @@ fn handle_task_on(task_id_str: String, args: Vec<String>) -> Result<()> {
         tx.commit()?;
         println!("Recorded session for task {} ({} to {})", task_id, start_ts, end_ts);
     } else {
-        SessionRepo::create(&tx, task_id, effective_start_ts)
+        SessionRepo::create(&tx, task_id, effective_start_ts, config)
             .context("Failed to start session")?;
         tx.commit()?;
         // Get task description for better message
@@ -3306,8 +4187,8 @@ fn check_and_amend_overlaps_transactional(conn: &Connection, new_start_ts: i64)
     Ok(())
 }
 
-fn handle_annotation_add(task_id_opt: Option<String>, note_args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_annotation_add(task_id_opt: Option<String>, note_args: Vec<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     if note_args.is_empty() {
@@ -3337,6 +4218,7 @@ fn handle_annotation_add(task_id_opt: Option<String>, note_args: Vec<String>) ->
     if TaskRepo::get_by_id(&conn, task_id)?.is_none() {
         user_error(&format!("Task {} not found", task_id));
     }
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Get current session if running (for session linking)
     let open_session = SessionRepo::get_open(&conn)?;
@@ -3360,8 +4242,8 @@ fn handle_annotation_add(task_id_opt: Option<String>, note_args: Vec<String>) ->
 }
 
 /// Handle annotation with filter support (multi-task annotation)
-fn handle_annotation_add_with_filter(id_or_filter: String, note_args: Vec<String>, yes: bool, interactive: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_annotation_add_with_filter(id_or_filter: String, note_args: Vec<String>, yes: bool, interactive: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     if note_args.is_empty() {
@@ -3385,7 +4267,7 @@ fn handle_annotation_add_with_filter(id_or_filter: String, note_args: Vec<String
                 Ok(expr) => expr,
                 Err(e) => user_error(&format!("Filter parse error: {}", e)),
             };
-            let matching_tasks = filter_tasks(&conn, &filter_expr)
+            let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                 .context("Failed to filter tasks")?;
             
             if matching_tasks.is_empty() {
@@ -3398,6 +4280,8 @@ fn handle_annotation_add_with_filter(id_or_filter: String, note_args: Vec<String
         }
     };
     
+    reject_trashed(&conn, &task_ids)?;
+
     // Get current session if running (for session linking)
     let open_session = SessionRepo::get_open(&conn)?;
     let _session_id = open_session.as_ref().and_then(|s| s.id);
@@ -3483,8 +4367,8 @@ fn handle_annotation_add_with_filter(id_or_filter: String, note_args: Vec<String
     Ok(())
 }
 
-fn handle_task_summary(id_or_filter: String) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_summary(id_or_filter: String, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Parse task ID spec (single ID, range, or list)
@@ -3503,7 +4387,7 @@ fn handle_task_summary(id_or_filter: String) -> Result<()> {
                         Ok(expr) => expr,
                         Err(e) => user_error(&format!("Filter parse error: {}", e)),
                     };
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     if matching_tasks.is_empty() {
@@ -3557,7 +4441,7 @@ fn handle_task_summary(id_or_filter: String) -> Result<()> {
             .map(|&pos| (pos, stack_total));
         
         // Format and print summary
-        let summary = format_task_summary(&conn, &task, &tags, &annotations, &sessions, stack_position)?;
+        let summary = format_task_summary(&conn, &task, &tags, &annotations, &sessions, stack_position, config)?;
         print!("{}", summary);
         
         // Add separator between multiple tasks
@@ -3574,8 +4458,8 @@ fn handle_task_summary(id_or_filter: String) -> Result<()> {
     Ok(())
 }
 
-fn handle_annotation_delete(task_id_str: String, annotation_id_str: String) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_annotation_delete(task_id_str: String, annotation_id_str: String, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let task_id = match validate_task_id(&task_id_str) {
@@ -3592,6 +4476,7 @@ fn handle_annotation_delete(task_id_str: String, annotation_id_str: String) -> R
     if TaskRepo::get_by_id(&conn, task_id)?.is_none() {
         user_error(&format!("Task {} not found", task_id));
     }
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Delete annotation (verifies it belongs to the task)
     AnnotationRepo::delete_for_task(&conn, task_id, annotation_id)
@@ -3606,8 +4491,11 @@ fn handle_task_finish(
     mut at_opt: Option<String>,
     yes: bool,
     interactive: bool,
+    flags: GlobalFlags,
+    config: &Config,
+    db: &LedgerTarget,
 ) -> Result<()> {
-    let conn = DbConnection::connect()
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
 
     // Disambiguation: allow `tatl finish <time>` (with no explicit target).
@@ -3632,6 +4520,22 @@ fn handle_task_finish(
         }
     }
     
+    if flags.explain {
+        let set_aside: Vec<(String, String)> = at_opt.iter().map(|at| {
+            let role = match parse_date_expr(at) {
+                Ok(ts) => format!("completion time, {}", crate::filter::format_instant(ts)),
+                Err(_) => "completion time, but not a date expression".to_string(),
+            };
+            (at.clone(), role)
+        }).collect();
+        match &id_or_filter_opt {
+            Some(target) => print_filter_explanation(&conn, std::slice::from_ref(target), &set_aside, flags.no_context, config)?,
+            None => println!("No filter: finish acts on the task at the top of the queue."),
+        }
+        println!("No tasks were finished (--explain).");
+        return Ok(());
+    }
+    
     // Determine end time for session
     let end_ts = if let Some(at_expr) = at_opt {
         parse_date_expr(&at_expr).context("Invalid end time expression")?
@@ -3676,7 +4580,8 @@ fn handle_task_finish(
                     // Filter expression
                     let filter_expr = parse_filter(vec![id_or_filter])
                         .map_err(|e| anyhow::anyhow!("Filter parse error: {}", e))?;
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     // Extract task IDs from matching tasks
@@ -3718,6 +4623,7 @@ fn handle_task_finish(
         
         vec![stack_task_id]
     };
+    reject_trashed(&conn, &task_ids)?;
     
     // Handle multiple tasks with confirmation
     if task_ids.len() > 1 {
@@ -3742,7 +4648,7 @@ fn handle_task_finish(
                 }
                 "i" | "interactive" => {
                     // Interactive mode - confirm one by one
-                    return handle_finish_interactive(&conn, &task_ids, end_ts);
+                    return handle_finish_interactive(&conn, &task_ids, end_ts, config);
                 }
                 _ => {
                     println!("Invalid input. Cancelled.");
@@ -3751,7 +4657,7 @@ fn handle_task_finish(
             }
         } else if interactive {
             // Force interactive mode
-            return handle_finish_interactive(&conn, &task_ids, end_ts);
+            return handle_finish_interactive(&conn, &task_ids, end_ts, config);
         }
     }
     
@@ -3769,7 +4675,7 @@ fn handle_task_finish(
             if session.task_id == *task_id {
                 // Close the session
                 effective_end_ts = std::cmp::max(end_ts, session.start_ts + 1);
-                SessionRepo::close_open(&conn, effective_end_ts)
+                SessionRepo::close_open(&conn, effective_end_ts, config)
                     .context("Failed to close session")?;
             }
         }
@@ -3805,15 +4711,19 @@ fn handle_task_finish(
         if let Some(item) = items.iter().find(|item| item.task_id == *task_id) {
             // Drop the task at this position using its ordinal
             StackRepo::drop(&conn, stack_id, item.ordinal as i32)?;
+            if item.ordinal == 0 {
+                warn_if_top_blocked(&conn, stack_id)?;
+            }
         }
         
         println!("Finished task {}", task_id);
+        print_unblocked_dependents(&conn, *task_id)?;
     }
     
     Ok(())
 }
 
-fn handle_finish_interactive(conn: &Connection, task_ids: &[i64], end_ts: i64) -> Result<()> {
+fn handle_finish_interactive(conn: &Connection, task_ids: &[i64], end_ts: i64, config: &Config) -> Result<()> {
     use std::io::{self, Write};
     
     let open_session = SessionRepo::get_open(conn)?;
@@ -3845,7 +4755,7 @@ fn handle_finish_interactive(conn: &Connection, task_ids: &[i64], end_ts: i64) -
         if let Some(session) = &open_session {
             if session.task_id == *task_id {
                 effective_end_ts = std::cmp::max(end_ts, session.start_ts + 1);
-                SessionRepo::close_open(conn, effective_end_ts)
+                SessionRepo::close_open(conn, effective_end_ts, config)
                     .context("Failed to close session")?;
             }
         }
@@ -3876,21 +4786,25 @@ fn handle_finish_interactive(conn: &Connection, task_ids: &[i64], end_ts: i64) -
         if let Some(item) = items.iter().find(|item| item.task_id == *task_id) {
             // Drop the task at this position using its ordinal
             StackRepo::drop(conn, stack_id, item.ordinal as i32)?;
+            if item.ordinal == 0 {
+                warn_if_top_blocked(conn, stack_id)?;
+            }
         }
         
         println!("Finished task {}", task_id);
+        print_unblocked_dependents(conn, *task_id)?;
     }
     
     Ok(())
 }
 
 /// Handle task close with optional target (defaults to queue[0])
-fn handle_task_close_optional(target: Option<String>, yes: bool, interactive: bool) -> Result<()> {
+fn handle_task_close_optional(target: Option<String>, yes: bool, interactive: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
     let id_or_filter = if let Some(t) = target {
         t
     } else {
         // Default to queue[0]
-        let conn = DbConnection::connect()
+        let conn = DbConnection::connect_to(db)
             .context("Failed to connect to database")?;
         let stack = StackRepo::get_or_create_default(&conn)?;
         let items = StackRepo::get_items(&conn, stack.id.unwrap())?;
@@ -3902,11 +4816,11 @@ fn handle_task_close_optional(target: Option<String>, yes: bool, interactive: bo
         items[0].task_id.to_string()
     };
     
-    handle_task_close(id_or_filter, yes, interactive)
+    handle_task_close(id_or_filter, yes, interactive, config, db)
 }
 
-fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Resolve task IDs
@@ -3925,7 +4839,7 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
                         Ok(expr) => expr,
                         Err(e) => user_error(&format!("Filter parse error: {}", e)),
                     };
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     if matching_tasks.is_empty() {
@@ -3940,6 +4854,8 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
         }
     };
     
+    reject_trashed(&conn, &task_ids)?;
+
     if task_ids.len() > 1 {
         if !yes && !interactive {
             println!("This will close {} task(s).", task_ids.len());
@@ -3958,7 +4874,7 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
                     return Ok(());
                 }
                 "i" | "interactive" => {
-                    return handle_close_interactive(&conn, &task_ids);
+                    return handle_close_interactive(&conn, &task_ids, config);
                 }
                 _ => {
                     println!("Invalid input. Cancelled.");
@@ -3966,7 +4882,7 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
                 }
             }
         } else if interactive {
-            return handle_close_interactive(&conn, &task_ids);
+            return handle_close_interactive(&conn, &task_ids, config);
         }
     }
     
@@ -3982,7 +4898,7 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
         
         if let Some(session) = &open_session {
             if !closed_open_session && session.task_id == *task_id {
-                SessionRepo::close_open(&conn, end_ts)
+                SessionRepo::close_open(&conn, end_ts, config)
                     .context("Failed to close session")?;
                 closed_open_session = true;
             }
@@ -4013,15 +4929,19 @@ fn handle_task_close(id_or_filter: String, yes: bool, interactive: bool) -> Resu
         let items = StackRepo::get_items(&conn, stack_id)?;
         if let Some(item) = items.iter().find(|item| item.task_id == *task_id) {
             StackRepo::drop(&conn, stack_id, item.ordinal as i32)?;
+            if item.ordinal == 0 {
+                warn_if_top_blocked(&conn, stack_id)?;
+            }
         }
         
         println!("Closed task {}", task_id);
+        print_unblocked_dependents(&conn, *task_id)?;
     }
     
     Ok(())
 }
 
-fn handle_close_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()> {
+fn handle_close_interactive(conn: &Connection, task_ids: &[i64], config: &Config) -> Result<()> {
     use std::io::{self, Write};
     
     let end_ts = chrono::Utc::now().timestamp();
@@ -4055,7 +4975,7 @@ fn handle_close_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()> {
         
         if let Some(session) = &open_session {
             if !closed_open_session && session.task_id == *task_id {
-                SessionRepo::close_open(conn, end_ts)
+                SessionRepo::close_open(conn, end_ts, config)
                     .context("Failed to close session")?;
                 closed_open_session = true;
             }
@@ -4082,17 +5002,21 @@ fn handle_close_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()> {
         let items = StackRepo::get_items(conn, stack_id)?;
         if let Some(item) = items.iter().find(|item| item.task_id == *task_id) {
             StackRepo::drop(conn, stack_id, item.ordinal as i32)?;
+            if item.ordinal == 0 {
+                warn_if_top_blocked(conn, stack_id)?;
+            }
         }
         
         println!("Closed task {}", task_id);
+        print_unblocked_dependents(conn, *task_id)?;
     }
     
     Ok(())
 }
 
 /// Handle task reopen (set status back to pending)
-fn handle_task_reopen(id_or_filter: String, yes: bool, interactive: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_reopen(id_or_filter: String, yes: bool, interactive: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Resolve task IDs
@@ -4111,7 +5035,7 @@ fn handle_task_reopen(id_or_filter: String, yes: bool, interactive: bool) -> Res
                         Ok(expr) => expr,
                         Err(e) => user_error(&format!("Filter parse error: {}", e)),
                     };
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     if matching_tasks.is_empty() {
@@ -4126,6 +5050,8 @@ fn handle_task_reopen(id_or_filter: String, yes: bool, interactive: bool) -> Res
         }
     };
     
+    reject_trashed(&conn, &task_ids)?;
+
     if task_ids.len() > 1 {
         if !yes && !interactive {
             println!("This will reopen {} task(s).", task_ids.len());
@@ -4222,8 +5148,8 @@ fn handle_reopen_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()>
 }
 
 /// Handle task deletion
-fn handle_task_delete(id_or_filter: String, yes: bool, interactive: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+fn handle_task_delete(id_or_filter: String, yes: bool, interactive: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Try to parse as task ID spec (single ID, range, or list) first
@@ -4242,7 +5168,7 @@ fn handle_task_delete(id_or_filter: String, yes: bool, interactive: bool) -> Res
                     // Treat as filter - get all matching tasks
                     let filter_expr = parse_filter(vec![id_or_filter.clone()])
                         .map_err(|e| anyhow::anyhow!("Filter parse error: {}", e))?;
-                    let matching_tasks = filter_tasks(&conn, &filter_expr)
+                    let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                         .context("Failed to filter tasks")?;
                     
                     if matching_tasks.is_empty() {
@@ -4258,22 +5184,25 @@ fn handle_task_delete(id_or_filter: String, yes: bool, interactive: bool) -> Res
     };
     
     if interactive {
-        handle_delete_interactive(&conn, &task_ids)
+        handle_delete_interactive(&conn, &task_ids, config)
     } else if yes {
-        handle_delete_yes(&conn, &task_ids)
+        handle_delete_yes(&conn, &task_ids, config)
     } else {
-        handle_delete_confirm(&conn, &task_ids)
+        handle_delete_confirm(&conn, &task_ids, config)
     }
 }
 
 /// Delete tasks with confirmation prompt
-fn handle_delete_confirm(conn: &Connection, task_ids: &[i64]) -> Result<()> {
+fn handle_delete_confirm(conn: &Connection, task_ids: &[i64], config: &Config) -> Result<()> {
     use std::io::{self, Write};
     
     if task_ids.len() == 1 {
         // Single task - show description
         let task = TaskRepo::get_by_id(conn, task_ids[0])?
             .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_ids[0]))?;
+        if task.status == crate::models::TaskStatus::Deleted {
+            user_error(&format!("Task {} is already in the trash", task_ids[0]));
+        }
         print!("Delete task {} ({})? (y/n): ", task_ids[0], task.description);
         io::stdout().flush()?;
         
@@ -4286,9 +5215,9 @@ fn handle_delete_confirm(conn: &Connection, task_ids: &[i64]) -> Result<()> {
             return Ok(());
         }
         
-        TaskRepo::delete(conn, task_ids[0])
-            .context("Failed to delete task")?;
+        TaskRepo::trash(conn, task_ids[0], config)?;
         println!("Deleted task {}: {}", task_ids[0], task.description);
+        println!("Restore it with 'tatl trash restore {}'.", task_ids[0]);
     } else {
         // Multiple tasks - show count
         print!("Delete {} tasks? (y/n): ", task_ids.len());
@@ -4303,21 +5232,23 @@ fn handle_delete_confirm(conn: &Connection, task_ids: &[i64]) -> Result<()> {
             return Ok(());
         }
         
-        return handle_delete_yes(conn, task_ids);
+        return handle_delete_yes(conn, task_ids, config);
     }
     
     Ok(())
 }
 
 /// Delete tasks without confirmation
-fn handle_delete_yes(conn: &Connection, task_ids: &[i64]) -> Result<()> {
+fn handle_delete_yes(conn: &Connection, task_ids: &[i64], config: &Config) -> Result<()> {
     let mut deleted_count = 0;
     
     for task_id in task_ids {
         match TaskRepo::get_by_id(conn, *task_id) {
+            Ok(Some(task)) if task.status == crate::models::TaskStatus::Deleted => {
+                eprintln!("Warning: Task {} is already in the trash, skipping", task_id);
+            }
             Ok(Some(task)) => {
-                TaskRepo::delete(conn, *task_id)
-                    .context(format!("Failed to delete task {}", task_id))?;
+                TaskRepo::trash(conn, *task_id, config)?;
                 println!("Deleted task {}: {}", task_id, task.description);
                 deleted_count += 1;
             }
@@ -4331,20 +5262,24 @@ fn handle_delete_yes(conn: &Connection, task_ids: &[i64]) -> Result<()> {
     }
     
     if deleted_count > 0 {
-        println!("Deleted {} task(s)", deleted_count);
+        println!("Deleted {} task(s). See 'tatl trash list'.", deleted_count);
     }
     
     Ok(())
 }
 
 /// Delete tasks with interactive confirmation
-fn handle_delete_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()> {
+fn handle_delete_interactive(conn: &Connection, task_ids: &[i64], config: &Config) -> Result<()> {
     use std::io::{self, Write};
     
     let mut deleted_count = 0;
     
     for task_id in task_ids {
         let task = match TaskRepo::get_by_id(conn, *task_id) {
+            Ok(Some(task)) if task.status == crate::models::TaskStatus::Deleted => {
+                eprintln!("Warning: Task {} is already in the trash, skipping", task_id);
+                continue;
+            }
             Ok(Some(task)) => task,
             Ok(None) => {
                 eprintln!("Warning: Task {} not found, skipping", task_id);
@@ -4368,14 +5303,13 @@ fn handle_delete_interactive(conn: &Connection, task_ids: &[i64]) -> Result<()>
             continue;
         }
         
-        TaskRepo::delete(conn, *task_id)
-            .context(format!("Failed to delete task {}", task_id))?;
+        TaskRepo::trash(conn, *task_id, config)?;
         println!("Deleted task {}: {}", task_id, task.description);
         deleted_count += 1;
     }
     
     if deleted_count > 0 {
-        println!("Deleted {} task(s)", deleted_count);
+        println!("Deleted {} task(s). See 'tatl trash list'.", deleted_count);
     }
     
     Ok(())
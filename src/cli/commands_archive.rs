@@ -0,0 +1,51 @@
+// Archive command handler
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget, ArchiveManager, BackupManager};
+use crate::cli::error::user_error;
+use crate::utils::parse_date_expr;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+
+/// Move finished tasks last changed before a date into the archive ledger
+pub fn handle_archive(before: String, dry_run: bool, yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let before_ts = parse_date_expr(&before)
+        .unwrap_or_else(|e| user_error(&format!("Invalid --before '{}': {}", before, e)));
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let archive_path = db.archive_path();
+
+    let task_ids = ArchiveManager::candidates(&conn, before_ts)?;
+    if task_ids.is_empty() {
+        println!("No completed, closed or deleted tasks before {}.", before);
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("Would archive {} task(s) to {}", task_ids.len(), archive_path.display());
+        return Ok(());
+    }
+
+    if !yes {
+        print!("Move {} task(s) with their sessions, annotations and history to {}? (y/n): ",
+            task_ids.len(), archive_path.display());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let dir = db.backup_dir();
+    BackupManager::snapshot(&conn, &dir, Some("pre-archive"))?;
+    BackupManager::rotate(&dir, Some("pre-archive"), config.backup.keep)?;
+
+    let summary = ArchiveManager::archive(&conn, &archive_path, &task_ids)?;
+    println!("Archived {} task(s) ({} session(s), {} annotation(s), {} event(s)) to {}",
+        summary.tasks, summary.sessions, summary.annotations, summary.events, archive_path.display());
+    Ok(())
+}
@@ -0,0 +1,119 @@
+// Backup and restore command handlers
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget, BackupManager};
+use crate::cli::error::user_error;
+use crate::cli::output::format_timestamp;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Format a byte count for display
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Resolve a snapshot argument: a path, a file name in the backup directory, or `latest`
+fn resolve_snapshot(snapshot: &str, db: &LedgerTarget) -> PathBuf {
+    let dir = db.backup_dir();
+    if snapshot == "latest" {
+        return match BackupManager::list(&dir) {
+            Ok(snapshots) if !snapshots.is_empty() => snapshots[0].path.clone(),
+            _ => user_error(&format!("No snapshots found in {}", dir.display())),
+        };
+    }
+    let path = PathBuf::from(snapshot);
+    if path.exists() {
+        return path;
+    }
+    for candidate in [dir.join(snapshot), dir.join(format!("{}.db", snapshot))] {
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+    user_error(&format!("Snapshot '{}' not found. List snapshots with: tatl backup --list", snapshot));
+}
+
+/// Take a snapshot of the ledger, or list existing snapshots
+pub fn handle_backup(list: bool, keep: Option<usize>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let dir = db.backup_dir();
+
+    if list {
+        let snapshots = BackupManager::list(&dir)?;
+        if snapshots.is_empty() {
+            println!("No snapshots in {}", dir.display());
+            return Ok(());
+        }
+        println!("{:<44} {:<17} {:>10} {:>7}", "Snapshot", "Created", "Size", "Schema");
+        for snapshot in snapshots {
+            let schema = snapshot.schema_version
+                .map(|v| format!("v{}", v))
+                .unwrap_or_else(|| "?".to_string());
+            println!("{:<44} {:<17} {:>10} {:>7}",
+                snapshot.name, format_timestamp(snapshot.modified_ts, config), format_size(snapshot.size_bytes), schema);
+        }
+        return Ok(());
+    }
+
+    let keep = keep.unwrap_or(config.backup.keep);
+    if keep == 0 {
+        user_error("--keep must be at least 1");
+    }
+
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let path = BackupManager::snapshot(&conn, &dir, None)?;
+    let removed = BackupManager::rotate(&dir, None, keep)?;
+
+    println!("Created snapshot {}", path.display());
+    if !removed.is_empty() {
+        println!("Removed {} old snapshot(s) (keeping {})", removed.len(), keep);
+    }
+    Ok(())
+}
+
+/// Replace the ledger with a snapshot after checking its schema version
+pub fn handle_restore(snapshot: String, yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let path = resolve_snapshot(&snapshot, db);
+    let version = match BackupManager::validate_snapshot(&path) {
+        Ok(version) => version,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+    let db_path = db.ledger.path.clone();
+
+    if !yes {
+        println!("Restore snapshot {}?", path.display());
+        println!("  Schema version: v{}", version);
+        println!("  Ledger: {}", db_path.display());
+        println!("  The current ledger will be snapshotted first.");
+        print!("\nAre you sure? (y/n): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if input.trim().to_lowercase() != "y" {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let dir = db.backup_dir();
+    if db_path.exists() {
+        let conn = rusqlite::Connection::open(&db_path)
+            .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+        let safety = BackupManager::snapshot(&conn, &dir, Some("pre-restore"))?;
+        println!("Saved current ledger as {}", safety.display());
+    } else if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    BackupManager::restore(&path, &db_path)?;
+    BackupManager::rotate(&dir, Some("pre-restore"), config.backup.keep)?;
+    println!("Restored {} (schema v{})", path.display(), version);
+    Ok(())
+}
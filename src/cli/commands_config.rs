@@ -0,0 +1,117 @@
+// Configuration command handlers
+
+use crate::config::{Config, CONFIG_KEYS, set_config_value, unset_config_value};
+use crate::db::{DbConnection, validate_ledger_name};
+use crate::cli::error::user_error;
+use anyhow::{Context, Result};
+
+/// Load ~/.tatl/rc, exiting with the validation error if it is invalid
+/// and warning about keys it does not know
+pub fn load_config() -> Config {
+    let path = DbConnection::config_path();
+    let config = Config::load_from(&path)
+        .unwrap_or_else(|e| user_error(&format!("{:#}", e)));
+    if !config.ignored_keys.is_empty() {
+        eprintln!("Warning: ignoring unknown configuration key(s) in {}: {}",
+            path.display(), config.ignored_keys.join(", "));
+    }
+    config
+}
+
+fn require_known_key(key: &str) {
+    if !Config::is_known_key(key) {
+        user_error(&format!("Unknown configuration key '{}'. List keys with: tatl config list", key));
+    }
+}
+
+fn write_rc(contents: &str) -> Result<()> {
+    let path = DbConnection::config_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write configuration: {}", path.display()))
+}
+
+/// Print the effective value of a key
+pub fn handle_config_get(key: String) -> Result<()> {
+    require_known_key(&key);
+    match load_config().get(&key) {
+        Some(value) => println!("{}", value),
+        None => user_error(&format!("'{}' is not set", key)),
+    }
+    Ok(())
+}
+
+/// Validate and store a key in the rc file
+pub fn handle_config_set(key: String, value: String) -> Result<()> {
+    require_known_key(&key);
+    if let Some(name) = key.strip_prefix("ledger.") {
+        if name != "active" {
+            if let Err(e) = validate_ledger_name(name) {
+                user_error(&e.to_string());
+            }
+        }
+    }
+    if let Err(e) = Config::default().set(&key, &value) {
+        user_error(&e.to_string());
+    }
+
+    let path = DbConnection::config_path();
+    let text = std::fs::read_to_string(&path).unwrap_or_default();
+    let updated = set_config_value(&text, &key, &value);
+    write_rc(&updated)?;
+    println!("Set {}={}", key, value);
+
+    // Other keys may still be invalid (e.g. the file was edited by hand)
+    if let Err(e) = Config::parse(&updated) {
+        eprintln!("Warning: {} still has errors: {:#}", path.display(), e);
+    }
+    Ok(())
+}
+
+/// Remove a key from the rc file. Unknown keys can be removed too, so that
+/// the file can be cleaned up.
+pub fn handle_config_unset(key: String) -> Result<()> {
+    let path = DbConnection::config_path();
+    let text = std::fs::read_to_string(&path).unwrap_or_default();
+    let updated = unset_config_value(&text, &key);
+    if updated == text {
+        println!("'{}' is not set in {}", key, path.display());
+        return Ok(());
+    }
+    write_rc(&updated)?;
+    match Config::key_info(&key) {
+        Some(info) if !info.default.is_empty() => println!("Unset {} (default: {})", key, info.default),
+        _ => println!("Unset {}", key),
+    }
+    Ok(())
+}
+
+/// List every key with its current value, in rc file syntax
+pub fn handle_config_list() -> Result<()> {
+    let config = load_config();
+    for info in CONFIG_KEYS {
+        let value = config.get(info.key).unwrap_or_default();
+        println!("# {}", info.description);
+        if value == info.default {
+            println!("{}={}", info.key, value);
+        } else {
+            println!("{}={}    # default: {}", info.key, value, info.default);
+        }
+    }
+    if !config.ledgers.is_empty() {
+        println!("# Ledger profiles (see 'tatl ledger')");
+        for (key, value) in &config.ledgers {
+            println!("{}={}", key, value);
+        }
+    }
+    if !config.udas.is_empty() {
+        println!("# UDA types used by filters");
+        for (name, uda_type) in &config.udas {
+            println!("uda.{}.type={}", name, uda_type.name());
+        }
+    }
+    Ok(())
+}
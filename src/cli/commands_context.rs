@@ -0,0 +1,134 @@
+// Filter context command handlers
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::repo::{ContextRepo, FilterContext};
+use crate::filter::{parse_filter, filter_tasks, FilterExpr};
+use crate::cli::error::user_error;
+use rusqlite::Connection;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+
+/// Words after `tatl context` that cannot be context names
+const RESERVED_NAMES: &[&str] = &["define", "list", "delete", "none"];
+
+/// The context that applies to this command: the active one, unless `--no-context` was given
+pub fn active_context(conn: &Connection, no_context: bool) -> Result<Option<FilterContext>> {
+    if no_context {
+        return Ok(None);
+    }
+    ContextRepo::get_active(conn)
+}
+
+/// Parsed filter of the context that applies to this command
+pub fn active_context_filter(conn: &Connection, no_context: bool) -> Result<Option<FilterExpr>> {
+    let Some(context) = active_context(conn, no_context)? else {
+        return Ok(None);
+    };
+    match parse_filter(context.filter_tokens) {
+        Ok(expr) => Ok(Some(expr)),
+        Err(e) => user_error(&format!(
+            "Context '{}' has an invalid filter: {}. Redefine it with 'tatl context define', or bypass it with --no-context",
+            context.name, e)),
+    }
+}
+
+/// AND the active context into a command's filter
+pub fn with_active_context(conn: &Connection, expr: FilterExpr, no_context: bool) -> Result<FilterExpr> {
+    Ok(match active_context_filter(conn, no_context)? {
+        Some(context) if matches!(expr, FilterExpr::All) => context,
+        Some(context) => FilterExpr::And(vec![context, expr]),
+        None => expr,
+    })
+}
+
+/// IDs of the tasks inside the active context, or None when no context applies
+pub fn active_context_task_ids(conn: &Connection, no_context: bool, config: &Config) -> Result<Option<HashSet<i64>>> {
+    let Some(context) = active_context_filter(conn, no_context)? else {
+        return Ok(None);
+    };
+    let tasks = filter_tasks(conn, &context, config).context("Failed to filter tasks by context")?;
+    Ok(Some(tasks.iter().filter_map(|(task, _)| task.id).collect()))
+}
+
+/// One-line description of a context for report headers
+pub fn format_active_context(context: &FilterContext) -> String {
+    format!("Context: {} ({})", context.name, context.filter_tokens.join(" "))
+}
+
+/// `tatl context [define <name> <filter...> | list | delete <name> | none | <name>]`
+pub fn handle_context(args: Vec<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let Some((first, rest)) = args.split_first() else {
+        match ContextRepo::get_active(&conn)? {
+            Some(context) => println!("{}", format_active_context(&context)),
+            None => println!("No context is active."),
+        }
+        return Ok(());
+    };
+
+    match first.as_str() {
+        "define" => {
+            let Some((name, filter)) = rest.split_first() else {
+                user_error("Usage: tatl context define <name> <filter...>");
+            };
+            if RESERVED_NAMES.contains(&name.as_str()) {
+                user_error(&format!("'{}' is reserved and cannot be used as a context name", name));
+            }
+            if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+                user_error(&format!("Invalid context name '{}': use letters, digits, '-' and '_'", name));
+            }
+            if filter.is_empty() {
+                user_error(&format!("Context '{}' needs a filter, e.g. tatl context define {} project=work", name, name));
+            }
+            if let Err(e) = parse_filter(filter.to_vec()) {
+                user_error(&format!("Filter parse error: {}", e));
+            }
+            let context = ContextRepo::define(&conn, name, filter)?;
+            println!("Defined context '{}': {}", context.name, context.filter_tokens.join(" "));
+        }
+        "list" => {
+            let contexts = ContextRepo::list(&conn)?;
+            if contexts.is_empty() {
+                println!("No contexts defined. Define one with: tatl context define <name> <filter...>");
+                return Ok(());
+            }
+            println!("  {:<16} Filter", "Name");
+            for context in contexts {
+                let marker = if context.is_active { "*" } else { " " };
+                println!("{} {:<16} {}", marker, context.name, context.filter_tokens.join(" "));
+            }
+        }
+        "delete" => {
+            let [name] = rest else {
+                user_error("Usage: tatl context delete <name>");
+            };
+            if !ContextRepo::delete(&conn, name)? {
+                user_error(&format!("Context '{}' not found", name));
+            }
+            println!("Deleted context '{}'.", name);
+        }
+        "none" => {
+            if !rest.is_empty() {
+                user_error("Usage: tatl context none");
+            }
+            ContextRepo::set_active(&conn, None)?;
+            println!("Context cleared.");
+        }
+        name => {
+            if !rest.is_empty() {
+                user_error(&format!("Unexpected arguments after context name '{}'. To define it: tatl context define {} <filter...>",
+                    name, name));
+            }
+            if !ContextRepo::set_active(&conn, Some(name))? {
+                user_error(&format!("Context '{}' not found. Define it with: tatl context define {} <filter...>", name, name));
+            }
+            let context = ContextRepo::get_active(&conn)?.expect("context was just activated");
+            println!("Switched to context '{}': {}", context.name, context.filter_tokens.join(" "));
+        }
+    }
+    Ok(())
+}
+
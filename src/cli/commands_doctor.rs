@@ -0,0 +1,78 @@
+// Ledger integrity check command handler
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget, BackupManager};
+use crate::doctor::{Doctor, Finding, Severity};
+use anyhow::{Context, Result};
+
+/// Findings that should make the command fail (errors and warnings)
+fn count_failing(findings: &[Finding]) -> usize {
+    findings.iter().filter(|f| f.severity != Severity::Info).count()
+}
+
+fn print_findings(findings: &[Finding]) {
+    for finding in findings {
+        println!("[{}] {}: {}", finding.severity.as_str(), finding.check, finding.message);
+        match &finding.fix {
+            Some(fix) => println!("    fix: {}", fix.describe()),
+            None => println!("    fix: none (resolve manually)"),
+        }
+    }
+}
+
+/// Scan the ledger for inconsistencies and optionally repair them.
+/// Fixes for info findings are only applied with `include_info`.
+/// Exits with status 1 when errors or warnings remain, so the command can be used from cron.
+pub fn handle_doctor(fix: bool, include_info: bool, json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let mut findings = Doctor::scan(&conn)?;
+    let mut fixed = 0;
+    let mut snapshot = None;
+    if fix && Doctor::fixes(&findings, include_info).next().is_some() {
+        let dir = db.backup_dir();
+        let path = BackupManager::snapshot(&conn, &dir, Some("pre-doctor"))
+            .context("Failed to snapshot the ledger before fixing")?;
+        BackupManager::rotate(&dir, Some("pre-doctor"), config.backup.keep)?;
+        snapshot = Some(path);
+        fixed = Doctor::fix(&conn, &findings, include_info)?;
+        findings = Doctor::scan(&conn)?;
+    }
+    let failing = count_failing(&findings);
+
+    if json {
+        let report = serde_json::json!({
+            "checked_ts": chrono::Utc::now().timestamp(),
+            "ok": failing == 0,
+            "errors": findings.iter().filter(|f| f.severity == Severity::Error).count(),
+            "warnings": findings.iter().filter(|f| f.severity == Severity::Warning).count(),
+            "info": findings.iter().filter(|f| f.severity == Severity::Info).count(),
+            "fixed": fixed,
+            "snapshot": snapshot.as_ref().map(|p| p.display().to_string()),
+            "findings": findings,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        if let Some(path) = &snapshot {
+            println!("Fixed {} issue(s) (snapshot: {})", fixed, path.display());
+        }
+        if findings.is_empty() {
+            println!("No problems found.");
+        } else {
+            print_findings(&findings);
+            println!("\n{} finding(s).", findings.len());
+            if !fix && Doctor::fixes(&findings, false).next().is_some() {
+                println!("Run 'tatl doctor --fix' to apply the suggested fixes.");
+            }
+            if !include_info && Doctor::fixes(&findings, true).count() > Doctor::fixes(&findings, false).count() {
+                println!("Run 'tatl doctor --fix --include-info' to also apply the fixes for info findings.");
+            }
+        }
+    }
+
+    if failing > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,264 @@
+// Event log verification and point-in-time snapshot handlers
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::models::{TaskSnapshot, Divergence, TaskEvent, LedgerEvent};
+use crate::repo::{TaskRepo, EventRepo};
+use crate::cli::error::user_error;
+use crate::cli::commands_history::{resolve_targets, field_label, format_value, describe_event};
+use crate::cli::output::{format_timestamp, format_duration};
+use crate::utils::parse_date_expr;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde_json::Value;
+
+/// All task ids, including tasks in the trash
+fn all_task_ids(conn: &Connection) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare("SELECT id FROM tasks ORDER BY id")?;
+    let ids = stmt.query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+    Ok(ids)
+}
+
+fn target_ids(conn: &Connection, target: &[String], config: &Config) -> Result<Vec<i64>> {
+    if target.is_empty() {
+        return all_task_ids(conn);
+    }
+    let ids = resolve_targets(conn, target, config)?;
+    if ids.is_empty() {
+        user_error("No matching tasks found");
+    }
+    Ok(ids)
+}
+
+/// Render a snapshot field for display
+fn format_field(conn: &Connection, field: &str, value: &Value, config: &Config) -> String {
+    match (field, value) {
+        ("tags", Value::Array(tags)) if tags.is_empty() => "none".to_string(),
+        ("tags", Value::Array(tags)) => tags.iter()
+            .filter_map(|t| t.as_str())
+            .map(|t| format!("+{}", t))
+            .collect::<Vec<_>>()
+            .join(" "),
+        ("udas", Value::Object(map)) if map.is_empty() => "none".to_string(),
+        ("udas", Value::Object(map)) => map.iter()
+            .map(|(k, v)| format!("{}={}", k, v.as_str().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => format_value(conn, field, value, config),
+    }
+}
+
+/// Replay every task's events and compare with the stored rows
+pub fn handle_events_verify(target: Vec<String>, json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let task_ids = target_ids(&conn, &target, config)?;
+
+    let mut diverging: Vec<(i64, String, Vec<Divergence>)> = Vec::new();
+    for task_id in &task_ids {
+        let divergences = EventRepo::verify(&conn, *task_id)?;
+        if !divergences.is_empty() {
+            let description = TaskRepo::get_by_id(&conn, *task_id)?
+                .map(|t| t.description)
+                .unwrap_or_default();
+            diverging.push((*task_id, description, divergences));
+        }
+    }
+
+    if json {
+        let doc = serde_json::json!({
+            "checked": task_ids.len(),
+            "diverging": diverging.iter().map(|(id, description, divergences)| serde_json::json!({
+                "id": id,
+                "description": description,
+                "divergences": divergences,
+            })).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+    } else {
+        for (task_id, description, divergences) in &diverging {
+            println!("Task {}: {}", task_id, description);
+            for d in divergences {
+                println!("  {}: events say {}, ledger has {}",
+                    field_label(&d.field),
+                    format_field(&conn, &d.field, &d.replayed, config),
+                    format_field(&conn, &d.field, &d.stored, config));
+            }
+        }
+        if diverging.is_empty() {
+            println!("Checked {} task(s): all match the event log.", task_ids.len());
+        } else {
+            println!("\nChecked {} task(s): {} diverge from the event log.", task_ids.len(), diverging.len());
+        }
+    }
+
+    if !diverging.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn snapshot_json(task_id: i64, uuid: &str, snapshot: &TaskSnapshot) -> Result<Value> {
+    let mut value = serde_json::to_value(snapshot)?;
+    if let Value::Object(map) = &mut value {
+        map.insert("id".to_string(), Value::from(task_id));
+        map.insert("uuid".to_string(), Value::from(uuid));
+    }
+    Ok(value)
+}
+
+/// Show tasks as they were at a past moment, rebuilt from the event log
+pub fn handle_events_snapshot(at: String, target: Vec<String>, json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let at_ts = parse_date_expr(&at)
+        .unwrap_or_else(|e| user_error(&format!("Invalid --at '{}': {}", at, e)));
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let whole_ledger = target.is_empty();
+    let task_ids = target_ids(&conn, &target, config)?;
+
+    let mut snapshots = Vec::new();
+    for task_id in task_ids {
+        let Some(task) = TaskRepo::get_by_id(&conn, task_id)? else { continue };
+        let snapshot = EventRepo::replay(&conn, task_id, Some(at_ts))?;
+        // The whole-ledger view leaves out tasks that did not exist or were in the trash
+        if whole_ledger && snapshot.as_ref().is_none_or(|s| s.status == "deleted") {
+            continue;
+        }
+        snapshots.push((task_id, task.uuid, snapshot));
+    }
+
+    if json {
+        let doc = snapshots.iter()
+            .filter_map(|(id, uuid, snapshot)| snapshot.as_ref().map(|s| snapshot_json(*id, uuid, s)))
+            .collect::<Result<Vec<_>>>()?;
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    if whole_ledger {
+        println!("Ledger at {} ({} task(s))", format_timestamp(at_ts, config), snapshots.len());
+        println!("{:<6} {:<10} {:<16} {:<19}  {:<16} Description", "ID", "Status", "Project", "Due", "Tags");
+        for (task_id, _, snapshot) in &snapshots {
+            let Some(s) = snapshot else { continue };
+            let project = s.project_id
+                .map(|id| format_value(&conn, "project_id", &Value::from(id), config))
+                .unwrap_or_default();
+            let due = s.due_ts.map(|ts| format_timestamp(ts, config)).unwrap_or_default();
+            let tags = s.tags.iter().map(|t| format!("+{}", t)).collect::<Vec<_>>().join(" ");
+            println!("{:<6} {:<10} {:<16} {:<19}  {:<16} {}", task_id, s.status, project, due, tags, s.description);
+        }
+        return Ok(());
+    }
+
+    for (i, (task_id, _, snapshot)) in snapshots.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let Some(s) = snapshot else {
+            println!("Task {} did not exist yet at {}", task_id, format_timestamp(at_ts, config));
+            continue;
+        };
+        println!("Task {} at {}", task_id, format_timestamp(at_ts, config));
+        for (field, value) in s.fields() {
+            println!("  {:<12} {}", format!("{}:", field_label(field)), format_field(&conn, field, &value, config));
+        }
+    }
+    Ok(())
+}
+
+/// Entity types accepted by `tatl events log --entity`
+const ENTITY_TYPES: [&str; 4] = ["task", "session", "external", "project"];
+
+fn format_interval(start: Option<i64>, end: Option<i64>, config: &Config) -> String {
+    match (start, end) {
+        (Some(start), Some(end)) => format!("{} - {} ({})",
+            format_timestamp(start, config), format_timestamp(end, config), format_duration(end - start)),
+        (Some(start), None) => format!("{} - running", format_timestamp(start, config)),
+        _ => "?".to_string(),
+    }
+}
+
+/// One-line description of a session, external or project event
+fn describe_entity_event(conn: &Connection, event: &LedgerEvent, config: &Config) -> String {
+    if event.entity_type == "task" {
+        let task_event = TaskEvent {
+            id: None,
+            task_id: event.entity_id,
+            ts: event.ts,
+            event_type: event.event_type.clone(),
+            payload: event.payload.clone(),
+        };
+        return describe_event(conn, &task_event, config);
+    }
+    let int = |key: &str| event.payload.get(key).and_then(|v| v.as_i64());
+    let text = |key: &str| event.payload.get(key).and_then(|v| v.as_str()).unwrap_or("?").to_string();
+    match event.event_type.as_str() {
+        "session_added" | "session_deleted" | "session_purged" => format_interval(int("start_ts"), int("end_ts"), config),
+        "session_modified" => format!("{} → {}",
+            format_interval(int("old_start_ts"), int("old_end_ts"), config),
+            format_interval(int("new_start_ts"), int("new_end_ts"), config)),
+        "session_merged" => format!("absorbed session {} ({}); start {} → {}",
+            int("merged_session_id").unwrap_or(0),
+            format_interval(int("merged_start_ts"), int("merged_end_ts"), config),
+            int("old_start_ts").map(|ts| format_timestamp(ts, config)).unwrap_or_default(),
+            int("new_start_ts").map(|ts| format_timestamp(ts, config)).unwrap_or_default()),
+        "external_sent" => match event.payload.get("request").and_then(|r| r.as_str()) {
+            Some(request) => format!("to {}: {}", text("recipient"), request),
+            None => format!("to {}", text("recipient")),
+        },
+        "external_returned" => format!("from {}", text("recipient")),
+        "project_renamed" => format!("{} → {}", text("old_name"), text("new_name")),
+        "project_merged" => format!("{} → {}", text("from_name"), text("into_name")),
+        "project_archived" | "project_unarchived" => text("name"),
+        "undone" => format!("reverted '{}'", text("command")),
+        _ => event.payload.to_string(),
+    }
+}
+
+/// Chronological audit log of all entities
+pub fn handle_events_log(entity: Option<String>, id: Option<i64>, since: Option<String>, json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    if let Some(entity) = &entity {
+        if !ENTITY_TYPES.contains(&entity.as_str()) {
+            user_error(&format!("Unknown entity '{}'. Expected one of: {}", entity, ENTITY_TYPES.join(", ")));
+        }
+    }
+    if id.is_some() && entity.is_none() {
+        user_error("--id requires --entity");
+    }
+    let since_ts = since.map(|expr| parse_date_expr(&expr)
+        .unwrap_or_else(|e| user_error(&format!("Invalid --since '{}': {}", expr, e))));
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let events = EventRepo::list_all(&conn, entity.as_deref(), id, since_ts)?;
+
+    if json {
+        let doc: Vec<Value> = events.iter().map(|event| serde_json::json!({
+            "ts": event.ts,
+            "entity_type": event.entity_type,
+            "entity_id": event.entity_id,
+            "task_id": event.task_id,
+            "event_type": event.event_type,
+            "summary": describe_entity_event(&conn, event, config),
+            "payload": event.payload,
+        })).collect();
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    if events.is_empty() {
+        println!("No events.");
+        return Ok(());
+    }
+    for event in &events {
+        let entity = format!("{} {}", event.entity_type, event.entity_id);
+        let task = match (event.entity_type.as_str(), event.task_id) {
+            ("task", _) | (_, None) => String::new(),
+            (_, Some(task_id)) => format!("task {}", task_id),
+        };
+        println!("{}  {:<12} {:<18} {:<9} {}",
+            format_timestamp(event.ts, config), entity, event.event_type, task, describe_entity_event(&conn, event, config));
+    }
+    Ok(())
+}
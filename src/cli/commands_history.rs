@@ -0,0 +1,166 @@
+// Task history command handler
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::models::{Task, TaskEvent};
+use crate::repo::{TaskRepo, EventRepo, ProjectRepo, AnnotationRepo};
+use crate::cli::error::{user_error, parse_task_id_spec};
+use crate::cli::output::{format_timestamp, format_duration};
+use crate::filter::{parse_filter, filter_tasks};
+use crate::utils::parse_date_expr;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde_json::Value;
+
+/// Resolve `<id|filter>` arguments to task ids
+pub fn resolve_targets(conn: &Connection, target: &[String], config: &Config) -> Result<Vec<i64>> {
+    if target.len() == 1 {
+        if let Ok(ids) = parse_task_id_spec(&target[0]) {
+            return Ok(ids);
+        }
+    }
+    let filter_expr = match parse_filter(target.to_vec()) {
+        Ok(expr) => expr,
+        Err(e) => user_error(&format!("Filter parse error: {}", e)),
+    };
+    let matching = filter_tasks(conn, &filter_expr, config).context("Failed to filter tasks")?;
+    Ok(matching.iter().filter_map(|(task, _)| task.id).collect())
+}
+
+/// User-facing name of an event field
+pub fn field_label(field: &str) -> &str {
+    match field {
+        "project_id" => "project",
+        "due_ts" => "due",
+        "scheduled_ts" => "scheduled",
+        "wait_ts" => "wait",
+        "alloc_secs" => "alloc",
+        other => other,
+    }
+}
+
+/// Render a recorded field value for display
+pub fn format_value(conn: &Connection, field: &str, value: &Value, config: &Config) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::Number(n) => match (field, n.as_i64()) {
+            ("due_ts" | "scheduled_ts" | "wait_ts", Some(ts)) => format_timestamp(ts, config),
+            ("alloc_secs", Some(secs)) => format_duration(secs),
+            ("project_id", Some(id)) => ProjectRepo::get_by_id(conn, id).ok().flatten()
+                .map(|p| p.name)
+                .unwrap_or_else(|| format!("#{}", id)),
+            _ => n.to_string(),
+        },
+        Value::String(s) if field == "description" => format!("\"{}\"", s),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// One-line description of an event, with before/after values for attribute changes
+pub fn describe_event(conn: &Connection, event: &TaskEvent, config: &Config) -> String {
+    let payload_i64 = |key: &str| event.payload.get(key).and_then(|v| v.as_i64());
+    match event.event_type.as_str() {
+        "created" => {
+            let description = event.payload.get("description").and_then(|d| d.as_str()).unwrap_or("");
+            match payload_i64("project_id") {
+                Some(_) => format!("\"{}\" in project {}", description,
+                    format_value(conn, "project_id", &event.payload["project_id"], config)),
+                None => format!("\"{}\"", description),
+            }
+        }
+        "modified" | "status_changed" => event.changes().iter()
+            .map(|c| format!("{}: {} → {}",
+                field_label(&c.field),
+                format_value(conn, &c.field, &c.old_value, config),
+                format_value(conn, &c.field, &c.new_value, config)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        "tag_added" | "tag_removed" => {
+            let sign = if event.event_type == "tag_added" { "+" } else { "-" };
+            format!("{}{}", sign, event.payload.get("tag").and_then(|t| t.as_str()).unwrap_or("?"))
+        }
+        "dependency_added" => format!("depends on task {}", payload_i64("depends_on").unwrap_or(0)),
+        "dependency_removed" => format!("no longer depends on task {}", payload_i64("depends_on").unwrap_or(0)),
+        "annotation_added" => match payload_i64("annotation_id") {
+            Some(id) => match AnnotationRepo::get_by_id(conn, id).ok().flatten() {
+                Some(annotation) => format!("\"{}\"", annotation.note),
+                None => format!("annotation {} (since deleted)", id),
+            },
+            None => String::new(),
+        },
+        "annotation_deleted" => format!("annotation {}", payload_i64("annotation_id").unwrap_or(0)),
+        "stack_added" => format!("queued at position {}", payload_i64("position").unwrap_or(0)),
+        "stack_removed" => "removed from queue".to_string(),
+        "session_started" => format!("started at {}", format_timestamp(payload_i64("start_ts").unwrap_or(event.ts), config)),
+        "session_ended" => match (payload_i64("start_ts"), payload_i64("end_ts")) {
+            (Some(start), Some(end)) => format!("{} - {} ({})",
+                format_timestamp(start, config), format_timestamp(end, config), format_duration(end - start)),
+            _ => String::new(),
+        },
+        "undone" => format!("reverted '{}'", event.payload.get("command").and_then(|c| c.as_str()).unwrap_or("?")),
+        _ => event.payload.to_string(),
+    }
+}
+
+fn history_json(conn: &Connection, task: &Task, events: &[TaskEvent], config: &Config) -> Value {
+    serde_json::json!({
+        "id": task.id,
+        "uuid": task.uuid,
+        "description": task.description,
+        "events": events.iter().map(|event| serde_json::json!({
+            "ts": event.ts,
+            "event_type": event.event_type,
+            "summary": describe_event(conn, event, config),
+            "changes": event.changes(),
+            "payload": event.payload,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// Show the event timeline of one or more tasks
+pub fn handle_history(target: Vec<String>, since: Option<String>, json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let since_ts = since.map(|expr| parse_date_expr(&expr)
+        .unwrap_or_else(|e| user_error(&format!("Invalid --since '{}': {}", expr, e))));
+    let task_ids = resolve_targets(&conn, &target, config)?;
+    if task_ids.is_empty() {
+        user_error("No matching tasks found");
+    }
+
+    let mut histories = Vec::new();
+    for task_id in task_ids {
+        let task = TaskRepo::get_by_id(&conn, task_id)?
+            .unwrap_or_else(|| user_error(&format!("Task {} not found", task_id)));
+        let events = EventRepo::get_by_task(&conn, task_id, since_ts)?;
+        histories.push((task, events));
+    }
+
+    if json {
+        let doc: Vec<Value> = histories.iter()
+            .map(|(task, events)| history_json(&conn, task, events, config))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    for (i, (task, events)) in histories.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("Task {}: {}", task.id.unwrap_or(0), task.description);
+        if events.is_empty() {
+            match since_ts {
+                Some(ts) => println!("  No events since {}", format_timestamp(ts, config)),
+                None => println!("  No events"),
+            }
+            continue;
+        }
+        for event in events {
+            println!("  {}  {:<18} {}", format_timestamp(event.ts, config), event.event_type, describe_event(&conn, event, config));
+        }
+    }
+    Ok(())
+}
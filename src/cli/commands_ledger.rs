@@ -0,0 +1,93 @@
+// Ledger profile command handlers
+
+use crate::db::{DbConnection, ActiveLedger, LedgerSource, LEDGER_ENV, validate_ledger_name, set_active_ledger_config, add_ledger_config};
+use crate::cli::error::user_error;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// One-line description of the active ledger for report headers
+pub fn format_active_ledger(ledger: &ActiveLedger) -> String {
+    match ledger.source {
+        LedgerSource::Flag | LedgerSource::Env => format!("Ledger: {} ({}, via {})",
+            ledger.name, ledger.path.display(), ledger.source.describe()),
+        _ => format!("Ledger: {} ({})", ledger.name, ledger.path.display()),
+    }
+}
+
+/// List configured ledgers, marking the active one
+pub fn handle_ledger_list(flag: Option<&str>) -> Result<()> {
+    let profiles = DbConnection::ledger_profiles();
+    let active = DbConnection::active_ledger(flag);
+
+    println!("  {:<16} Path", "Name");
+    for (name, path) in profiles.list() {
+        let is_active = active.as_ref().is_ok_and(|a| a.path == path);
+        let marker = if is_active { "*" } else { " " };
+        println!("{} {:<16} {}", marker, name, path.display());
+    }
+    match &active {
+        Ok(active) if matches!(active.source, LedgerSource::Flag | LedgerSource::Env) => {
+            println!("\nActive ledger selected via {}: {} ({})", active.source.describe(), active.name, active.path.display());
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: {}", e),
+    }
+    Ok(())
+}
+
+/// Make a named ledger the default for future commands
+pub fn handle_ledger_switch(name: String) -> Result<()> {
+    let profiles = DbConnection::ledger_profiles();
+    let path = profiles.lookup(&name).unwrap_or_else(|| {
+        let known: Vec<String> = profiles.list().into_iter().map(|(n, _)| n).collect();
+        user_error(&format!("Unknown ledger '{}'. Known ledgers: {}. Add one with: tatl ledger add <name> <path>",
+            name, known.join(", ")))
+    });
+
+    let config_path = DbConnection::config_path();
+    let config = std::fs::read_to_string(&config_path).unwrap_or_default();
+    write_config(&config_path, &set_active_ledger_config(&config, &name))?;
+
+    println!("Switched to ledger '{}' ({})", name, path.display());
+    if let Ok(value) = std::env::var(LEDGER_ENV) {
+        if !value.trim().is_empty() {
+            eprintln!("Warning: {}={} is set and takes precedence over the switched ledger", LEDGER_ENV, value);
+        }
+    }
+    Ok(())
+}
+
+/// Register a named ledger in the rc file
+pub fn handle_ledger_add(name: String, path: String) -> Result<()> {
+    if let Err(e) = validate_ledger_name(&name) {
+        user_error(&e.to_string());
+    }
+    let profiles = DbConnection::ledger_profiles();
+    if profiles.lookup(&name).is_some() {
+        user_error(&format!("Ledger '{}' already exists", name));
+    }
+
+    // Store relative paths as absolute so they do not depend on the rc file location
+    let mut db_path = PathBuf::from(&path);
+    if db_path.is_relative() && !path.starts_with("~/") {
+        db_path = std::env::current_dir()?.join(db_path);
+    }
+    let stored = if path.starts_with("~/") { path.clone() } else { db_path.display().to_string() };
+
+    let config_path = DbConnection::config_path();
+    let config = std::fs::read_to_string(&config_path).unwrap_or_default();
+    write_config(&config_path, &add_ledger_config(&config, &name, &stored))?;
+
+    println!("Added ledger '{}' ({})", name, stored);
+    println!("Use it with: tatl --ledger {} <command>, or: tatl ledger switch {}", name, name);
+    Ok(())
+}
+
+fn write_config(config_path: &std::path::Path, contents: &str) -> Result<()> {
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    std::fs::write(config_path, contents)
+        .with_context(|| format!("Failed to write configuration: {}", config_path.display()))
+}
@@ -0,0 +1,50 @@
+// Search command handler
+
+use crate::db::{DbConnection, LedgerTarget};
+use crate::repo::{SearchRepo, TaskRepo, ProjectRepo};
+use anyhow::{Context, Result};
+
+/// Full-text search over task descriptions and annotations
+pub fn handle_search(words: Vec<String>, limit: usize, json: bool, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let hits = SearchRepo::search(&conn, &words, limit)?;
+
+    let mut results = Vec::new();
+    for hit in hits {
+        if let Some(task) = TaskRepo::get_by_id(&conn, hit.task_id)? {
+            let project = match task.project_id {
+                Some(id) => ProjectRepo::get_by_id(&conn, id)?.map(|p| p.name).unwrap_or_default(),
+                None => String::new(),
+            };
+            results.push((task, project, hit));
+        }
+    }
+
+    if json {
+        let doc: Vec<serde_json::Value> = results.iter().map(|(task, project, hit)| serde_json::json!({
+            "id": task.id,
+            "uuid": task.uuid,
+            "description": task.description,
+            "status": task.status.as_str(),
+            "project": if project.is_empty() { None } else { Some(project) },
+            "rank": hit.rank,
+            "note": hit.note,
+        })).collect();
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No tasks match '{}'.", words.join(" "));
+        return Ok(());
+    }
+    println!("{:<6} {:<10} {:<16} Description", "ID", "Status", "Project");
+    for (task, project, hit) in &results {
+        println!("{:<6} {:<10} {:<16} {}", task.id.unwrap_or(0), task.status.as_str(), project, task.description);
+        if let Some(note) = &hit.note {
+            println!("{:<35} note: {}", "", note);
+        }
+    }
+    Ok(())
+}
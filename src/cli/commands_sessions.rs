@@ -1,10 +1,16 @@
 // Sessions command handlers
 
-use crate::db::DbConnection;
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget, ArchiveManager};
 use crate::repo::{SessionRepo, TaskRepo, AnnotationRepo, ViewRepo};
 use crate::models::Session;
 use crate::cli::error::{user_error, validate_task_id};
 use crate::cli::output::is_tty;
+use crate::cli::commands_ledger::format_active_ledger;
+use crate::cli::explain::print_filter_explanation;
+use crate::cli::commands::GlobalFlags;
+use crate::filter::format_instant;
+use crate::cli::commands_context::{active_context, active_context_task_ids, with_active_context, format_active_context};
 use crate::filter::{parse_filter, filter_tasks};
 use crate::utils::parse_date_expr;
 use anyhow::{Context, Result};
@@ -50,6 +56,15 @@ fn format_duration(secs: i64) -> String {
     }
 }
 
+/// What a `start:`/`end:` session bound does, for --explain
+fn describe_session_bound(field: &str, bound: Option<(i64, Option<i64>)>) -> String {
+    match bound {
+        Some((from, Some(to))) => format!("sessions with {} from {} to {}", field, format_instant(from), format_instant(to)),
+        Some((from, None)) => format!("sessions with {} at or after {}", field, format_instant(from)),
+        None => format!("ignored: not a valid {} date", field),
+    }
+}
+
 /// Parse a session date filter expression
 /// Supports:
 /// - Single date: "today", "-7d", "2024-01-01"
@@ -113,8 +128,8 @@ fn parse_session_date_filter(expr: &str, filter_type: &str) -> Option<(i64, Opti
 }
 
 /// Handle `task [<id>] sessions list [--json]`
-pub fn handle_task_sessions_list(task_id_opt: Option<String>, json: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_task_sessions_list(task_id_opt: Option<String>, json: bool, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let sessions = if let Some(ref task_id_str) = task_id_opt {
@@ -201,8 +216,8 @@ pub fn handle_task_sessions_list(task_id_opt: Option<String>, json: bool) -> Res
 }
 
 /// Handle `task [<id>] sessions show`
-pub fn handle_task_sessions_show(task_id_opt: Option<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_task_sessions_show(task_id_opt: Option<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let session = if let Some(ref task_id_str) = task_id_opt {
@@ -277,10 +292,12 @@ struct ListRequest {
     group_columns: Vec<String>,
     hide_columns: Vec<String>,
     save_alias: Option<String>,
+    option_tokens: Vec<String>, // Display option tokens as given, for --explain
 }
 
 fn parse_list_request(tokens: Vec<String>) -> ListRequest {
     let mut filter_tokens = Vec::new();
+    let mut option_tokens = Vec::new();
     let mut sort_columns = Vec::new();
     let mut group_columns = Vec::new();
     let mut hide_columns = Vec::new();
@@ -299,7 +316,9 @@ fn parse_list_request(tokens: Vec<String>) -> ListRequest {
             }
         } else {
             filter_tokens.push(token);
+            continue;
         }
+        option_tokens.push(token);
     }
     
     ListRequest {
@@ -308,6 +327,7 @@ fn parse_list_request(tokens: Vec<String>) -> ListRequest {
         group_columns,
         hide_columns,
         save_alias,
+        option_tokens,
     }
 }
 
@@ -621,35 +641,40 @@ fn format_sessions_list_table(
     output
 }
 
-pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: bool, flags: GlobalFlags, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Separate session date filters from task filters
     let mut task_filters = Vec::new();
     let mut session_start_filter: Option<(i64, Option<i64>)> = None; // (from, optional to)
     let mut session_end_filter: Option<(i64, Option<i64>)> = None;   // (from, optional to)
+    let mut set_aside: Vec<(String, String)> = Vec::new();
 
     for arg in &filter_args {
         if let Some(date_expr) = arg.strip_prefix("start:") {
             // Parse date expression for session start filtering
             // Supports interval syntax: start:2024-01-01..2024-01-31
             session_start_filter = parse_session_date_filter(date_expr, "start");
+            set_aside.push((arg.clone(), describe_session_bound("start", session_start_filter)));
         } else if let Some(date_expr) = arg.strip_prefix("end:") {
             // Parse date expression for session end filtering
             session_end_filter = parse_session_date_filter(date_expr, "end");
+            set_aside.push((arg.clone(), describe_session_bound("end", session_end_filter)));
         } else {
             task_filters.push(arg.clone());
         }
     }
     
     let mut request = parse_list_request(task_filters);
+    set_aside.extend(request.option_tokens.iter().map(|token| (token.clone(), "display option".to_string())));
     if request.sort_columns.is_empty()
         && request.group_columns.is_empty()
         && request.filter_tokens.len() == 1
         && is_view_name_token(&request.filter_tokens[0])
     {
         if let Some(view) = ViewRepo::get_by_name(&conn, "sessions", &request.filter_tokens[0])? {
+            set_aside.push((view.name.clone(), "saved view, replaced by its filter and display options".to_string()));
             request.filter_tokens = view.filter_tokens;
             request.sort_columns = view.sort_columns;
             request.group_columns = view.group_columns;
@@ -657,6 +682,10 @@ pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: boo
         }
     }
     
+    if flags.explain {
+        return print_filter_explanation(&conn, &request.filter_tokens, &set_aside, flags.no_context, config);
+    }
+    
     if let Some(alias) = request.save_alias.clone() {
         ViewRepo::upsert(
             &conn,
@@ -673,8 +702,12 @@ pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: boo
     }
     
     let sessions = if request.filter_tokens.is_empty() {
-        // List all sessions
-        SessionRepo::list_all(&conn)?
+        // List all sessions, or those of the tasks in the active context
+        let sessions = SessionRepo::list_all(&conn)?;
+        match active_context_task_ids(&conn, flags.no_context, config)? {
+            Some(task_ids) => sessions.into_iter().filter(|s| task_ids.contains(&s.task_id)).collect(),
+            None => sessions,
+        }
     } else if request.filter_tokens.len() == 1 {
         // Single argument - try to parse as task ID first, otherwise treat as filter
         match validate_task_id(&request.filter_tokens[0]) {
@@ -691,7 +724,8 @@ pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: boo
                     Ok(expr) => expr,
                     Err(e) => user_error(&format!("Filter parse error: {}", e)),
                 };
-                let matching_tasks = filter_tasks(&conn, &filter_expr)
+                let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+                let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                     .context("Failed to filter tasks")?;
                 
                 if matching_tasks.is_empty() {
@@ -723,7 +757,8 @@ pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: boo
             Ok(expr) => expr,
             Err(e) => user_error(&format!("Filter parse error: {}", e)),
         };
-        let matching_tasks = filter_tasks(&conn, &filter_expr)
+        let filter_expr = with_active_context(&conn, filter_expr, flags.no_context)?;
+        let matching_tasks = filter_tasks(&conn, &filter_expr, config)
             .context("Failed to filter tasks")?;
         
         if matching_tasks.is_empty() {
@@ -835,8 +870,8 @@ pub fn handle_task_sessions_list_with_filter(filter_args: Vec<String>, json: boo
 }
 
 /// Handle `task [<id|filter>] sessions show` with filter support
-pub fn handle_task_sessions_show_with_filter(id_or_filter_opt: Option<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_task_sessions_show_with_filter(id_or_filter_opt: Option<String>, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     let session = if let Some(ref id_or_filter) = id_or_filter_opt {
@@ -855,7 +890,7 @@ pub fn handle_task_sessions_show_with_filter(id_or_filter_opt: Option<String>) -
                     Ok(expr) => expr,
                     Err(e) => user_error(&format!("Filter parse error: {}", e)),
                 };
-                let matching_tasks = filter_tasks(&conn, &filter_expr)
+                let matching_tasks = filter_tasks(&conn, &filter_expr, config)
                     .context("Failed to filter tasks")?;
                 
                 if matching_tasks.is_empty() {
@@ -1081,8 +1116,8 @@ fn check_session_overlaps(
 }
 
 /// Handle `task sessions <session_id> modify [start:<expr>] [end:<expr>] [--yes] [--force]`
-pub fn handle_sessions_modify(session_id: i64, args: Vec<String>, yes: bool, force: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_sessions_modify(session_id: i64, args: Vec<String>, yes: bool, force: bool, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Get session
@@ -1177,8 +1212,8 @@ pub fn handle_sessions_modify(session_id: i64, args: Vec<String>, yes: bool, for
 }
 
 /// Handle `task sessions <session_id> delete [--yes]`
-pub fn handle_sessions_delete(session_id: i64, yes: bool) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_sessions_delete(session_id: i64, yes: bool, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Get session
@@ -1295,8 +1330,8 @@ fn parse_session_add_args(args: Vec<String>) -> Result<(i64, i64, i64, Option<St
 
 /// Handle `task sessions add task=<id> start=<time> end=<time> [note=<note>]`
 /// Or: `task sessions add <id> <start> <end> [<note>]`
-pub fn handle_sessions_add(args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()
+pub fn handle_sessions_add(args: Vec<String>, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
         .context("Failed to connect to database")?;
     
     // Parse arguments
@@ -1305,6 +1340,7 @@ pub fn handle_sessions_add(args: Vec<String>) -> Result<()> {
     // Validate task exists
     let task = TaskRepo::get_by_id(&conn, task_id)?
         .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+    TaskRepo::ensure_not_trashed(&conn, task_id)?;
     
     // Validate start < end
     if start_ts >= end_ts {
@@ -1405,14 +1441,15 @@ fn format_percentage(part_secs: i64, total_secs: i64) -> String {
     }
 }
 
-/// Build project hierarchy tree from session data
+/// Add session time to the project hierarchy tree. Returns the time of sessions without a project.
+/// Totals are computed by the caller once all sessions are added.
 fn build_project_tree(
+    roots: &mut BTreeMap<String, ProjectNode>,
     conn: &Connection,
     sessions: &[Session],
     period_start: i64,
     period_end: i64,
-) -> (BTreeMap<String, ProjectNode>, i64) {
-    let mut roots: BTreeMap<String, ProjectNode> = BTreeMap::new();
+) -> i64 {
     let mut no_project_secs: i64 = 0;
     
     // Group sessions by task, then get project for each task
@@ -1442,7 +1479,7 @@ fn build_project_tree(
             Some(proj_name) => {
                 // Insert into hierarchy
                 let parts: Vec<&str> = proj_name.split('.').collect();
-                insert_into_tree(&mut roots, &parts, duration);
+                insert_into_tree(roots, &parts, duration);
             }
             None => {
                 no_project_secs += duration;
@@ -1450,12 +1487,7 @@ fn build_project_tree(
         }
     }
     
-    // Compute totals for all nodes
-    for node in roots.values_mut() {
-        node.compute_totals();
-    }
-    
-    (roots, no_project_secs)
+    no_project_secs
 }
 
 /// Insert time into the project hierarchy tree
@@ -1529,8 +1561,9 @@ fn print_project_tree(
 
 /// Handle the sessions report command
 /// Args format: [start] [end] [filter...] or [start..end] [filter...]
-pub fn handle_sessions_report(args: Vec<String>) -> Result<()> {
-    let conn = DbConnection::connect()?;
+pub fn handle_sessions_report(args: Vec<String>, include_archive: bool, flags: GlobalFlags, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)?;
+    let context = active_context(&conn, flags.no_context)?;
     let now = chrono::Utc::now().timestamp();
     
     // Separate date args from filter args
@@ -1538,13 +1571,19 @@ pub fn handle_sessions_report(args: Vec<String>) -> Result<()> {
     let mut filter_tokens: Vec<String> = Vec::new();
     
     for arg in args {
-        // If it looks like a filter token (contains : but isn't a time, or starts with + or -)
+        if arg.starts_with("--") {
+            user_error(&format!(
+                "Unknown option '{}' among the report arguments; options go before the period and filter (e.g. tatl sessions report --include-archive -7d)",
+                arg));
+        }
         if arg.starts_with('+') || arg.starts_with('-') && !arg.chars().skip(1).all(|c| c.is_ascii_digit() || c == 'd' || c == 'w' || c == 'm' || c == 'y') {
+            // If it looks like a filter token (contains : but isn't a time, or starts with + or -)
             filter_tokens.push(arg);
         } else if (arg.contains('=') || arg.contains('>') || arg.contains('<')) && !is_time_like(&arg) {
             // Looks like project=X or similar filter, not a time
             filter_tokens.push(arg);
-        } else if arg == "or" || arg == "not" {
+        } else if matches!(arg.as_str(), "and" | "or" | "xor" | "not") || arg.starts_with('(') || arg.ends_with(')')
+            || arg.starts_with("uda.") {
             filter_tokens.push(arg);
         } else if date_args.len() < 2 {
             // First two non-filter args are date args
@@ -1555,42 +1594,75 @@ pub fn handle_sessions_report(args: Vec<String>) -> Result<()> {
         }
     }
     
+    // Sessions of the ledger, then of the archive when requested
+    let mut sources: Vec<(Connection, Vec<Session>)> = Vec::new();
+    let ledger_sessions = SessionRepo::list_all(&conn)?;
+    sources.push((conn, ledger_sessions));
+    if include_archive {
+        let archive = match ArchiveManager::open(&db.archive_path()) {
+            Ok(archive) => archive,
+            Err(e) => user_error(&format!("{:#}", e)),
+        };
+        let archived_sessions = SessionRepo::list_all(&archive)?;
+        sources.push((archive, archived_sessions));
+    }
+    
     // Parse date arguments
-    let (period_start, period_end) = parse_report_date_args(&conn, &date_args, now)?;
+    let earliest_start = sources.iter()
+        .flat_map(|(_, sessions)| sessions.iter().map(|s| s.start_ts))
+        .min()
+        .unwrap_or(now);
+    let (period_start, period_end) = parse_report_date_args(&date_args, earliest_start, now)?;
     
-    // Get all sessions that overlap with the period
-    let all_sessions = SessionRepo::list_all(&conn)?;
-    let mut sessions: Vec<_> = all_sessions.into_iter()
-        .filter(|s| {
-            let session_end = s.end_ts.unwrap_or(now);
-            // Session overlaps if it starts before period ends and ends after period starts
-            s.start_ts < period_end && session_end > period_start
-        })
-        .collect();
+    if flags.explain {
+        let period = format!("report period, {} to {}", format_instant(period_start), format_instant(period_end));
+        let set_aside: Vec<(String, String)> = date_args.iter().map(|arg| (arg.clone(), period.clone())).collect();
+        return print_filter_explanation(&sources[0].0, &filter_tokens, &set_aside, flags.no_context, config);
+    }
     
-    // Apply task filter if provided
-    if !filter_tokens.is_empty() {
-        use crate::filter::{parse_filter, filter_tasks};
-        
+    let filter_expr = if filter_tokens.is_empty() && context.is_none() {
+        None
+    } else {
         let filter_expr = parse_filter(filter_tokens)
             .map_err(|e| anyhow::anyhow!("Filter parse error: {}", e))?;
+        Some(with_active_context(&sources[0].0, filter_expr, flags.no_context)?)
+    };
+    
+    for (source_conn, sessions) in sources.iter_mut() {
+        // Keep sessions that overlap with the period
+        sessions.retain(|s| {
+            let session_end = s.end_ts.unwrap_or(now);
+            // Session overlaps if it starts before period ends and ends after period starts
+            s.start_ts < period_end && session_end > period_start
+        });
         
-        let matching_tasks = filter_tasks(&conn, &filter_expr)?;
-        let matching_task_ids: std::collections::HashSet<i64> = matching_tasks
-            .iter()
-            .filter_map(|(task, _)| task.id)
-            .collect();
-        
-        sessions.retain(|s| matching_task_ids.contains(&s.task_id));
+        // Apply task filter if provided
+        if let Some(filter_expr) = &filter_expr {
+            let matching_tasks = filter_tasks(source_conn, filter_expr, config)?;
+            let matching_task_ids: std::collections::HashSet<i64> = matching_tasks
+                .iter()
+                .filter_map(|(task, _)| task.id)
+                .collect();
+            
+            sessions.retain(|s| matching_task_ids.contains(&s.task_id));
+        }
     }
     
-    if sessions.is_empty() {
+    let session_count: usize = sources.iter().map(|(_, sessions)| sessions.len()).sum();
+    if session_count == 0 {
         println!("No sessions found for this period.");
         return Ok(());
     }
     
     // Build project hierarchy tree
-    let (roots, no_project_secs) = build_project_tree(&conn, &sessions, period_start, period_end);
+    let mut roots: BTreeMap<String, ProjectNode> = BTreeMap::new();
+    let mut no_project_secs: i64 = 0;
+    for (source_conn, sessions) in &sources {
+        no_project_secs += build_project_tree(&mut roots, source_conn, sessions, period_start, period_end);
+    }
+    for node in roots.values_mut() {
+        node.compute_totals();
+    }
     
     // Calculate grand total
     let project_total: i64 = roots.values().map(|n| n.total_secs).sum();
@@ -1611,6 +1683,11 @@ pub fn handle_sessions_report(args: Vec<String>) -> Result<()> {
     // Print report
     let project_width = 25;
     
+    println!("{}", format_active_ledger(&db.ledger));
+    if let Some(context) = &context {
+        println!("{}", format_active_context(context));
+    }
+    println!();
     println!("{:<width$} {:>12} {:>8}", "Project", "Time", "%", width = project_width);
     println!("{} {} {}", "─".repeat(project_width), "─".repeat(12), "─".repeat(8));
     
@@ -1632,7 +1709,7 @@ pub fn handle_sessions_report(args: Vec<String>) -> Result<()> {
     let total_time_str = format_duration_hm(grand_total);
     println!("{:<width$} {:>12} {:>8}", "TOTAL", total_time_str, "100.0%", width = project_width);
     println!();
-    println!("Sessions: {} | Period: {} days | {}..{}", sessions.len(), period_days, start_date, end_date);
+    println!("Sessions: {} | Period: {} days | {}..{}", session_count, period_days, start_date, end_date);
     println!();
     
     Ok(())
@@ -1652,12 +1729,10 @@ fn is_time_like(s: &str) -> bool {
 }
 
 /// Parse report date arguments (handles interval syntax too)
-fn parse_report_date_args(conn: &Connection, date_args: &[String], now: i64) -> Result<(i64, i64)> {
+fn parse_report_date_args(date_args: &[String], earliest_start: i64, now: i64) -> Result<(i64, i64)> {
     if date_args.is_empty() {
         // No date args - use all time to now
-        let sessions = SessionRepo::list_all(conn)?;
-        let ps = sessions.iter().map(|s| s.start_ts).min().unwrap_or(now);
-        return Ok((ps, now));
+        return Ok((earliest_start, now));
     }
     
     let first = &date_args[0];
@@ -1669,8 +1744,7 @@ fn parse_report_date_args(conn: &Connection, date_args: &[String], now: i64) ->
         let end_str = parts.get(1).map(|s| s.trim()).unwrap_or("");
         
         let ps = if start_str.is_empty() {
-            let sessions = SessionRepo::list_all(conn)?;
-            sessions.iter().map(|s| s.start_ts).min().unwrap_or(now)
+            earliest_start
         } else {
             parse_date_expr(start_str)
                 .context(format!("Invalid start date: {}", start_str))?
@@ -0,0 +1,120 @@
+// Ledger sync command handler
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget, BackupManager, MigrationManager};
+use crate::transfer::{LedgerSync, SyncChanges};
+use crate::cli::error::user_error;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use rusqlite::backup::Backup;
+use std::path::Path;
+use std::time::Duration;
+
+/// Copy a ledger into memory so a dry run can merge without touching either file
+fn in_memory_copy(conn: &Connection) -> Result<Connection> {
+    let mut copy = Connection::open_in_memory()
+        .context("Failed to open in-memory database")?;
+    {
+        let backup = Backup::new(conn, &mut copy)?;
+        backup.run_to_completion(256, Duration::from_millis(10), None)?;
+    }
+    Ok(copy)
+}
+
+fn print_changes(title: &str, path: &Path, changes: &SyncChanges) {
+    println!("{} ({}):", title, path.display());
+    if changes.is_empty() {
+        println!("  No changes");
+        return;
+    }
+    let counts = [
+        (changes.tasks_added, "task(s) added"),
+        (changes.projects_created, "project(s) created"),
+        (changes.tags_added, "tag(s) added"),
+        (changes.tags_removed, "tag(s) removed"),
+        (changes.dependencies, "dependency(ies) added"),
+        (changes.annotations, "annotation(s) added"),
+        (changes.sessions, "session(s) added"),
+        (changes.sessions_updated, "session(s) updated"),
+        (changes.externals, "external(s) added or updated"),
+        (changes.events, "event(s) added"),
+        (changes.templates, "template(s) added"),
+        (changes.views, "view(s) added"),
+        (changes.contexts, "context(s) added"),
+    ];
+    for (count, label) in counts {
+        if count > 0 {
+            println!("  {} {}", count, label);
+        }
+    }
+    if changes.tasks_tombstoned > 0 {
+        println!("  {} task(s) not copied back (purged or archived in this ledger)", changes.tasks_tombstoned);
+    }
+    if changes.sessions_skipped > 0 {
+        println!("  {} open session(s) skipped (a session is already running)", changes.sessions_skipped);
+    }
+    if !changes.updated.is_empty() {
+        println!("  {} task(s) updated:", changes.updated.len());
+        for task in &changes.updated {
+            let fields: Vec<&str> = task.fields.iter()
+                .map(|f| f.trim_end_matches("_ts").trim_end_matches("_secs"))
+                .collect();
+            let short_uuid: String = task.uuid.chars().take(8).collect();
+            println!("    {} {}: {}", short_uuid, task.description, fields.join(", "));
+        }
+    }
+}
+
+/// Merge another ledger file into the active ledger and the active ledger into it
+pub fn handle_sync(other: String, dry_run: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let (name, other_path) = DbConnection::ledger_profiles().resolve(&other)
+        .unwrap_or_else(|e| user_error(&e.to_string()));
+    if !other_path.exists() {
+        user_error(&format!("Ledger not found: {}", other_path.display()));
+    }
+    if let Err(e) = BackupManager::validate_snapshot(&other_path) {
+        user_error(&format!("Cannot sync with {}: {:#}", other_path.display(), e));
+    }
+    let local_path = db.ledger.path.clone();
+    let same_file = match (local_path.canonicalize(), other_path.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    };
+    if same_file {
+        user_error("Cannot sync a ledger with itself");
+    }
+
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let remote = Connection::open(&other_path)
+        .with_context(|| format!("Failed to open database: {}", other_path.display()))?;
+
+    let report = if dry_run {
+        let local_copy = in_memory_copy(&conn)?;
+        let remote_copy = in_memory_copy(&remote)?;
+        MigrationManager::initialize(&remote_copy)
+            .context("Failed to initialize database schema")?;
+        LedgerSync::sync(&local_copy, &remote_copy)?
+    } else {
+        // Both ledgers are rewritten, so keep a copy of each
+        let dir = db.backup_dir();
+        BackupManager::snapshot(&conn, &dir, Some("pre-sync"))
+            .context("Failed to snapshot the ledger before syncing")?;
+        BackupManager::snapshot(&remote, &dir, Some(&format!("pre-sync-{}", name)))
+            .with_context(|| format!("Failed to snapshot {} before syncing", other_path.display()))?;
+        let keep = config.backup.keep;
+        BackupManager::rotate(&dir, Some("pre-sync"), keep)?;
+        BackupManager::rotate(&dir, Some(&format!("pre-sync-{}", name)), keep)?;
+        MigrationManager::initialize(&remote)
+            .context("Failed to initialize database schema")?;
+        LedgerSync::sync(&conn, &remote)?
+    };
+
+    println!("Sync with {}", name);
+    print_changes("This ledger", &local_path, &report.local);
+    print_changes("Other ledger", &other_path, &report.remote);
+    if dry_run {
+        println!("Dry run: nothing was written.");
+    }
+    Ok(())
+}
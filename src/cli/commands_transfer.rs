@@ -0,0 +1,219 @@
+// Export/import command handlers
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::transfer::{LedgerTransfer, TaskwarriorTransfer, IntervalImporter, OverlapStrategy, parse_timewarrior, parse_csv};
+use crate::cli::error::user_error;
+use crate::cli::output::{format_duration, format_timestamp};
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+
+/// Read an import file, or stdin when the path is `-`
+fn read_input(file: &str) -> Result<String> {
+    if file == "-" {
+        let mut buf = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)
+            .context("Failed to read import from stdin")?;
+        Ok(buf)
+    } else {
+        Ok(std::fs::read_to_string(file)
+            .unwrap_or_else(|e| user_error(&format!("Failed to read '{}': {}", file, e))))
+    }
+}
+
+/// Export the ledger as JSON in the requested format
+pub fn handle_export(output: Option<String>, to: String, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let (json, summary) = match to.as_str() {
+        "tatl" => {
+            let doc = LedgerTransfer::export(&conn)?;
+            let summary = format!("{} task(s), {} session(s), {} event(s)",
+                doc.tasks.len(), doc.sessions.len(), doc.task_events.len());
+            (serde_json::to_string_pretty(&doc), summary)
+        }
+        "taskwarrior" | "tw" => {
+            let tasks = TaskwarriorTransfer::export(&conn)?;
+            let summary = format!("{} task(s)", tasks.len());
+            (serde_json::to_string_pretty(&tasks), summary)
+        }
+        other => user_error(&format!("Unknown export format '{}'. Use 'tatl' or 'taskwarrior'.", other)),
+    };
+    let json = json.context("Failed to serialize export")?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, format!("{}\n", json))
+                .with_context(|| format!("Failed to write export file: {}", path))?;
+            println!("Exported {} to {}", summary, path);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// Import a JSON export in the requested format
+pub fn handle_import(file: String, from: String, db: &LedgerTarget) -> Result<()> {
+    match from.as_str() {
+        "tatl" => handle_ledger_import(&file, db),
+        "taskwarrior" | "tw" => handle_taskwarrior_import(&file, db),
+        other => user_error(&format!("Unknown import format '{}'. Use 'tatl' or 'taskwarrior'.", other)),
+    }
+}
+
+fn handle_ledger_import(file: &str, db: &LedgerTarget) -> Result<()> {
+    let json = read_input(file)?;
+    let doc = match LedgerTransfer::parse(&json) {
+        Ok(doc) => doc,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let summary = LedgerTransfer::import(&conn, &doc)?;
+
+    println!("Imported {} task(s) ({} already present, skipped)", summary.tasks_imported, summary.tasks_skipped);
+    if summary.projects_created > 0 {
+        println!("  Projects created: {}", summary.projects_created);
+    }
+    println!("  Sessions: {}", summary.sessions);
+    if summary.sessions_skipped > 0 {
+        println!("  Open sessions skipped (a session is already running): {}", summary.sessions_skipped);
+    }
+    println!("  Tags: {}, dependencies: {}, annotations: {}, events: {}",
+        summary.tags, summary.dependencies, summary.annotations, summary.events);
+    println!("  Queue entries: {}, externals: {}", summary.stack_items, summary.externals);
+    if summary.templates > 0 || summary.views > 0 || summary.contexts > 0 {
+        println!("  Templates: {}, views: {}, contexts: {}", summary.templates, summary.views, summary.contexts);
+    }
+    Ok(())
+}
+
+fn handle_taskwarrior_import(file: &str, db: &LedgerTarget) -> Result<()> {
+    let json = read_input(file)?;
+    let items = match TaskwarriorTransfer::parse(&json) {
+        Ok(items) => items,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let summary = match TaskwarriorTransfer::import(&conn, &items) {
+        Ok(summary) => summary,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+
+    println!("Imported {} task(s) from Taskwarrior ({} already present, skipped)", summary.tasks_imported, summary.tasks_skipped);
+    if summary.projects_created > 0 {
+        println!("  Projects created: {}", summary.projects_created);
+    }
+    if summary.annotations > 0 {
+        println!("  Annotations: {}", summary.annotations);
+    }
+    if summary.recurring_templates_skipped > 0 {
+        println!("  Recurring parent tasks skipped (their instances carry the respawn rule): {}", summary.recurring_templates_skipped);
+    }
+    for recur in &summary.unmapped_recur {
+        eprintln!("Warning: recurrence '{}' has no respawn equivalent; kept as the 'recur' UDA", recur);
+    }
+    Ok(())
+}
+
+/// Import Timewarrior or CSV intervals as closed sessions
+pub fn handle_sessions_import(file: String, from: Option<String>, overlap: String, dry_run: bool, yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let strategy = OverlapStrategy::parse(&overlap)
+        .unwrap_or_else(|| user_error(&format!("Invalid overlap strategy '{}'. Use skip, clip or force.", overlap)));
+
+    let format = from.unwrap_or_else(|| {
+        if file.to_lowercase().ends_with(".csv") { "csv".to_string() } else { "timewarrior".to_string() }
+    });
+    // Standard input is used up by the intervals, so there is no answer to read
+    if file == "-" && !yes && !dry_run {
+        user_error("Reading sessions from standard input: pass -y to import them or --dry-run to preview");
+    }
+    let text = read_input(&file)?;
+    let parsed = match format.as_str() {
+        "timewarrior" | "timew" => parse_timewarrior(&text),
+        "csv" => parse_csv(&text),
+        other => user_error(&format!("Unknown session import format '{}'. Use 'timewarrior' or 'csv'.", other)),
+    };
+    let intervals = match parsed {
+        Ok(intervals) => intervals,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+    if intervals.is_empty() {
+        println!("No intervals found in {}.", file);
+        return Ok(());
+    }
+
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    // Run the import inside a transaction so the summary reflects exactly what
+    // would be written; it is rolled back unless confirmed.
+    let tx = conn.unchecked_transaction()?;
+    let summary = match IntervalImporter::import(&tx, &intervals, strategy) {
+        Ok(summary) => summary,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+
+    let first = intervals.iter().map(|i| i.start_ts).min().unwrap_or(0);
+    let last = intervals.iter().filter_map(|i| i.end_ts).max().unwrap_or(first);
+    println!("Session import summary ({} interval(s), {} to {}):", intervals.len(), format_timestamp(first, config), format_timestamp(last, config));
+    println!("  Sessions to create: {} ({})", summary.sessions_created, format_duration(summary.seconds_imported));
+    println!("  Tasks matched: {}", summary.tasks_matched);
+    if !summary.tasks_created.is_empty() {
+        println!("  Tasks to create: {}", summary.tasks_created.len());
+        for description in &summary.tasks_created {
+            println!("    + {}", description);
+        }
+    }
+    if summary.skipped_overlap > 0 {
+        println!("  Skipped (overlap): {}", summary.skipped_overlap);
+    }
+    if summary.clipped > 0 {
+        println!("  Clipped to avoid overlap: {}", summary.clipped);
+    }
+    if summary.forced > 0 {
+        println!("  Imported despite overlap: {}", summary.forced);
+    }
+    if summary.skipped_open > 0 {
+        println!("  Skipped (still running): {}", summary.skipped_open);
+    }
+
+    if dry_run {
+        println!("Dry run: nothing imported.");
+        return Ok(());
+    }
+    if summary.sessions_created == 0 {
+        println!("Nothing to import.");
+        return Ok(());
+    }
+    if yes {
+        tx.commit()?;
+        println!("Imported {} session(s).", summary.sessions_created);
+        return Ok(());
+    }
+
+    // Don't keep the ledger locked while waiting for an answer
+    tx.rollback()?;
+    print!("\nImport these sessions? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    // Import again, as the ledger may have changed while the prompt was open
+    let tx = conn.unchecked_transaction()?;
+    let summary = match IntervalImporter::import(&tx, &intervals, strategy) {
+        Ok(summary) => summary,
+        Err(e) => user_error(&format!("{:#}", e)),
+    };
+    tx.commit()?;
+    println!("Imported {} session(s).", summary.sessions_created);
+    Ok(())
+}
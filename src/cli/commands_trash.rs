@@ -0,0 +1,107 @@
+// Trash bin command handlers
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::repo::{TaskRepo, ProjectRepo};
+use crate::cli::error::{user_error, parse_task_id_spec};
+use crate::cli::output::format_timestamp;
+use crate::utils::parse_duration;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+
+/// List tasks in the trash, most recently deleted first
+pub fn handle_trash_list(json: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let trashed = TaskRepo::list_trashed(&conn)?;
+
+    if json {
+        let doc: Vec<serde_json::Value> = trashed.iter().map(|(task, trashed_ts)| serde_json::json!({
+            "id": task.id,
+            "uuid": task.uuid,
+            "description": task.description,
+            "project_id": task.project_id,
+            "deleted_ts": trashed_ts,
+        })).collect();
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    if trashed.is_empty() {
+        println!("Trash is empty.");
+        return Ok(());
+    }
+    println!("{:<6} {:<19}  {:<16} Description", "ID", "Deleted", "Project");
+    for (task, trashed_ts) in &trashed {
+        let project = match task.project_id {
+            Some(id) => ProjectRepo::get_by_id(&conn, id)?.map(|p| p.name).unwrap_or_default(),
+            None => String::new(),
+        };
+        println!("{:<6} {:<19}  {:<16} {}",
+            task.id.unwrap_or(0), format_timestamp(*trashed_ts, config), project, task.description);
+    }
+    Ok(())
+}
+
+/// Take tasks out of the trash
+pub fn handle_trash_restore(target: String, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+    let task_ids = parse_task_id_spec(&target)
+        .unwrap_or_else(|e| user_error(&e));
+
+    for task_id in task_ids {
+        let task = TaskRepo::get_by_id(&conn, task_id)?
+            .unwrap_or_else(|| user_error(&format!("Task {} not found", task_id)));
+        match TaskRepo::restore(&conn, task_id) {
+            Ok(status) => println!("Restored task {}: {} ({})", task_id, task.description, status.as_str()),
+            Err(e) => user_error(&e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// Permanently remove tasks that have been in the trash long enough
+pub fn handle_trash_purge(older_than: Option<String>, yes: bool, db: &LedgerTarget) -> Result<()> {
+    let min_age = match &older_than {
+        Some(expr) => parse_duration(expr)
+            .unwrap_or_else(|e| user_error(&format!("Invalid --older-than '{}': {}", expr, e))),
+        None => 0,
+    };
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    let cutoff = chrono::Utc::now().timestamp() - min_age;
+    let expired: Vec<_> = TaskRepo::list_trashed(&conn)?
+        .into_iter()
+        .filter(|(_, trashed_ts)| *trashed_ts <= cutoff)
+        .collect();
+    if expired.is_empty() {
+        match older_than {
+            Some(expr) => println!("No tasks in the trash older than {}.", expr),
+            None => println!("Trash is empty."),
+        }
+        return Ok(());
+    }
+
+    if !yes {
+        print!("Permanently delete {} task(s) with their sessions, annotations and history? (y/n): ", expired.len());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    for (task, _) in &expired {
+        let task_id = task.id.unwrap_or(0);
+        TaskRepo::purge(&conn, task_id)
+            .with_context(|| format!("Failed to purge task {}", task_id))?;
+    }
+    println!("Purged {} task(s) from the trash.", expired.len());
+    Ok(())
+}
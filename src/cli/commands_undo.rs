@@ -0,0 +1,65 @@
+// Undo command handler
+
+use crate::config::Config;
+use crate::db::{DbConnection, LedgerTarget};
+use crate::repo::UndoRepo;
+use crate::cli::error::user_error;
+use crate::cli::output::format_timestamp;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+
+/// Number of operations shown by `tatl undo --list`
+const UNDO_LIST_LIMIT: usize = 10;
+
+fn task_list(task_ids: &[i64]) -> String {
+    if task_ids.is_empty() {
+        return "-".to_string();
+    }
+    task_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Reverse the most recent journaled command, or list recent commands
+pub fn handle_undo(list: bool, yes: bool, config: &Config, db: &LedgerTarget) -> Result<()> {
+    let conn = DbConnection::connect_to(db)
+        .context("Failed to connect to database")?;
+
+    if list {
+        let ops = UndoRepo::list(&conn, UNDO_LIST_LIMIT)?;
+        if ops.is_empty() {
+            println!("Nothing to undo.");
+            return Ok(());
+        }
+        println!("{:<6} {:<19}  {:>7}  {:<12} Command", "Op", "When", "Changes", "Tasks");
+        for op in ops {
+            let marker = if op.undone_ts.is_some() { "  (undone)" } else { "" };
+            println!("{:<6} {:<19}  {:>7}  {:<12} {}{}",
+                op.id, format_timestamp(op.created_ts, config), op.changes, task_list(&op.task_ids), op.command, marker);
+        }
+        return Ok(());
+    }
+
+    let Some(op) = UndoRepo::last_undoable(&conn)? else {
+        println!("Nothing to undo.");
+        return Ok(());
+    };
+
+    if !yes {
+        println!("Undo '{}' from {}?", op.command, format_timestamp(op.created_ts, config));
+        println!("  {} change(s) to task(s): {}", op.changes, task_list(&op.task_ids));
+        print!("\nAre you sure? (y/n): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if input.trim().to_lowercase() != "y" {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    if let Err(e) = UndoRepo::undo(&conn, &op) {
+        user_error(&format!("{:#}", e));
+    }
+    println!("Undid '{}'", op.command);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+// Filter explain mode (--explain)
+
+use crate::config::Config;
+use crate::filter::{parse_filter, explain_filter};
+use crate::repo::TaskRepo;
+use crate::cli::commands_context::{active_context, with_active_context, format_active_context};
+use crate::cli::error::{user_error, parse_task_id_spec};
+use rusqlite::Connection;
+use anyhow::Result;
+
+/// Print how a command's filter tokens are understood and what each part matches.
+///
+/// `set_aside` lists the command's arguments that are not part of the filter, each with
+/// what the command does with it instead. `no_context` leaves the active context out, as
+/// `--no-context` does for the command itself.
+pub fn print_filter_explanation(conn: &Connection, filter_tokens: &[String], set_aside: &[(String, String)], no_context: bool, config: &Config) -> Result<()> {
+    if filter_tokens.is_empty() {
+        println!("Filter: (none)");
+    } else {
+        println!("Filter: {}", filter_tokens.join(" "));
+    }
+    if !set_aside.is_empty() {
+        println!("Not part of the filter:");
+        for (token, reason) in set_aside {
+            println!("  {:<24} {}", token, reason);
+        }
+    }
+
+    // A single ID, range or list selects tasks directly, without the context
+    if let [token] = filter_tokens {
+        if let Ok(ids) = parse_task_id_spec(token) {
+            println!();
+            println!("Task IDs: {} (explicit IDs are not filtered and ignore the context)",
+                ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "));
+            for id in ids {
+                if TaskRepo::get_by_id(conn, id)?.is_none() {
+                    println!("  ! Task {} does not exist", id);
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    let expr = match parse_filter(filter_tokens.to_vec()) {
+        Ok(expr) => expr,
+        Err(e) => user_error(&format!("Filter parse error: {}", e)),
+    };
+    let filter_is_empty = matches!(expr, crate::filter::FilterExpr::All);
+    let combined = with_active_context(conn, expr, no_context)?;
+    let mut tree = explain_filter(conn, &combined, config)?;
+
+    if let Some(context) = active_context(conn, no_context)? {
+        println!("{}, ANDed into the filter (bypass with --no-context)", format_active_context(&context));
+        let context_node = if filter_is_empty { &mut tree } else { &mut tree.children[0] };
+        context_node.label = format!("context '{}': {}", context.name, context_node.label);
+    }
+
+    println!();
+    print!("{}", tree.render());
+    println!();
+    println!("Result: {} task{}", tree.matches, if tree.matches == 1 { "" } else { "s" });
+    Ok(())
+}
@@ -1,11 +1,24 @@
 pub mod commands;
 pub mod commands_sessions;
+pub mod commands_transfer;
+pub mod commands_backup;
+pub mod commands_archive;
+pub mod commands_doctor;
+pub mod commands_sync;
+pub mod commands_history;
+pub mod commands_undo;
+pub mod commands_trash;
+pub mod commands_events;
+pub mod commands_ledger;
+pub mod commands_context;
+pub mod commands_config;
+pub mod commands_search;
 pub mod error;
 pub mod output;
 pub mod parser;
 pub mod status;
+pub mod explain;
 pub mod abbrev;
-pub mod priority;
 
 pub use commands::*;
 pub use parser::*;
@@ -1,8 +1,10 @@
 // Output formatting utilities
 
 use crate::models::{Task, TaskStatus};
-use crate::repo::{ProjectRepo, SessionRepo, StackRepo, TaskRepo, ExternalRepo};
-use crate::cli::priority::calculate_priority;
+use crate::repo::{ProjectRepo, SessionRepo, StackRepo, TaskRepo, ExternalRepo, DependencyRepo};
+use crate::utils::priority::calculate_priority;
+use crate::config::Config;
+use crate::filter::virtual_tags_for;
 use chrono::Local;
 use rusqlite::Connection;
 use anyhow::Result;
@@ -63,45 +65,13 @@ const CATEGORICAL_BG_PALETTE: &[&str] = &[
     ANSI_BG_BRIGHT_BLACK,
 ];
 
-/// Semantic colors for known column values
-fn get_semantic_fg_color(column: &str, value: &str) -> Option<&'static str> {
-    match column {
-        "status" => match value {
-            "pending" => None, // Default color
-            "completed" => Some(ANSI_FG_GREEN),
-            "closed" => Some(ANSI_FG_BRIGHT_BLACK),
-            _ => None,
-        },
-        "kanban" => match value {
-            "proposed" => Some(ANSI_FG_BRIGHT_BLACK),
-            "stalled" => Some(ANSI_FG_YELLOW),
-            "queued" => Some(ANSI_FG_BLUE),
-            "external" => Some(ANSI_FG_MAGENTA),
-            "done" => Some(ANSI_FG_GREEN),
-            _ => None,
-        },
-        _ => None,
-    }
+/// Semantic colors for known column values (`color.status.*` and `color.kanban.*`)
+fn get_semantic_fg_color(column: &str, value: &str, config: &Config) -> Option<&'static str> {
+    config.colors.for_value(column, value).and_then(|c| c.fg_code())
 }
 
-fn get_semantic_bg_color(column: &str, value: &str) -> Option<&'static str> {
-    match column {
-        "status" => match value {
-            "pending" => None,
-            "completed" => Some(ANSI_BG_GREEN),
-            "closed" => Some(ANSI_BG_BRIGHT_BLACK),
-            _ => None,
-        },
-        "kanban" => match value {
-            "proposed" => Some(ANSI_BG_BRIGHT_BLACK),
-            "stalled" => Some(ANSI_BG_YELLOW),
-            "queued" => Some(ANSI_BG_BLUE),
-            "external" => Some(ANSI_BG_MAGENTA),
-            "done" => Some(ANSI_BG_GREEN),
-            _ => None,
-        },
-        _ => None,
-    }
+fn get_semantic_bg_color(column: &str, value: &str, config: &Config) -> Option<&'static str> {
+    config.colors.for_value(column, value).and_then(|c| c.bg_code())
 }
 
 /// Get foreground color for a value using hash-based palette
@@ -340,10 +310,13 @@ fn bold_if_tty(text: &str, is_tty: bool) -> String {
 /// | proposed  | pending   | Not in stack     | Task id not in sessions list   | No externals |
 /// | stalled   | pending   | Not in stack     | Task id in sessions list       | No externals |
 /// | queued    | pending   | In stack (any)   | (any)                          | No externals |
+/// | blocked   | pending   | (any)            | (any)                          | No externals |
 /// | external  | pending   | (any)            | (any)                          | Has externals|
 /// | done      | completed | (ineligible)     | N/A                            | N/A          |
 /// | done      | closed    | (ineligible)     | N/A                            | N/A          |
 /// 
+/// A task is blocked while it depends on a pending task, in or out of the stack.
+///
 /// Note: Q column shows exact queue position (0, 1, 2, etc. or E for external)
 pub fn calculate_kanban_status(
     task: &Task,
@@ -352,6 +325,7 @@ pub fn calculate_kanban_status(
     _open_session_task_id: Option<i64>,  // Not used after removing NEXT/LIVE, kept for API compatibility
     _stack_top_task_id: Option<i64>,      // Not used after removing NEXT/LIVE, kept for API compatibility
     has_externals: bool,
+    is_blocked: bool,
 ) -> &'static str {
     // Completed/closed tasks are "done"
     if task.status == TaskStatus::Completed || task.status == TaskStatus::Closed {
@@ -363,6 +337,11 @@ pub fn calculate_kanban_status(
         return "external";
     }
     
+    // Waiting on a pending dependency
+    if is_blocked {
+        return "blocked";
+    }
+    
     // At this point, task is pending (or other non-terminal status)
     match stack_position {
         Some(_pos) => {
@@ -408,26 +387,38 @@ fn get_tasks_with_externals(conn: &Connection) -> Result<HashSet<i64>> {
     Ok(task_ids)
 }
 
+/// Get IDs of pending tasks that depend on a pending task
+fn get_blocked_tasks(conn: &Connection) -> Result<HashSet<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT d.task_id FROM task_dependencies d
+         JOIN tasks t ON t.id = d.task_id JOIN tasks u ON u.id = d.depends_on_id
+         WHERE t.status = 'pending' AND u.status = 'pending'"
+    )?;
+    let task_ids = stmt.query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<HashSet<i64>>>()?;
+    Ok(task_ids)
+}
+
 /// Format timestamp for display
-pub fn format_timestamp(ts: i64) -> String {
+pub fn format_timestamp(ts: i64, config: &Config) -> String {
     use chrono::TimeZone;
     let dt = Local.timestamp_opt(ts, 0)
         .single()
         .unwrap_or_else(|| Local.timestamp_opt(0, 0).single().unwrap());
-    dt.format("%Y-%m-%d %H:%M:%S").to_string()
+    dt.format(&config.dates.datetime_format).to_string()
 }
 
 /// Format date for display (date only, no time)
-pub fn format_date(ts: i64) -> String {
+pub fn format_date(ts: i64, config: &Config) -> String {
     use chrono::TimeZone;
     let dt = Local.timestamp_opt(ts, 0)
         .single()
         .unwrap_or_else(|| Local.timestamp_opt(0, 0).single().unwrap());
-    dt.format("%Y-%m-%d").to_string()
+    dt.format(&config.dates.date_format).to_string()
 }
 
 /// Format date as relative time (e.g., "2 days ago", "in 3 days", "today", "overdue")
-pub fn format_relative_date(ts: i64) -> String {
+pub fn format_relative_date(ts: i64, config: &Config) -> String {
     use chrono::{Local, TimeZone};
     let now = Local::now();
     let due_dt = Local.timestamp_opt(ts, 0)
@@ -461,7 +452,7 @@ pub fn format_relative_date(ts: i64) -> String {
         format!("in {} days", days_diff)
     } else {
         // More than a year in future - show absolute date
-        format_date(ts)
+        format_date(ts, config)
     }
 }
 
@@ -489,6 +480,8 @@ pub struct TaskListOptions {
     pub color_column: Option<String>,  // Column for text color
     pub fill_column: Option<String>,   // Column for background color
     pub full_width: bool, // Show all columns regardless of terminal width
+    pub columns: Vec<String>, // Columns after id/description (empty: built-in defaults)
+    pub virtual_tags: bool, // Append virtual tags (+OVERDUE...) to the Tags column
 }
 
 /// Parse a sort specification, detecting negation prefix for descending order
@@ -506,19 +499,20 @@ fn parse_sort_spec(spec: &str) -> SortSpec {
 }
 
 /// Ordinal value for kanban status (workflow progression)
-/// Order: proposed → stalled → external → queued → done
+/// Order: proposed → stalled → blocked → external → queued → done
 fn kanban_sort_order(kanban: &str) -> i64 {
     match kanban.to_lowercase().as_str() {
         "proposed" => 0,
         "stalled" => 1,
-        "external" => 2,  // Moved before queued
-        "queued" => 3,
-        "done" => 4,
+        "blocked" => 2,
+        "external" => 3,  // Moved before queued
+        "queued" => 4,
+        "done" => 5,
         // Legacy support (for migration period)
         "paused" => 1,  // Maps to stalled
-        "next" => 3,   // Maps to queued
-        "live" => 3,   // Maps to queued
-        "quit" => 4,   // Maps to done
+        "next" => 4,   // Maps to queued
+        "live" => 4,   // Maps to queued
+        "quit" => 5,   // Maps to done
         _ => 99,
     }
 }
@@ -541,6 +535,7 @@ fn get_row_colors(
     fill_column: &Option<String>,
     priority_range: Option<(f64, f64)>,  // (min, max) for gradient normalization
     due_range: Option<(i64, i64)>,       // (min, max) for date heat map
+    config: &Config,
 ) -> (String, String, bool) {
     let mut fg_color = String::new();
     let mut bg_color = String::new();
@@ -553,7 +548,7 @@ fn get_row_colors(
                 fg_color = match color_type {
                     ColumnColorType::Categorical => {
                         // Try semantic color first, fall back to hash-based
-                        get_semantic_fg_color(col_name, value)
+                        get_semantic_fg_color(col_name, value, config)
                             .unwrap_or_else(|| get_hash_fg_color(value))
                             .to_string()
                     }
@@ -600,7 +595,7 @@ fn get_row_colors(
                 let color_type = detect_column_color_type(col_name);
                 bg_color = match color_type {
                     ColumnColorType::Categorical => {
-                        get_semantic_bg_color(col_name, value)
+                        get_semantic_bg_color(col_name, value, config)
                             .unwrap_or_else(|| get_hash_bg_color(value))
                             .to_string()
                     }
@@ -777,6 +772,7 @@ pub fn format_task_list_table(
     conn: &Connection,
     tasks: &[(Task, Vec<String>)],
     options: &TaskListOptions,
+    config: &Config,
 ) -> Result<String> {
     if tasks.is_empty() {
         return Ok("No tasks found.".to_string());
@@ -789,6 +785,7 @@ pub fn format_task_list_table(
     let stack_top_task_id = stack_items.first().map(|item| item.task_id);
     let tasks_with_sessions = get_tasks_with_sessions(conn)?;
     let tasks_with_externals = get_tasks_with_externals(conn)?;
+    let blocked_tasks = get_blocked_tasks(conn)?;
     let open_session_task_id = SessionRepo::get_open(conn)?.map(|s| s.task_id);
     
     let mut rows: Vec<TaskRow> = Vec::new();
@@ -804,6 +801,7 @@ pub fn format_task_list_table(
             open_session_task_id,
             stack_top_task_id,
             has_externals,
+            blocked_tasks.contains(&task_id),
         );
         
         let project = if let Some(project_id) = task.project_id {
@@ -816,17 +814,17 @@ pub fn format_task_list_table(
             String::new()
         };
         
-        let tag_str = if !tags.is_empty() {
-            tags.iter().map(|t| format!("+{}", t)).collect::<Vec<_>>().join(" ")
-        } else {
-            String::new()
-        };
+        let mut tag_names: Vec<String> = tags.iter().map(|t| format!("+{}", t)).collect();
+        if options.virtual_tags {
+            tag_names.extend(virtual_tags_for(task, conn)?.iter().map(|t| format!("+{}", t.name())));
+        }
+        let tag_str = tag_names.join(" ");
         
         let due = if let Some(due_ts) = task.due_ts {
             if options.use_relative_time {
-                format_relative_date(due_ts)
+                format_relative_date(due_ts, config)
             } else {
-                format_date(due_ts)
+                format_date(due_ts, config)
             }
         } else {
             String::new()
@@ -853,7 +851,7 @@ pub fn format_task_list_table(
         };
         
         let priority = if task.status == TaskStatus::Pending {
-            if let Ok(prio) = calculate_priority(task, conn) {
+            if let Ok(prio) = calculate_priority(task, conn, config) {
                 format!("{:.1}", prio)
             } else {
                 String::new()
@@ -863,7 +861,7 @@ pub fn format_task_list_table(
         };
         
         // Queue position indicator
-        // Priority: queue position/▶ > @ (external) > ✓ (completed) > x (closed) > ! (stalled) > ? (proposed)
+        // Priority: queue position/▶ > @ (external) > ✓ (completed) > x (closed) > ! (stalled) > ⊘ (blocked) > ? (proposed)
         let queue_pos_str = if stack_pos == Some(0) && open_session_task_id == task.id {
             // Active task at top of queue
             "▶".to_string()
@@ -882,6 +880,9 @@ pub fn format_task_list_table(
         } else if kanban == "stalled" {
             // Has sessions but not in queue
             "!".to_string()
+        } else if kanban == "blocked" {
+            // Waiting on a pending dependency
+            "⊘".to_string()
         } else if kanban == "proposed" {
             // New task, not started
             "?".to_string()
@@ -913,7 +914,7 @@ pub fn format_task_list_table(
         sort_values.insert(TaskListColumn::Due, task.due_ts.map(SortValue::Int));
         sort_values.insert(TaskListColumn::Alloc, task.alloc_secs.map(SortValue::Int));
         sort_values.insert(TaskListColumn::Priority, if task.status == TaskStatus::Pending {
-            calculate_priority(task, conn).ok().map(SortValue::Float)
+            calculate_priority(task, conn, config).ok().map(SortValue::Float)
         } else {
             None
         });
@@ -954,16 +955,23 @@ pub fn format_task_list_table(
         }
     }
     
-    let default_columns = [
-        TaskListColumn::Status,
-        TaskListColumn::Kanban,
-        TaskListColumn::Project,
-        TaskListColumn::Tags,
-        TaskListColumn::Due,
-        TaskListColumn::Alloc,
-        TaskListColumn::Priority,
-        TaskListColumn::Clock,
-    ];
+    let default_columns = if options.columns.is_empty() {
+        vec![
+            TaskListColumn::Status,
+            TaskListColumn::Kanban,
+            TaskListColumn::Project,
+            TaskListColumn::Tags,
+            TaskListColumn::Due,
+            TaskListColumn::Alloc,
+            TaskListColumn::Priority,
+            TaskListColumn::Clock,
+        ]
+    } else {
+        options.columns.iter()
+            .map(|name| parse_task_column(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown column: {}", name)))
+            .collect::<Result<Vec<_>>>()?
+    };
     for column in default_columns {
         if !columns.contains(&column) {
             columns.push(column);
@@ -1188,7 +1196,7 @@ pub fn format_task_list_table(
         for row in &rows {
             // Get row colors (based on the column value, but will be applied selectively)
             let (fg_color, bg_color, _) = if colors_enabled {
-                get_row_colors(row, &options.color_column, &options.fill_column, priority_range, due_range)
+                get_row_colors(row, &options.color_column, &options.fill_column, priority_range, due_range, config)
             } else {
                 (String::new(), String::new(), false)
             };
@@ -1303,7 +1311,7 @@ pub fn format_task_list_table(
                         .and_then(|idx| group_values.get(idx))
                         .unwrap_or(&group_values[0]); // Fallback to first if not found
                     let col_name = options.color_column.as_deref().unwrap_or("");
-                    fg = get_semantic_fg_color(col_name, group_value)
+                    fg = get_semantic_fg_color(col_name, group_value, config)
                         .unwrap_or_else(|| get_hash_fg_color(group_value))
                         .to_string();
                 }
@@ -1317,7 +1325,7 @@ pub fn format_task_list_table(
                         .and_then(|idx| group_values.get(idx))
                         .unwrap_or(&group_values[0]); // Fallback to first if not found
                     let col_name = options.fill_column.as_deref().unwrap_or("");
-                    bg = get_semantic_bg_color(col_name, group_value)
+                    bg = get_semantic_bg_color(col_name, group_value, config)
                         .unwrap_or_else(|| get_hash_bg_color(group_value))
                         .to_string();
                     // Automatically add contrasting foreground for legibility if no color_column was set
@@ -1342,7 +1350,7 @@ pub fn format_task_list_table(
             for row in group_rows {
                 // Get row colors (always compute, but apply selectively)
                 let (fg_color, bg_color, _) = if colors_enabled {
-                    get_row_colors(row, &options.color_column, &options.fill_column, priority_range, due_range)
+                    get_row_colors(row, &options.color_column, &options.fill_column, priority_range, due_range, config)
                 } else {
                     (String::new(), String::new(), false)
                 };
@@ -1438,6 +1446,7 @@ pub fn format_stack_display(items: &[(i64, i32)]) -> String {
 pub fn format_clock_list_table(
     conn: &Connection,
     clock_tasks: &[(usize, Task, Vec<String>)],
+    config: &Config,
 ) -> Result<String> {
     if clock_tasks.is_empty() {
         return Ok("Clock stack is empty.".to_string());
@@ -1532,7 +1541,7 @@ pub fn format_clock_list_table(
         };
         
         let due = if let Some(due_ts) = task.due_ts {
-            format_date(due_ts)
+            format_date(due_ts, config)
         } else {
             String::new()
         };
@@ -1587,6 +1596,7 @@ pub fn format_task_summary(
     annotations: &[crate::models::Annotation],
     sessions: &[crate::models::Session],
     stack_position: Option<(i32, i32)>, // (position, total)
+    config: &Config,
 ) -> Result<String> {
     let mut output = String::new();
     
@@ -1607,8 +1617,8 @@ pub fn format_task_summary(
     output.push_str("Status: ");
     output.push_str(task.status.as_str());
     output.push_str("\n");
-    output.push_str(&format!("Created: {}\n", format_timestamp(task.created_ts)));
-    output.push_str(&format!("Modified: {}\n\n", format_timestamp(task.modified_ts)));
+    output.push_str(&format!("Created: {}\n", format_timestamp(task.created_ts, config)));
+    output.push_str(&format!("Modified: {}\n\n", format_timestamp(task.modified_ts, config)));
     
     // Attributes
     output.push_str("Attributes:\n");
@@ -1626,21 +1636,21 @@ pub fn format_task_summary(
     
     // Due
     if let Some(due_ts) = task.due_ts {
-        output.push_str(&format!("  Due:         {}\n", format_date(due_ts)));
+        output.push_str(&format!("  Due:         {}\n", format_date(due_ts, config)));
     } else {
         output.push_str("  Due:         (none)\n");
     }
     
     // Scheduled
     if let Some(scheduled_ts) = task.scheduled_ts {
-        output.push_str(&format!("  Scheduled:   {}\n", format_date(scheduled_ts)));
+        output.push_str(&format!("  Scheduled:   {}\n", format_date(scheduled_ts, config)));
     } else {
         output.push_str("  Scheduled:   (none)\n");
     }
     
     // Wait
     if let Some(wait_ts) = task.wait_ts {
-        output.push_str(&format!("  Wait:        {}\n", format_date(wait_ts)));
+        output.push_str(&format!("  Wait:        {}\n", format_date(wait_ts, config)));
     } else {
         output.push_str("  Wait:        (none)\n");
     }
@@ -1693,9 +1703,31 @@ pub fn format_task_summary(
         output.push_str(&format!("  Position:    {} of {}\n\n", position + 1, total));
     }
 
+    // Dependencies (upstream and downstream)
+    let task_id = task.id.unwrap_or(0);
+    let upstream = DependencyRepo::get_dependencies(conn, task_id)?;
+    let downstream = DependencyRepo::get_dependents(conn, task_id)?;
+    if !upstream.is_empty() || !downstream.is_empty() {
+        output.push_str("Dependencies:\n");
+        for (label, ids) in [("Depends on:", &upstream), ("Required by:", &downstream)] {
+            for (idx, id) in ids.iter().enumerate() {
+                let label = if idx == 0 { label } else { "" };
+                match TaskRepo::get_by_id(conn, *id)? {
+                    Some(other) => output.push_str(&format!("  {:<13}{} {} [{}]\n",
+                        label, id, other.description, other.status.as_str())),
+                    None => output.push_str(&format!("  {:<13}{}\n", label, id)),
+                }
+            }
+        }
+        if DependencyRepo::is_blocked(conn, task_id)? {
+            output.push_str("  (Blocked until the pending tasks it depends on are finished or closed)\n");
+        }
+        output.push('\n');
+    }
+
     // Priority Score (only for pending tasks)
     if task.status == TaskStatus::Pending {
-        if let Ok(priority) = calculate_priority(task, conn) {
+        if let Ok(priority) = calculate_priority(task, conn, config) {
             output.push_str("Priority:\n");
             output.push_str(&format!("  Score:       {:.1}\n", priority));
             output.push_str("  (Auto-calculated based on due date, allocation remaining, and task age)\n\n");
@@ -1716,7 +1748,7 @@ pub fn format_task_summary(
         output.push_str("  (none)\n");
     } else {
         for (idx, annotation) in annotations.iter().enumerate() {
-            output.push_str(&format!("  {}. {}\n", idx + 1, format_timestamp(annotation.entry_ts)));
+            output.push_str(&format!("  {}. {}\n", idx + 1, format_timestamp(annotation.entry_ts, config)));
             // Format note with indentation for multi-line notes
             for line in annotation.note.lines() {
                 output.push_str(&format!("     {}\n", line));
@@ -1732,9 +1764,9 @@ pub fn format_task_summary(
         output.push_str("  (none)\n");
     } else {
         for (idx, session) in sessions.iter().enumerate() {
-            output.push_str(&format!("  {}. {} - ", idx + 1, format_timestamp(session.start_ts)));
+            output.push_str(&format!("  {}. {} - ", idx + 1, format_timestamp(session.start_ts, config)));
             if let Some(end_ts) = session.end_ts {
-                output.push_str(&format!("{}", format_timestamp(end_ts)));
+                output.push_str(&format!("{}", format_timestamp(end_ts, config)));
                 if let Some(duration) = session.duration_secs() {
                     output.push_str(&format!(" ({})", format_duration(duration)));
                 }
@@ -1767,6 +1799,7 @@ pub fn format_dashboard(
     today_duration: i64,
     overdue_count: usize,
     next_overdue_ts: Option<i64>,
+    config: &Config,
 ) -> Result<String> {
     let mut output = String::new();
     
@@ -1831,7 +1864,7 @@ pub fn format_dashboard(
             };
             
             let due_str = if let Some(due_ts) = task.due_ts {
-                format!(" due={}", format_date(due_ts))
+                format!(" due={}", format_date(due_ts, config))
             } else {
                 "".to_string()
             };
@@ -1884,7 +1917,7 @@ pub fn format_dashboard(
             };
             
             let due_str = if let Some(due_ts) = task.due_ts {
-                format!(" due={}", format_date(due_ts))
+                format!(" due={}", format_date(due_ts, config))
             } else {
                 "".to_string()
             };
@@ -1919,7 +1952,7 @@ pub fn format_dashboard(
     if overdue_count > 0 {
         output.push_str(&format!("{} task(s) overdue\n", overdue_count));
     } else if let Some(next_ts) = next_overdue_ts {
-        output.push_str(&format!("No overdue tasks. Next due: {}\n", format_date(next_ts)));
+        output.push_str(&format!("No overdue tasks. Next due: {}\n", format_date(next_ts, config)));
     } else {
         output.push_str("No overdue tasks. No tasks with due dates.\n");
     }
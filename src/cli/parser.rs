@@ -14,6 +14,7 @@ pub struct ParsedTaskArgs {
     pub allocation: Option<String>,
     pub template: Option<String>,
     pub respawn: Option<String>,
+    pub depends: Option<String>,
     pub tags_add: Vec<String>,
     pub tags_remove: Vec<String>,
     pub udas: HashMap<String, String>,
@@ -74,6 +75,7 @@ const FIELD_NAMES: &[&str] = &[
     "allocation",
     "template",
     "respawn",
+    "depends",
 ];
 
 /// Fields that are read-only (cannot be modified via modify command)
@@ -219,8 +221,11 @@ pub fn parse_task_args(args: Vec<String>) -> Result<ParsedTaskArgs, FieldParseEr
     while i < args.len() {
         let arg = &args[i];
 
-        // Check for flags (--flag)
-        if arg.starts_with("--") {
+        // Check for flags (--flag). The global flags only apply before the description,
+        // so after it they are text (`tatl add fix --explain output`)
+        let is_global_flag = matches!(arg.as_str(), "--explain" | "--no-context" | "--ledger")
+            || arg.starts_with("--ledger=");
+        if arg.starts_with("--") && !is_global_flag {
             // Skip flags for now (will handle --template, --yes, etc. in command handler)
             i += 1;
             continue;
@@ -239,6 +244,7 @@ pub fn parse_task_args(args: Vec<String>) -> Result<ParsedTaskArgs, FieldParseEr
                     "allocation" => parsed.allocation = Some(value),
                     "template" => parsed.template = Some(value),
                     "respawn" => parsed.respawn = Some(value),
+                    "depends" => parsed.depends = Some(value),
                     _ => {
                         // Check if it's a UDA (uda.<key>=<value>)
                         if field.starts_with("uda.") {
@@ -297,9 +303,15 @@ pub fn parse_task_args(args: Vec<String>) -> Result<ParsedTaskArgs, FieldParseEr
         // If not handled as field token, check for tags or treat as description
         if !handled {
             // Check for tag tokens (+tag or -tag)
-            if arg.starts_with('+') || arg.starts_with('-') {
+            if (arg.starts_with('+') || arg.starts_with('-')) && !is_global_flag {
                 // This looks like a tag token
                 if let Some(tag) = parse_tag_token(arg) {
+                    // Removing is allowed so tags stored before virtual tags existed can be dropped
+                    if tag.starts_with('+') && crate::filter::VirtualTag::parse(&tag[1..]).is_some() {
+                        return Err(FieldParseError::InvalidTag {
+                            message: format!("'{}' is a virtual tag derived from the task's state; it can only be used in filters.", tag),
+                        });
+                    }
                     if tag.starts_with('+') {
                         parsed.tags_add.push(tag.strip_prefix('+').unwrap().to_string());
                     } else if tag.starts_with('-') {
@@ -388,6 +400,27 @@ mod tests {
         assert_eq!(parsed.tags_add, vec!["urgent", "important"]);
     }
 
+    #[test]
+    fn test_virtual_tags_are_read_only() {
+        let err = parse_task_args(vec!["fix".to_string(), "+OVERDUE".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("virtual tag"));
+        // Global flags written after a description are text
+        assert_eq!(parse_task_args(vec!["fix".to_string(), "--explain".to_string()]).unwrap().description, vec!["fix", "--explain"]);
+        // Removing is allowed, for tags stored before virtual tags existed
+        assert_eq!(parse_task_args(vec!["-ACTIVE".to_string()]).unwrap().tags_remove, vec!["ACTIVE"]);
+        // Tag names are case-sensitive, so the lower-case ones are ordinary tags
+        assert_eq!(parse_task_args(vec!["+active".to_string()]).unwrap().tags_add, vec!["active"]);
+    }
+
+    #[test]
+    fn test_parse_depends() {
+        let args = vec!["deploy".to_string(), "depends=12,15".to_string()];
+        let parsed = parse_task_args(args).unwrap();
+        assert_eq!(parsed.description, vec!["deploy"]);
+        assert_eq!(parsed.depends, Some("12,15".to_string()));
+        assert_eq!(parse_task_args(vec!["dep=".to_string()]).unwrap().depends, Some("none".to_string()));
+    }
+
     #[test]
     fn test_parse_mixed_order() {
         let args = vec!["project=work".to_string(), "fix".to_string(), "bug".to_string(), "+urgent".to_string()];
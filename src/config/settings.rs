@@ -0,0 +1,572 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{anyhow, Context, Result};
+
+/// A documented configuration key
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKey {
+    pub key: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+/// Every key accepted in `~/.tatl/rc`, apart from ledger profiles (`ledger.<name>`, `ledger.active`)
+/// and UDA type declarations (`uda.<name>.type`)
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey { key: "data.location", default: "", description: "Path of the default ledger (empty: ~/.tatl/ledger.db)" },
+    ConfigKey { key: "backup.keep", default: "10", description: "Snapshots of each kind (manual, pre-migration, pre-restore...) kept in the backup directory" },
+    ConfigKey { key: "session.micro_seconds", default: "30", description: "Sessions shorter than this are micro-sessions, merged or purged when the next session starts within the same window" },
+    ConfigKey { key: "urgency.pending", default: "1.0", description: "Urgency added to every pending task" },
+    ConfigKey { key: "urgency.overdue", default: "15.0", description: "Urgency of an overdue task, reduced by 0.5 per day overdue (minimum 1.0)" },
+    ConfigKey { key: "urgency.due_week", default: "12.0", description: "Urgency of a task due within a week, reduced by 1.0 per day until due" },
+    ConfigKey { key: "urgency.due_month", default: "5.0", description: "Urgency of a task due within a month, reduced by 0.1 per day until due" },
+    ConfigKey { key: "urgency.due_later", default: "2.0", description: "Urgency of a task due later than a month, decaying with distance" },
+    ConfigKey { key: "urgency.alloc_critical", default: "3.0", description: "Urgency when less than 25% of the allocation remains" },
+    ConfigKey { key: "urgency.alloc_low", default: "1.5", description: "Urgency when less than 50% of the allocation remains" },
+    ConfigKey { key: "urgency.alloc_ok", default: "0.5", description: "Urgency when at least 50% of the allocation remains" },
+    ConfigKey { key: "urgency.age", default: "0.1", description: "Urgency per 30 days of age for tasks older than 30 days (at most 2x)" },
+    ConfigKey { key: "color.status.pending", default: "none", description: "Color of the 'pending' status" },
+    ConfigKey { key: "color.status.completed", default: "green", description: "Color of the 'completed' status" },
+    ConfigKey { key: "color.status.closed", default: "bright_black", description: "Color of the 'closed' status" },
+    ConfigKey { key: "color.kanban.proposed", default: "bright_black", description: "Color of the 'proposed' kanban status" },
+    ConfigKey { key: "color.kanban.stalled", default: "yellow", description: "Color of the 'stalled' kanban status" },
+    ConfigKey { key: "color.kanban.blocked", default: "red", description: "Color of the 'blocked' kanban status" },
+    ConfigKey { key: "color.kanban.queued", default: "blue", description: "Color of the 'queued' kanban status" },
+    ConfigKey { key: "color.kanban.external", default: "magenta", description: "Color of the 'external' kanban status" },
+    ConfigKey { key: "color.kanban.done", default: "green", description: "Color of the 'done' kanban status" },
+    ConfigKey { key: "list.columns", default: "status,kanban,project,tags,due,alloc,priority,clock", description: "Columns shown by 'tatl list' after the id and description" },
+    ConfigKey { key: "list.sort", default: "", description: "Default sort columns for 'tatl list' (prefix with - for descending)" },
+    ConfigKey { key: "list.hide", default: "", description: "Columns hidden by default in 'tatl list'" },
+    ConfigKey { key: "list.virtual_tags", default: "false", description: "Show virtual tags (+OVERDUE, +ACTIVE...) after the tags in the Tags column of 'tatl list'" },
+    ConfigKey { key: "date.format", default: "%Y-%m-%d", description: "strftime format for dates" },
+    ConfigKey { key: "datetime.format", default: "%Y-%m-%d %H:%M:%S", description: "strftime format for timestamps" },
+];
+
+/// Column names accepted in the `list.*` keys
+const LIST_COLUMNS: &[&str] = &[
+    "id", "q", "queue", "description", "desc", "kanban", "project", "proj", "tags", "tag",
+    "due", "alloc", "allocation", "priority", "prio", "pri", "clock", "status",
+];
+
+/// Terminal colors that can be assigned in `color.*` keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    None,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    const ALL: &'static [(&'static str, Color)] = &[
+        ("none", Color::None),
+        ("black", Color::Black),
+        ("red", Color::Red),
+        ("green", Color::Green),
+        ("yellow", Color::Yellow),
+        ("blue", Color::Blue),
+        ("magenta", Color::Magenta),
+        ("cyan", Color::Cyan),
+        ("white", Color::White),
+        ("bright_black", Color::BrightBlack),
+        ("bright_red", Color::BrightRed),
+        ("bright_green", Color::BrightGreen),
+        ("bright_yellow", Color::BrightYellow),
+        ("bright_blue", Color::BrightBlue),
+        ("bright_magenta", Color::BrightMagenta),
+        ("bright_cyan", Color::BrightCyan),
+        ("bright_white", Color::BrightWhite),
+    ];
+
+    pub fn parse(s: &str) -> Option<Color> {
+        let s = s.trim().to_lowercase().replace('-', "_");
+        Self::ALL.iter().find(|(name, _)| *name == s).map(|(_, c)| *c)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::ALL.iter().find(|(_, c)| c == self).map(|(name, _)| *name).unwrap_or("none")
+    }
+
+    /// ANSI foreground escape code (None for the terminal default)
+    pub fn fg_code(&self) -> Option<&'static str> {
+        match self {
+            Color::None => None,
+            Color::Black => Some("\x1b[30m"),
+            Color::Red => Some("\x1b[31m"),
+            Color::Green => Some("\x1b[32m"),
+            Color::Yellow => Some("\x1b[33m"),
+            Color::Blue => Some("\x1b[34m"),
+            Color::Magenta => Some("\x1b[35m"),
+            Color::Cyan => Some("\x1b[36m"),
+            Color::White => Some("\x1b[37m"),
+            Color::BrightBlack => Some("\x1b[90m"),
+            Color::BrightRed => Some("\x1b[91m"),
+            Color::BrightGreen => Some("\x1b[92m"),
+            Color::BrightYellow => Some("\x1b[93m"),
+            Color::BrightBlue => Some("\x1b[94m"),
+            Color::BrightMagenta => Some("\x1b[95m"),
+            Color::BrightCyan => Some("\x1b[96m"),
+            Color::BrightWhite => Some("\x1b[97m"),
+        }
+    }
+
+    /// ANSI background escape code (None for the terminal default)
+    pub fn bg_code(&self) -> Option<&'static str> {
+        match self {
+            Color::None => None,
+            Color::Black => Some("\x1b[40m"),
+            Color::Red => Some("\x1b[41m"),
+            Color::Green => Some("\x1b[42m"),
+            Color::Yellow => Some("\x1b[43m"),
+            Color::Blue => Some("\x1b[44m"),
+            Color::Magenta => Some("\x1b[45m"),
+            Color::Cyan => Some("\x1b[46m"),
+            Color::White => Some("\x1b[47m"),
+            Color::BrightBlack => Some("\x1b[100m"),
+            Color::BrightRed => Some("\x1b[101m"),
+            Color::BrightGreen => Some("\x1b[102m"),
+            Color::BrightYellow => Some("\x1b[103m"),
+            Color::BrightBlue => Some("\x1b[104m"),
+            Color::BrightMagenta => Some("\x1b[105m"),
+            Color::BrightCyan => Some("\x1b[106m"),
+            Color::BrightWhite => Some("\x1b[107m"),
+        }
+    }
+}
+
+/// Declared type of a user-defined attribute, used when filtering on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UdaType {
+    String,
+    Numeric,
+    Date,
+}
+
+impl UdaType {
+    const ALL: &'static [(&'static str, UdaType)] = &[
+        ("string", UdaType::String),
+        ("numeric", UdaType::Numeric),
+        ("date", UdaType::Date),
+    ];
+
+    pub fn parse(s: &str) -> Option<UdaType> {
+        let s = s.trim().to_lowercase();
+        Self::ALL.iter().find(|(name, _)| *name == s).map(|(_, t)| *t)
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::ALL.iter().find(|(_, t)| t == self).map(|(name, _)| *name).unwrap_or("string")
+    }
+}
+
+/// Snapshot rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupConfig {
+    pub keep: usize,
+}
+
+/// Micro-session handling
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionConfig {
+    pub micro_seconds: i64,
+}
+
+/// Urgency coefficients used by the priority calculation
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrgencyConfig {
+    pub pending: f64,
+    pub overdue: f64,
+    pub due_week: f64,
+    pub due_month: f64,
+    pub due_later: f64,
+    pub alloc_critical: f64,
+    pub alloc_low: f64,
+    pub alloc_ok: f64,
+    pub age: f64,
+}
+
+/// Semantic colors for the status and kanban columns, keyed by value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorConfig {
+    pub status: BTreeMap<String, Color>,
+    pub kanban: BTreeMap<String, Color>,
+}
+
+impl ColorConfig {
+    /// Color configured for a column value (None when the column has no semantic colors)
+    pub fn for_value(&self, column: &str, value: &str) -> Option<Color> {
+        match column {
+            "status" => self.status.get(value).copied(),
+            "kanban" => self.kanban.get(value).copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Defaults for `tatl list`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListConfig {
+    pub columns: Vec<String>,
+    pub sort: Vec<String>,
+    pub hide: Vec<String>,
+    pub virtual_tags: bool,
+}
+
+/// Date display formats
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateConfig {
+    pub date_format: String,
+    pub datetime_format: String,
+}
+
+/// Typed contents of `~/.tatl/rc`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub data_location: Option<String>,
+    /// Ledger profile entries (`ledger.<name>` and `ledger.active`), validated by the ledger commands
+    pub ledgers: BTreeMap<String, String>,
+    /// Declared UDA types (`uda.<name>.type`), keyed by name without the `uda.` prefix
+    pub udas: BTreeMap<String, UdaType>,
+    pub backup: BackupConfig,
+    pub session: SessionConfig,
+    pub urgency: UrgencyConfig,
+    pub colors: ColorConfig,
+    pub list: ListConfig,
+    pub dates: DateConfig,
+    /// Keys in the rc file that tatl does not know; they are skipped when loading
+    pub ignored_keys: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut config = Config {
+            data_location: None,
+            ledgers: BTreeMap::new(),
+            udas: BTreeMap::new(),
+            backup: BackupConfig { keep: 0 },
+            session: SessionConfig { micro_seconds: 0 },
+            urgency: UrgencyConfig {
+                pending: 0.0, overdue: 0.0, due_week: 0.0, due_month: 0.0, due_later: 0.0,
+                alloc_critical: 0.0, alloc_low: 0.0, alloc_ok: 0.0, age: 0.0,
+            },
+            colors: ColorConfig { status: BTreeMap::new(), kanban: BTreeMap::new() },
+            list: ListConfig { columns: Vec::new(), sort: Vec::new(), hide: Vec::new(), virtual_tags: false },
+            dates: DateConfig { date_format: String::new(), datetime_format: String::new() },
+            ignored_keys: Vec::new(),
+        };
+        for key in CONFIG_KEYS {
+            config.set(key.key, key.default).expect("built-in configuration defaults are valid");
+        }
+        config
+    }
+}
+
+fn invalid(key: &str, expected: &str, value: &str) -> anyhow::Error {
+    anyhow!("Invalid value for '{}': expected {}, got '{}'", key, expected, value)
+}
+
+fn parse_int(key: &str, value: &str, min: i64) -> Result<i64> {
+    match value.parse::<i64>() {
+        Ok(n) if n >= min => Ok(n),
+        _ => Err(invalid(key, &format!("an integer >= {}", min), value)),
+    }
+}
+
+fn parse_float(key: &str, value: &str) -> Result<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err(invalid(key, "a number", value)),
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(invalid(key, "true or false", value)),
+    }
+}
+
+fn parse_color(key: &str, value: &str) -> Result<Color> {
+    Color::parse(value).ok_or_else(|| {
+        let names: Vec<&str> = Color::ALL.iter().map(|(name, _)| *name).collect();
+        invalid(key, &format!("one of {}", names.join(", ")), value)
+    })
+}
+
+fn parse_columns(key: &str, value: &str, allow_descending: bool) -> Result<Vec<String>> {
+    let mut columns = Vec::new();
+    for column in value.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let name = if allow_descending { column.strip_prefix('-').unwrap_or(column) } else { column };
+        if !LIST_COLUMNS.contains(&name.to_lowercase().as_str()) {
+            return Err(invalid(key, &format!("comma-separated columns ({})", LIST_COLUMNS.join(", ")), column));
+        }
+        columns.push(column.to_string());
+    }
+    Ok(columns)
+}
+
+fn parse_format(key: &str, value: &str) -> Result<String> {
+    use chrono::format::{Item, StrftimeItems};
+    if value.is_empty() || StrftimeItems::new(value).any(|item| matches!(item, Item::Error)) {
+        return Err(invalid(key, "a strftime format such as %Y-%m-%d", value));
+    }
+    Ok(value.to_string())
+}
+
+fn parse_uda_type(key: &str, value: &str) -> Result<UdaType> {
+    UdaType::parse(value).ok_or_else(|| {
+        let names: Vec<&str> = UdaType::ALL.iter().map(|(name, _)| *name).collect();
+        invalid(key, &format!("one of {}", names.join(", ")), value)
+    })
+}
+
+fn format_float(n: f64) -> String {
+    if n.fract() == 0.0 { format!("{:.1}", n) } else { n.to_string() }
+}
+
+/// Ledger profile keys are managed by `tatl ledger` but may also be set here
+fn is_ledger_key(key: &str) -> bool {
+    key.strip_prefix("ledger.").is_some_and(|name| !name.is_empty())
+}
+
+/// UDA name declared by a `uda.<name>.type` key
+fn uda_type_name(key: &str) -> Option<&str> {
+    key.strip_prefix("uda.")?.strip_suffix(".type").filter(|name| !name.is_empty())
+}
+
+impl Config {
+    /// Documentation for a key
+    pub fn key_info(key: &str) -> Option<&'static ConfigKey> {
+        CONFIG_KEYS.iter().find(|k| k.key == key)
+    }
+
+    /// Whether `key` can be set in the rc file
+    pub fn is_known_key(key: &str) -> bool {
+        Self::key_info(key).is_some() || is_ledger_key(key) || uda_type_name(key).is_some()
+    }
+
+    /// Parse rc file contents. Errors name the line and key. Unknown keys are
+    /// collected in `ignored_keys` rather than rejected, so that an rc file
+    /// written for another version keeps working.
+    pub fn parse(text: &str) -> Result<Config> {
+        let mut config = Config::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected key=value, got '{}'", n + 1, line))?;
+            if !Self::is_known_key(key.trim()) {
+                config.ignored_keys.push(key.trim().to_string());
+                continue;
+            }
+            config.set(key.trim(), value.trim())
+                .with_context(|| format!("line {}", n + 1))?;
+        }
+        Ok(config)
+    }
+
+    /// Load the rc file at `path` (missing file: defaults)
+    pub fn load_from(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read configuration: {}", path.display()))?;
+        Self::parse(&text)
+            .with_context(|| format!("Invalid configuration in {}", path.display()))
+    }
+
+    /// Set a key from its string value, validating it
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "data.location" => {
+                self.data_location = if value.is_empty() { None } else { Some(value.to_string()) };
+            }
+            "backup.keep" => self.backup.keep = parse_int(key, value, 1)? as usize,
+            "session.micro_seconds" => self.session.micro_seconds = parse_int(key, value, 0)?,
+            "urgency.pending" => self.urgency.pending = parse_float(key, value)?,
+            "urgency.overdue" => self.urgency.overdue = parse_float(key, value)?,
+            "urgency.due_week" => self.urgency.due_week = parse_float(key, value)?,
+            "urgency.due_month" => self.urgency.due_month = parse_float(key, value)?,
+            "urgency.due_later" => self.urgency.due_later = parse_float(key, value)?,
+            "urgency.alloc_critical" => self.urgency.alloc_critical = parse_float(key, value)?,
+            "urgency.alloc_low" => self.urgency.alloc_low = parse_float(key, value)?,
+            "urgency.alloc_ok" => self.urgency.alloc_ok = parse_float(key, value)?,
+            "urgency.age" => self.urgency.age = parse_float(key, value)?,
+            "list.columns" => self.list.columns = parse_columns(key, value, false)?,
+            "list.sort" => self.list.sort = parse_columns(key, value, true)?,
+            "list.hide" => self.list.hide = parse_columns(key, value, false)?,
+            "list.virtual_tags" => self.list.virtual_tags = parse_bool(key, value)?,
+            "date.format" => self.dates.date_format = parse_format(key, value)?,
+            "datetime.format" => self.dates.datetime_format = parse_format(key, value)?,
+            _ if Self::key_info(key).is_some() && key.starts_with("color.") => {
+                let color = parse_color(key, value)?;
+                let (group, name) = key["color.".len()..].split_once('.').unwrap();
+                let map = if group == "status" { &mut self.colors.status } else { &mut self.colors.kanban };
+                map.insert(name.to_string(), color);
+            }
+            _ if is_ledger_key(key) => {
+                self.ledgers.insert(key.to_string(), value.to_string());
+            }
+            _ if uda_type_name(key).is_some() => {
+                let uda_type = parse_uda_type(key, value)?;
+                self.udas.insert(uda_type_name(key).unwrap().to_string(), uda_type);
+            }
+            _ => return Err(anyhow!("Unknown configuration key '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Current value of a key as it would be written in the rc file
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "data.location" => self.data_location.clone().unwrap_or_default(),
+            "backup.keep" => self.backup.keep.to_string(),
+            "session.micro_seconds" => self.session.micro_seconds.to_string(),
+            "urgency.pending" => format_float(self.urgency.pending),
+            "urgency.overdue" => format_float(self.urgency.overdue),
+            "urgency.due_week" => format_float(self.urgency.due_week),
+            "urgency.due_month" => format_float(self.urgency.due_month),
+            "urgency.due_later" => format_float(self.urgency.due_later),
+            "urgency.alloc_critical" => format_float(self.urgency.alloc_critical),
+            "urgency.alloc_low" => format_float(self.urgency.alloc_low),
+            "urgency.alloc_ok" => format_float(self.urgency.alloc_ok),
+            "urgency.age" => format_float(self.urgency.age),
+            "list.columns" => self.list.columns.join(","),
+            "list.sort" => self.list.sort.join(","),
+            "list.hide" => self.list.hide.join(","),
+            "list.virtual_tags" => self.list.virtual_tags.to_string(),
+            "date.format" => self.dates.date_format.clone(),
+            "datetime.format" => self.dates.datetime_format.clone(),
+            _ if Self::key_info(key).is_some() && key.starts_with("color.") => {
+                let (column, value) = key["color.".len()..].split_once('.').unwrap();
+                self.colors.for_value(column, value).unwrap_or(Color::None).name().to_string()
+            }
+            _ if is_ledger_key(key) => return self.ledgers.get(key).cloned(),
+            _ if uda_type_name(key).is_some() => {
+                return self.udas.get(uda_type_name(key).unwrap()).map(|t| t.name().to_string());
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Return rc contents with `key` set to `value`, replacing an existing entry
+/// in place (comments and other lines are kept)
+pub fn set_config_value(text: &str, key: &str, value: &str) -> String {
+    let entry = format!("{}={}", key, value);
+    let mut replaced = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let is_key = line.split_once('=').map(|(k, _)| k.trim()) == Some(key) && !line.trim_start().starts_with('#');
+        if is_key {
+            if !replaced {
+                lines.push(entry.clone());
+                replaced = true;
+            }
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    if !replaced {
+        lines.push(entry);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Return rc contents with every entry for `key` removed
+pub fn unset_config_value(text: &str, key: &str) -> String {
+    let lines: Vec<&str> = text.lines()
+        .filter(|line| line.trim_start().starts_with('#') || line.split_once('=').map(|(k, _)| k.trim()) != Some(key))
+        .collect();
+    if lines.is_empty() {
+        String::new()
+    } else {
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_round_trip() {
+        let config = Config::default();
+        for key in CONFIG_KEYS {
+            assert_eq!(config.get(key.key).as_deref(), Some(key.default), "{}", key.key);
+        }
+        assert_eq!(config.session.micro_seconds, 30);
+        assert_eq!(config.backup.keep, 10);
+        assert_eq!(config.colors.for_value("kanban", "queued"), Some(Color::Blue));
+        assert_eq!(config.list.columns.len(), 8);
+    }
+
+    #[test]
+    fn test_parse_rc() {
+        let config = Config::parse("# comment\ndata.location=~/x.db\n\nsession.micro_seconds = 10\nurgency.overdue=20\ncolor.kanban.queued=bright-cyan\nlist.sort=-priority,due\nledger.work=/w.db\n").unwrap();
+        assert_eq!(config.data_location.as_deref(), Some("~/x.db"));
+        assert_eq!(config.session.micro_seconds, 10);
+        assert_eq!(config.urgency.overdue, 20.0);
+        assert_eq!(config.colors.for_value("kanban", "queued"), Some(Color::BrightCyan));
+        assert_eq!(config.list.sort, vec!["-priority", "due"]);
+        assert_eq!(config.get("ledger.work").as_deref(), Some("/w.db"));
+    }
+
+    #[test]
+    fn test_uda_types() {
+        let config = Config::parse("uda.points.type=numeric\nuda.deadline.type=Date\n").unwrap();
+        assert_eq!(config.udas.get("points"), Some(&UdaType::Numeric));
+        assert_eq!(config.get("uda.deadline.type").as_deref(), Some("date"));
+        assert_eq!(config.get("uda.client.type"), None);
+        assert!(Config::is_known_key("uda.client.type"));
+        assert!(!Config::is_known_key("uda..type"));
+
+        let err = format!("{:#}", Config::parse("uda.points.type=integer\n").unwrap_err());
+        assert!(err.contains("'uda.points.type'") && err.contains("numeric"), "{}", err);
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let config = Config::parse("data.location=a.db\nsession.micro_secs=5\n").unwrap();
+        assert_eq!(config.ignored_keys, vec!["session.micro_secs"]);
+        assert!(Config::default().set("session.micro_secs", "5").unwrap_err().to_string()
+            .contains("Unknown configuration key 'session.micro_secs'"));
+
+        let err = format!("{:#}", Config::parse("session.micro_seconds=-1\n").unwrap_err());
+        assert!(err.contains("'session.micro_seconds'") && err.contains("'-1'"), "{}", err);
+
+        let mut config = Config::default();
+        assert!(config.set("color.status.completed", "chartreuse").unwrap_err().to_string().contains("color.status.completed"));
+        assert!(config.set("list.hide", "status,nope").unwrap_err().to_string().contains("'nope'"));
+        assert!(config.set("date.format", "%Q").is_err());
+        assert!(config.set("list.virtual_tags", "maybe").unwrap_err().to_string().contains("true or false"));
+        config.set("list.virtual_tags", "yes").unwrap();
+        assert_eq!(config.get("list.virtual_tags").as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn test_rewrite_rc() {
+        let text = "# mine\ndata.location=a.db\nurgency.age=0.2\n";
+        assert_eq!(set_config_value(text, "urgency.age", "0.5"), "# mine\ndata.location=a.db\nurgency.age=0.5\n");
+        assert_eq!(set_config_value("", "list.sort", "due"), "list.sort=due\n");
+        assert_eq!(unset_config_value(text, "urgency.age"), "# mine\ndata.location=a.db\n");
+    }
+}
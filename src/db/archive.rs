@@ -0,0 +1,359 @@
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use crate::db::migrations::{MigrationManager, CURRENT_VERSION};
+use crate::repo::EventRepo;
+
+/// Statuses of tasks that can be moved to the archive
+const ARCHIVABLE_STATUSES: &str = "'completed','closed','deleted'";
+
+/// Rows moved by one archive run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    pub tasks: usize,
+    pub sessions: usize,
+    pub annotations: usize,
+    pub events: usize,
+}
+
+/// Cold storage for old finished work. The archive is a ledger file with the
+/// same schema, attached to the live ledger while tasks are moved into it.
+pub struct ArchiveManager;
+
+impl ArchiveManager {
+    /// Archive file kept next to a ledger (`ledger.db` -> `ledger.archive.db`)
+    pub fn path_for(db_path: &Path) -> PathBuf {
+        let stem = db_path.file_stem().and_then(|s| s.to_str()).unwrap_or("ledger");
+        db_path.with_file_name(format!("{}.archive.db", stem))
+    }
+
+    /// Ids of completed, closed and deleted tasks last modified before `before_ts`.
+    /// Tasks with a running session are never archived.
+    pub fn candidates(conn: &Connection, before_ts: i64) -> Result<Vec<i64>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM main.tasks
+             WHERE status IN ({}) AND modified_ts < ?1
+               AND id NOT IN (SELECT task_id FROM main.sessions WHERE end_ts IS NULL)
+             ORDER BY id",
+            ARCHIVABLE_STATUSES
+        ))?;
+        let ids = stmt.query_map([before_ts], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Move the given tasks, with their tags, sessions, annotations, externals
+    /// and events, from the ledger into the archive at `archive_path`.
+    /// Task ids are kept unless the archive already uses them. Dependencies
+    /// between archived tasks move with them; a dependency on the other side
+    /// of the archive is dropped and recorded as a `dependency_removed` event.
+    pub fn archive(conn: &Connection, archive_path: &Path, task_ids: &[i64]) -> Result<ArchiveSummary> {
+        let mut summary = ArchiveSummary::default();
+        if task_ids.is_empty() {
+            return Ok(summary);
+        }
+
+        // Create the archive, or bring an older one up to the current schema
+        {
+            let archive = Connection::open(archive_path)
+                .with_context(|| format!("Failed to open archive: {}", archive_path.display()))?;
+            MigrationManager::initialize(&archive)
+                .context("Failed to initialize archive schema")?;
+        }
+
+        conn.execute("ATTACH DATABASE ?1 AS archive", [archive_path.to_string_lossy()])
+            .with_context(|| format!("Failed to attach archive: {}", archive_path.display()))?;
+        let result = Self::move_tasks(conn, task_ids, &mut summary);
+        conn.execute("DETACH DATABASE archive", [])?;
+        result?;
+        Ok(summary)
+    }
+
+    fn move_tasks(conn: &Connection, task_ids: &[i64], summary: &mut ArchiveSummary) -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        // Deleting a task cascades to its dependencies, so read them first
+        let dependencies = Self::split_dependencies(&tx, task_ids)?;
+        let mut archived_ids: HashMap<i64, i64> = HashMap::new();
+        for &task_id in task_ids {
+            let archived_id = Self::move_task(&tx, task_id, summary)
+                .with_context(|| format!("Failed to archive task {}", task_id))?;
+            archived_ids.insert(task_id, archived_id);
+        }
+        for (task_id, depends_on_id, created_ts) in dependencies {
+            tx.execute(
+                "INSERT OR IGNORE INTO archive.task_dependencies (task_id, depends_on_id, created_ts) VALUES (?1, ?2, ?3)",
+                rusqlite::params![archived_ids[&task_id], archived_ids[&depends_on_id], created_ts],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Dependencies with both tasks among `task_ids`, which move to the archive.
+    /// Those crossing into the live ledger are recorded as removed on the
+    /// dependent task, before its events are copied if it is archived too.
+    fn split_dependencies(conn: &Connection, task_ids: &[i64]) -> Result<Vec<(i64, i64, i64)>> {
+        let mut stmt = conn.prepare(
+            "SELECT task_id, depends_on_id, created_ts FROM main.task_dependencies
+             WHERE task_id = ?1 OR depends_on_id = ?1")?;
+        let mut dependencies = Vec::new();
+        for &task_id in task_ids {
+            let rows = stmt.query_map([task_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            dependencies.extend(rows);
+        }
+        dependencies.sort_unstable();
+        dependencies.dedup();
+
+        let mut kept = Vec::new();
+        for (dependent, depends_on, created_ts) in dependencies {
+            if task_ids.contains(&dependent) && task_ids.contains(&depends_on) {
+                kept.push((dependent, depends_on, created_ts));
+            } else {
+                EventRepo::record_dependency_removed(conn, dependent, depends_on)?;
+            }
+        }
+        Ok(kept)
+    }
+
+    fn move_task(conn: &Connection, task_id: i64, summary: &mut ArchiveSummary) -> Result<i64> {
+        // Projects are matched by name: archive and ledger number them independently
+        let project: Option<(String, bool, i64, i64)> = conn.query_row(
+            "SELECT p.name, p.is_archived, p.created_ts, p.modified_ts
+             FROM main.tasks t JOIN main.projects p ON p.id = t.project_id WHERE t.id = ?1",
+            [task_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).optional()?;
+        let archive_project_id = match project {
+            Some((name, is_archived, created_ts, modified_ts)) => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO archive.projects (name, is_archived, created_ts, modified_ts)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![name, is_archived, created_ts, modified_ts],
+                )?;
+                Some(conn.query_row("SELECT id FROM archive.projects WHERE name = ?1", [&name], |row| row.get::<_, i64>(0))?)
+            }
+            None => None,
+        };
+
+        let id_taken: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM archive.tasks WHERE id = ?1", [task_id], |row| row.get(0))?;
+        conn.execute(
+            "INSERT INTO archive.tasks (id, uuid, description, status, project_id, due_ts, scheduled_ts,
+                    wait_ts, alloc_secs, template, respawn, udas_json, created_ts, modified_ts)
+             SELECT ?2, uuid, description, status, ?3, due_ts, scheduled_ts,
+                    wait_ts, alloc_secs, template, respawn, udas_json, created_ts, modified_ts
+             FROM main.tasks WHERE id = ?1",
+            rusqlite::params![task_id, if id_taken { None } else { Some(task_id) }, archive_project_id],
+        )?;
+        let archived_id = conn.last_insert_rowid();
+
+        conn.execute(
+            "INSERT INTO archive.task_tags (task_id, tag) SELECT ?2, tag FROM main.task_tags WHERE task_id = ?1",
+            [task_id, archived_id],
+        )?;
+
+        let mut session_ids: HashMap<i64, i64> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT id FROM main.sessions WHERE task_id = ?1 ORDER BY id")?;
+        let sessions = stmt.query_map([task_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        for session_id in sessions {
+            conn.execute(
+                "INSERT INTO archive.sessions (task_id, start_ts, end_ts, created_ts)
+                 SELECT ?2, start_ts, end_ts, created_ts FROM main.sessions WHERE id = ?1",
+                [session_id, archived_id],
+            )?;
+            session_ids.insert(session_id, conn.last_insert_rowid());
+        }
+        summary.sessions += session_ids.len();
+
+        let mut stmt = conn.prepare(
+            "SELECT session_id, note, entry_ts, created_ts FROM main.task_annotations WHERE task_id = ?1 ORDER BY id")?;
+        let annotations = stmt.query_map([task_id], |row| {
+            Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+        for (session_id, note, entry_ts, created_ts) in &annotations {
+            conn.execute(
+                "INSERT INTO archive.task_annotations (task_id, session_id, note, entry_ts, created_ts)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![archived_id, session_id.and_then(|id| session_ids.get(&id)), note, entry_ts, created_ts],
+            )?;
+        }
+        summary.annotations += annotations.len();
+
+        let mut external_ids: HashMap<i64, i64> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT id FROM main.externals WHERE task_id = ?1 ORDER BY id")?;
+        let externals = stmt.query_map([task_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        for external_id in externals {
+            conn.execute(
+                "INSERT INTO archive.externals (task_id, recipient, request, sent_ts, returned_ts, created_ts, modified_ts)
+                 SELECT ?2, recipient, request, sent_ts, returned_ts, created_ts, modified_ts
+                 FROM main.externals WHERE id = ?1",
+                [external_id, archived_id],
+            )?;
+            external_ids.insert(external_id, conn.last_insert_rowid());
+        }
+
+        summary.events += conn.execute(
+            "INSERT INTO archive.task_events (task_id, ts, event_type, payload_json)
+             SELECT ?2, ts, event_type, payload_json FROM main.task_events WHERE task_id = ?1 ORDER BY id",
+            [task_id, archived_id],
+        )?;
+
+        // Session and external audit events follow their task
+        let mut stmt = conn.prepare(
+            "SELECT entity_type, entity_id, ts, event_type, payload_json FROM main.entity_events
+             WHERE task_id = ?1 ORDER BY id")?;
+        let entity_events = stmt.query_map([task_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?, row.get::<_, String>(4)?))
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+        for (entity_type, entity_id, ts, event_type, payload_json) in &entity_events {
+            let ids = if entity_type == "session" { &session_ids } else { &external_ids };
+            // Events of rows that are already gone have no archive id; a ledger id would name a foreign row
+            let Some(archived_entity_id) = ids.get(entity_id) else {
+                continue;
+            };
+            conn.execute(
+                "INSERT INTO archive.entity_events (entity_type, entity_id, task_id, ts, event_type, payload_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![entity_type, archived_entity_id, archived_id, ts, event_type, payload_json],
+            )?;
+            summary.events += 1;
+        }
+        conn.execute("DELETE FROM main.entity_events WHERE task_id = ?1", [task_id])?;
+
+        // Keep the uuid so that sync does not bring the task back
+        conn.execute(
+            "INSERT OR REPLACE INTO main.task_tombstones (uuid, reason, ts)
+             SELECT uuid, 'archived', ?2 FROM main.tasks WHERE id = ?1",
+            rusqlite::params![task_id, chrono::Utc::now().timestamp()],
+        )?;
+
+        // CASCADE removes the task's rows from the ledger
+        conn.execute("DELETE FROM main.tasks WHERE id = ?1", [task_id])?;
+        // Journaled commands that touched the task can no longer be undone
+        conn.execute(
+            "DELETE FROM main.undo_ops WHERE id IN (SELECT op_id FROM main.undo_journal WHERE task_id = ?1)",
+            [task_id],
+        )?;
+
+        summary.tasks += 1;
+        Ok(archived_id)
+    }
+
+    /// Open an existing archive for reading alongside the ledger
+    pub fn open(archive_path: &Path) -> Result<Connection> {
+        if !archive_path.exists() {
+            return Err(anyhow!("No archive found at {}", archive_path.display()));
+        }
+        let conn = Connection::open_with_flags(archive_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open archive: {}", archive_path.display()))?;
+        let version = MigrationManager::stored_version(&conn)
+            .with_context(|| format!("{} is not a tatl ledger", archive_path.display()))?;
+        if version != CURRENT_VERSION {
+            return Err(anyhow!(
+                "Archive schema v{} does not match the ledger (v{}); run 'tatl archive' to upgrade it",
+                version, CURRENT_VERSION
+            ));
+        }
+        Ok(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::{AnnotationRepo, DependencyRepo, ProjectRepo, SessionRepo, TaskRepo};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_moves_finished_tasks() {
+        let dir = TempDir::new().unwrap();
+        let conn = Connection::open(dir.path().join("ledger.db")).unwrap();
+        MigrationManager::initialize(&conn).unwrap();
+        let project = ProjectRepo::create(&conn, "work").unwrap();
+        let done = TaskRepo::create(&conn, "Done", project.id).unwrap().id.unwrap();
+        let open = TaskRepo::create(&conn, "Open", None).unwrap().id.unwrap();
+        let session = SessionRepo::create_closed(&conn, done, 100, 200).unwrap();
+        AnnotationRepo::create(&conn, done, "Note".to_string(), session.id).unwrap();
+        TaskRepo::complete(&conn, done).unwrap();
+
+        let future = chrono::Utc::now().timestamp() + 60;
+        let ids = ArchiveManager::candidates(&conn, future).unwrap();
+        assert_eq!(ids, vec![done]);
+
+        let archive_path = ArchiveManager::path_for(&dir.path().join("ledger.db"));
+        assert!(archive_path.ends_with("ledger.archive.db"));
+        let summary = ArchiveManager::archive(&conn, &archive_path, &ids).unwrap();
+        assert_eq!((summary.tasks, summary.sessions, summary.annotations), (1, 1, 1));
+
+        assert!(TaskRepo::get_by_id(&conn, done).unwrap().is_none());
+        assert!(TaskRepo::get_by_id(&conn, open).unwrap().is_some());
+        let tombstones: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM task_tombstones WHERE reason = 'archived'", [], |r| r.get(0)).unwrap();
+        assert_eq!(tombstones, 1);
+
+        let archive = ArchiveManager::open(&archive_path).unwrap();
+        let task = TaskRepo::get_by_id(&archive, done).unwrap().unwrap();
+        assert_eq!(task.description, "Done");
+        let archived_project = ProjectRepo::get_by_id(&archive, task.project_id.unwrap()).unwrap().unwrap();
+        assert_eq!(archived_project.name, "work");
+        let sessions = SessionRepo::get_by_task(&archive, done).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let annotations = AnnotationRepo::get_by_task(&archive, done).unwrap();
+        assert_eq!(annotations[0].session_id, sessions[0].id);
+    }
+
+    #[test]
+    fn test_archive_keeps_dependencies_or_records_their_removal() {
+        let dir = TempDir::new().unwrap();
+        let conn = Connection::open(dir.path().join("ledger.db")).unwrap();
+        MigrationManager::initialize(&conn).unwrap();
+        let design = TaskRepo::create(&conn, "Design", None).unwrap().id.unwrap();
+        let build = TaskRepo::create(&conn, "Build", None).unwrap().id.unwrap();
+        let ship = TaskRepo::create(&conn, "Ship", None).unwrap().id.unwrap();
+        DependencyRepo::set(&conn, build, &[design]).unwrap();
+        DependencyRepo::set(&conn, ship, &[design, build]).unwrap();
+        TaskRepo::complete(&conn, design).unwrap();
+        TaskRepo::complete(&conn, build).unwrap();
+
+        let archive_path = ArchiveManager::path_for(&dir.path().join("ledger.db"));
+        ArchiveManager::archive(&conn, &archive_path, &[design, build]).unwrap();
+
+        assert!(DependencyRepo::get_dependencies(&conn, ship).unwrap().is_empty());
+        let removed: Vec<i64> = EventRepo::get_by_task(&conn, ship, None).unwrap().iter()
+            .filter(|e| e.event_type == "dependency_removed")
+            .filter_map(|e| e.payload["depends_on"].as_i64())
+            .collect();
+        assert_eq!(removed, vec![design, build]);
+
+        let archive = ArchiveManager::open(&archive_path).unwrap();
+        assert_eq!(DependencyRepo::get_dependencies(&archive, build).unwrap(), vec![design]);
+    }
+
+    #[test]
+    fn test_archive_drops_events_of_deleted_sessions() {
+        let dir = TempDir::new().unwrap();
+        let conn = Connection::open(dir.path().join("ledger.db")).unwrap();
+        MigrationManager::initialize(&conn).unwrap();
+        let done = TaskRepo::create(&conn, "Done", None).unwrap().id.unwrap();
+        SessionRepo::create_closed(&conn, done, 100, 200).unwrap();
+        let deleted = SessionRepo::create_closed(&conn, done, 300, 400).unwrap().id.unwrap();
+        SessionRepo::delete(&conn, deleted).unwrap();
+        TaskRepo::complete(&conn, done).unwrap();
+
+        let archive_path = ArchiveManager::path_for(&dir.path().join("ledger.db"));
+        ArchiveManager::archive(&conn, &archive_path, &[done]).unwrap();
+
+        let archive = ArchiveManager::open(&archive_path).unwrap();
+        let kept = SessionRepo::get_by_task(&archive, done).unwrap()[0].id.unwrap();
+        let entity_ids: Vec<i64> = archive.prepare("SELECT DISTINCT entity_id FROM entity_events WHERE entity_type = 'session'")
+            .unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(entity_ids, vec![kept]);
+    }
+}
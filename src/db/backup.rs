@@ -0,0 +1,298 @@
+use rusqlite::{Connection, OpenFlags};
+use rusqlite::backup::Backup;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{anyhow, Context, Result};
+use crate::db::migrations::{MigrationManager, CURRENT_VERSION};
+
+/// Number of snapshots of each kind kept in the backup directory by default
+pub const DEFAULT_SNAPSHOT_KEEP: usize = 10;
+
+/// Kind of snapshots taken by `tatl backup` (no label)
+pub const MANUAL_SNAPSHOT_KIND: &str = "manual";
+
+/// Label prefix of the snapshots taken before migrations; the schema version that
+/// follows it is not part of the kind
+const MIGRATION_KIND: &str = "pre-migration";
+
+/// Pages copied per backup step; the source is unlocked between steps so the
+/// ledger stays usable while a snapshot is taken
+const PAGES_PER_STEP: i32 = 256;
+
+/// Metadata about a snapshot file
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified_ts: i64,
+    /// What took the snapshot ("manual", "pre-migration", "pre-restore"...); rotation
+    /// only weighs snapshots of the same kind against each other
+    pub kind: String,
+    /// Schema version stored in the snapshot (None if it could not be read)
+    pub schema_version: Option<u32>,
+}
+
+/// Ledger snapshots using SQLite's online backup API
+pub struct BackupManager;
+
+impl BackupManager {
+    /// Copy the live database into a timestamped snapshot in `dir`.
+    /// `label` is appended to the file name (e.g. "pre-migration-v7").
+    pub fn snapshot(conn: &Connection, dir: &Path, label: Option<&str>) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create backup directory: {}", dir.display()))?;
+
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let base = match label {
+            Some(label) => format!("ledger-{}-{}", stamp, label),
+            None => format!("ledger-{}", stamp),
+        };
+        let mut path = dir.join(format!("{}.db", base));
+        let mut n = 1;
+        while path.exists() {
+            path = dir.join(format!("{}-{}.db", base, n));
+            n += 1;
+        }
+
+        let mut dst = Connection::open(&path)
+            .with_context(|| format!("Failed to create snapshot: {}", path.display()))?;
+        {
+            let backup = Backup::new(conn, &mut dst)?;
+            backup.run_to_completion(PAGES_PER_STEP, Duration::from_millis(10), None)
+                .with_context(|| format!("Failed to write snapshot: {}", path.display()))?;
+        }
+        Ok(path)
+    }
+
+    /// List snapshots in `dir`, newest first
+    pub fn list(dir: &Path) -> Result<Vec<SnapshotInfo>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut snapshots = Vec::new();
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read backup directory: {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) if name.starts_with("ledger-") && name.ends_with(".db") => name.to_string(),
+                _ => continue,
+            };
+            let metadata = std::fs::metadata(&path)?;
+            let modified_ts = metadata.modified().ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            snapshots.push(SnapshotInfo {
+                kind: Self::kind_of_name(&name),
+                schema_version: Self::schema_version(&path).ok(),
+                path,
+                name,
+                size_bytes: metadata.len(),
+                modified_ts,
+            });
+        }
+        // Names embed the creation time, so they sort chronologically (compare without the
+        // extension so a same-second "-1" suffix sorts after the first snapshot)
+        snapshots.sort_by(|a, b| b.name.trim_end_matches(".db").cmp(a.name.trim_end_matches(".db")));
+        Ok(snapshots)
+    }
+
+    /// Kind of the snapshots taken with `label` (see `snapshot`)
+    pub fn kind(label: Option<&str>) -> String {
+        let Some(label) = label else {
+            return MANUAL_SNAPSHOT_KIND.to_string();
+        };
+        if label.starts_with(MIGRATION_KIND) {
+            MIGRATION_KIND.to_string()
+        } else {
+            label.to_string()
+        }
+    }
+
+    /// Kind of a snapshot from its file name (`ledger-<date>-<time>[-<label>][-<n>].db`)
+    fn kind_of_name(name: &str) -> String {
+        let rest = name.trim_end_matches(".db").trim_start_matches("ledger-");
+        // Skip the YYYYMMDD-HHMMSS stamp, then drop a same-second "-<n>" suffix
+        let label = rest.get(15..).unwrap_or("").trim_start_matches('-');
+        let label = match label.rsplit_once('-') {
+            Some((head, n)) if n.chars().all(|c| c.is_ascii_digit()) => head,
+            _ if label.chars().all(|c| c.is_ascii_digit()) => "",
+            _ => label,
+        };
+        Self::kind(Some(label).filter(|l| !l.is_empty()))
+    }
+
+    /// Delete the oldest snapshots taken with `label`'s kind so that at most `keep` of
+    /// that kind remain; other kinds are left alone. Returns the removed paths.
+    pub fn rotate(dir: &Path, label: Option<&str>, keep: usize) -> Result<Vec<PathBuf>> {
+        let kind = Self::kind(label);
+        let mut removed = Vec::new();
+        let snapshots = Self::list(dir)?.into_iter().filter(|s| s.kind == kind);
+        for snapshot in snapshots.skip(keep) {
+            std::fs::remove_file(&snapshot.path)
+                .with_context(|| format!("Failed to remove old snapshot: {}", snapshot.path.display()))?;
+            removed.push(snapshot.path);
+        }
+        Ok(removed)
+    }
+
+    /// Read the schema version stored in a snapshot without modifying it
+    pub fn schema_version(path: &Path) -> Result<u32> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open snapshot: {}", path.display()))?;
+        MigrationManager::stored_version(&conn)
+            .with_context(|| format!("{} is not a tatl ledger", path.display()))
+    }
+
+    /// Check that a snapshot can be restored by this version of tatl
+    pub fn validate_snapshot(path: &Path) -> Result<u32> {
+        if !path.exists() {
+            return Err(anyhow!("Snapshot not found: {}", path.display()));
+        }
+        let version = Self::schema_version(path)?;
+        if version == 0 {
+            return Err(anyhow!("{} is not a tatl ledger (no schema version)", path.display()));
+        }
+        if version > CURRENT_VERSION {
+            return Err(anyhow!(
+                "Snapshot schema version {} is newer than this tatl supports ({})",
+                version, CURRENT_VERSION
+            ));
+        }
+        // FTS5's part of the check needs a writable handle, so check an in-memory copy
+        // and never open the snapshot itself for writing
+        let src = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open snapshot: {}", path.display()))?;
+        let mut copy = Connection::open_in_memory()?;
+        Backup::new(&src, &mut copy)?
+            .run_to_completion(PAGES_PER_STEP, Duration::from_millis(0), None)
+            .with_context(|| format!("Failed to read snapshot: {}", path.display()))?;
+        let check: String = copy.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(anyhow!("Snapshot failed integrity check: {}", check));
+        }
+        Ok(version)
+    }
+
+    /// Replace the contents of the ledger at `db_path` with a snapshot.
+    /// Older snapshots are migrated the next time the ledger is opened.
+    pub fn restore(snapshot: &Path, db_path: &Path) -> Result<u32> {
+        let version = Self::validate_snapshot(snapshot)?;
+        let src = Connection::open_with_flags(snapshot, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut dst = Connection::open(db_path)
+            .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+        let backup = Backup::new(&src, &mut dst)?;
+        backup.run_to_completion(PAGES_PER_STEP, Duration::from_millis(10), None)
+            .with_context(|| format!("Failed to restore snapshot: {}", snapshot.display()))?;
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn ledger_with_task(path: &Path, description: &str) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        MigrationManager::initialize(&conn).unwrap();
+        crate::repo::TaskRepo::create(&conn, description, None).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("ledger.db");
+        let backups = dir.path().join("backups");
+        let conn = ledger_with_task(&db_path, "Before snapshot");
+
+        let snapshot = BackupManager::snapshot(&conn, &backups, None).unwrap();
+        assert_eq!(BackupManager::schema_version(&snapshot).unwrap(), CURRENT_VERSION);
+
+        crate::repo::TaskRepo::create(&conn, "After snapshot", None).unwrap();
+        drop(conn);
+
+        BackupManager::restore(&snapshot, &db_path).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest() {
+        let dir = TempDir::new().unwrap();
+        let conn = ledger_with_task(&dir.path().join("ledger.db"), "Task");
+        let backups = dir.path().join("backups");
+        for _ in 0..4 {
+            BackupManager::snapshot(&conn, &backups, None).unwrap();
+        }
+        assert_eq!(BackupManager::list(&backups).unwrap().len(), 4);
+
+        let removed = BackupManager::rotate(&backups, None, 2).unwrap();
+        assert_eq!(removed.len(), 2);
+        let remaining = BackupManager::list(&backups).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[0].name.trim_end_matches(".db") > remaining[1].name.trim_end_matches(".db"));
+    }
+
+    #[test]
+    fn test_rotate_per_kind() {
+        let dir = TempDir::new().unwrap();
+        let conn = ledger_with_task(&dir.path().join("ledger.db"), "Task");
+        let backups = dir.path().join("backups");
+        BackupManager::snapshot(&conn, &backups, None).unwrap();
+        BackupManager::snapshot(&conn, &backups, Some("pre-migration-v7")).unwrap();
+        for label in ["pre-migration-v8", "pre-restore", "pre-restore"] {
+            BackupManager::snapshot(&conn, &backups, Some(label)).unwrap();
+        }
+        let kinds: Vec<String> = BackupManager::list(&backups).unwrap().into_iter().map(|s| s.kind).collect();
+        assert_eq!(kinds.iter().filter(|k| *k == "pre-restore").count(), 2);
+        assert_eq!(kinds.iter().filter(|k| *k == "pre-migration").count(), 2);
+
+        // Automatic snapshots never push out the manual one
+        assert_eq!(BackupManager::rotate(&backups, Some("pre-restore"), 1).unwrap().len(), 1);
+        assert_eq!(BackupManager::rotate(&backups, Some("pre-migration-v9"), 1).unwrap().len(), 1);
+        let remaining = BackupManager::list(&backups).unwrap();
+        assert_eq!(remaining.len(), 3);
+        assert!(remaining.iter().any(|s| s.kind == MANUAL_SNAPSHOT_KIND));
+        assert!(remaining.iter().any(|s| s.name.contains("pre-migration-v8")));
+    }
+
+    #[test]
+    fn test_validate_rejects_newer_schema() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("future.db");
+        let conn = ledger_with_task(&path, "Task");
+        conn.execute("INSERT INTO schema_version (version) VALUES (?1)", [CURRENT_VERSION + 1]).unwrap();
+        drop(conn);
+
+        let err = BackupManager::validate_snapshot(&path).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_validate_does_not_modify_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let conn = ledger_with_task(&dir.path().join("ledger.db"), "Task");
+        let snapshot = BackupManager::snapshot(&conn, &dir.path().join("backups"), None).unwrap();
+        let before = std::fs::read(&snapshot).unwrap();
+
+        let mut perms = std::fs::metadata(&snapshot).unwrap().permissions();
+        perms.set_readonly(true);
+        std::fs::set_permissions(&snapshot, perms).unwrap();
+
+        assert_eq!(BackupManager::validate_snapshot(&snapshot).unwrap(), CURRENT_VERSION);
+        assert_eq!(std::fs::read(&snapshot).unwrap(), before);
+    }
+
+    #[test]
+    fn test_validate_rejects_non_ledger() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("other.db");
+        Connection::open(&path).unwrap().execute("CREATE TABLE t (x)", []).unwrap();
+        assert!(BackupManager::validate_snapshot(&path).is_err());
+    }
+}
@@ -1,7 +1,58 @@
 use rusqlite::Connection;
 use std::path::PathBuf;
 use anyhow::{Context, Result};
-use crate::db::migrations::MigrationManager;
+use crate::db::migrations::{MigrationManager, CURRENT_VERSION};
+use crate::db::backup::BackupManager;
+use crate::db::archive::ArchiveManager;
+use crate::db::profiles::{ActiveLedger, LedgerProfiles, DEFAULT_LEDGER, LEDGER_ENV};
+use crate::config::Config;
+use crate::repo::UndoRepo;
+
+/// The ledger a command works on, resolved once when the command starts
+/// and passed to every connection it opens
+#[derive(Debug, Clone)]
+pub struct LedgerTarget {
+    pub ledger: ActiveLedger,
+    /// Undo operation that journals the changes made through these connections
+    pub undo_op: Option<i64>,
+    /// Snapshots of each kind to keep (`backup.keep`)
+    pub snapshot_keep: usize,
+}
+
+impl LedgerTarget {
+    /// Select the ledger: `--ledger` flag, then `TATL_LEDGER`, then `ledger.active`, then the default
+    pub fn resolve(flag: Option<&str>, config: &Config) -> Result<LedgerTarget> {
+        Ok(LedgerTarget {
+            ledger: DbConnection::active_ledger(flag)?,
+            undo_op: None,
+            snapshot_keep: config.backup.keep,
+        })
+    }
+
+    /// Journal every change made through this target's connections as one
+    /// undoable operation labelled with `command`
+    pub fn begin_undo(&mut self, command: &str) -> Result<()> {
+        let conn = DbConnection::connect_to(self)?;
+        self.undo_op = Some(UndoRepo::begin(&conn, command)?);
+        Ok(())
+    }
+
+    /// Path of the ledger's archive
+    pub fn archive_path(&self) -> PathBuf {
+        ArchiveManager::path_for(&self.ledger.path)
+    }
+
+    /// Snapshot directory. Named ledgers keep their snapshots in a
+    /// subdirectory so that `restore latest` never crosses ledgers.
+    pub fn backup_dir(&self) -> PathBuf {
+        let dir = DbConnection::home_dir().join(".tatl").join("backups");
+        if self.ledger.name == DEFAULT_LEDGER {
+            dir
+        } else {
+            dir.join(&self.ledger.name)
+        }
+    }
+}
 
 /// Database connection manager
 pub struct DbConnection;
@@ -12,30 +63,15 @@ impl DbConnection {
         Self::home_dir().join(".tatl").join("ledger.db")
     }
 
-    /// Get database path from configuration file or default
-    pub fn resolve_path() -> Result<PathBuf> {
-        let config_path = Self::config_path();
-        
-        if config_path.exists() {
-            if let Ok(config) = std::fs::read_to_string(&config_path) {
-                for line in config.lines() {
-                    let line = line.trim();
-                    if line.starts_with("data.location=") {
-                        let path_str = line.strip_prefix("data.location=").unwrap().trim();
-                        let path = PathBuf::from(path_str);
-                        
-                        // If path is relative, resolve relative to config file directory
-                        if path.is_relative() {
-                            return Ok(config_path.parent().unwrap().join(path));
-                        } else {
-                            return Ok(path);
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(Self::default_path())
+    /// Ledger profiles from the configuration file
+    pub fn ledger_profiles() -> LedgerProfiles {
+        LedgerProfiles::load(&Self::config_path(), Self::default_path())
+    }
+
+    /// Resolve the ledger commands should use; `flag` is the `--ledger` selection
+    pub fn active_ledger(flag: Option<&str>) -> Result<ActiveLedger> {
+        let env = std::env::var(LEDGER_ENV).ok().filter(|v| !v.trim().is_empty());
+        Self::ledger_profiles().active(flag, env.as_deref())
     }
 
     /// Get the home directory (cross-platform)
@@ -48,9 +84,15 @@ impl DbConnection {
         Self::home_dir().join(".tatl").join("rc")
     }
 
-    /// Connect to the database, creating it and parent directories if needed
+    /// Connect to the ledger selected by `TATL_LEDGER` or the rc file, with
+    /// the default settings
     pub fn connect() -> Result<Connection> {
-        let db_path = Self::resolve_path()?;
+        Self::connect_to(&LedgerTarget::resolve(None, &Config::default())?)
+    }
+
+    /// Connect to the target's ledger, creating it and parent directories if needed
+    pub fn connect_to(target: &LedgerTarget) -> Result<Connection> {
+        let db_path = &target.ledger.path;
         
         // Create parent directory if it doesn't exist
         if let Some(parent) = db_path.parent() {
@@ -58,12 +100,28 @@ impl DbConnection {
                 .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
         }
         
-        let conn = Connection::open(&db_path)
+        let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
         
+        // Snapshot the ledger before pending migrations rewrite it
+        if MigrationManager::has_pending(&conn)? {
+            let from = MigrationManager::stored_version(&conn)?;
+            let label = format!("pre-migration-v{}", CURRENT_VERSION);
+            let snapshot = BackupManager::snapshot(&conn, &target.backup_dir(), Some(&label))
+                .context("Failed to snapshot database before migration")?;
+            BackupManager::rotate(&target.backup_dir(), Some(&label), target.snapshot_keep)?;
+            eprintln!("Upgrading ledger schema v{} -> v{} (snapshot: {})", from, CURRENT_VERSION, snapshot.display());
+        }
+        
         // Initialize schema
         MigrationManager::initialize(&conn)
             .context("Failed to initialize database schema")?;
+
+        // All connections opened by a journaled command record into the same operation
+        if let Some(op_id) = target.undo_op {
+            UndoRepo::install_triggers(&conn, op_id)
+                .context("Failed to start the undo journal")?;
+        }
         
         Ok(conn)
     }
@@ -120,12 +178,55 @@ mod tests {
         assert!(db_path.exists());
     }
 
+    #[test]
+    fn test_connect_to_uses_the_given_ledger() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = LedgerTarget {
+            ledger: ActiveLedger {
+                name: "work".to_string(),
+                path: temp_dir.path().join("nested").join("work.db"),
+                source: crate::db::LedgerSource::Flag,
+            },
+            undo_op: None,
+            snapshot_keep: 1,
+        };
+        DbConnection::connect_to(&target).unwrap();
+        assert!(target.ledger.path.exists());
+        assert_eq!(target.archive_path(), temp_dir.path().join("nested").join("work.archive.db"));
+        assert!(target.backup_dir().ends_with("backups/work"));
+    }
+
+    #[test]
+    fn test_only_connections_of_an_undoable_target_are_journaled() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut target = LedgerTarget {
+            ledger: ActiveLedger {
+                name: "default".to_string(),
+                path: temp_dir.path().join("ledger.db"),
+                source: crate::db::LedgerSource::Default,
+            },
+            undo_op: None,
+            snapshot_keep: 1,
+        };
+        let plain = DbConnection::connect_to(&target).unwrap();
+        crate::repo::ProjectRepo::create(&plain, "home").unwrap();
+
+        target.begin_undo("projects add work").unwrap();
+        let journaled = DbConnection::connect_to(&target).unwrap();
+        crate::repo::ProjectRepo::create(&journaled, "work").unwrap();
+
+        let ops = crate::repo::UndoRepo::list(&plain, 10).unwrap();
+        assert_eq!(ops.len(), 1);
+        assert_eq!(Some(ops[0].id), target.undo_op);
+        assert_eq!(ops[0].command, "projects add work");
+    }
+
     #[test]
     fn test_connect_in_memory() {
         let conn = DbConnection::connect_in_memory().unwrap();
 
         // Verify schema was initialized
         let version = MigrationManager::get_version(&conn).unwrap();
-        assert_eq!(version, 7);
+        assert_eq!(version, CURRENT_VERSION);
     }
 }
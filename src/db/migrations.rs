@@ -2,7 +2,7 @@ use rusqlite::{Connection, Result};
 use std::collections::HashMap;
 
 /// Current database schema version
-const CURRENT_VERSION: u32 = 7;
+pub const CURRENT_VERSION: u32 = 14;
 
 /// Migration system for managing database schema versions
 pub struct MigrationManager;
@@ -11,6 +11,11 @@ impl MigrationManager {
     /// Initialize the database with the current schema
     /// This creates the schema_version table and applies all migrations
     pub fn initialize(conn: &Connection) -> Result<()> {
+        Self::migrate_to(conn, CURRENT_VERSION)
+    }
+
+    /// Apply pending migrations up to `target`, e.g. to build an older ledger for upgrade tests
+    pub fn migrate_to(conn: &Connection, target: u32) -> Result<()> {
         // Create schema_version table to track migrations
         conn.execute(
             "CREATE TABLE IF NOT EXISTS schema_version (
@@ -28,8 +33,8 @@ impl MigrationManager {
             )
             .unwrap_or(0);
 
-        // Apply migrations up to current version
-        for version in (current_version + 1)..=CURRENT_VERSION {
+        // Apply migrations up to the target version
+        for version in (current_version + 1)..=target {
             Self::apply_migration(conn, version)?;
         }
 
@@ -77,6 +82,27 @@ impl MigrationManager {
             |row| row.get(0),
         )
     }
+
+    /// Get the schema version of a database without modifying it.
+    /// Returns 0 when the database has no schema_version table (new or foreign file).
+    pub fn stored_version(conn: &Connection) -> Result<u32> {
+        let has_table: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )?;
+        if has_table {
+            Self::get_version(conn)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Whether an existing ledger has migrations waiting to be applied
+    pub fn has_pending(conn: &Connection) -> Result<bool> {
+        let version = Self::stored_version(conn)?;
+        Ok(version > 0 && version < CURRENT_VERSION)
+    }
 }
 
 /// Get all migrations indexed by version
@@ -89,6 +115,13 @@ fn get_migrations() -> HashMap<u32, fn(&rusqlite::Transaction) -> Result<(), rus
     migrations.insert(5, migration_v5);
     migrations.insert(6, migration_v6);
     migrations.insert(7, migration_v7);
+    migrations.insert(8, migration_v8);
+    migrations.insert(9, migration_v9);
+    migrations.insert(10, migration_v10);
+    migrations.insert(11, migration_v11);
+    migrations.insert(12, migration_v12);
+    migrations.insert(13, migration_v13);
+    migrations.insert(14, migration_v14);
     migrations
 }
 
@@ -512,6 +545,188 @@ fn migration_v7(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
     Ok(())
 }
 
+/// Migration v8: Add the undo journal (one row per journaled command, one per reversible row change)
+fn migration_v8(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE undo_ops (
+            id INTEGER PRIMARY KEY,
+            command TEXT NOT NULL,
+            created_ts INTEGER NOT NULL,
+            undone_ts INTEGER NULL
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE undo_journal (
+            id INTEGER PRIMARY KEY,
+            op_id INTEGER NOT NULL REFERENCES undo_ops(id) ON DELETE CASCADE,
+            table_name TEXT NOT NULL,
+            task_id INTEGER NULL,
+            undo_sql TEXT NOT NULL
+        )",
+        [],
+    )?;
+    // Note: undo_sql restores the row as it was before the change; entries are replayed newest first
+
+    tx.execute(
+        "CREATE INDEX idx_undo_journal_op ON undo_journal(op_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Migration v9: Audit events for sessions, externals and projects, and a view over all events
+fn migration_v9(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    // No foreign key: the audit trail outlives deleted sessions and merged projects
+    tx.execute(
+        "CREATE TABLE entity_events (
+            id INTEGER PRIMARY KEY,
+            entity_type TEXT NOT NULL CHECK(entity_type IN ('session','external','project')),
+            entity_id INTEGER NOT NULL,
+            task_id INTEGER NULL,
+            ts INTEGER NOT NULL,
+            event_type TEXT NOT NULL,
+            payload_json TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE INDEX idx_entity_events_entity ON entity_events(entity_type, entity_id)",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE VIEW all_events AS
+            SELECT 'task' AS entity_type, task_id AS entity_id, task_id, ts, event_type, payload_json
+            FROM task_events
+            UNION ALL
+            SELECT entity_type, entity_id, task_id, ts, event_type, payload_json
+            FROM entity_events",
+        [],
+    )?;
+
+    // Row changed by each journal entry, so undo can audit what it restored
+    tx.execute(
+        "ALTER TABLE undo_journal ADD COLUMN row_id INTEGER NULL",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Migration v10: Full-text indexes over task descriptions and annotation notes
+fn migration_v10(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    // External content tables: the text stays in tasks and task_annotations, the triggers
+    // below keep the indexes in step with every insert, edit and delete
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE task_fts USING fts5(
+            description, content='tasks', content_rowid='id', tokenize='porter unicode61'
+        );
+        CREATE VIRTUAL TABLE annotation_fts USING fts5(
+            note, content='task_annotations', content_rowid='id', tokenize='porter unicode61'
+        );
+
+        CREATE TRIGGER task_fts_insert AFTER INSERT ON tasks BEGIN
+            INSERT INTO task_fts (rowid, description) VALUES (new.id, new.description);
+        END;
+        CREATE TRIGGER task_fts_delete AFTER DELETE ON tasks BEGIN
+            INSERT INTO task_fts (task_fts, rowid, description) VALUES ('delete', old.id, old.description);
+        END;
+        CREATE TRIGGER task_fts_update AFTER UPDATE OF id, description ON tasks BEGIN
+            INSERT INTO task_fts (task_fts, rowid, description) VALUES ('delete', old.id, old.description);
+            INSERT INTO task_fts (rowid, description) VALUES (new.id, new.description);
+        END;
+
+        CREATE TRIGGER annotation_fts_insert AFTER INSERT ON task_annotations BEGIN
+            INSERT INTO annotation_fts (rowid, note) VALUES (new.id, new.note);
+        END;
+        CREATE TRIGGER annotation_fts_delete AFTER DELETE ON task_annotations BEGIN
+            INSERT INTO annotation_fts (annotation_fts, rowid, note) VALUES ('delete', old.id, old.note);
+        END;
+        CREATE TRIGGER annotation_fts_update AFTER UPDATE OF id, note ON task_annotations BEGIN
+            INSERT INTO annotation_fts (annotation_fts, rowid, note) VALUES ('delete', old.id, old.note);
+            INSERT INTO annotation_fts (rowid, note) VALUES (new.id, new.note);
+        END;
+
+        INSERT INTO task_fts (task_fts) VALUES ('rebuild');
+        INSERT INTO annotation_fts (annotation_fts) VALUES ('rebuild');",
+    )?;
+
+    Ok(())
+}
+
+/// Migration v11: Named filter contexts, at most one of them active
+fn migration_v11(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE contexts (
+            name TEXT PRIMARY KEY,
+            filter_json TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 0,
+            created_ts INTEGER NOT NULL,
+            modified_ts INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE UNIQUE INDEX idx_contexts_active ON contexts(is_active) WHERE is_active = 1",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Migration v12: Task dependencies
+fn migration_v12(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE task_dependencies (
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            depends_on_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            created_ts INTEGER NOT NULL,
+            PRIMARY KEY(task_id, depends_on_id),
+            CHECK(task_id != depends_on_id)
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE INDEX idx_task_dependencies_depends_on ON task_dependencies(depends_on_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Migration v13: Row images checked by undo
+fn migration_v13(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    // The row as the journaled command left it ('' once deleted), so undo can tell
+    // whether anything changed it since. NULL for entries journaled before v13.
+    tx.execute(
+        "ALTER TABLE undo_journal ADD COLUMN post_image TEXT NULL",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Migration v14: Uuids of tasks that left the ledger
+fn migration_v14(tx: &rusqlite::Transaction) -> Result<(), rusqlite::Error> {
+    // Purged and archived tasks, so that sync does not copy them back from another ledger
+    tx.execute(
+        "CREATE TABLE task_tombstones (
+            uuid TEXT PRIMARY KEY,
+            reason TEXT NOT NULL CHECK(reason IN ('purged', 'archived')),
+            ts INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -656,4 +871,38 @@ mod tests {
         ).unwrap();
         assert_eq!(respawn, Some("daily".to_string()), "Respawn value should be migrated from recur");
     }
+
+    #[test]
+    fn test_migration_v10_indexes_existing_text() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY)",
+            [],
+        ).unwrap();
+        for version in 1..=9 {
+            MigrationManager::apply_migration(&conn, version).unwrap();
+        }
+
+        conn.execute(
+            "INSERT INTO tasks (uuid, description, status, created_ts, modified_ts)
+             VALUES ('uuid1', 'Renew passport', 'pending', 1000, 1000)",
+            [],
+        ).unwrap();
+        let task_id = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT INTO task_annotations (task_id, note, entry_ts, created_ts) VALUES (?1, 'Photos taken', 1000, 1000)",
+            [task_id],
+        ).unwrap();
+
+        MigrationManager::apply_migration(&conn, 10).unwrap();
+
+        let found: i64 = conn.query_row(
+            "SELECT rowid FROM task_fts WHERE task_fts MATCH 'passport'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(found, task_id);
+        let notes: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM annotation_fts WHERE annotation_fts MATCH 'photo'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(notes, 1, "porter stemming matches 'photo' to 'Photos'");
+    }
 }
@@ -1,5 +1,11 @@
 pub mod migrations;
 pub mod connection;
+pub mod backup;
+pub mod profiles;
+pub mod archive;
 
 pub use connection::*;
 pub use migrations::*;
+pub use backup::*;
+pub use profiles::*;
+pub use archive::*;
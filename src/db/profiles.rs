@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+
+/// Name of the ledger configured by `data.location` (or the built-in default path)
+pub const DEFAULT_LEDGER: &str = "default";
+
+/// Environment variable selecting a ledger by name or path
+pub const LEDGER_ENV: &str = "TATL_LEDGER";
+
+/// rc key prefix for named ledgers (`ledger.<name>=<path>`)
+const LEDGER_KEY_PREFIX: &str = "ledger.";
+
+/// rc key recording the ledger chosen with `tatl ledger switch`
+const ACTIVE_KEY: &str = "ledger.active";
+
+/// Where the active ledger selection came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerSource {
+    /// The `--ledger` command-line flag
+    Flag,
+    /// The `TATL_LEDGER` environment variable
+    Env,
+    /// `ledger.active` in the rc file
+    Config,
+    /// No selection: the default ledger
+    Default,
+}
+
+impl LedgerSource {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            LedgerSource::Flag => "--ledger",
+            LedgerSource::Env => LEDGER_ENV,
+            LedgerSource::Config => "ledger switch",
+            LedgerSource::Default => "default",
+        }
+    }
+}
+
+/// The ledger commands operate on
+#[derive(Debug, Clone)]
+pub struct ActiveLedger {
+    pub name: String,
+    pub path: PathBuf,
+    pub source: LedgerSource,
+}
+
+/// Ledger profiles defined in the rc file:
+///
+/// ```text
+/// data.location=~/.tatl/ledger.db   # the "default" ledger
+/// ledger.work=~/work/tatl.db
+/// ledger.client-x=/srv/clients/x.db
+/// ledger.active=work
+/// ```
+#[derive(Debug, Clone)]
+pub struct LedgerProfiles {
+    default_path: PathBuf,
+    profiles: Vec<(String, PathBuf)>,
+    active: Option<String>,
+}
+
+/// Resolve a configured path: `~/` expands to the home directory and relative
+/// paths are taken relative to `base`
+fn resolve_config_path(value: &str, base: &Path) -> PathBuf {
+    if let Some(rest) = value.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    let path = PathBuf::from(value);
+    if path.is_relative() {
+        base.join(path)
+    } else {
+        path
+    }
+}
+
+/// Check that a ledger profile name can be stored in the rc file
+pub fn validate_ledger_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("Invalid ledger name '{}'. Use letters, digits, '-' and '_'.", name));
+    }
+    if name == DEFAULT_LEDGER || name == "active" {
+        return Err(anyhow!("'{}' is a reserved ledger name", name));
+    }
+    Ok(())
+}
+
+impl LedgerProfiles {
+    /// Parse rc file contents. Relative paths are resolved against `config_dir`.
+    pub fn parse(config: &str, config_dir: &Path, fallback: PathBuf) -> Self {
+        let mut default_path = fallback;
+        let mut profiles: Vec<(String, PathBuf)> = Vec::new();
+        let mut active = None;
+
+        for line in config.lines() {
+            let line = line.trim();
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let (key, value) = (key.trim(), value.trim());
+            if key == "data.location" {
+                default_path = resolve_config_path(value, config_dir);
+            } else if key == ACTIVE_KEY {
+                active = Some(value.to_string());
+            } else if let Some(name) = key.strip_prefix(LEDGER_KEY_PREFIX) {
+                let path = resolve_config_path(value, config_dir);
+                match profiles.iter_mut().find(|(n, _)| n == name) {
+                    Some(existing) => existing.1 = path,
+                    None => profiles.push((name.to_string(), path)),
+                }
+            }
+        }
+
+        LedgerProfiles { default_path, profiles, active }
+    }
+
+    /// Load profiles from the rc file at `config_path` (missing file: default ledger only)
+    pub fn load(config_path: &Path, fallback: PathBuf) -> Self {
+        let config = std::fs::read_to_string(config_path).unwrap_or_default();
+        let config_dir = config_path.parent().unwrap_or(Path::new("."));
+        Self::parse(&config, config_dir, fallback)
+    }
+
+    /// All ledgers, the default first
+    pub fn list(&self) -> Vec<(String, PathBuf)> {
+        std::iter::once((DEFAULT_LEDGER.to_string(), self.default_path.clone()))
+            .chain(self.profiles.iter().cloned())
+            .collect()
+    }
+
+    /// Path of a named ledger
+    pub fn lookup(&self, name: &str) -> Option<PathBuf> {
+        if name == DEFAULT_LEDGER {
+            return Some(self.default_path.clone());
+        }
+        self.profiles.iter().find(|(n, _)| n == name).map(|(_, p)| p.clone())
+    }
+
+    /// Ledger selected with `tatl ledger switch`, if any
+    pub fn switched(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Resolve a selector that is either a ledger name or a path to a database file.
+    /// Paths are recognised by a path separator or a `.db` extension.
+    pub fn resolve(&self, selector: &str) -> Result<(String, PathBuf)> {
+        if let Some(path) = self.lookup(selector) {
+            return Ok((selector.to_string(), path));
+        }
+        if selector.contains('/') || selector.contains('\\') || selector.ends_with(".db") {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let path = resolve_config_path(selector, &cwd);
+            // A path that belongs to a profile is reported under the profile's name
+            let name = self.list().into_iter()
+                .find(|(_, p)| *p == path)
+                .map(|(n, _)| n)
+                .unwrap_or_else(|| path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| selector.to_string()));
+            return Ok((name, path));
+        }
+        let known: Vec<String> = self.list().into_iter().map(|(n, _)| n).collect();
+        Err(anyhow!("Unknown ledger '{}'. Known ledgers: {}", selector, known.join(", ")))
+    }
+
+    /// Pick the active ledger: `--ledger` flag, then `TATL_LEDGER`, then `ledger.active`, then the default
+    pub fn active(&self, flag: Option<&str>, env: Option<&str>) -> Result<ActiveLedger> {
+        let (selector, source) = if let Some(flag) = flag {
+            (flag, LedgerSource::Flag)
+        } else if let Some(env) = env {
+            (env, LedgerSource::Env)
+        } else if let Some(active) = self.active.as_deref() {
+            (active, LedgerSource::Config)
+        } else {
+            (DEFAULT_LEDGER, LedgerSource::Default)
+        };
+        let (name, path) = self.resolve(selector)?;
+        Ok(ActiveLedger { name, path, source })
+    }
+}
+
+/// Return rc contents with `ledger.active` set to `name` (removed for the default ledger)
+pub fn set_active_ledger_config(config: &str, name: &str) -> String {
+    let mut lines: Vec<&str> = config.lines()
+        .filter(|line| line.split_once('=').map(|(k, _)| k.trim()) != Some(ACTIVE_KEY))
+        .collect();
+    let entry = format!("{}={}", ACTIVE_KEY, name);
+    if name != DEFAULT_LEDGER {
+        lines.push(&entry);
+    }
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Return rc contents with a `ledger.<name>=<path>` entry added
+pub fn add_ledger_config(config: &str, name: &str, path: &str) -> String {
+    let mut out = config.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("{}{}={}\n", LEDGER_KEY_PREFIX, name, path));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(config: &str) -> LedgerProfiles {
+        LedgerProfiles::parse(config, Path::new("/cfg"), PathBuf::from("/home/me/.tatl/ledger.db"))
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let p = profiles("data.location=main.db\nledger.work=/data/work.db\nledger.client-x = clients/x.db\n");
+        let list = p.list();
+        assert_eq!(list[0], ("default".to_string(), PathBuf::from("/cfg/main.db")));
+        assert_eq!(list[1], ("work".to_string(), PathBuf::from("/data/work.db")));
+        assert_eq!(list[2], ("client-x".to_string(), PathBuf::from("/cfg/clients/x.db")));
+        assert_eq!(p.switched(), None);
+    }
+
+    #[test]
+    fn test_active_precedence() {
+        let p = profiles("ledger.work=/w.db\nledger.home=/h.db\nledger.active=home\n");
+        assert_eq!(p.active(None, None).unwrap().name, "home");
+        assert_eq!(p.active(None, Some("work")).unwrap().source, LedgerSource::Env);
+        let flag = p.active(Some("default"), Some("work")).unwrap();
+        assert_eq!(flag.source, LedgerSource::Flag);
+        assert_eq!(flag.path, PathBuf::from("/home/me/.tatl/ledger.db"));
+        assert_eq!(profiles("").active(None, None).unwrap().source, LedgerSource::Default);
+    }
+
+    #[test]
+    fn test_resolve_paths_and_unknown_names() {
+        let p = profiles("ledger.work=/data/work.db\n");
+        assert_eq!(p.resolve("/data/work.db").unwrap().0, "work");
+        assert_eq!(p.resolve("/tmp/scratch.db").unwrap(), ("scratch".to_string(), PathBuf::from("/tmp/scratch.db")));
+        let err = p.resolve("wrok").unwrap_err().to_string();
+        assert!(err.contains("Unknown ledger 'wrok'"));
+        assert!(err.contains("default, work"));
+    }
+
+    #[test]
+    fn test_rewrite_config() {
+        let config = "data.location=a.db\nledger.active=old\n";
+        assert_eq!(set_active_ledger_config(config, "work"), "data.location=a.db\nledger.active=work\n");
+        assert_eq!(set_active_ledger_config(config, "default"), "data.location=a.db\n");
+        assert_eq!(add_ledger_config("data.location=a.db", "work", "/w.db"), "data.location=a.db\nledger.work=/w.db\n");
+        assert!(validate_ledger_name("client-x").is_ok());
+        assert!(validate_ledger_name("active").is_err());
+        assert!(validate_ledger_name("a b").is_err());
+    }
+}
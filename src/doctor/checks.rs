@@ -0,0 +1,473 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use anyhow::Result;
+use crate::filter::VirtualTag;
+use crate::repo::{EventRepo, ExternalRepo, ProjectRepo, SessionRepo, StackRepo};
+use crate::respawn::RespawnRule;
+
+/// How serious a finding is. Errors and warnings make `tatl doctor` exit non-zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+/// A repair that can be applied to a finding with `tatl doctor --fix`.
+/// Every action keeps the data it touches recoverable (nothing is deleted
+/// except queue membership).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FixAction {
+    /// Remove a non-pending task from a queue
+    RemoveStackItem { stack_id: i64, task_id: i64 },
+    /// Swap the start and end of a session recorded backwards
+    SwapSessionTimes { session_id: i64 },
+    /// Move a session's start forward so it no longer overlaps the previous one
+    TrimSessionStart { session_id: i64, start_ts: i64 },
+    /// Mark an outstanding external as returned
+    ReturnExternal { external_id: i64 },
+    /// Archive a project that has no tasks
+    ArchiveProject { name: String },
+    /// Clear a respawn rule that cannot be parsed (the old value is kept in the task history)
+    ClearRespawn { task_id: i64 },
+    /// Keep unreadable UDA data as a single `udas_raw` UDA
+    WrapUdas { task_id: i64 },
+    /// Rename a stored tag that has a virtual tag's name to its lower-case form
+    RenameTag { task_id: i64, tag: String },
+}
+
+impl FixAction {
+    /// Human-readable description of the repair
+    pub fn describe(&self) -> String {
+        match self {
+            FixAction::RemoveStackItem { task_id, .. } => format!("remove task {} from the queue", task_id),
+            FixAction::SwapSessionTimes { session_id } => format!("swap start and end of session {}", session_id),
+            FixAction::TrimSessionStart { session_id, .. } => format!("move start of session {} to the end of the previous session", session_id),
+            FixAction::ReturnExternal { external_id } => format!("mark external {} as returned", external_id),
+            FixAction::ArchiveProject { name } => format!("archive project '{}'", name),
+            FixAction::ClearRespawn { task_id } => format!("clear respawn rule on task {}", task_id),
+            FixAction::WrapUdas { task_id } => format!("keep the raw UDA text of task {} as the 'udas_raw' UDA", task_id),
+            FixAction::RenameTag { task_id, tag } => format!("rename tag '{}' of task {} to '{}'", tag, task_id, tag.to_lowercase()),
+        }
+    }
+}
+
+/// A single inconsistency found in the ledger
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Name of the check that produced the finding (e.g. "session_inverted")
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub task_id: Option<i64>,
+    pub fix: Option<FixAction>,
+}
+
+/// Ledger integrity checks
+pub struct Doctor;
+
+impl Doctor {
+    /// Run every check and return the findings
+    pub fn scan(conn: &Connection) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        Self::check_stack_items(conn, &mut findings)?;
+        Self::check_inverted_sessions(conn, &mut findings)?;
+        Self::check_overlapping_sessions(conn, &mut findings)?;
+        Self::check_externals(conn, &mut findings)?;
+        Self::check_empty_projects(conn, &mut findings)?;
+        Self::check_respawn_rules(conn, &mut findings)?;
+        Self::check_udas(conn, &mut findings)?;
+        Self::check_reserved_tags(conn, &mut findings)?;
+        Ok(findings)
+    }
+
+    /// Apply the fixes of all error and warning findings that have one (and of info
+    /// findings when `include_info` is set), in a single transaction.
+    /// Returns the number of fixes applied.
+    pub fn fix(conn: &Connection, findings: &[Finding], include_info: bool) -> Result<usize> {
+        let tx = conn.unchecked_transaction()?;
+        let mut applied = 0;
+        for fix in Self::fixes(findings, include_info) {
+            Self::apply(&tx, fix)?;
+            applied += 1;
+        }
+        tx.commit()?;
+        Ok(applied)
+    }
+
+    /// Fixes that `fix` would apply
+    pub fn fixes(findings: &[Finding], include_info: bool) -> impl Iterator<Item = &FixAction> {
+        findings.iter()
+            .filter(move |f| include_info || f.severity != Severity::Info)
+            .filter_map(|f| f.fix.as_ref())
+    }
+
+    /// Apply a single fix
+    pub fn apply(conn: &Connection, fix: &FixAction) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        match fix {
+            FixAction::RemoveStackItem { stack_id, task_id } => {
+                StackRepo::remove_task(conn, *stack_id, *task_id)?;
+            }
+            FixAction::SwapSessionTimes { session_id } => {
+                if let Some(session) = SessionRepo::get_by_id(conn, *session_id)? {
+                    if let Some(end_ts) = session.end_ts.filter(|end| *end < session.start_ts) {
+                        SessionRepo::update_times(conn, *session_id, end_ts, Some(session.start_ts))?;
+                    }
+                }
+            }
+            FixAction::TrimSessionStart { session_id, start_ts } => {
+                if let Some(session) = SessionRepo::get_by_id(conn, *session_id)? {
+                    if session.end_ts.is_some_and(|end| end > *start_ts) {
+                        SessionRepo::modify_start_time(conn, *session_id, *start_ts)?;
+                    }
+                }
+            }
+            FixAction::ReturnExternal { external_id } => {
+                ExternalRepo::mark_returned(conn, *external_id)?;
+            }
+            FixAction::ArchiveProject { name } => {
+                ProjectRepo::archive(conn, name)?;
+            }
+            FixAction::ClearRespawn { task_id } => {
+                let old: Option<String> = conn.query_row(
+                    "SELECT respawn FROM tasks WHERE id = ?1", [task_id], |row| row.get(0))?;
+                conn.execute(
+                    "UPDATE tasks SET respawn = NULL, modified_ts = ?1 WHERE id = ?2",
+                    rusqlite::params![now, task_id],
+                )?;
+                EventRepo::record_modified(conn, *task_id, "respawn",
+                    old.map(serde_json::Value::String), None)?;
+            }
+            FixAction::WrapUdas { task_id } => {
+                let raw: Option<String> = conn.query_row(
+                    "SELECT udas_json FROM tasks WHERE id = ?1", [task_id], |row| row.get(0))?;
+                if let Some(raw) = raw {
+                    let wrapped = serde_json::json!({ "udas_raw": raw }).to_string();
+                    conn.execute(
+                        "UPDATE tasks SET udas_json = ?1, modified_ts = ?2 WHERE id = ?3",
+                        rusqlite::params![wrapped, now, task_id],
+                    )?;
+                    EventRepo::record_modified(conn, *task_id, "udas",
+                        Some(serde_json::Value::String(raw)), Some(serde_json::Value::String(wrapped)))?;
+                }
+            }
+            FixAction::RenameTag { task_id, tag } => {
+                let renamed = tag.to_lowercase();
+                if conn.execute("DELETE FROM task_tags WHERE task_id = ?1 AND tag = ?2",
+                    rusqlite::params![task_id, tag])? > 0 {
+                    EventRepo::record_tag_removed(conn, *task_id, tag)?;
+                    if conn.execute("INSERT OR IGNORE INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                        rusqlite::params![task_id, renamed])? > 0 {
+                        EventRepo::record_tag_added(conn, *task_id, &renamed)?;
+                    }
+                    conn.execute("UPDATE tasks SET modified_ts = ?1 WHERE id = ?2", rusqlite::params![now, task_id])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Queued tasks must be pending
+    fn check_stack_items(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT si.stack_id, si.task_id, t.status FROM stack_items si
+             JOIN tasks t ON t.id = si.task_id
+             WHERE t.status != 'pending'
+             ORDER BY si.stack_id, si.ordinal"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for row in rows {
+            let (stack_id, task_id, status) = row?;
+            findings.push(Finding {
+                check: "stack_item_not_pending",
+                severity: Severity::Error,
+                message: format!("Task {} is queued but {}", task_id, status),
+                task_id: Some(task_id),
+                fix: Some(FixAction::RemoveStackItem { stack_id, task_id }),
+            });
+        }
+        Ok(())
+    }
+
+    /// Sessions must not end before they start
+    fn check_inverted_sessions(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, start_ts, end_ts FROM sessions
+             WHERE end_ts IS NOT NULL AND end_ts < start_ts ORDER BY start_ts"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+        })?;
+        for row in rows {
+            let (session_id, task_id, start_ts, end_ts) = row?;
+            findings.push(Finding {
+                check: "session_inverted",
+                severity: Severity::Error,
+                message: format!("Session {} ends {}s before it starts", session_id, start_ts - end_ts),
+                task_id: Some(task_id),
+                fix: Some(FixAction::SwapSessionTimes { session_id }),
+            });
+        }
+        Ok(())
+    }
+
+    /// Closed sessions must not overlap each other. Each session is compared
+    /// with the latest end seen among the sessions that started before it.
+    fn check_overlapping_sessions(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, start_ts, end_ts FROM sessions
+             WHERE end_ts IS NOT NULL AND end_ts >= start_ts ORDER BY start_ts, id"
+        )?;
+        let sessions: Vec<(i64, i64, i64, i64)> = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?.collect::<Result<_, _>>()?;
+
+        let mut latest: Option<(i64, i64)> = None; // (session_id, end_ts)
+        for (session_id, task_id, start_ts, end_ts) in sessions {
+            if let Some((prev_id, prev_end)) = latest {
+                if start_ts < prev_end {
+                    // A session entirely inside the previous one cannot be trimmed safely
+                    let fix = if end_ts > prev_end {
+                        Some(FixAction::TrimSessionStart { session_id, start_ts: prev_end })
+                    } else {
+                        None
+                    };
+                    findings.push(Finding {
+                        check: "session_overlap",
+                        severity: Severity::Warning,
+                        message: format!("Session {} overlaps session {} by {}s",
+                            session_id, prev_id, prev_end.min(end_ts) - start_ts),
+                        task_id: Some(task_id),
+                        fix,
+                    });
+                }
+                if end_ts > prev_end {
+                    latest = Some((session_id, end_ts));
+                }
+            } else {
+                latest = Some((session_id, end_ts));
+            }
+        }
+        Ok(())
+    }
+
+    /// Deleted tasks must not have outstanding externals
+    fn check_externals(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT e.id, e.task_id, e.recipient FROM externals e
+             JOIN tasks t ON t.id = e.task_id
+             WHERE t.status = 'deleted' AND e.returned_ts IS NULL
+             ORDER BY e.id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for row in rows {
+            let (external_id, task_id, recipient) = row?;
+            findings.push(Finding {
+                check: "external_on_deleted_task",
+                severity: Severity::Warning,
+                message: format!("Task {} is deleted but still sent to {}", task_id, recipient),
+                task_id: Some(task_id),
+                fix: Some(FixAction::ReturnExternal { external_id }),
+            });
+        }
+        Ok(())
+    }
+
+    /// Active projects without tasks, counting tasks in sub-projects
+    fn check_empty_projects(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT p.name FROM projects p
+             WHERE p.is_archived = 0 AND NOT EXISTS (
+                 SELECT 1 FROM tasks t JOIN projects c ON c.id = t.project_id
+                 WHERE c.id = p.id OR substr(c.name, 1, length(p.name) + 1) = p.name || '.'
+             )
+             ORDER BY p.name"
+        )?;
+        let names: Vec<String> = stmt.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        for name in names {
+            findings.push(Finding {
+                check: "project_empty",
+                severity: Severity::Info,
+                message: format!("Project '{}' has no tasks", name),
+                task_id: None,
+                fix: Some(FixAction::ArchiveProject { name }),
+            });
+        }
+        Ok(())
+    }
+
+    /// Respawn rules must parse
+    fn check_respawn_rules(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT id, respawn FROM tasks WHERE respawn IS NOT NULL ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (task_id, rule) = row?;
+            if let Err(e) = RespawnRule::parse(&rule) {
+                findings.push(Finding {
+                    check: "respawn_invalid",
+                    severity: Severity::Error,
+                    message: format!("Task {} has invalid respawn rule '{}': {}", task_id, rule, e),
+                    task_id: Some(task_id),
+                    fix: Some(FixAction::ClearRespawn { task_id }),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// UDAs must be a JSON object of strings; anything else is silently dropped when tasks are read
+    fn check_udas(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT id, udas_json FROM tasks WHERE udas_json IS NOT NULL ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (task_id, json) = row?;
+            if let Err(e) = serde_json::from_str::<HashMap<String, String>>(&json) {
+                findings.push(Finding {
+                    check: "udas_invalid",
+                    severity: Severity::Error,
+                    message: format!("Task {} has unreadable UDA data: {}", task_id, e),
+                    task_id: Some(task_id),
+                    fix: Some(FixAction::WrapUdas { task_id }),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Stored tags named like a virtual tag (added before virtual tags existed) can no
+    /// longer be filtered on
+    fn check_reserved_tags(conn: &Connection, findings: &mut Vec<Finding>) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT task_id, tag FROM task_tags ORDER BY task_id, tag")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (task_id, tag) = row?;
+            if VirtualTag::parse(&tag).is_some() {
+                findings.push(Finding {
+                    check: "tag_reserved",
+                    severity: Severity::Warning,
+                    message: format!("Task {} has tag '{}', which filters read as the virtual tag +{}", task_id, tag, tag),
+                    task_id: Some(task_id),
+                    fix: Some(FixAction::RenameTag { task_id, tag }),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::repo::{SessionRepo, TaskRepo};
+
+    fn checks(findings: &[Finding]) -> Vec<&'static str> {
+        findings.iter().map(|f| f.check).collect()
+    }
+
+    #[test]
+    fn test_clean_ledger_has_no_findings() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let project = ProjectRepo::create(&conn, "work").unwrap();
+        let task = TaskRepo::create(&conn, "Task", project.id).unwrap();
+        SessionRepo::create_closed(&conn, task.id.unwrap(), 1_000, 2_000).unwrap();
+        assert!(Doctor::scan(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detects_and_fixes_inconsistencies() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Task", None).unwrap().id.unwrap();
+        let stack = StackRepo::get_or_create_default(&conn).unwrap();
+        StackRepo::enqueue(&conn, stack.id.unwrap(), task).unwrap();
+        conn.execute("UPDATE tasks SET status = 'completed', respawn = 'fortnightly', udas_json = 'not json' WHERE id = ?1", [task]).unwrap();
+        conn.execute("INSERT INTO sessions (task_id, start_ts, end_ts, created_ts) VALUES (?1, 5000, 4000, 0)", [task]).unwrap();
+
+        let findings = Doctor::scan(&conn).unwrap();
+        assert_eq!(checks(&findings), vec!["stack_item_not_pending", "session_inverted", "respawn_invalid", "udas_invalid"]);
+
+        assert_eq!(Doctor::fix(&conn, &findings, false).unwrap(), 4);
+        assert!(Doctor::scan(&conn).unwrap().is_empty());
+
+        let task = TaskRepo::get_by_id(&conn, task).unwrap().unwrap();
+        assert_eq!(task.respawn, None);
+        assert_eq!(task.udas.get("udas_raw").map(String::as_str), Some("not json"));
+    }
+
+    #[test]
+    fn test_overlapping_sessions() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Task", None).unwrap().id.unwrap();
+        SessionRepo::create_closed(&conn, task, 1_000, 2_000).unwrap();
+        // Overlaps the first session's tail
+        SessionRepo::create_closed(&conn, task, 1_500, 3_000).unwrap();
+        // Entirely inside the second session: reported without a fix
+        SessionRepo::create_closed(&conn, task, 2_200, 2_400).unwrap();
+
+        let findings = Doctor::scan(&conn).unwrap();
+        assert_eq!(checks(&findings), vec!["session_overlap", "session_overlap"]);
+        assert_eq!(findings[0].fix, Some(FixAction::TrimSessionStart { session_id: 2, start_ts: 2_000 }));
+        assert_eq!(findings[1].fix, None);
+
+        Doctor::fix(&conn, &findings, false).unwrap();
+        assert_eq!(Doctor::scan(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reserved_tags_are_renamed() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Task", None).unwrap().id.unwrap();
+        let other = TaskRepo::create(&conn, "Other", None).unwrap().id.unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO task_tags (task_id, tag) VALUES ({task}, 'ACTIVE'), ({task}, 'Blocked'), ({other}, 'QUEUED'), ({other}, 'queued');"
+        )).unwrap();
+
+        let findings = Doctor::scan(&conn).unwrap();
+        assert_eq!(checks(&findings), vec!["tag_reserved", "tag_reserved"]);
+        assert_eq!(Doctor::fix(&conn, &findings, false).unwrap(), 2);
+        assert!(Doctor::scan(&conn).unwrap().is_empty());
+
+        assert_eq!(TaskRepo::get_tags(&conn, task).unwrap(), vec!["Blocked", "active"]);
+        assert_eq!(TaskRepo::get_tags(&conn, other).unwrap(), vec!["queued"]);
+        let events = EventRepo::get_by_task(&conn, task, None).unwrap();
+        assert!(events.iter().any(|e| e.event_type == "tag_removed"));
+        assert!(events.iter().any(|e| e.event_type == "tag_added"));
+    }
+
+    #[test]
+    fn test_empty_projects_consider_subprojects() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        ProjectRepo::create(&conn, "work").unwrap();
+        let email = ProjectRepo::create(&conn, "work.email").unwrap();
+        ProjectRepo::create(&conn, "home").unwrap();
+        TaskRepo::create(&conn, "Inbox zero", email.id).unwrap();
+
+        let findings = Doctor::scan(&conn).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].fix, Some(FixAction::ArchiveProject { name: "home".to_string() }));
+
+        // Info fixes are only applied on request
+        assert_eq!(Doctor::fix(&conn, &findings, false).unwrap(), 0);
+        assert_eq!(Doctor::scan(&conn).unwrap().len(), 1);
+        assert_eq!(Doctor::fix(&conn, &findings, true).unwrap(), 1);
+        assert!(Doctor::scan(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod checks;
+
+pub use checks::*;
@@ -4,9 +4,9 @@
 //!
 //! # Evaluation Process
 //!
-//! 1. Load all tasks from the database
-//! 2. For each task, evaluate the filter expression
-//! 3. Return tasks that match
+//! 1. Compile the filter to a SQL WHERE clause (see `filter::sql`)
+//! 2. Load the tasks the clause selects
+//! 3. Evaluate any terms that could not be compiled against each of them
 //!
 //! # Filter Terms
 //!
@@ -17,14 +17,31 @@
 //! - `due=<expr>` - Match by due date (supports =, >, <, >=, <=, !=)
 //! - `scheduled=<expr>` - Match by scheduled date
 //! - `wait=<expr>` - Match by wait date
+//! - `created=<expr>`, `modified=<expr>` - Match by creation or last modification time
+//! - `end=<expr>` - Match by completion or close time (from status_changed events)
+//! - `lastactive=<expr>` - Match by the end of the most recent session (now while it is open)
+//! - `alloc=<duration>`, `logged=<duration>`, `remaining=<duration>` - Match by allocated time,
+//!   time logged in sessions, or their difference (supports =, >, <, >=, <=, !=)
+//! - `+OVERDUE`, `-ACTIVE`, ... - Virtual tags derived from the task's state (see `filter::virtual_tags`)
+//! - `priority=<score>` - Match by urgency score (see `utils::priority::calculate_priority`)
+//! - `queue=<position>` - Match by position in the queue, 0 at the top (also `any`, `none`)
 //! - `waiting` - Derived: matches tasks with wait_ts in the future
-//! - `kanban=<status>` - Derived: matches tasks by kanban status (proposed, stalled, queued, done)
+//! - `kanban=<status>` - Derived: matches tasks by kanban status (proposed, stalled, blocked, queued, done)
+//! - `depends=<ids>` - Match tasks that depend on any of the given tasks (also `any`, `none`)
+//! - `desc=<text>`, `desc~<pattern>` - Match the description by substring or `/regex/`
+//! - `note=<text>`, `note~<pattern>` - Match the text of any annotation
+//! - `uda.<name>=<value>` - Match a user-defined attribute (supports =, >, <, >=, <=, != and ~),
+//!   comparing as numbers, dates or strings (see `uda_comparison_type`)
 
 use crate::models::{Task, TaskStatus};
-use crate::repo::{TaskRepo, SessionRepo, StackRepo, ExternalRepo};
+use crate::repo::{TaskRepo, SessionRepo, StackRepo, ExternalRepo, AnnotationRepo, DependencyRepo};
 use crate::filter::parser::{FilterTerm, ComparisonOp};
+use crate::filter::sql::compile_filter;
+use crate::config::{Config, UdaType};
+use crate::utils::priority::calculate_priority;
 use rusqlite::Connection;
 use anyhow::Result;
+use std::cmp::Ordering;
 
 #[derive(Debug, Clone)]
 pub enum FilterExpr {
@@ -32,18 +49,19 @@ pub enum FilterExpr {
     Term(FilterTerm),
     And(Vec<FilterExpr>),
     Or(Vec<FilterExpr>),
+    Xor(Box<FilterExpr>, Box<FilterExpr>), // Exactly one side matches
     Not(Box<FilterExpr>),
 }
 
 impl FilterExpr {
     /// Evaluate filter against a task
-    pub fn matches(&self, task: &Task, conn: &Connection) -> Result<bool> {
+    pub fn matches(&self, task: &Task, conn: &Connection, config: &Config) -> Result<bool> {
         match self {
             FilterExpr::All => Ok(true),
-            FilterExpr::Term(term) => term.matches(task, conn),
+            FilterExpr::Term(term) => term.matches(task, conn, config),
             FilterExpr::And(exprs) => {
                 for expr in exprs {
-                    if !expr.matches(task, conn)? {
+                    if !expr.matches(task, conn, config)? {
                         return Ok(false);
                     }
                 }
@@ -51,14 +69,17 @@ impl FilterExpr {
             }
             FilterExpr::Or(exprs) => {
                 for expr in exprs {
-                    if expr.matches(task, conn)? {
+                    if expr.matches(task, conn, config)? {
                         return Ok(true);
                     }
                 }
                 Ok(false)
             }
+            FilterExpr::Xor(left, right) => {
+                Ok(left.matches(task, conn, config)? != right.matches(task, conn, config)?)
+            }
             FilterExpr::Not(expr) => {
-                Ok(!expr.matches(task, conn)?)
+                Ok(!expr.matches(task, conn, config)?)
             }
         }
     }
@@ -164,11 +185,87 @@ fn match_date_field(
                 Err(_) => Ok(false),
             }
         }
+        // The parser only accepts `~` on UDA values
+        ComparisonOp::Match => Ok(false),
+    }
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Type a UDA comparison is made in: the type declared with `uda.<name>.type`, otherwise
+/// numeric or date when the filter value parses as one, otherwise string. Task values that
+/// don't parse as the comparison type only match `!=`, so `uda.points>=3` never compares
+/// a stray `abc` as a string.
+pub(crate) fn uda_comparison_type(name: &str, expr: &str, config: &Config) -> UdaType {
+    if let Some(declared) = config.udas.get(name) {
+        return *declared;
+    }
+    if parse_number(expr).is_some() {
+        UdaType::Numeric
+    } else if crate::utils::parse_date_expr(expr).is_ok() {
+        UdaType::Date
+    } else {
+        UdaType::String
+    }
+}
+
+fn compare_ordering(ordering: Ordering, op: &ComparisonOp) -> bool {
+    match op {
+        ComparisonOp::Eq => ordering == Ordering::Equal,
+        ComparisonOp::Neq => ordering != Ordering::Equal,
+        ComparisonOp::Gt => ordering == Ordering::Greater,
+        ComparisonOp::Lt => ordering == Ordering::Less,
+        ComparisonOp::Gte => ordering != Ordering::Less,
+        ComparisonOp::Lte => ordering != Ordering::Greater,
+        ComparisonOp::Match => false,
+    }
+}
+
+/// Helper to evaluate a UDA value with a comparison operator. Tasks without the UDA only
+/// match `!=`, and values that do not parse as the comparison type match nothing but `!=`.
+fn match_uda_field(name: &str, value: Option<&str>, op: &ComparisonOp, expr: &str, config: &Config) -> Result<bool> {
+    match (op, expr) {
+        (ComparisonOp::Eq, "any") | (ComparisonOp::Neq, "none") => return Ok(value.is_some()),
+        (ComparisonOp::Eq, "none") | (ComparisonOp::Neq, "any") => return Ok(value.is_none()),
+        _ => {}
+    }
+    let Some(value) = value else {
+        return Ok(*op == ComparisonOp::Neq);
+    };
+    match uda_comparison_type(name, expr, config) {
+        UdaType::String => Ok(compare_ordering(value.cmp(expr), op)),
+        UdaType::Numeric => {
+            let Some(filter_number) = parse_number(expr) else {
+                return Ok(false);
+            };
+            Ok(match parse_number(value).and_then(|n| n.partial_cmp(&filter_number)) {
+                Some(ordering) => compare_ordering(ordering, op),
+                None => *op == ComparisonOp::Neq,
+            })
+        }
+        UdaType::Date => match_date_field(crate::utils::parse_date_expr(value).ok(), op, expr),
+    }
+}
+
+/// Helper to evaluate an integer (a duration in seconds, a queue position) with a comparison
+/// operator. `None` as the filter value stands for "none"; tasks without a value only match
+/// `!=` otherwise.
+fn match_integer_field(value: Option<i64>, op: &ComparisonOp, filter_secs: Option<i64>) -> bool {
+    match (value, filter_secs) {
+        (_, None) => match op {
+            ComparisonOp::Eq => value.is_none(),
+            ComparisonOp::Neq => value.is_some(),
+            _ => false,
+        },
+        (None, Some(_)) => *op == ComparisonOp::Neq,
+        (Some(secs), Some(filter_secs)) => compare_ordering(secs.cmp(&filter_secs), op),
     }
 }
 
 impl FilterTerm {
-    fn matches(&self, task: &Task, conn: &Connection) -> Result<bool> {
+    fn matches(&self, task: &Task, conn: &Connection, config: &Config) -> Result<bool> {
         match self {
             FilterTerm::Id(id) => {
                 Ok(task.id == Some(*id))
@@ -238,6 +335,50 @@ impl FilterTerm {
             FilterTerm::Wait(op, expr) => {
                 match_date_field(task.wait_ts, op, expr)
             }
+            FilterTerm::Created(op, expr) => {
+                match_date_field(Some(task.created_ts), op, expr)
+            }
+            FilterTerm::Modified(op, expr) => {
+                match_date_field(Some(task.modified_ts), op, expr)
+            }
+            FilterTerm::End(op, expr) => {
+                let end_ts = TaskRepo::get_end_ts(conn, task.id.unwrap_or(0))?;
+                match_date_field(end_ts, op, expr)
+            }
+            FilterTerm::LastActive(op, expr) => {
+                let last_active_ts = SessionRepo::get_most_recent_for_task(conn, task.id.unwrap_or(0))?
+                    .map(|session| session.end_ts.unwrap_or_else(|| chrono::Utc::now().timestamp()));
+                match_date_field(last_active_ts, op, expr)
+            }
+            FilterTerm::Alloc(op, secs) => {
+                Ok(match_integer_field(task.alloc_secs, op, *secs))
+            }
+            FilterTerm::Logged(op, secs) => {
+                let logged = TaskRepo::get_total_logged_time(conn, task.id.unwrap_or(0))?;
+                Ok(match_integer_field(Some(logged), op, *secs))
+            }
+            FilterTerm::Remaining(op, secs) => {
+                let remaining = match task.alloc_secs {
+                    Some(alloc) => Some(alloc - TaskRepo::get_total_logged_time(conn, task.id.unwrap_or(0))?),
+                    None => None,
+                };
+                Ok(match_integer_field(remaining, op, *secs))
+            }
+            FilterTerm::VirtualTag(tag, is_positive) => {
+                Ok(tag.applies_to(task, conn)? == *is_positive)
+            }
+            FilterTerm::Priority(op, score) => {
+                if task.status != TaskStatus::Pending {
+                    return Ok(*op == ComparisonOp::Neq);
+                }
+                let priority = calculate_priority(task, conn, config)?;
+                Ok(priority.partial_cmp(score).is_some_and(|ordering| compare_ordering(ordering, op)))
+            }
+            FilterTerm::Queue(op, position) => {
+                let items = StackRepo::default_items(conn)?;
+                let task_position = items.iter().position(|item| Some(item.task_id) == task.id);
+                Ok(match_integer_field(task_position.map(|p| p as i64), op, *position))
+            }
             FilterTerm::Waiting => {
                 Ok(task.is_waiting())
             }
@@ -248,10 +389,11 @@ impl FilterTerm {
                 Ok(statuses.iter().any(|s| task_kanban_lower == s.to_lowercase()))
             }
             FilterTerm::Desc(pattern) => {
-                // Case-insensitive substring match on description
-                let desc_lower = task.description.to_lowercase();
-                let pattern_lower = pattern.to_lowercase();
-                Ok(desc_lower.contains(&pattern_lower))
+                Ok(pattern.is_match(&task.description))
+            }
+            FilterTerm::Note(pattern) => {
+                let annotations = AnnotationRepo::get_by_task(conn, task.id.unwrap_or(0))?;
+                Ok(annotations.iter().any(|a| pattern.is_match(&a.note)))
             }
             FilterTerm::External(recipient) => {
                 // Check if task has active externals matching the recipient
@@ -259,6 +401,22 @@ impl FilterTerm {
                 let externals = ExternalRepo::get_active_for_task(conn, task_id)?;
                 Ok(externals.iter().any(|e| e.recipient == *recipient))
             }
+            FilterTerm::Depends(op, ids) => {
+                let dependencies = DependencyRepo::get_dependencies(conn, task.id.unwrap_or(0))?;
+                // With no IDs, `=` asks for tasks without dependencies
+                let found = if ids.is_empty() {
+                    dependencies.is_empty()
+                } else {
+                    dependencies.iter().any(|id| ids.contains(id))
+                };
+                Ok(if *op == ComparisonOp::Eq { found } else { !found })
+            }
+            FilterTerm::Uda(name, op, expr) => {
+                match_uda_field(name, task.udas.get(name).map(|v| v.as_str()), op, expr, config)
+            }
+            FilterTerm::UdaMatch(name, pattern) => {
+                Ok(task.udas.get(name).is_some_and(|value| pattern.is_match(value)))
+            }
         }
     }
 }
@@ -280,9 +438,12 @@ fn calculate_task_kanban(task: &Task, conn: &Connection) -> Result<String> {
         return Ok("external".to_string());
     }
 
+    if DependencyRepo::is_blocked(conn, task_id)? {
+        return Ok("blocked".to_string());
+    }
+
     // Get stack position
-    let stack = StackRepo::get_or_create_default(conn)?;
-    let items = StackRepo::get_items(conn, stack.id.unwrap())?;
+    let items = StackRepo::default_items(conn)?;
     let stack_position = items.iter().position(|item| item.task_id == task_id);
 
     // Check if task has sessions
@@ -306,12 +467,16 @@ fn calculate_task_kanban(task: &Task, conn: &Connection) -> Result<String> {
 }
 
 /// Get tasks matching a filter expression
-pub fn filter_tasks(conn: &Connection, filter: &FilterExpr) -> Result<Vec<(Task, Vec<String>)>> {
-    let all_tasks = TaskRepo::list_all(conn)?;
-    let mut matching = Vec::new();
+pub fn filter_tasks(conn: &Connection, filter: &FilterExpr, config: &Config) -> Result<Vec<(Task, Vec<String>)>> {
+    let compiled = compile_filter(filter, config);
+    let candidates = TaskRepo::list_matching(conn, &compiled.clause, &compiled.params)?;
+    let Some(residual) = compiled.residual else {
+        return Ok(candidates);
+    };
 
-    for (task, tags) in all_tasks {
-        if filter.matches(&task, conn)? {
+    let mut matching = Vec::new();
+    for (task, tags) in candidates {
+        if residual.matches(&task, conn, config)? {
             matching.push((task, tags));
         }
     }
@@ -336,7 +501,7 @@ mod tests {
 
         // Filter by ID
         let filter = parse_filter(vec!["1".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.id, task1.id);
     }
@@ -351,7 +516,7 @@ mod tests {
 
         // Filter by status
         let filter = parse_filter(vec!["status=pending".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 2);
     }
 
@@ -370,7 +535,7 @@ mod tests {
 
         // Filter by project
         let filter = parse_filter(vec!["project=work".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -394,12 +559,12 @@ mod tests {
 
         // Filter by parent project (should match all)
         let filter = parse_filter(vec!["project=admin".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 3);
 
         // Filter by specific nested project
         let filter = parse_filter(vec!["project=admin.email".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -413,13 +578,13 @@ mod tests {
 
         // Filter by positive tag
         let filter = parse_filter(vec!["+urgent".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.id, task1.id);
 
         // Filter by negative tag
         let filter = parse_filter(vec!["-urgent".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -434,13 +599,13 @@ mod tests {
 
         // Filter by due=any
         let filter = parse_filter(vec!["due=any".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.id, task1.id);
 
         // Filter by due=none
         let filter = parse_filter(vec!["due=none".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -455,7 +620,7 @@ mod tests {
 
         // Filter by due!=none (should match tasks WITH a due date)
         let filter = parse_filter(vec!["due!=none".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.id, task1.id);
     }
@@ -474,7 +639,7 @@ mod tests {
 
         // Filter by waiting
         let filter = parse_filter(vec!["waiting".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -489,8 +654,40 @@ mod tests {
 
         // Combined filter: project AND tag
         let filter = parse_filter(vec!["project=work".to_string(), "+urgent".to_string()]).unwrap();
-        let results = filter_tasks(&conn, &filter).unwrap();
+        let results = filter_tasks(&conn, &filter, &Config::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0.id, task1.id);
     }
+
+    #[test]
+    fn test_queue_terms_do_not_write() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("archive.db");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            crate::db::MigrationManager::initialize(&conn).unwrap();
+            TaskRepo::create(&conn, "Archived", None).unwrap();
+        }
+        // Like the archive ledger: read-only and without a default stack
+        let conn = rusqlite::Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+        let task = TaskRepo::get_by_id(&conn, 1).unwrap().unwrap();
+        for (term, expected) in [("queue=1", false), ("queue!=1", true), ("+QUEUED", false), ("kanban=proposed", true)] {
+            let filter = parse_filter(vec![term.to_string()]).unwrap();
+            assert_eq!(filter.matches(&task, &conn, &Config::default()).unwrap(), expected, "{}", term);
+        }
+    }
+
+    #[test]
+    fn test_uda_comparison_follows_the_given_config() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let udas = std::collections::HashMap::from([("points".to_string(), "10".to_string())]);
+        TaskRepo::create_full(&conn, "Estimate", None, None, None, None, None, None, None, &udas, &[]).unwrap();
+
+        // Inferred from the value, 10 > 9 numerically; declared as a string, "10" < "9"
+        let filter = parse_filter(vec!["uda.points>9".to_string()]).unwrap();
+        let mut config = Config::default();
+        assert_eq!(filter_tasks(&conn, &filter, &config).unwrap().len(), 1);
+        config.set("uda.points.type", "string").unwrap();
+        assert_eq!(filter_tasks(&conn, &filter, &config).unwrap().len(), 0);
+    }
 }
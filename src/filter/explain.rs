@@ -0,0 +1,334 @@
+//! Filter explanations
+//!
+//! Backs `--explain`: the parsed expression tree with the number of tasks each node
+//! matches, date expressions resolved to absolute times, project names expanded to the
+//! projects they cover, and warnings for terms that cannot match anything.
+
+use crate::filter::evaluator::{FilterExpr, filter_tasks, uda_comparison_type};
+use crate::filter::parser::{FilterTerm, ComparisonOp, TextPattern, format_op};
+use crate::filter::virtual_tags::VirtualTag;
+use crate::config::Config;
+use crate::repo::TaskRepo;
+use rusqlite::Connection;
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use std::fmt::Write;
+
+const STATUSES: &[&str] = &["pending", "completed", "closed", "deleted"];
+const KANBAN_STATUSES: &[&str] = &["proposed", "stalled", "blocked", "queued", "external", "done"];
+
+/// One node of an explained filter
+#[derive(Debug, Clone)]
+pub struct ExplainNode {
+    /// Operator (`and`, `or`, ...) or the term as it was understood
+    pub label: String,
+    /// Number of tasks this node matches on its own
+    pub matches: usize,
+    /// How the term was resolved (dates, project expansions, ...)
+    pub notes: Vec<String>,
+    /// Why the term may not do what was intended
+    pub warnings: Vec<String>,
+    pub children: Vec<ExplainNode>,
+}
+
+impl ExplainNode {
+    /// Indented tree, one node per line with its match count, notes and warnings below it
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_into(0, &mut out);
+        out
+    }
+
+    fn render_into(&self, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let noun = if self.matches == 1 { "task" } else { "tasks" };
+        let _ = writeln!(out, "{:<48} {:>5} {}", format!("{}{}", indent, self.label), self.matches, noun);
+        for note in &self.notes {
+            let _ = writeln!(out, "{}  → {}", indent, note);
+        }
+        for warning in &self.warnings {
+            let _ = writeln!(out, "{}  ! {}", indent, warning);
+        }
+        for child in &self.children {
+            child.render_into(depth + 1, out);
+        }
+    }
+}
+
+/// Explain a filter expression against the tasks in the ledger
+pub fn explain_filter(conn: &Connection, expr: &FilterExpr, config: &Config) -> Result<ExplainNode> {
+    let matches = filter_tasks(conn, expr, config)?.len();
+    let mut node = ExplainNode { label: String::new(), matches, notes: Vec::new(), warnings: Vec::new(), children: Vec::new() };
+    match expr {
+        FilterExpr::All => node.label = "(all tasks)".to_string(),
+        FilterExpr::Term(term) => {
+            node.label = term_label(term);
+            explain_term(conn, term, config, &mut node)?;
+        }
+        FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+            node.label = if matches!(expr, FilterExpr::And(_)) { "and" } else { "or" }.to_string();
+            for child in exprs {
+                node.children.push(explain_filter(conn, child, config)?);
+            }
+        }
+        FilterExpr::Xor(left, right) => {
+            node.label = "xor".to_string();
+            node.children.push(explain_filter(conn, left, config)?);
+            node.children.push(explain_filter(conn, right, config)?);
+        }
+        FilterExpr::Not(inner) => {
+            node.label = "not".to_string();
+            node.children.push(explain_filter(conn, inner, config)?);
+        }
+    }
+    Ok(node)
+}
+
+/// A term written back as filter syntax, after normalisation by the parser
+pub fn term_label(term: &FilterTerm) -> String {
+    let presence = |secs: &Option<i64>| secs.map(format_secs).unwrap_or_else(|| "none".to_string());
+    match term {
+        FilterTerm::Id(id) => id.to_string(),
+        FilterTerm::Status(values) => format!("status={}", values.join(",")),
+        FilterTerm::Project(values) => format!("project={}", values.join(",")),
+        FilterTerm::Tag(tag, true) => format!("+{}", tag),
+        FilterTerm::Tag(tag, false) => format!("-{}", tag),
+        FilterTerm::Due(op, value) => format!("due{}{}", format_op(op), value),
+        FilterTerm::Scheduled(op, value) => format!("scheduled{}{}", format_op(op), value),
+        FilterTerm::Wait(op, value) => format!("wait{}{}", format_op(op), value),
+        FilterTerm::Created(op, value) => format!("created{}{}", format_op(op), value),
+        FilterTerm::Modified(op, value) => format!("modified{}{}", format_op(op), value),
+        FilterTerm::End(op, value) => format!("end{}{}", format_op(op), value),
+        FilterTerm::LastActive(op, value) => format!("lastactive{}{}", format_op(op), value),
+        FilterTerm::Alloc(op, secs) => format!("alloc{}{}", format_op(op), presence(secs)),
+        FilterTerm::Logged(op, secs) => format!("logged{}{}", format_op(op), presence(secs)),
+        FilterTerm::Remaining(op, secs) => format!("remaining{}{}", format_op(op), presence(secs)),
+        FilterTerm::VirtualTag(tag, true) => format!("+{}", tag.name()),
+        FilterTerm::VirtualTag(tag, false) => format!("-{}", tag.name()),
+        FilterTerm::Priority(op, score) => format!("priority{}{}", format_op(op), score),
+        FilterTerm::Queue(op, position) => format!("queue{}{}", format_op(op),
+            position.map(|p| p.to_string()).unwrap_or_else(|| "none".to_string())),
+        FilterTerm::Waiting => "waiting".to_string(),
+        FilterTerm::Kanban(values) => format!("kanban={}", values.join(",")),
+        FilterTerm::Desc(pattern) => format!("desc{}", pattern_label(pattern)),
+        FilterTerm::Note(pattern) => format!("note{}", pattern_label(pattern)),
+        FilterTerm::External(recipient) => format!("external={}", recipient),
+        FilterTerm::Depends(op, ids) if ids.is_empty() => format!("depends{}none", format_op(op)),
+        FilterTerm::Depends(op, ids) => format!("depends{}{}", format_op(op),
+            ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")),
+        FilterTerm::Uda(name, op, value) => format!("uda.{}{}{}", name, format_op(op), value),
+        FilterTerm::UdaMatch(name, pattern) => format!("uda.{}~{}", name, pattern_text(pattern)),
+    }
+}
+
+fn pattern_text(pattern: &TextPattern) -> String {
+    match pattern {
+        TextPattern::Contains(text) => text.clone(),
+        TextPattern::Regex(regex) => format!("/{}/", regex.as_str()),
+    }
+}
+
+fn pattern_label(pattern: &TextPattern) -> String {
+    match pattern {
+        TextPattern::Contains(text) => format!("={}", text),
+        TextPattern::Regex(_) => format!("~{}", pattern_text(pattern)),
+    }
+}
+
+/// Compact duration such as `1h30m`, `-15m` or `0`
+fn format_secs(secs: i64) -> String {
+    if secs == 0 {
+        return "0".to_string();
+    }
+    let sign = if secs < 0 { "-" } else { "" };
+    let secs = secs.abs();
+    let mut text = sign.to_string();
+    for (value, unit) in [(secs / 3600, "h"), (secs % 3600 / 60, "m"), (secs % 60, "s")] {
+        if value > 0 {
+            let _ = write!(text, "{}{}", value, unit);
+        }
+    }
+    text
+}
+
+/// Absolute local time with its Unix timestamp
+pub fn format_instant(ts: i64) -> String {
+    let local = Local.timestamp_opt(ts, 0).single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S %:z").to_string())
+        .unwrap_or_default();
+    format!("{} ({})", local, ts)
+}
+
+/// Resolve the value of a date term, noting the absolute time or warning that it matches nothing
+fn explain_date(field: &str, op: &ComparisonOp, value: &str, node: &mut ExplainNode) {
+    if matches!(value, "any" | "none") && matches!(op, ComparisonOp::Eq | ComparisonOp::Neq) {
+        let set = (value == "any") == (*op == ComparisonOp::Eq);
+        node.notes.push(format!("{} is {}", field, if set { "set" } else { "not set" }));
+        return;
+    }
+    match crate::utils::parse_date_expr(value) {
+        Ok(ts) => {
+            let comparison = match op {
+                ComparisonOp::Eq => "on the same day as",
+                ComparisonOp::Neq => "not on the same day as",
+                ComparisonOp::Gt => "after",
+                ComparisonOp::Lt => "before",
+                ComparisonOp::Gte => "at or after",
+                ComparisonOp::Lte => "at or before",
+                ComparisonOp::Match => "matching",
+            };
+            node.notes.push(format!("{} {} {}", field, comparison, format_instant(ts)));
+        }
+        Err(_) => node.warnings.push(format!(
+            "'{}' is not a date expression; this term matches no tasks", value)),
+    }
+}
+
+fn explain_term(conn: &Connection, term: &FilterTerm, config: &Config, node: &mut ExplainNode) -> Result<()> {
+    match term {
+        FilterTerm::Id(id) => {
+            if TaskRepo::get_by_id(conn, *id)?.is_none() {
+                node.warnings.push(format!("Task {} does not exist", id));
+            }
+        }
+        FilterTerm::Status(values) => {
+            for value in values.iter().filter(|v| !STATUSES.contains(&v.as_str())) {
+                node.warnings.push(format!("'{}' is not a status ({}); it matches nothing", value, STATUSES.join(", ")));
+            }
+        }
+        FilterTerm::Kanban(values) => {
+            for value in values.iter().filter(|v| !KANBAN_STATUSES.contains(&v.as_str())) {
+                node.warnings.push(format!("'{}' is not a kanban status ({}); it matches nothing",
+                    value, KANBAN_STATUSES.join(", ")));
+            }
+        }
+        FilterTerm::Project(values) => {
+            for value in values {
+                if value.is_empty() || value.eq_ignore_ascii_case("none") {
+                    node.notes.push("tasks without a project".to_string());
+                    continue;
+                }
+                let mut stmt = conn.prepare(
+                    "SELECT name FROM projects
+                     WHERE name = ?1 OR substr(name, 1, length(?1) + 1) = ?1 || '.'
+                     ORDER BY name"
+                )?;
+                let names = stmt.query_map([value], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                if names.is_empty() {
+                    node.warnings.push(format!("No project is named '{}' or nested below it", value));
+                } else {
+                    node.notes.push(format!("'{}' covers {}", value, names.join(", ")));
+                }
+            }
+        }
+        FilterTerm::Due(op, value) => explain_date("due", op, value, node),
+        FilterTerm::Scheduled(op, value) => explain_date("scheduled", op, value, node),
+        FilterTerm::Wait(op, value) => explain_date("wait", op, value, node),
+        FilterTerm::Created(op, value) => explain_date("created", op, value, node),
+        FilterTerm::Modified(op, value) => explain_date("modified", op, value, node),
+        FilterTerm::End(op, value) => explain_date("completed or closed", op, value, node),
+        FilterTerm::LastActive(op, value) => {
+            explain_date("last session ended", op, value, node);
+            node.notes.push("a running session counts as ending now".to_string());
+        }
+        FilterTerm::Logged(..) => node.notes.push("includes a running session".to_string()),
+        FilterTerm::Remaining(..) => node.notes.push("allocation minus logged time; none without an allocation".to_string()),
+        FilterTerm::VirtualTag(tag, _) => match tag {
+            VirtualTag::Overdue | VirtualTag::DueToday | VirtualTag::DueWeek => {
+                let now = chrono::Utc::now().timestamp();
+                let (start, end) = tag.due_window(now);
+                let note = match start {
+                    Some(start) => format!("pending and due from {} to before {}", format_instant(start), format_instant(end)),
+                    None => format!("pending and due before {}", format_instant(end)),
+                };
+                node.notes.push(note);
+            }
+            _ => node.notes.push("virtual tag, derived from the task's state".to_string()),
+        },
+        FilterTerm::Depends(_, ids) => {
+            node.notes.push("direct dependencies, finished or not".to_string());
+            for id in ids {
+                if TaskRepo::get_by_id(conn, *id)?.is_none() {
+                    node.warnings.push(format!("Task {} does not exist", id));
+                }
+            }
+        }
+        FilterTerm::Priority(..) => node.notes.push("only pending tasks have a priority".to_string()),
+        FilterTerm::Queue(..) => node.notes.push("0 is the top of the queue".to_string()),
+        FilterTerm::Waiting => node.notes.push("wait date in the future".to_string()),
+        FilterTerm::Desc(pattern) | FilterTerm::Note(pattern) | FilterTerm::UdaMatch(_, pattern) => {
+            node.notes.push(match pattern {
+                TextPattern::Contains(_) => "case-insensitive substring".to_string(),
+                TextPattern::Regex(_) => "regular expression".to_string(),
+            });
+        }
+        FilterTerm::Uda(name, op, value) => {
+            if !(matches!(value.as_str(), "any" | "none") && matches!(op, ComparisonOp::Eq | ComparisonOp::Neq)) {
+                let uda_type = uda_comparison_type(name, value, config);
+                node.notes.push(format!("compared as {}", uda_type.name()));
+                if uda_type == crate::config::UdaType::Date {
+                    if let Ok(ts) = crate::utils::parse_date_expr(value) {
+                        node.notes.push(format!("'{}' is {}", value, format_instant(ts)));
+                    }
+                }
+            }
+        }
+        FilterTerm::Tag(..) | FilterTerm::Alloc(..) | FilterTerm::External(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::filter::parse_filter;
+    use crate::repo::ProjectRepo;
+
+    fn explain(conn: &Connection, filter: &str) -> ExplainNode {
+        let tokens = filter.split_whitespace().map(|t| t.to_string()).collect();
+        explain_filter(conn, &parse_filter(tokens).unwrap(), &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_explain_counts_and_project_expansion() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let work = ProjectRepo::create(&conn, "work").unwrap();
+        let email = ProjectRepo::create(&conn, "work.email").unwrap();
+        ProjectRepo::create(&conn, "workshop").unwrap();
+        TaskRepo::create(&conn, "Report", work.id).unwrap();
+        TaskRepo::create(&conn, "Inbox zero", email.id).unwrap();
+        TaskRepo::create(&conn, "Groceries", None).unwrap();
+
+        let node = explain(&conn, "project=work or desc=groceries");
+        assert_eq!(node.label, "or");
+        assert_eq!(node.matches, 3);
+        assert_eq!(node.children[0].matches, 2);
+        assert_eq!(node.children[0].notes, vec!["'work' covers work, work.email"]);
+        assert_eq!(node.children[1].label, "desc=groceries");
+
+        let node = explain(&conn, "project=home");
+        assert_eq!(node.matches, 0);
+        assert!(node.warnings[0].contains("No project is named 'home'"), "{:?}", node.warnings);
+    }
+
+    #[test]
+    fn test_explain_flags_terms_that_match_nothing() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let node = explain(&conn, "due<nextfryday status=pending,open");
+        assert!(node.children[0].warnings[0].contains("'nextfryday' is not a date expression"));
+        assert!(node.children[1].warnings[0].contains("'open' is not a status"));
+
+        let node = explain(&conn, "due>=2026-01-15");
+        assert!(node.notes[0].starts_with("due at or after 2026-01-15 00:00:00"), "{:?}", node.notes);
+    }
+
+    #[test]
+    fn test_format_secs() {
+        assert_eq!(format_secs(0), "0");
+        assert_eq!(format_secs(5400), "1h30m");
+        assert_eq!(format_secs(-900), "-15m");
+        assert_eq!(format_secs(90061), "25h1m1s");
+    }
+}
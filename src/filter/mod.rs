@@ -1,5 +1,11 @@
 pub mod parser;
 pub mod evaluator;
+pub mod sql;
+pub mod virtual_tags;
+pub mod explain;
 
 pub use parser::*;
 pub use evaluator::*;
+pub use sql::*;
+pub use virtual_tags::*;
+pub use explain::*;
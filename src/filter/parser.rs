@@ -1,19 +1,26 @@
 //! Filter parser for task queries
 //!
-//! Implements boolean expression parsing with AND/OR/NOT operators.
+//! Implements boolean expression parsing with AND/OR/XOR/NOT operators and grouping.
 //!
 //! # Grammar
 //!
 //! ```text
-//! filter := term | filter "or" term | "not" term
-//! term := id | status=<status> | project=<name> | +tag | -tag | due=<expr> | due>expr | ...
+//! filter  := xor ("or" xor)*
+//! xor     := and ("xor" and)*
+//! and     := unary (["and"] unary)*
+//! unary   := "not" unary | "(" filter ")" | term
+//! term    := id | status=<status> | project=<name> | +tag | -tag | due=<expr> | due>expr | desc~/regex/i | ...
 //! ```
 //!
 //! # Precedence
 //!
 //! 1. `not` (highest)
-//! 2. Implicit `and` (between adjacent terms)
-//! 3. `or` (lowest)
+//! 2. `and`, explicit or implicit (between adjacent terms)
+//! 3. `xor`
+//! 4. `or` (lowest)
+//!
+//! Parentheses may be separate tokens or attached to terms: `( +a or +b )` and `(+a or +b)`
+//! are equivalent.
 //!
 //! # Examples
 //!
@@ -30,11 +37,37 @@
 //! // Complex
 //! project=work +urgent or project=home +important
 //!
+//! // Grouping
+//! (project=work or project=home) and +urgent
+//!
+//! // Exactly one of two
+//! +urgent xor +important
+//!
 //! // Comparison operators
 //! due>tomorrow due<=eod
+//!
+//! // Activity dates
+//! end>=-7d lastactive<-30d
+//!
+//! // Time estimates and logged time
+//! alloc>2h logged>=30m remaining<0
+//!
+//! // Virtual tags, derived from the task's state
+//! +OVERDUE -ACTIVE +OVERBUDGET
+//!
+//! // Urgency and queue position (0 is the top of the queue)
+//! priority>8 queue<3 queue=none
+//!
+//! // User-defined attributes
+//! uda.client=acme uda.points>=3 uda.ticket=none uda.client~acm
+//!
+//! // Regular expressions on descriptions, annotations and UDA values
+//! desc~/auth.*bug/i note~/^sent to/
 //! ```
 
 use crate::filter::evaluator::FilterExpr;
+use crate::filter::virtual_tags::VirtualTag;
+use regex::{Regex, RegexBuilder};
 
 /// Comparison operators for filter expressions
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +78,41 @@ pub enum ComparisonOp {
     Lt,    // <
     Gte,   // >=
     Lte,   // <=
+    Match, // ~ (text pattern, see TextPattern)
+}
+
+/// Text pattern of `desc`, `note` and `uda.<name>~` terms
+#[derive(Debug, Clone)]
+pub enum TextPattern {
+    /// Case-insensitive substring
+    Contains(String),
+    /// `/regex/`, or `/regex/i` to ignore case
+    Regex(Regex),
+}
+
+impl TextPattern {
+    /// Parse the value of a `~` term. Values written as `/regex/` or `/regex/i` are regular
+    /// expressions; anything else (including paths such as `/usr/bin`) is a substring.
+    pub fn parse(value: &str) -> Result<TextPattern, String> {
+        let regex = value.strip_prefix('/')
+            .and_then(|body| body.rsplit_once('/'))
+            .filter(|(_, flags)| flags.chars().all(|c| c == 'i'));
+        let Some((pattern, flags)) = regex else {
+            return Ok(TextPattern::Contains(value.to_string()));
+        };
+        RegexBuilder::new(pattern)
+            .case_insensitive(!flags.is_empty())
+            .build()
+            .map(TextPattern::Regex)
+            .map_err(|e| format!("Invalid regular expression '{}': {}", value, e))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            TextPattern::Contains(pattern) => text.to_lowercase().contains(&pattern.to_lowercase()),
+            TextPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
 }
 
 /// Parse filter tokens into a FilterExpr
@@ -67,46 +135,156 @@ pub fn parse_filter(tokens: Vec<String>) -> Result<FilterExpr, String> {
         return Ok(FilterExpr::All); // No filter = match all
     }
 
-    // First pass: parse tokens into filter terms and operators
-    let mut parsed: Vec<FilterToken> = Vec::new();
-    let mut i = 0;
-
-    while i < tokens.len() {
-        let token = &tokens[i];
+    let mut parser = FilterParser { tokens: split_parentheses(&tokens), pos: 0 };
+    if parser.tokens.is_empty() {
+        return Ok(FilterExpr::All);
+    }
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        // parse_or only stops early at a closing parenthesis without a matching '('
+        return Err(parser.error_at(parser.pos, "Unexpected ')' without a matching '('"));
+    }
+    Ok(expr)
+}
 
-        // Check for operators
-        if token == "or" {
-            parsed.push(FilterToken::Or);
-            i += 1;
-            continue;
+/// Separate parentheses attached to terms into their own tokens.
+/// Leading '(' are always split off; trailing ')' only while they are unbalanced within
+/// the term, so values such as `desc=f(x)` keep their parentheses.
+fn split_parentheses(tokens: &[String]) -> Vec<String> {
+    let mut result = Vec::new();
+    for token in tokens {
+        let mut rest = token.as_str();
+        while let Some(inner) = rest.strip_prefix('(') {
+            result.push("(".to_string());
+            rest = inner;
         }
+        let mut closing = 0;
+        while rest.ends_with(')') && rest.matches(')').count() > rest.matches('(').count() {
+            rest = &rest[..rest.len() - 1];
+            closing += 1;
+        }
+        if !rest.is_empty() {
+            result.push(rest.to_string());
+        }
+        result.extend(std::iter::repeat_n(")".to_string(), closing));
+    }
+    result
+}
+
+fn is_keyword(token: &str) -> bool {
+    matches!(token, "and" | "or" | "xor" | "not")
+}
+
+/// Recursive descent parser over filter tokens
+struct FilterParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
 
-        if token == "not" {
-            parsed.push(FilterToken::Not);
-            i += 1;
-            continue;
+    /// Error message followed by the filter with the offending token underlined
+    fn error_at(&self, index: usize, message: &str) -> String {
+        let offset: usize = self.tokens[..index.min(self.tokens.len())].iter()
+            .map(|t| t.chars().count() + 1)
+            .sum();
+        let width = self.tokens.get(index).map(|t| t.chars().count()).unwrap_or(1).max(1);
+        format!("{}\n  {}\n  {}{}", message, self.tokens.join(" "), " ".repeat(offset), "^".repeat(width))
+    }
+
+    /// Check that a binary operator at `op_index` is followed by an operand
+    fn expect_operand(&self, op_index: usize) -> Result<(), String> {
+        match self.peek() {
+            None | Some(")") | Some("and") | Some("or") | Some("xor") => Err(self.error_at(
+                op_index,
+                &format!("'{}' must be followed by a term", self.tokens[op_index]),
+            )),
+            _ => Ok(()),
         }
+    }
 
-        // Parse as filter term
-        match parse_filter_term(token) {
-            Ok(Some(term)) => parsed.push(FilterToken::Term(term)),
-            Ok(None) => return Err(format!("Invalid filter token: {}", token)),
-            Err(err) => return Err(err),
+    fn parse_or(&mut self) -> Result<FilterExpr, String> {
+        let mut operands = vec![self.parse_xor()?];
+        while self.peek() == Some("or") {
+            let op_index = self.pos;
+            self.pos += 1;
+            self.expect_operand(op_index)?;
+            operands.push(self.parse_xor()?);
         }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { FilterExpr::Or(operands) })
+    }
 
-        i += 1;
+    fn parse_xor(&mut self) -> Result<FilterExpr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some("xor") {
+            let op_index = self.pos;
+            self.pos += 1;
+            self.expect_operand(op_index)?;
+            expr = FilterExpr::Xor(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
     }
 
-    // Second pass: build expression tree respecting precedence
-    // Precedence: not > and > or
-    build_expression(parsed)
-}
+    fn parse_and(&mut self) -> Result<FilterExpr, String> {
+        let mut operands = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some("and") => {
+                    let op_index = self.pos;
+                    self.pos += 1;
+                    self.expect_operand(op_index)?;
+                }
+                // Implicit AND between adjacent terms
+                Some(token) if token != ")" && !matches!(token, "or" | "xor") => {}
+                _ => break,
+            }
+            operands.push(self.parse_unary()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { FilterExpr::And(operands) })
+    }
 
-#[derive(Debug, Clone)]
-enum FilterToken {
-    Term(FilterTerm),
-    Not,
-    Or,
+    fn parse_unary(&mut self) -> Result<FilterExpr, String> {
+        let index = self.pos;
+        let Some(token) = self.peek().map(|t| t.to_string()) else {
+            return Err("Filter ends unexpectedly".to_string());
+        };
+        self.pos += 1;
+        match token.as_str() {
+            "not" => {
+                if matches!(self.peek(), None | Some(")")) || self.peek().is_some_and(|t| is_keyword(t) && t != "not") {
+                    return Err(self.error_at(index, "'not' must be followed by a term"));
+                }
+                Ok(FilterExpr::Not(Box::new(self.parse_unary()?)))
+            }
+            "(" => {
+                if self.peek() == Some(")") {
+                    return Err(self.error_at(index, "Empty parentheses"));
+                }
+                if self.peek().is_none() {
+                    return Err(self.error_at(index, "Missing ')' to close this '('"));
+                }
+                let expr = self.parse_or()?;
+                if self.peek() != Some(")") {
+                    return Err(self.error_at(index, "Missing ')' to close this '('"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            ")" => Err(self.error_at(index, "Unexpected ')' without a matching '('")),
+            "and" | "or" | "xor" => Err(self.error_at(
+                index,
+                &format!("'{}' must be preceded by a term", token),
+            )),
+            _ => match parse_filter_term(&token) {
+                Ok(Some(term)) => Ok(FilterExpr::Term(term)),
+                Ok(None) => Err(self.error_at(index, &format!("Invalid filter token: {}", token))),
+                Err(err) => Err(self.error_at(index, &err)),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -118,17 +296,31 @@ pub enum FilterTerm {
     Due(ComparisonOp, String),
     Scheduled(ComparisonOp, String),
     Wait(ComparisonOp, String),
+    Created(ComparisonOp, String),
+    Modified(ComparisonOp, String),
+    End(ComparisonOp, String), // Completion or close time
+    LastActive(ComparisonOp, String), // End of the most recent session (now while it is open)
+    Alloc(ComparisonOp, Option<i64>), // Allocated seconds; None compares with "none"
+    Logged(ComparisonOp, Option<i64>), // Seconds logged in sessions, including an open one
+    Remaining(ComparisonOp, Option<i64>), // Allocation minus logged time; none without an allocation
+    VirtualTag(VirtualTag, bool), // (tag, is_positive), e.g. +OVERDUE
+    Priority(ComparisonOp, f64), // Urgency score; only pending tasks have one
+    Queue(ComparisonOp, Option<i64>), // Position in the queue, 0 at the top; None compares with "none"
     Waiting,
     Kanban(Vec<String>), // Kanban status filter (proposed, stalled, queued, external, done) - supports comma-separated values
-    Desc(String), // Description substring search (case-insensitive)
+    Desc(TextPattern), // Description substring (case-insensitive) or regex search
+    Note(TextPattern), // Any annotation of the task matches
     External(String), // External recipient filter
+    Depends(ComparisonOp, Vec<i64>), // Depends on any of the tasks (= or !=); no IDs compares with "none"
+    Uda(String, ComparisonOp, String), // (name without "uda.", operator, value)
+    UdaMatch(String, TextPattern), // uda.<name>~<pattern>
 }
 
 /// Split a token into (key, operator, value) using operator detection.
 /// Returns None if no operator is found.
 fn split_on_operator(token: &str) -> Option<(String, ComparisonOp, String)> {
     // Find the first operator character position
-    let op_start = token.find(|c: char| c == '=' || c == '>' || c == '<' || c == '!')?;
+    let op_start = token.find(|c: char| c == '=' || c == '>' || c == '<' || c == '!' || c == '~')?;
 
     let key = token[..op_start].to_string();
     if key.is_empty() {
@@ -152,6 +344,8 @@ fn split_on_operator(token: &str) -> Option<(String, ComparisonOp, String)> {
         (ComparisonOp::Gt, 1)
     } else if rest.starts_with('<') {
         (ComparisonOp::Lt, 1)
+    } else if rest.starts_with('~') {
+        (ComparisonOp::Match, 1)
     } else {
         return None;
     };
@@ -163,7 +357,8 @@ fn split_on_operator(token: &str) -> Option<(String, ComparisonOp, String)> {
 /// Known filter keys (exact match only)
 const FILTER_KEYS: &[&str] = &[
     "id", "status", "project", "due", "scheduled", "wait",
-    "kanban", "desc", "description", "external",
+    "created", "modified", "end", "lastactive", "alloc", "logged", "remaining", "priority", "queue",
+    "kanban", "desc", "description", "note", "external", "depends",
 ];
 
 /// Resolve a filter key, supporting unambiguous prefix abbreviations.
@@ -206,6 +401,36 @@ fn resolve_filter_key(key: &str) -> Result<String, String> {
     }
 }
 
+/// Operator of an `any`/`none` comparison against "none": `=any` is `!=none` and `!=any`
+/// is `=none`. None if the value is not `any` or `none`, or the operator is not `=` or `!=`.
+fn presence_op(op: &ComparisonOp, value: &str) -> Option<ComparisonOp> {
+    match (op, value) {
+        (ComparisonOp::Eq | ComparisonOp::Neq, "none") => Some(op.clone()),
+        (ComparisonOp::Eq, "any") => Some(ComparisonOp::Neq),
+        (ComparisonOp::Neq, "any") => Some(ComparisonOp::Eq),
+        _ => None,
+    }
+}
+
+/// Parse the value of a duration filter (alloc, logged, remaining): a duration such as
+/// `1h30m`, `0`, a negative duration such as `-15m`, or `any`/`none` (see `presence_op`)
+fn parse_duration_value(key: &str, op: ComparisonOp, value: &str) -> Result<(ComparisonOp, Option<i64>), String> {
+    if let Some(op) = presence_op(&op, value) {
+        return Ok((op, None));
+    }
+    let (negative, magnitude) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let secs = if magnitude == "0" {
+        0
+    } else {
+        crate::utils::parse_duration(magnitude)
+            .map_err(|e| format!("Invalid duration '{}' in {} filter: {}", value, key, e))?
+    };
+    Ok((op, Some(if negative { -secs } else { secs })))
+}
+
 /// Parse a single filter term token
 fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
     // Bare numeric ID
@@ -215,7 +440,22 @@ fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
 
     // Try to split on operator (=, >, <, >=, <=, !=, <>)
     if let Some((key, op, value)) = split_on_operator(token) {
+        // uda.<name> compares a user-defined attribute
+        if let Some(name) = key.strip_prefix("uda.") {
+            if name.is_empty() {
+                return Err("UDA filter needs a name: uda.<name>=<value>".to_string());
+            }
+            if op == ComparisonOp::Match {
+                return Ok(Some(FilterTerm::UdaMatch(name.to_string(), TextPattern::parse(&value)?)));
+            }
+            return Ok(Some(FilterTerm::Uda(name.to_string(), op, value)));
+        }
         let key_resolved = resolve_filter_key(&key)?;
+        if op == ComparisonOp::Match && !matches!(key_resolved.as_str(), "desc" | "description" | "note") {
+            return Err(format!(
+                "'~' is only supported on desc, note and UDA values (uda.<name>~<text>), not '{}'", key
+            ));
+        }
         return match key_resolved.as_str() {
             "id" => {
                 if let Ok(id) = value.parse::<i64>() {
@@ -254,6 +494,31 @@ fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
             "due" => Ok(Some(FilterTerm::Due(op, value))),
             "scheduled" => Ok(Some(FilterTerm::Scheduled(op, value))),
             "wait" => Ok(Some(FilterTerm::Wait(op, value))),
+            "created" => Ok(Some(FilterTerm::Created(op, value))),
+            "modified" => Ok(Some(FilterTerm::Modified(op, value))),
+            "end" => Ok(Some(FilterTerm::End(op, value))),
+            "lastactive" => Ok(Some(FilterTerm::LastActive(op, value))),
+            "alloc" | "logged" | "remaining" => {
+                let (op, secs) = parse_duration_value(&key_resolved, op, &value)?;
+                Ok(Some(match key_resolved.as_str() {
+                    "alloc" => FilterTerm::Alloc(op, secs),
+                    "logged" => FilterTerm::Logged(op, secs),
+                    _ => FilterTerm::Remaining(op, secs),
+                }))
+            },
+            "priority" => {
+                let score = value.parse::<f64>().ok().filter(|n| n.is_finite())
+                    .ok_or_else(|| format!("Priority filter needs a number, got '{}'", value))?;
+                Ok(Some(FilterTerm::Priority(op, score)))
+            },
+            "queue" => {
+                if let Some(op) = presence_op(&op, &value) {
+                    return Ok(Some(FilterTerm::Queue(op, None)));
+                }
+                let position = value.parse::<i64>()
+                    .map_err(|_| format!("Queue filter needs a position, 'any' or 'none', got '{}'", value))?;
+                Ok(Some(FilterTerm::Queue(op, Some(position))))
+            },
             "kanban" => {
                 if op != ComparisonOp::Eq {
                     return Err(format!("Kanban filter only supports '=' operator, got '{}'", format_op(&op)));
@@ -264,10 +529,18 @@ fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
                 Ok(Some(FilterTerm::Kanban(values)))
             },
             "desc" | "description" => {
-                if op != ComparisonOp::Eq {
-                    return Err(format!("Description filter only supports '=' operator, got '{}'", format_op(&op)));
+                match op {
+                    ComparisonOp::Eq => Ok(Some(FilterTerm::Desc(TextPattern::Contains(value)))),
+                    ComparisonOp::Match => Ok(Some(FilterTerm::Desc(TextPattern::parse(&value)?))),
+                    _ => Err(format!("Description filter only supports '=' and '~' operators, got '{}'", format_op(&op))),
+                }
+            },
+            "note" => {
+                match op {
+                    ComparisonOp::Eq => Ok(Some(FilterTerm::Note(TextPattern::Contains(value)))),
+                    ComparisonOp::Match => Ok(Some(FilterTerm::Note(TextPattern::parse(&value)?))),
+                    _ => Err(format!("Note filter only supports '=' and '~' operators, got '{}'", format_op(&op))),
                 }
-                Ok(Some(FilterTerm::Desc(value)))
             },
             "external" => {
                 if op != ComparisonOp::Eq {
@@ -275,15 +548,34 @@ fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
                 }
                 Ok(Some(FilterTerm::External(value)))
             },
+            "depends" => {
+                if let Some(op) = presence_op(&op, &value) {
+                    return Ok(Some(FilterTerm::Depends(op, Vec::new())));
+                }
+                if !matches!(op, ComparisonOp::Eq | ComparisonOp::Neq) {
+                    return Err(format!("Depends filter only supports '=' and '!=' operators, got '{}'", format_op(&op)));
+                }
+                let ids = value.split(',')
+                    .map(|v| v.trim().parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("Depends filter needs task IDs, 'any' or 'none', got '{}'", value))?;
+                Ok(Some(FilterTerm::Depends(op, ids)))
+            },
             _ => Ok(None),
         };
     }
 
-    // +tag or -tag
+    // +tag or -tag, virtual when the name is one of the upper-case derived tags
     if let Some(tag) = token.strip_prefix('+') {
+        if let Some(virtual_tag) = VirtualTag::parse(tag) {
+            return Ok(Some(FilterTerm::VirtualTag(virtual_tag, true)));
+        }
         return Ok(Some(FilterTerm::Tag(tag.to_string(), true)));
     }
     if let Some(tag) = token.strip_prefix('-') {
+        if let Some(virtual_tag) = VirtualTag::parse(tag) {
+            return Ok(Some(FilterTerm::VirtualTag(virtual_tag, false)));
+        }
         return Ok(Some(FilterTerm::Tag(tag.to_string(), false)));
     }
 
@@ -296,7 +588,7 @@ fn parse_filter_term(token: &str) -> Result<Option<FilterTerm>, String> {
 }
 
 /// Format a ComparisonOp for display
-fn format_op(op: &ComparisonOp) -> &'static str {
+pub(crate) fn format_op(op: &ComparisonOp) -> &'static str {
     match op {
         ComparisonOp::Eq => "=",
         ComparisonOp::Neq => "!=",
@@ -304,103 +596,7 @@ fn format_op(op: &ComparisonOp) -> &'static str {
         ComparisonOp::Lt => "<",
         ComparisonOp::Gte => ">=",
         ComparisonOp::Lte => "<=",
-    }
-}
-
-/// Build expression tree from parsed tokens
-/// Precedence: not > and > or
-fn build_expression(tokens: Vec<FilterToken>) -> Result<FilterExpr, String> {
-    if tokens.is_empty() {
-        return Ok(FilterExpr::All);
-    }
-
-    // First, apply NOT operators (highest precedence)
-    let mut after_not = Vec::new();
-    let mut i = 0;
-    while i < tokens.len() {
-        if let FilterToken::Not = tokens[i] {
-            if i + 1 >= tokens.len() {
-                return Err("NOT operator requires a following term".to_string());
-            }
-            if let FilterToken::Term(term) = &tokens[i + 1] {
-                after_not.push(FilterToken::Term(term.clone()));
-                after_not.push(FilterToken::Not); // Mark as negated
-                i += 2;
-            } else {
-                return Err("NOT operator must be followed by a term".to_string());
-            }
-        } else {
-            after_not.push(tokens[i].clone());
-            i += 1;
-        }
-    }
-
-    // Now handle OR operators (lowest precedence)
-    // Split by OR to get AND groups
-    let mut or_groups: Vec<Vec<FilterToken>> = Vec::new();
-    let mut current_group = Vec::new();
-
-    for token in after_not {
-        if let FilterToken::Or = token {
-            if !current_group.is_empty() {
-                or_groups.push(current_group);
-                current_group = Vec::new();
-            }
-        } else {
-            current_group.push(token);
-        }
-    }
-    if !current_group.is_empty() {
-        or_groups.push(current_group);
-    }
-
-    // Convert each group (AND group) to expression
-    let mut or_exprs = Vec::new();
-    for group in or_groups {
-        let and_expr = build_and_expression(group)?;
-        or_exprs.push(and_expr);
-    }
-
-    // Combine OR expressions
-    if or_exprs.len() == 1 {
-        Ok(or_exprs.remove(0))
-    } else {
-        Ok(FilterExpr::Or(or_exprs))
-    }
-}
-
-/// Build AND expression from a group of terms (implicit AND)
-fn build_and_expression(tokens: Vec<FilterToken>) -> Result<FilterExpr, String> {
-    if tokens.is_empty() {
-        return Ok(FilterExpr::All);
-    }
-
-    let mut and_terms = Vec::new();
-
-    for token in tokens {
-        match token {
-            FilterToken::Term(term) => {
-                and_terms.push(FilterExpr::Term(term));
-            }
-            FilterToken::Not => {
-                // Apply NOT to the last term
-                if let Some(last) = and_terms.pop() {
-                    and_terms.push(FilterExpr::Not(Box::new(last)));
-                } else {
-                    return Err("NOT operator without preceding term".to_string());
-                }
-            }
-            FilterToken::Or => {
-                // Should not happen in AND group, but handle gracefully
-                return Err("Unexpected OR in AND group".to_string());
-            }
-        }
-    }
-
-    if and_terms.len() == 1 {
-        Ok(and_terms.remove(0))
-    } else {
-        Ok(FilterExpr::And(and_terms))
+        ComparisonOp::Match => "~",
     }
 }
 
@@ -550,4 +746,139 @@ mod tests {
         let result = parse_filter(vec!["status>pending".to_string()]);
         assert!(result.is_err());
     }
+
+    fn parse(filter: &str) -> Result<FilterExpr, String> {
+        parse_filter(filter.split_whitespace().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_parentheses_separate_and_attached() {
+        for filter in ["( +a or +b ) +c", "(+a or +b) +c", "(+a or +b) and +c"] {
+            match parse(filter).unwrap() {
+                FilterExpr::And(terms) => {
+                    assert_eq!(terms.len(), 2, "filter '{}'", filter);
+                    assert!(matches!(terms[0], FilterExpr::Or(_)), "filter '{}'", filter);
+                }
+                other => panic!("Expected And expression for '{}', got {:?}", filter, other),
+            }
+        }
+        // Parentheses inside a value are kept
+        match parse("desc=f(x)").unwrap() {
+            FilterExpr::Term(FilterTerm::Desc(TextPattern::Contains(pattern))) => assert_eq!(pattern, "f(x)"),
+            other => panic!("Expected Desc term, got {:?}", other),
+        }
+        match parse("(desc=f(x))").unwrap() {
+            FilterExpr::Term(FilterTerm::Desc(TextPattern::Contains(pattern))) => assert_eq!(pattern, "f(x)"),
+            other => panic!("Expected Desc term, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        // and binds tighter than xor, which binds tighter than or
+        match parse("+a or +b xor +c and +d").unwrap() {
+            FilterExpr::Or(operands) => {
+                assert_eq!(operands.len(), 2);
+                match &operands[1] {
+                    FilterExpr::Xor(_, right) => assert!(matches!(**right, FilterExpr::And(_))),
+                    other => panic!("Expected Xor expression, got {:?}", other),
+                }
+            }
+            other => panic!("Expected Or expression, got {:?}", other),
+        }
+        match parse("not (+a or +b)").unwrap() {
+            FilterExpr::Not(inner) => assert!(matches!(*inner, FilterExpr::Or(_))),
+            other => panic!("Expected Not expression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_token() {
+        let err = parse("(+a or +b +c").unwrap_err();
+        assert!(err.starts_with("Missing ')' to close this '('"));
+        assert!(err.ends_with("\n  ( +a or +b +c\n  ^"), "{}", err);
+
+        let err = parse("+a ) +b").unwrap_err();
+        assert!(err.starts_with("Unexpected ')'"));
+        assert!(err.ends_with("\n  +a ) +b\n     ^"), "{}", err);
+
+        let err = parse("+a or").unwrap_err();
+        assert!(err.starts_with("'or' must be followed by a term"));
+        assert!(err.ends_with("\n  +a or\n     ^^"), "{}", err);
+
+        assert!(parse("and +a").unwrap_err().starts_with("'and' must be preceded by a term"));
+        assert!(parse("+a xor ) ").unwrap_err().starts_with("'xor' must be followed by a term"));
+        assert!(parse("not").unwrap_err().starts_with("'not' must be followed by a term"));
+        assert!(parse("+a ()").unwrap_err().starts_with("Empty parentheses"));
+        assert!(parse("+a bogus=1").unwrap_err().contains("Unknown filter field"));
+        assert!(parse("+a or or +b").is_err());
+    }
+
+    #[test]
+    fn test_parse_uda_terms() {
+        match parse("uda.points>=3").unwrap() {
+            FilterExpr::Term(FilterTerm::Uda(name, ComparisonOp::Gte, value)) => {
+                assert_eq!(name, "points");
+                assert_eq!(value, "3");
+            }
+            other => panic!("Expected Uda term, got {:?}", other),
+        }
+        assert!(matches!(parse("uda.client~acm").unwrap(), FilterExpr::Term(FilterTerm::UdaMatch(_, TextPattern::Contains(_)))));
+        assert!(matches!(parse("uda.ticket=none").unwrap(), FilterExpr::Term(FilterTerm::Uda(_, ComparisonOp::Eq, _))));
+        assert!(parse("uda.=x").unwrap_err().contains("needs a name"));
+        assert!(parse("due~today").unwrap_err().contains("only supported on desc, note and UDA values"));
+    }
+
+    #[test]
+    fn test_parse_text_patterns() {
+        let pattern = |token: &str| match parse(token) {
+            Ok(FilterExpr::Term(FilterTerm::Desc(pattern) | FilterTerm::Note(pattern))) => pattern,
+            other => panic!("Expected a text pattern for '{}', got {:?}", token, other),
+        };
+        let auth = pattern("desc~/auth.*bug/i");
+        assert!(matches!(auth, TextPattern::Regex(_)));
+        assert!(auth.is_match("Fix AUTH token bug") && !auth.is_match("bug in auth"));
+        assert!(!pattern("desc~/^fix/").is_match("Fix it"));
+        assert!(pattern("note~Sent").is_match("sent to finance"));
+        assert!(matches!(pattern("note=/usr/bin"), TextPattern::Contains(_)));
+        assert!(matches!(pattern("desc~/usr/bin"), TextPattern::Contains(_)));
+        assert!(parse("desc~/(unclosed/").unwrap_err().contains("Invalid regular expression"));
+        assert!(parse("note>x").unwrap_err().contains("only supports '=' and '~'"));
+    }
+
+    #[test]
+    fn test_parse_duration_terms() {
+        assert!(matches!(parse("alloc>2h").unwrap(), FilterExpr::Term(FilterTerm::Alloc(ComparisonOp::Gt, Some(7200)))));
+        assert!(matches!(parse("logged>=1h30m").unwrap(), FilterExpr::Term(FilterTerm::Logged(ComparisonOp::Gte, Some(5400)))));
+        assert!(matches!(parse("remaining<0").unwrap(), FilterExpr::Term(FilterTerm::Remaining(ComparisonOp::Lt, Some(0)))));
+        assert!(matches!(parse("remaining<=-15m").unwrap(), FilterExpr::Term(FilterTerm::Remaining(ComparisonOp::Lte, Some(-900)))));
+        assert!(matches!(parse("alloc=none").unwrap(), FilterExpr::Term(FilterTerm::Alloc(ComparisonOp::Eq, None))));
+        assert!(matches!(parse("alloc=any").unwrap(), FilterExpr::Term(FilterTerm::Alloc(ComparisonOp::Neq, None))));
+        assert!(parse("alloc>2x").unwrap_err().contains("Invalid duration '2x' in alloc filter"));
+        assert!(parse("logged>none").unwrap_err().contains("Invalid duration"));
+    }
+
+    #[test]
+    fn test_parse_virtual_tags() {
+        assert!(matches!(parse("+OVERBUDGET").unwrap(), FilterExpr::Term(FilterTerm::VirtualTag(VirtualTag::OverBudget, true))));
+        assert!(matches!(parse("-ACTIVE").unwrap(), FilterExpr::Term(FilterTerm::VirtualTag(VirtualTag::Active, false))));
+        assert!(matches!(parse("+DUETODAY").unwrap(), FilterExpr::Term(FilterTerm::VirtualTag(VirtualTag::DueToday, true))));
+        // Only the upper-case names are virtual
+        assert!(matches!(parse("+active").unwrap(), FilterExpr::Term(FilterTerm::Tag(_, true))));
+        assert!(matches!(parse("+OVERDUEISH").unwrap(), FilterExpr::Term(FilterTerm::Tag(_, true))));
+    }
+
+    #[test]
+    fn test_parse_priority_and_queue_terms() {
+        assert!(matches!(parse("priority>8").unwrap(), FilterExpr::Term(FilterTerm::Priority(ComparisonOp::Gt, n)) if n == 8.0));
+        assert!(matches!(parse("queue<3").unwrap(), FilterExpr::Term(FilterTerm::Queue(ComparisonOp::Lt, Some(3)))));
+        assert!(matches!(parse("queue=none").unwrap(), FilterExpr::Term(FilterTerm::Queue(ComparisonOp::Eq, None))));
+        assert!(matches!(parse("queue=any").unwrap(), FilterExpr::Term(FilterTerm::Queue(ComparisonOp::Neq, None))));
+        assert!(matches!(parse("depends=12,15").unwrap(), FilterExpr::Term(FilterTerm::Depends(ComparisonOp::Eq, ids)) if ids == vec![12, 15]));
+        assert!(matches!(parse("depends=any").unwrap(), FilterExpr::Term(FilterTerm::Depends(ComparisonOp::Neq, ids)) if ids.is_empty()));
+        assert!(parse("depends>3").is_err());
+        assert!(parse("depends=soon").is_err());
+        assert!(parse("priority>high").unwrap_err().contains("needs a number"));
+        assert!(parse("queue>top").unwrap_err().contains("needs a position"));
+    }
 }
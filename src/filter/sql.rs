@@ -0,0 +1,497 @@
+//! Filter compilation to SQL
+//!
+//! Translates a `FilterExpr` into a parameterised WHERE clause over the `tasks` table
+//! (aliased `t`), so that filtering happens in the database instead of loading every task.
+//!
+//! # Translation
+//!
+//! - Attribute terms (`id`, `status`, dates, `waiting`) compare columns of `t`
+//! - `end` and `lastactive` derive their times from `task_events` and `sessions`
+//! - `logged` and `remaining` sum the durations of `sessions`
+//! - Virtual tags probe the tables their state is derived from
+//! - `project` joins `projects`, `+tag`/`-tag` probe `task_tags`
+//! - `queue` counts the items of the default stack ahead of `t`
+//! - `kanban` derives the status from `externals`, `task_dependencies`, `stack_items` and `sessions`
+//! - `external` probes active rows of `externals`, `depends` probes `task_dependencies`
+//! - `note` probes `task_annotations`, `uda.<name>` reads `udas_json` with `json_extract`
+//!
+//! Every clause evaluates to 0 or 1, never NULL, so `not` behaves as it does in memory.
+//!
+//! # Fallback
+//!
+//! Terms that cannot be expressed in SQL (`priority`, regular expressions, text patterns outside ASCII,
+//! whose case folding SQLite does not implement, and UDA comparisons that may be numeric or
+//! by date) are returned as a residual expression and
+//! evaluated in memory on the rows the clause selected. Within an implicit `and` only the
+//! untranslatable terms fall back; an `or`, `xor` or `not` containing one falls back as a whole.
+
+use crate::filter::evaluator::{FilterExpr, uda_comparison_type};
+use crate::filter::parser::{FilterTerm, ComparisonOp, TextPattern};
+use crate::filter::virtual_tags::VirtualTag;
+use crate::config::{Config, UdaType};
+use rusqlite::types::Value;
+
+/// A filter compiled to SQL
+#[derive(Debug, Clone)]
+pub struct SqlFilter {
+    /// Condition on `tasks t`, with `?` placeholders
+    pub clause: String,
+    /// Values bound to the placeholders, in order
+    pub params: Vec<Value>,
+    /// Part of the filter left for in-memory evaluation
+    pub residual: Option<FilterExpr>,
+}
+
+/// Compile a filter expression into a WHERE clause and an optional residual
+pub fn compile_filter(expr: &FilterExpr, config: &Config) -> SqlFilter {
+    let now = chrono::Utc::now().timestamp();
+    let mut clauses = Vec::new();
+    let mut params = Vec::new();
+    let mut residual = Vec::new();
+    split_conjunction(expr, now, config, &mut clauses, &mut params, &mut residual);
+
+    SqlFilter {
+        clause: if clauses.is_empty() { "1".to_string() } else { clauses.join(" AND ") },
+        params,
+        residual: match residual.len() {
+            0 => None,
+            1 => residual.pop(),
+            _ => Some(FilterExpr::And(residual)),
+        },
+    }
+}
+
+/// Translate the operands of an implicit `and` one by one, keeping those that fail
+fn split_conjunction(
+    expr: &FilterExpr,
+    now: i64,
+    config: &Config,
+    clauses: &mut Vec<String>,
+    params: &mut Vec<Value>,
+    residual: &mut Vec<FilterExpr>,
+) {
+    if let FilterExpr::And(exprs) = expr {
+        for expr in exprs {
+            split_conjunction(expr, now, config, clauses, params, residual);
+        }
+        return;
+    }
+    let mut expr_params = Vec::new();
+    match expr_to_sql(expr, now, config, &mut expr_params) {
+        Some(clause) => {
+            clauses.push(clause);
+            params.extend(expr_params);
+        }
+        None => residual.push(expr.clone()),
+    }
+}
+
+/// Translate a whole expression, or None if any term in it cannot be translated
+fn expr_to_sql(expr: &FilterExpr, now: i64, config: &Config, params: &mut Vec<Value>) -> Option<String> {
+    match expr {
+        FilterExpr::All => Some("1".to_string()),
+        FilterExpr::Term(term) => term_to_sql(term, now, config, params),
+        FilterExpr::And(exprs) => join_exprs(exprs, " AND ", now, config, params),
+        FilterExpr::Or(exprs) => join_exprs(exprs, " OR ", now, config, params),
+        FilterExpr::Xor(left, right) => {
+            let left = expr_to_sql(left, now, config, params)?;
+            let right = expr_to_sql(right, now, config, params)?;
+            Some(format!("({} != {})", left, right))
+        }
+        FilterExpr::Not(expr) => expr_to_sql(expr, now, config, params).map(|clause| format!("(NOT {})", clause)),
+    }
+}
+
+fn join_exprs(exprs: &[FilterExpr], separator: &str, now: i64, config: &Config, params: &mut Vec<Value>) -> Option<String> {
+    let clauses = exprs.iter()
+        .map(|expr| expr_to_sql(expr, now, config, params))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", clauses.join(separator)))
+}
+
+/// `?` placeholders for a list of text values
+fn text_list(values: &[String], params: &mut Vec<Value>) -> String {
+    params.extend(values.iter().map(|v| Value::Text(v.clone())));
+    vec!["?"; values.len()].join(", ")
+}
+
+fn term_to_sql(term: &FilterTerm, now: i64, config: &Config, params: &mut Vec<Value>) -> Option<String> {
+    match term {
+        FilterTerm::Id(id) => {
+            params.push(Value::Integer(*id));
+            Some("(t.id = ?)".to_string())
+        }
+        FilterTerm::Status(statuses) => {
+            Some(format!("(t.status IN ({}))", text_list(statuses, params)))
+        }
+        FilterTerm::Project(project_names) => {
+            let mut alternatives = Vec::new();
+            if project_names.iter().any(|n| n.is_empty() || n.eq_ignore_ascii_case("none")) {
+                alternatives.push("t.project_id IS NULL".to_string());
+            }
+            for name in project_names {
+                if name.is_empty() || name.eq_ignore_ascii_case("none") {
+                    continue;
+                }
+                // Exact match, or a nested project below it (the prefix is bound for its length too)
+                let prefix = format!("{}.", name);
+                params.extend([Value::Text(name.clone()), Value::Text(prefix.clone()), Value::Text(prefix)]);
+                alternatives.push("EXISTS (SELECT 1 FROM projects p WHERE p.id = t.project_id
+                    AND (p.name = ? OR substr(p.name, 1, length(?)) = ?))".to_string());
+            }
+            Some(format!("({})", alternatives.join(" OR ")))
+        }
+        FilterTerm::Tag(tag, is_positive) => {
+            params.push(Value::Text(tag.clone()));
+            let exists = "EXISTS (SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag = ?)";
+            Some(if *is_positive { format!("({})", exists) } else { format!("(NOT {})", exists) })
+        }
+        FilterTerm::Due(op, expr) => Some(date_to_sql("t.due_ts", op, expr, params)),
+        FilterTerm::Scheduled(op, expr) => Some(date_to_sql("t.scheduled_ts", op, expr, params)),
+        FilterTerm::Wait(op, expr) => Some(date_to_sql("t.wait_ts", op, expr, params)),
+        FilterTerm::Created(op, expr) => Some(date_to_sql("t.created_ts", op, expr, params)),
+        FilterTerm::Modified(op, expr) => Some(date_to_sql("t.modified_ts", op, expr, params)),
+        FilterTerm::End(op, expr) => Some(date_to_sql(END_SQL, op, expr, params)),
+        FilterTerm::LastActive(op, expr) => {
+            let last_active = format!("(SELECT COALESCE(se.end_ts, {}) FROM sessions se
+                WHERE se.task_id = t.id ORDER BY se.start_ts DESC LIMIT 1)", now);
+            Some(date_to_sql(&last_active, op, expr, params))
+        }
+        FilterTerm::Alloc(op, secs) => Some(integer_to_sql("t.alloc_secs", op, *secs, params)),
+        FilterTerm::Logged(op, secs) => Some(integer_to_sql(&logged_sql(now), op, *secs, params)),
+        FilterTerm::Remaining(op, secs) => {
+            let remaining = format!("(t.alloc_secs - {})", logged_sql(now));
+            Some(integer_to_sql(&remaining, op, *secs, params))
+        }
+        FilterTerm::VirtualTag(tag, is_positive) => {
+            let clause = virtual_tag_to_sql(*tag, now, params);
+            Some(if *is_positive { clause } else { format!("(NOT {})", clause) })
+        }
+        // The urgency score depends on configured coefficients
+        FilterTerm::Priority(..) => None,
+        FilterTerm::Queue(op, position) => Some(integer_to_sql(QUEUE_SQL, op, *position, params)),
+        FilterTerm::Waiting => {
+            params.push(Value::Integer(now));
+            Some("(t.wait_ts IS NOT NULL AND t.wait_ts > ?)".to_string())
+        }
+        FilterTerm::Kanban(statuses) => {
+            Some(format!("({} IN ({}))", KANBAN_SQL, text_list(statuses, params)))
+        }
+        FilterTerm::Desc(pattern) => contains_to_sql("t.description", pattern, params),
+        FilterTerm::Note(pattern) => {
+            let clause = contains_to_sql("a.note", pattern, params)?;
+            Some(format!("(EXISTS (SELECT 1 FROM task_annotations a WHERE a.task_id = t.id AND {}))", clause))
+        }
+        FilterTerm::External(recipient) => {
+            params.push(Value::Text(recipient.clone()));
+            Some("(EXISTS (SELECT 1 FROM externals e
+                WHERE e.task_id = t.id AND e.returned_ts IS NULL AND e.recipient = ?))".to_string())
+        }
+        FilterTerm::Depends(op, ids) => {
+            let mut clause = "EXISTS (SELECT 1 FROM task_dependencies d WHERE d.task_id = t.id".to_string();
+            if !ids.is_empty() {
+                params.extend(ids.iter().map(|id| Value::Integer(*id)));
+                clause.push_str(&format!(" AND d.depends_on_id IN ({})", vec!["?"; ids.len()].join(", ")));
+            }
+            clause.push(')');
+            // With no IDs, `=` asks for tasks without dependencies
+            let matches = (*op == ComparisonOp::Eq) != ids.is_empty();
+            Some(if matches { format!("({})", clause) } else { format!("(NOT {})", clause) })
+        }
+        FilterTerm::Uda(name, op, expr) => uda_to_sql(name, op, expr, config, params),
+        FilterTerm::UdaMatch(name, pattern) => {
+            if name.contains('"') || !matches!(pattern, TextPattern::Contains(_)) {
+                return None;
+            }
+            let value = uda_value_sql(name, params);
+            let clause = contains_to_sql(value, pattern, params)?;
+            Some(format!("(coalesce({}, 0))", clause))
+        }
+    }
+}
+
+/// Case-insensitive substring match on a text expression, or None for regular
+/// expressions and for patterns outside ASCII (SQLite's lower() only folds ASCII letters)
+fn contains_to_sql(column: &str, pattern: &TextPattern, params: &mut Vec<Value>) -> Option<String> {
+    match pattern {
+        TextPattern::Contains(text) if text.is_ascii() => {
+            params.push(Value::Text(text.to_lowercase()));
+            Some(format!("(instr(lower({}), ?) > 0)", column))
+        }
+        _ => None,
+    }
+}
+
+/// Value of a UDA, binding its JSON path. Rows whose UDAs do not parse have none in memory.
+fn uda_value_sql(name: &str, params: &mut Vec<Value>) -> &'static str {
+    params.push(Value::Text(format!("$.\"{}\"", name)));
+    "(CASE WHEN json_valid(t.udas_json) THEN json_extract(t.udas_json, ?) END)"
+}
+
+/// Compare a UDA value as a string, or None when the comparison needs parsing
+/// (numbers and dates)
+fn uda_to_sql(name: &str, op: &ComparisonOp, expr: &str, config: &Config, params: &mut Vec<Value>) -> Option<String> {
+    if name.contains('"') {
+        return None;
+    }
+    let is_presence = matches!((op, expr), (ComparisonOp::Eq | ComparisonOp::Neq, "any" | "none"));
+    if !is_presence && uda_comparison_type(name, expr, config) != UdaType::String {
+        return None;
+    }
+
+    let value = uda_value_sql(name, params);
+    Some(match (op, expr) {
+        (ComparisonOp::Eq, "any") | (ComparisonOp::Neq, "none") => format!("({} IS NOT NULL)", value),
+        (ComparisonOp::Eq, "none") | (ComparisonOp::Neq, "any") => format!("({} IS NULL)", value),
+        _ => {
+            params.push(Value::Text(expr.to_string()));
+            let (sql_op, missing) = match op {
+                ComparisonOp::Neq => ("!=", 1),
+                ComparisonOp::Gt => (">", 0),
+                ComparisonOp::Lt => ("<", 0),
+                ComparisonOp::Gte => (">=", 0),
+                ComparisonOp::Lte => ("<=", 0),
+                _ => ("=", 0),
+            };
+            format!("(coalesce({} {} ?, {}))", value, sql_op, missing)
+        }
+    })
+}
+
+/// Kanban status of `t`, as computed by the in-memory evaluator
+const KANBAN_SQL: &str = "CASE
+    WHEN t.status IN ('completed', 'closed') THEN 'done'
+    WHEN EXISTS (SELECT 1 FROM externals e WHERE e.task_id = t.id AND e.returned_ts IS NULL) THEN 'external'
+    WHEN t.status = 'pending' AND EXISTS (SELECT 1 FROM task_dependencies d JOIN tasks u ON u.id = d.depends_on_id
+                 WHERE d.task_id = t.id AND u.status = 'pending') THEN 'blocked'
+    WHEN EXISTS (SELECT 1 FROM stack_items si
+                 WHERE si.stack_id = (SELECT id FROM stacks WHERE name = 'default') AND si.task_id = t.id) THEN 'queued'
+    WHEN EXISTS (SELECT 1 FROM sessions se WHERE se.task_id = t.id) THEN 'stalled'
+    ELSE 'proposed'
+END";
+
+/// Position of `t` in the default stack, 0 at the top, or NULL when it is not queued
+const QUEUE_SQL: &str = "(SELECT (SELECT COUNT(*) FROM stack_items ahead
+                               WHERE ahead.stack_id = si.stack_id AND ahead.ordinal < si.ordinal)
+    FROM stack_items si
+    WHERE si.stack_id = (SELECT id FROM stacks WHERE name = 'default') AND si.task_id = t.id)";
+
+/// Completion or close time of `t`, as returned by `TaskRepo::get_end_ts`
+const END_SQL: &str = "(CASE WHEN t.status IN ('completed', 'closed') THEN COALESCE(
+    (SELECT MAX(e.ts) FROM task_events e
+     WHERE e.task_id = t.id AND e.event_type = 'status_changed'
+       AND json_extract(e.payload_json, '$.new_status') IN ('completed', 'closed')),
+    t.modified_ts) END)";
+
+/// Seconds logged on `t`, as returned by `TaskRepo::get_total_logged_time`
+fn logged_sql(now: i64) -> String {
+    format!("(SELECT COALESCE(SUM(COALESCE(se.end_ts, {}) - se.start_ts), 0) FROM sessions se
+        WHERE se.task_id = t.id)", now)
+}
+
+/// Whether `t` carries a virtual tag, as `VirtualTag::applies_to` decides
+fn virtual_tag_to_sql(tag: VirtualTag, now: i64, params: &mut Vec<Value>) -> String {
+    match tag {
+        VirtualTag::Overdue | VirtualTag::DueToday | VirtualTag::DueWeek => {
+            let (start, end) = tag.due_window(now);
+            let mut clause = "(t.status = 'pending' AND t.due_ts IS NOT NULL".to_string();
+            if let Some(start) = start {
+                params.push(Value::Integer(start));
+                clause.push_str(" AND t.due_ts >= ?");
+            }
+            params.push(Value::Integer(end));
+            clause.push_str(" AND t.due_ts < ?)");
+            clause
+        }
+        VirtualTag::Active => "(EXISTS (SELECT 1 FROM sessions se WHERE se.task_id = t.id AND se.end_ts IS NULL))".to_string(),
+        VirtualTag::Queued => "(EXISTS (SELECT 1 FROM stack_items si
+            WHERE si.stack_id = (SELECT id FROM stacks WHERE name = 'default') AND si.task_id = t.id))".to_string(),
+        VirtualTag::External => "(EXISTS (SELECT 1 FROM externals e WHERE e.task_id = t.id AND e.returned_ts IS NULL))".to_string(),
+        VirtualTag::Respawning => "(t.respawn IS NOT NULL)".to_string(),
+        VirtualTag::Annotated => "(EXISTS (SELECT 1 FROM task_annotations a WHERE a.task_id = t.id))".to_string(),
+        VirtualTag::Uda => "(CASE WHEN json_valid(t.udas_json) THEN EXISTS (SELECT 1 FROM json_each(t.udas_json)) ELSE 0 END)".to_string(),
+        VirtualTag::Templated => "(t.template IS NOT NULL)".to_string(),
+        VirtualTag::OverBudget => format!("(t.alloc_secs IS NOT NULL AND {} > t.alloc_secs)", logged_sql(now)),
+        VirtualTag::Blocked => "(t.status = 'pending' AND EXISTS (SELECT 1 FROM task_dependencies d
+            JOIN tasks u ON u.id = d.depends_on_id WHERE d.task_id = t.id AND u.status = 'pending'))".to_string(),
+        VirtualTag::Blocking => "(t.status = 'pending' AND EXISTS (SELECT 1 FROM task_dependencies d
+            JOIN tasks down ON down.id = d.task_id WHERE d.depends_on_id = t.id AND down.status = 'pending'))".to_string(),
+    }
+}
+
+/// Compare an integer expression (a duration in seconds, a queue position) with a filter
+/// value, `None` standing for "none". NULL only matches `!=`, as in memory.
+fn integer_to_sql(column: &str, op: &ComparisonOp, filter_secs: Option<i64>, params: &mut Vec<Value>) -> String {
+    let Some(filter_secs) = filter_secs else {
+        return match op {
+            ComparisonOp::Eq => format!("({} IS NULL)", column),
+            ComparisonOp::Neq => format!("({} IS NOT NULL)", column),
+            _ => "(0)".to_string(),
+        };
+    };
+    params.push(Value::Integer(filter_secs));
+    let (sql_op, missing) = match op {
+        ComparisonOp::Neq => ("!=", 1),
+        ComparisonOp::Gt => (">", 0),
+        ComparisonOp::Lt => ("<", 0),
+        ComparisonOp::Gte => (">=", 0),
+        ComparisonOp::Lte => ("<=", 0),
+        _ => ("=", 0),
+    };
+    format!("(coalesce({} {} ?, {}))", column, sql_op, missing)
+}
+
+/// Compare a timestamp column with a date expression. Dates that fail to parse match
+/// nothing, as in memory; `=` and `!=` compare calendar days (UTC).
+fn date_to_sql(column: &str, op: &ComparisonOp, expr: &str, params: &mut Vec<Value>) -> String {
+    match (op, expr) {
+        (ComparisonOp::Eq, "any") | (ComparisonOp::Neq, "none") => return format!("({} IS NOT NULL)", column),
+        (ComparisonOp::Eq, "none") | (ComparisonOp::Neq, "any") => return format!("({} IS NULL)", column),
+        // The parser only accepts `~` on UDA values
+        (ComparisonOp::Match, _) => return "(0)".to_string(),
+        _ => {}
+    }
+    let Ok(filter_ts) = crate::utils::parse_date_expr(expr) else {
+        return "(0)".to_string();
+    };
+    params.push(Value::Integer(filter_ts));
+    match op {
+        ComparisonOp::Eq => format!("({0} IS NOT NULL AND date({0}, 'unixepoch') = date(?, 'unixepoch'))", column),
+        ComparisonOp::Neq => format!("({0} IS NULL OR date({0}, 'unixepoch') != date(?, 'unixepoch'))", column),
+        ComparisonOp::Gt => format!("({0} IS NOT NULL AND {0} > ?)", column),
+        ComparisonOp::Lt => format!("({0} IS NOT NULL AND {0} < ?)", column),
+        ComparisonOp::Gte => format!("({0} IS NOT NULL AND {0} >= ?)", column),
+        ComparisonOp::Lte => format!("({0} IS NOT NULL AND {0} <= ?)", column),
+        ComparisonOp::Match => unreachable!("handled above"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::filter::parse_filter;
+    use crate::repo::{TaskRepo, ProjectRepo, StackRepo, SessionRepo, ExternalRepo, AnnotationRepo, DependencyRepo};
+    use std::collections::HashMap;
+
+    fn matching_ids(conn: &rusqlite::Connection, filter: &str) -> (Vec<i64>, Vec<i64>) {
+        let tokens = filter.split_whitespace().map(|t| t.to_string()).collect();
+        let expr = parse_filter(tokens).unwrap();
+        let compiled: Vec<i64> = crate::filter::filter_tasks(conn, &expr, &Config::default()).unwrap()
+            .iter().filter_map(|(task, _)| task.id).collect();
+        let in_memory: Vec<i64> = TaskRepo::list_all(conn).unwrap().into_iter()
+            .filter(|(task, _)| expr.matches(task, conn, &Config::default()).unwrap())
+            .filter_map(|(task, _)| task.id)
+            .collect();
+        (compiled, in_memory)
+    }
+
+    #[test]
+    fn test_compiled_filters_match_in_memory_evaluation() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let admin = ProjectRepo::create(&conn, "admin").unwrap();
+        let email = ProjectRepo::create(&conn, "admin.email").unwrap();
+        let adminx = ProjectRepo::create(&conn, "adminx").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let no_udas = HashMap::new();
+
+        let queued = TaskRepo::create_full(&conn, "Queued Report", admin.id, Some(now + 86400), None, None, Some(7200),
+            None, None, &no_udas, &["urgent".to_string()]).unwrap().id.unwrap();
+        let stalled = TaskRepo::create_full(&conn, "Stalled email", email.id, None, Some(now - 86400), Some(now + 3600),
+            Some(1800), None, None, &no_udas, &["urgent".to_string(), "home".to_string()]).unwrap().id.unwrap();
+        let external = TaskRepo::create_full(&conn, "Waiting on bob", adminx.id, Some(now - 86400), None, Some(now - 3600),
+            None, None, None, &no_udas, &[]).unwrap().id.unwrap();
+        let done = TaskRepo::create(&conn, "Finished report", None).unwrap().id.unwrap();
+        let proposed = TaskRepo::create_full(&conn, "Proposed idea", None, Some(now + 60), None, None, None,
+            Some("weekly".to_string()), Some("daily".to_string()), &no_udas, &[]).unwrap().id.unwrap();
+        for udas in [
+            [("client", "acme"), ("points", "3"), ("due", "2026-01-15")],
+            [("client", "Globex"), ("points", "10"), ("due", "2026-03-01")],
+            [("client", "initech"), ("points", "2"), ("due", "soon")],
+        ] {
+            let udas = udas.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            TaskRepo::create_full(&conn, "With udas", None, None, None, None, None, None, None, &udas, &[]).unwrap();
+        }
+
+        let stack = StackRepo::get_or_create_default(&conn).unwrap();
+        StackRepo::enqueue(&conn, stack.id.unwrap(), queued).unwrap();
+        StackRepo::enqueue(&conn, stack.id.unwrap(), external).unwrap();
+        SessionRepo::create_closed(&conn, stalled, now - 7200, now - 3600).unwrap();
+        ExternalRepo::create(&conn, external, "bob".to_string(), None).unwrap();
+        TaskRepo::complete(&conn, done).unwrap();
+        AnnotationRepo::create(&conn, stalled, "Sent to Finance".to_string(), None).unwrap();
+        SessionRepo::create(&conn, proposed, now - 600, &Config::default()).unwrap();
+        // The tasks with UDAs (6 to 8) wait on a queued task, a done task and each other
+        DependencyRepo::set(&conn, 6, &[queued]).unwrap();
+        DependencyRepo::set(&conn, 7, &[done]).unwrap();
+        DependencyRepo::set(&conn, 8, &[6, done]).unwrap();
+
+        for filter in [
+            "1", "id=3", "status=pending", "status=completed,pending",
+            "project=admin", "project=admin.email", "project=adminx", "project=none", "project=none,admin.email",
+            "+urgent", "-urgent", "+urgent +home", "+urgent or -home", "not +urgent", "not project=admin",
+            "due=any", "due=none", "due!=none", "due>today", "due<today", "due>=-2d", "due<=+2d", "due=tomorrow", "due!=tomorrow",
+            "scheduled<today", "wait=any", "waiting", "not waiting", "due>notadate", "not due>notadate",
+            "kanban=queued", "kanban=stalled", "kanban=external", "kanban=done", "kanban=proposed,done",
+            "desc=REPORT", "description=email", "desc=", "desc~report", "desc~/^[A-Z][a-z]+\\sr/",
+            "note~FINANCE", "note=nothing", "not note~finance", "note~/fin.nce/", "external=bob", "external=alice",
+            "project=admin +urgent or kanban=done", "not kanban=queued status=pending",
+            "(project=admin or project=none) and +urgent", "+urgent xor +home", "not +urgent xor waiting",
+            "( due=any or waiting ) xor kanban=external",
+            "uda.client=acme", "uda.client!=acme", "uda.client=any", "uda.client=none", "uda.client!=none",
+            "uda.client>b", "uda.client<=acme", "uda.client~CM", "not uda.client~cm", "uda.client~/^G/i", "uda.points>=3",
+            "uda.points<10", "uda.points!=3", "uda.points=2.0", "uda.due<2026-02-01", "uda.due=2026-01-15",
+            "uda.points>=3 or uda.client=globex", "uda.missing=any",
+            "created=today", "created>-1h", "modified<=today", "modified!=yesterday",
+            "end=any", "end=none", "end=today", "end>=-7d", "not end<-7d",
+            "lastactive=any", "lastactive=none", "lastactive<-30d", "lastactive>=-3h", "lastactive=today",
+            "alloc>1h", "alloc<=30m", "alloc=none", "alloc=any", "alloc!=2h", "logged>=30m", "logged=0", "logged!=1h",
+            "remaining<0", "remaining>=2h", "remaining!=none", "not remaining<0", "+OVERBUDGET", "-OVERBUDGET",
+            "queue=0", "queue=1", "queue<1", "queue>=1", "queue!=0", "queue=none", "queue=any", "not queue<3",
+            "priority>1", "priority<=1 or queue=0",
+            "+OVERDUE", "-OVERDUE", "+DUETODAY", "+DUEWEEK", "-DUEWEEK", "+ACTIVE", "-ACTIVE", "+QUEUED", "+EXTERNAL",
+            "+RESPAWNING", "+ANNOTATED", "-ANNOTATED", "+UDA", "-UDA", "+TEMPLATED", "+QUEUED or +ACTIVE",
+            "+BLOCKED", "-BLOCKED", "+BLOCKING", "-BLOCKING", "kanban=blocked", "kanban=queued,blocked",
+            "depends=1", "depends=4,6", "depends!=4", "depends=any", "depends=none", "not depends=none",
+        ] {
+            let (compiled, in_memory) = matching_ids(&conn, filter);
+            assert_eq!(compiled, in_memory, "filter '{}'", filter);
+        }
+    }
+
+    #[test]
+    fn test_untranslatable_terms_fall_back_to_memory() {
+        let expr = parse_filter(vec!["+urgent".to_string(), "desc=Ärger".to_string()]).unwrap();
+        let compiled = compile_filter(&expr, &Config::default());
+        assert!(compiled.clause.contains("task_tags"));
+        assert!(matches!(compiled.residual, Some(FilterExpr::Term(FilterTerm::Desc(_)))));
+
+        // An `or` containing an untranslatable term falls back as a whole
+        let expr = parse_filter(vec!["+urgent".to_string(), "or".to_string(), "desc=Ärger".to_string()]).unwrap();
+        let compiled = compile_filter(&expr, &Config::default());
+        assert_eq!(compiled.clause, "1");
+        assert!(compiled.params.is_empty());
+        assert!(matches!(compiled.residual, Some(FilterExpr::Or(_))));
+
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Viel Ärger", None).unwrap();
+        TaskRepo::create(&conn, "Other", None).unwrap();
+        let expr = parse_filter(vec!["desc=ärger".to_string()]).unwrap();
+        let results = crate::filter::filter_tasks(&conn, &expr, &Config::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, task.id);
+    }
+
+    #[test]
+    fn test_uda_comparisons_compile_only_as_strings() {
+        let compile = |token: &str| compile_filter(&parse_filter(vec![token.to_string()]).unwrap(), &Config::default());
+        for token in ["uda.client=acme", "uda.client~acm", "uda.points=none", "uda.points!=any"] {
+            let compiled = compile(token);
+            assert!(compiled.clause.contains("json_extract") && compiled.residual.is_none(), "{}", token);
+        }
+        // Numbers and dates are parsed per task, in memory
+        for token in ["uda.points>=3", "uda.due<tomorrow", "uda.client~Ärger", "uda.client~/^a/", "desc~/^fix/", "note~/x/", "priority>8"] {
+            assert!(compile(token).residual.is_some(), "{}", token);
+        }
+    }
+}
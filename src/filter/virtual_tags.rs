@@ -0,0 +1,122 @@
+//! Virtual tags
+//!
+//! Read-only tags derived from a task's state when a filter asks for them, written in
+//! upper case like `+OVERDUE` or `-ACTIVE`. They cannot be added to or removed from tasks.
+//!
+//! - `OVERDUE` - Pending and due before now
+//! - `DUETODAY` - Pending and due today
+//! - `DUEWEEK` - Pending and due within the next 7 days, today included
+//! - `ACTIVE` - Has the open session
+//! - `QUEUED` - In the queue
+//! - `EXTERNAL` - Sent to an external recipient that has not returned it
+//! - `RESPAWNING` - Has a respawn rule
+//! - `ANNOTATED` - Has annotations
+//! - `UDA` - Has user-defined attributes
+//! - `TEMPLATED` - Created from a template
+//! - `OVERBUDGET` - More time logged than allocated
+//! - `BLOCKED` - Pending and depends on a pending task
+//! - `BLOCKING` - Pending and a pending task depends on it
+
+use crate::models::{Task, TaskStatus};
+use crate::repo::{TaskRepo, SessionRepo, StackRepo, ExternalRepo, AnnotationRepo, DependencyRepo};
+use rusqlite::Connection;
+use anyhow::Result;
+
+/// A tag computed from a task's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualTag {
+    Overdue,
+    DueToday,
+    DueWeek,
+    Active,
+    Queued,
+    External,
+    Respawning,
+    Annotated,
+    Uda,
+    Templated,
+    OverBudget,
+    Blocked,
+    Blocking,
+}
+
+impl VirtualTag {
+    pub const ALL: [VirtualTag; 13] = [
+        VirtualTag::Overdue, VirtualTag::DueToday, VirtualTag::DueWeek, VirtualTag::Active,
+        VirtualTag::Queued, VirtualTag::External, VirtualTag::Respawning, VirtualTag::Annotated,
+        VirtualTag::Uda, VirtualTag::Templated, VirtualTag::OverBudget, VirtualTag::Blocked,
+        VirtualTag::Blocking,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VirtualTag::Overdue => "OVERDUE",
+            VirtualTag::DueToday => "DUETODAY",
+            VirtualTag::DueWeek => "DUEWEEK",
+            VirtualTag::Active => "ACTIVE",
+            VirtualTag::Queued => "QUEUED",
+            VirtualTag::External => "EXTERNAL",
+            VirtualTag::Respawning => "RESPAWNING",
+            VirtualTag::Annotated => "ANNOTATED",
+            VirtualTag::Uda => "UDA",
+            VirtualTag::Templated => "TEMPLATED",
+            VirtualTag::OverBudget => "OVERBUDGET",
+            VirtualTag::Blocked => "BLOCKED",
+            VirtualTag::Blocking => "BLOCKING",
+        }
+    }
+
+    /// The virtual tag with this name (case-sensitive: `+active` is an ordinary tag)
+    pub fn parse(name: &str) -> Option<VirtualTag> {
+        Self::ALL.into_iter().find(|tag| tag.name() == name)
+    }
+
+    /// Whether the task carries this tag
+    pub fn applies_to(self, task: &Task, conn: &Connection) -> Result<bool> {
+        let task_id = task.id.unwrap_or(0);
+        Ok(match self {
+            VirtualTag::Overdue | VirtualTag::DueToday | VirtualTag::DueWeek => {
+                let Some(due_ts) = task.due_ts.filter(|_| task.status == TaskStatus::Pending) else {
+                    return Ok(false);
+                };
+                let (start, end) = self.due_window(chrono::Utc::now().timestamp());
+                start.is_none_or(|start| due_ts >= start) && due_ts < end
+            }
+            VirtualTag::Active => SessionRepo::get_open(conn)?.is_some_and(|s| s.task_id == task_id),
+            VirtualTag::Queued => StackRepo::default_items(conn)?.iter().any(|item| item.task_id == task_id),
+            VirtualTag::External => ExternalRepo::has_active_externals(conn, task_id)?,
+            VirtualTag::Respawning => task.respawn.is_some(),
+            VirtualTag::Annotated => !AnnotationRepo::get_by_task(conn, task_id)?.is_empty(),
+            VirtualTag::Uda => !task.udas.is_empty(),
+            VirtualTag::Templated => task.template.is_some(),
+            VirtualTag::OverBudget => match task.alloc_secs {
+                Some(alloc) => TaskRepo::get_total_logged_time(conn, task_id)? > alloc,
+                None => false,
+            },
+            VirtualTag::Blocked => DependencyRepo::is_blocked(conn, task_id)?,
+            VirtualTag::Blocking => DependencyRepo::is_blocking(conn, task_id)?,
+        })
+    }
+
+    /// Range of due times `[start, end)` of the due tags, unbounded below for OVERDUE.
+    /// Days start at local midnight, as `today` does in date expressions.
+    pub(crate) fn due_window(self, now: i64) -> (Option<i64>, i64) {
+        let today = crate::utils::parse_date_expr("today").unwrap_or(now);
+        match self {
+            VirtualTag::Overdue => (None, now),
+            VirtualTag::DueToday => (Some(today), crate::utils::parse_date_expr("tomorrow").unwrap_or(today + 86400)),
+            _ => (Some(today), today + 7 * 86400),
+        }
+    }
+}
+
+/// Virtual tags carried by a task, in the order of `VirtualTag::ALL`
+pub fn virtual_tags_for(task: &Task, conn: &Connection) -> Result<Vec<VirtualTag>> {
+    let mut tags = Vec::new();
+    for tag in VirtualTag::ALL {
+        if tag.applies_to(task, conn)? {
+            tags.push(tag);
+        }
+    }
+    Ok(tags)
+}
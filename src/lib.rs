@@ -7,6 +7,9 @@
 //! - CLI command parsing and execution
 //! - Filter expression parsing and evaluation
 //! - Respawn rule parsing and task respawning on completion
+//! - Ledger export and import
+//! - Ledger integrity checks
+//! - Typed configuration (`~/.tatl/rc`)
 //! - Date/time and duration utilities
 //!
 //! # Example
@@ -28,4 +31,7 @@ pub mod repo;
 pub mod cli;
 pub mod utils;
 pub mod filter;
-pub mod respawn;
\ No newline at end of file
+pub mod respawn;
+pub mod transfer;
+pub mod doctor;
+pub mod config;
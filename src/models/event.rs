@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::models::Task;
+
+/// Task event model (one row of the append-only task_events log)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub id: Option<i64>,
+    pub task_id: i64,
+    pub ts: i64,
+    pub event_type: String,
+    pub payload: Value,
+}
+
+/// One row of the `all_events` view: a task event or an audit event of a session, external or project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEvent {
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub task_id: Option<i64>,
+    pub ts: i64,
+    pub event_type: String,
+    pub payload: Value,
+}
+
+/// Field-level before/after values decoded from an event payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+/// Optional attributes recorded in a created event (older events only have project_id)
+const CREATED_FIELDS: [&str; 8] = [
+    "project_id", "due_ts", "scheduled_ts", "wait_ts", "alloc_secs", "template", "respawn", "udas",
+];
+
+impl FieldChange {
+    fn new(field: &str, old_value: Value, new_value: Value) -> Self {
+        Self { field: field.to_string(), old_value, new_value }
+    }
+}
+
+impl TaskEvent {
+    fn payload_value(&self, key: &str) -> Value {
+        self.payload.get(key).cloned().unwrap_or(Value::Null)
+    }
+
+    /// Decode the attribute changes recorded by this event.
+    /// Events that do not change a task attribute (queue, session, annotation) return no changes.
+    pub fn changes(&self) -> Vec<FieldChange> {
+        match self.event_type.as_str() {
+            "created" => {
+                let mut changes = vec![FieldChange::new("description", Value::Null, self.payload_value("description"))];
+                for field in CREATED_FIELDS {
+                    let value = self.payload_value(field);
+                    if !value.is_null() {
+                        changes.push(FieldChange::new(field, Value::Null, value));
+                    }
+                }
+                changes
+            }
+            "modified" => match self.payload.get("field").and_then(|f| f.as_str()) {
+                Some(field) => vec![FieldChange::new(field, self.payload_value("old_value"), self.payload_value("new_value"))],
+                None => Vec::new(),
+            },
+            "status_changed" => vec![FieldChange::new(
+                "status",
+                self.payload_value("old_status"),
+                self.payload_value("new_status"),
+            )],
+            "tag_added" => vec![FieldChange::new("tag", Value::Null, self.payload_value("tag"))],
+            "tag_removed" => vec![FieldChange::new("tag", self.payload_value("tag"), Value::Null)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Task attributes and tags rebuilt by replaying the event log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSnapshot {
+    pub description: String,
+    pub status: String,
+    pub project_id: Option<i64>,
+    pub due_ts: Option<i64>,
+    pub scheduled_ts: Option<i64>,
+    pub wait_ts: Option<i64>,
+    pub alloc_secs: Option<i64>,
+    pub template: Option<String>,
+    pub respawn: Option<String>,
+    pub udas: BTreeMap<String, String>,
+    pub tags: BTreeSet<String>,
+}
+
+/// A field whose replayed value differs from the stored one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub field: String,
+    pub replayed: Value,
+    pub stored: Value,
+}
+
+/// Decode a recorded UDA map; older events store it as a JSON string
+fn udas_from_value(value: &Value) -> BTreeMap<String, String> {
+    let object = match value {
+        Value::String(raw) => serde_json::from_str::<Value>(raw).unwrap_or(Value::Null),
+        other => other.clone(),
+    };
+    match object {
+        Value::Object(map) => map.into_iter()
+            .map(|(k, v)| (k, v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string())))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+impl Default for TaskSnapshot {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            status: "pending".to_string(),
+            project_id: None,
+            due_ts: None,
+            scheduled_ts: None,
+            wait_ts: None,
+            alloc_secs: None,
+            template: None,
+            respawn: None,
+            udas: BTreeMap::new(),
+            tags: BTreeSet::new(),
+        }
+    }
+}
+
+impl TaskSnapshot {
+    /// Current state of a stored task
+    pub fn from_task(task: &Task, tags: &[String]) -> Self {
+        Self {
+            description: task.description.clone(),
+            status: task.status.as_str().to_string(),
+            project_id: task.project_id,
+            due_ts: task.due_ts,
+            scheduled_ts: task.scheduled_ts,
+            wait_ts: task.wait_ts,
+            alloc_secs: task.alloc_secs,
+            template: task.template.clone(),
+            respawn: task.respawn.clone(),
+            udas: task.udas.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            tags: tags.iter().cloned().collect(),
+        }
+    }
+
+    /// Replay events in order. Returns None when there are no events.
+    pub fn replay(events: &[TaskEvent]) -> Option<Self> {
+        if events.is_empty() {
+            return None;
+        }
+        let mut snapshot = Self::default();
+        for event in events {
+            snapshot.apply(event);
+        }
+        Some(snapshot)
+    }
+
+    /// Apply one event. Events that do not touch attributes or tags are ignored.
+    pub fn apply(&mut self, event: &TaskEvent) {
+        match event.event_type.as_str() {
+            "created" => {
+                // Older created events only carry description and project
+                for (field, value) in event.payload.as_object().into_iter().flatten() {
+                    self.set(field, value);
+                }
+            }
+            "modified" => {
+                if let Some(field) = event.payload.get("field").and_then(|f| f.as_str()) {
+                    self.set(field, &event.payload_value("new_value"));
+                }
+            }
+            "status_changed" => {
+                if let Some(status) = event.payload.get("new_status").and_then(|s| s.as_str()) {
+                    self.status = status.to_string();
+                }
+            }
+            "tag_added" => {
+                if let Some(tag) = event.payload.get("tag").and_then(|t| t.as_str()) {
+                    self.tags.insert(tag.to_string());
+                }
+            }
+            "tag_removed" => {
+                if let Some(tag) = event.payload.get("tag").and_then(|t| t.as_str()) {
+                    self.tags.remove(tag);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set(&mut self, field: &str, value: &Value) {
+        let text = value.as_str().map(str::to_string);
+        match field {
+            "description" => self.description = text.unwrap_or_default(),
+            "status" => if let Some(status) = text { self.status = status },
+            "project_id" => self.project_id = value.as_i64(),
+            "due_ts" => self.due_ts = value.as_i64(),
+            "scheduled_ts" => self.scheduled_ts = value.as_i64(),
+            "wait_ts" => self.wait_ts = value.as_i64(),
+            "alloc_secs" => self.alloc_secs = value.as_i64(),
+            "template" => self.template = text,
+            "respawn" => self.respawn = text,
+            "udas" => self.udas = udas_from_value(value),
+            _ => {}
+        }
+    }
+
+    /// Field values in a comparable form
+    pub fn fields(&self) -> Vec<(&'static str, Value)> {
+        let int = |v: Option<i64>| v.map(Value::from).unwrap_or(Value::Null);
+        let text = |v: &Option<String>| v.clone().map(Value::String).unwrap_or(Value::Null);
+        vec![
+            ("description", Value::String(self.description.clone())),
+            ("status", Value::String(self.status.clone())),
+            ("project_id", int(self.project_id)),
+            ("due_ts", int(self.due_ts)),
+            ("scheduled_ts", int(self.scheduled_ts)),
+            ("wait_ts", int(self.wait_ts)),
+            ("alloc_secs", int(self.alloc_secs)),
+            ("template", text(&self.template)),
+            ("respawn", text(&self.respawn)),
+            ("udas", serde_json::to_value(&self.udas).unwrap_or(Value::Null)),
+            ("tags", serde_json::to_value(&self.tags).unwrap_or(Value::Null)),
+        ]
+    }
+
+    /// Fields where this (replayed) snapshot differs from the stored one
+    pub fn divergences(&self, stored: &TaskSnapshot) -> Vec<Divergence> {
+        self.fields().into_iter()
+            .zip(stored.fields())
+            .filter(|((_, replayed), (_, stored))| replayed != stored)
+            .map(|((field, replayed), (_, stored))| Divergence { field: field.to_string(), replayed, stored })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, payload: Value) -> TaskEvent {
+        TaskEvent { id: None, task_id: 1, ts: 0, event_type: event_type.to_string(), payload }
+    }
+
+    #[test]
+    fn test_modified_event_changes() {
+        let e = event("modified", serde_json::json!({"field": "due_ts", "old_value": 100, "new_value": 200}));
+        assert_eq!(e.changes(), vec![FieldChange::new("due_ts", Value::from(100), Value::from(200))]);
+    }
+
+    #[test]
+    fn test_status_and_tag_changes() {
+        let e = event("status_changed", serde_json::json!({"old_status": "pending", "new_status": "completed"}));
+        assert_eq!(e.changes()[0].field, "status");
+        assert_eq!(e.changes()[0].new_value, "completed");
+
+        let e = event("tag_removed", serde_json::json!({"tag": "urgent"}));
+        assert_eq!(e.changes()[0].old_value, "urgent");
+        assert!(e.changes()[0].new_value.is_null());
+
+        assert!(event("stack_added", serde_json::json!({"stack_id": 1})).changes().is_empty());
+    }
+
+    #[test]
+    fn test_replay_rebuilds_attributes_and_tags() {
+        let events = vec![
+            event("tag_added", serde_json::json!({"tag": "home"})),
+            event("created", serde_json::json!({"description": "Alpha", "project_id": 2, "due_ts": 100})),
+            event("modified", serde_json::json!({"field": "due_ts", "old_value": 100, "new_value": 200})),
+            event("modified", serde_json::json!({"field": "udas", "old_value": null, "new_value": {"size": "L"}})),
+            event("tag_added", serde_json::json!({"tag": "work"})),
+            event("tag_removed", serde_json::json!({"tag": "home"})),
+            event("status_changed", serde_json::json!({"old_status": "pending", "new_status": "completed"})),
+        ];
+        let snapshot = TaskSnapshot::replay(&events).unwrap();
+        assert_eq!(snapshot.description, "Alpha");
+        assert_eq!(snapshot.project_id, Some(2));
+        assert_eq!(snapshot.due_ts, Some(200));
+        assert_eq!(snapshot.udas.get("size").map(String::as_str), Some("L"));
+        assert_eq!(snapshot.tags.iter().collect::<Vec<_>>(), vec!["work"]);
+        assert_eq!(snapshot.status, "completed");
+
+        let mut stored = snapshot.clone();
+        stored.due_ts = None;
+        let divergences = snapshot.divergences(&stored);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].field, "due_ts");
+        assert_eq!(divergences[0].replayed, Value::from(200));
+
+        assert!(TaskSnapshot::replay(&[]).is_none());
+    }
+}
@@ -7,10 +7,12 @@ pub mod session;
 pub mod stack;
 pub mod annotation;
 pub mod external;
+pub mod event;
 
 pub use task::*;
 pub use project::*;
 pub use session::*;
 pub use stack::*;
 pub use annotation::*;
-pub use external::*;
\ No newline at end of file
+pub use external::*;
+pub use event::*;
\ No newline at end of file
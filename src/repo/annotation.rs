@@ -175,6 +175,7 @@ impl AnnotationRepo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
     use crate::db::DbConnection;
     use crate::repo::TaskRepo;
 
@@ -201,7 +202,7 @@ mod tests {
         // Create a session first
         use crate::repo::SessionRepo;
         let start_ts = chrono::Utc::now().timestamp();
-        let session = SessionRepo::create(&conn, task_id, start_ts).unwrap();
+        let session = SessionRepo::create(&conn, task_id, start_ts, &Config::default()).unwrap();
         let session_id = session.id.unwrap();
         
         // Create annotation linked to session
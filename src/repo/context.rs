@@ -0,0 +1,128 @@
+use rusqlite::{Connection, OptionalExtension};
+use anyhow::{Context, Result};
+
+/// A named filter ANDed into filter-taking commands while it is active
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterContext {
+    pub name: String,
+    pub filter_tokens: Vec<String>,
+    pub is_active: bool,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+/// Repository for filter contexts
+pub struct ContextRepo;
+
+impl ContextRepo {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<FilterContext> {
+        let filter_json: String = row.get(1)?;
+        Ok(FilterContext {
+            name: row.get(0)?,
+            filter_tokens: serde_json::from_str(&filter_json).unwrap_or_default(),
+            is_active: row.get(2)?,
+            created_ts: row.get(3)?,
+            modified_ts: row.get(4)?,
+        })
+    }
+
+    pub fn get_by_name(conn: &Connection, name: &str) -> Result<Option<FilterContext>> {
+        let context = conn.query_row(
+            "SELECT name, filter_json, is_active, created_ts, modified_ts FROM contexts WHERE name = ?1",
+            [name],
+            Self::from_row,
+        ).optional()?;
+        Ok(context)
+    }
+
+    /// The active context, if any
+    pub fn get_active(conn: &Connection) -> Result<Option<FilterContext>> {
+        let context = conn.query_row(
+            "SELECT name, filter_json, is_active, created_ts, modified_ts FROM contexts WHERE is_active = 1",
+            [],
+            Self::from_row,
+        ).optional()?;
+        Ok(context)
+    }
+
+    /// All contexts, by name
+    pub fn list(conn: &Connection) -> Result<Vec<FilterContext>> {
+        let mut stmt = conn.prepare(
+            "SELECT name, filter_json, is_active, created_ts, modified_ts FROM contexts ORDER BY name"
+        )?;
+        let contexts = stmt.query_map([], Self::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(contexts)
+    }
+
+    /// Create a context or replace its filter, keeping it active if it was
+    pub fn define(conn: &Connection, name: &str, filter_tokens: &[String]) -> Result<FilterContext> {
+        let now = chrono::Utc::now().timestamp();
+        let filter_json = serde_json::to_string(filter_tokens)?;
+        conn.execute(
+            "INSERT INTO contexts (name, filter_json, is_active, created_ts, modified_ts)
+             VALUES (?1, ?2, 0, ?3, ?3)
+             ON CONFLICT(name) DO UPDATE SET
+               filter_json = excluded.filter_json,
+               modified_ts = excluded.modified_ts",
+            rusqlite::params![name, filter_json, now],
+        )
+        .with_context(|| format!("Failed to save context '{}'", name))?;
+        Ok(Self::get_by_name(conn, name)?.expect("context was just saved"))
+    }
+
+    /// Make a context the active one, or deactivate all with None.
+    /// Returns false if the named context does not exist.
+    pub fn set_active(conn: &Connection, name: Option<&str>) -> Result<bool> {
+        if let Some(name) = name {
+            if Self::get_by_name(conn, name)?.is_none() {
+                return Ok(false);
+            }
+        }
+        conn.execute("UPDATE contexts SET is_active = 0 WHERE is_active = 1", [])?;
+        if let Some(name) = name {
+            conn.execute("UPDATE contexts SET is_active = 1 WHERE name = ?1", [name])?;
+        }
+        Ok(true)
+    }
+
+    /// Delete a context. Returns false if it does not exist.
+    pub fn delete(conn: &Connection, name: &str) -> Result<bool> {
+        let deleted = conn.execute("DELETE FROM contexts WHERE name = ?1", [name])?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+
+    fn tokens(filter: &str) -> Vec<String> {
+        filter.split_whitespace().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_define_activate_and_delete() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        ContextRepo::define(&conn, "work", &tokens("project=work or +oncall")).unwrap();
+        ContextRepo::define(&conn, "home", &tokens("project=home")).unwrap();
+        assert_eq!(ContextRepo::get_active(&conn).unwrap(), None);
+
+        assert!(ContextRepo::set_active(&conn, Some("work")).unwrap());
+        assert!(ContextRepo::set_active(&conn, Some("home")).unwrap());
+        assert!(!ContextRepo::set_active(&conn, Some("gym")).unwrap());
+        let active = ContextRepo::get_active(&conn).unwrap().unwrap();
+        assert_eq!(active.name, "home");
+
+        // Redefining keeps the context active
+        ContextRepo::define(&conn, "home", &tokens("project=home +errand")).unwrap();
+        assert_eq!(ContextRepo::get_active(&conn).unwrap().unwrap().filter_tokens, tokens("project=home +errand"));
+
+        assert!(ContextRepo::set_active(&conn, None).unwrap());
+        assert_eq!(ContextRepo::get_active(&conn).unwrap(), None);
+        assert!(ContextRepo::delete(&conn, "work").unwrap());
+        assert!(!ContextRepo::delete(&conn, "work").unwrap());
+        assert_eq!(ContextRepo::list(&conn).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+use rusqlite::Connection;
+use anyhow::{bail, Result};
+use crate::repo::{EventRepo, TaskRepo};
+use crate::models::TaskStatus;
+
+/// Repository for task dependencies.
+///
+/// A task depends on its upstream tasks and is blocked while any of them is pending.
+/// Blocking is derived from task status, so finishing or closing the last pending
+/// upstream task unblocks its dependents without touching this table.
+pub struct DependencyRepo;
+
+impl DependencyRepo {
+    fn ids(conn: &Connection, sql: &str, task_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = conn.prepare(sql)?;
+        let ids = stmt.query_map([task_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Tasks this task depends on (upstream)
+    pub fn get_dependencies(conn: &Connection, task_id: i64) -> Result<Vec<i64>> {
+        Self::ids(conn,
+            "SELECT depends_on_id FROM task_dependencies WHERE task_id = ?1 ORDER BY depends_on_id",
+            task_id)
+    }
+
+    /// Tasks that depend on this task (downstream)
+    pub fn get_dependents(conn: &Connection, task_id: i64) -> Result<Vec<i64>> {
+        Self::ids(conn,
+            "SELECT task_id FROM task_dependencies WHERE depends_on_id = ?1 ORDER BY task_id",
+            task_id)
+    }
+
+    /// Pending tasks this task depends on
+    pub fn get_blockers(conn: &Connection, task_id: i64) -> Result<Vec<i64>> {
+        Self::ids(conn,
+            "SELECT d.depends_on_id FROM task_dependencies d JOIN tasks u ON u.id = d.depends_on_id
+             WHERE d.task_id = ?1 AND u.status = 'pending' ORDER BY d.depends_on_id",
+            task_id)
+    }
+
+    /// Whether a pending task waits on another pending task
+    pub fn is_blocked(conn: &Connection, task_id: i64) -> Result<bool> {
+        let blocked = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM tasks t JOIN task_dependencies d ON d.task_id = t.id
+                            JOIN tasks u ON u.id = d.depends_on_id
+                            WHERE t.id = ?1 AND t.status = 'pending' AND u.status = 'pending')",
+            [task_id],
+            |row| row.get(0),
+        )?;
+        Ok(blocked)
+    }
+
+    /// Whether a pending task holds up another pending task
+    pub fn is_blocking(conn: &Connection, task_id: i64) -> Result<bool> {
+        let blocking = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM tasks t JOIN task_dependencies d ON d.depends_on_id = t.id
+                            JOIN tasks down ON down.id = d.task_id
+                            WHERE t.id = ?1 AND t.status = 'pending' AND down.status = 'pending')",
+            [task_id],
+            |row| row.get(0),
+        )?;
+        Ok(blocking)
+    }
+
+    /// Whether `depends_on_id` already depends on `task_id`, directly or through other tasks,
+    /// so that making `task_id` depend on it would close a cycle
+    pub fn would_create_cycle(conn: &Connection, task_id: i64, depends_on_id: i64) -> Result<bool> {
+        if task_id == depends_on_id {
+            return Ok(true);
+        }
+        let cycle = conn.query_row(
+            "WITH RECURSIVE upstream(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT d.depends_on_id FROM task_dependencies d JOIN upstream u ON d.task_id = u.id
+             )
+             SELECT EXISTS (SELECT 1 FROM upstream WHERE id = ?2)",
+            rusqlite::params![depends_on_id, task_id],
+            |row| row.get(0),
+        )?;
+        Ok(cycle)
+    }
+
+    /// Replace the tasks a task depends on, recording an event for each change.
+    /// Fails without changing anything if a task does not exist or a dependency would form a cycle.
+    pub fn set(conn: &Connection, task_id: i64, depends_on: &[i64]) -> Result<()> {
+        let current = Self::get_dependencies(conn, task_id)?;
+        for &depends_on_id in depends_on {
+            if depends_on_id == task_id {
+                bail!("Task {} cannot depend on itself", task_id);
+            }
+            match TaskRepo::get_by_id(conn, depends_on_id)? {
+                Some(task) if task.status != TaskStatus::Deleted => {}
+                _ => bail!("Task {} not found", depends_on_id),
+            }
+            if !current.contains(&depends_on_id) && Self::would_create_cycle(conn, task_id, depends_on_id)? {
+                bail!("Task {} cannot depend on task {}: task {} already depends on task {}, which would form a cycle",
+                    task_id, depends_on_id, depends_on_id, task_id);
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        for &removed in current.iter().filter(|id| !depends_on.contains(id)) {
+            conn.execute(
+                "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+                rusqlite::params![task_id, removed],
+            )?;
+            EventRepo::record_dependency_removed(conn, task_id, removed)?;
+        }
+        for &added in depends_on {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_ts) VALUES (?1, ?2, ?3)",
+                rusqlite::params![task_id, added, now],
+            )?;
+            if inserted > 0 {
+                EventRepo::record_dependency_added(conn, task_id, added)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pending dependents of a task that no longer wait on any pending task,
+    /// e.g. after the task was finished
+    pub fn get_unblocked_dependents(conn: &Connection, task_id: i64) -> Result<Vec<i64>> {
+        let mut unblocked = Vec::new();
+        for dependent in Self::get_dependents(conn, task_id)? {
+            let pending = TaskRepo::get_by_id(conn, dependent)?
+                .is_some_and(|task| task.status == TaskStatus::Pending);
+            if pending && Self::get_blockers(conn, dependent)?.is_empty() {
+                unblocked.push(dependent);
+            }
+        }
+        Ok(unblocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+
+    #[test]
+    fn test_dependencies_block_until_finished() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let design = TaskRepo::create(&conn, "Design", None).unwrap().id.unwrap();
+        let build = TaskRepo::create(&conn, "Build", None).unwrap().id.unwrap();
+        let ship = TaskRepo::create(&conn, "Ship", None).unwrap().id.unwrap();
+
+        DependencyRepo::set(&conn, build, &[design]).unwrap();
+        DependencyRepo::set(&conn, ship, &[build, design]).unwrap();
+        assert_eq!(DependencyRepo::get_dependencies(&conn, ship).unwrap(), vec![design, build]);
+        assert_eq!(DependencyRepo::get_dependents(&conn, design).unwrap(), vec![build, ship]);
+        assert!(DependencyRepo::is_blocked(&conn, build).unwrap());
+        assert!(DependencyRepo::is_blocking(&conn, design).unwrap());
+        assert!(!DependencyRepo::is_blocked(&conn, design).unwrap());
+
+        TaskRepo::complete(&conn, design).unwrap();
+        assert_eq!(DependencyRepo::get_unblocked_dependents(&conn, design).unwrap(), vec![build]);
+        assert!(!DependencyRepo::is_blocked(&conn, build).unwrap());
+        assert_eq!(DependencyRepo::get_blockers(&conn, ship).unwrap(), vec![build]);
+
+        DependencyRepo::set(&conn, ship, &[]).unwrap();
+        assert!(DependencyRepo::get_dependencies(&conn, ship).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cycles_and_missing_tasks_are_rejected() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let a = TaskRepo::create(&conn, "A", None).unwrap().id.unwrap();
+        let b = TaskRepo::create(&conn, "B", None).unwrap().id.unwrap();
+        let c = TaskRepo::create(&conn, "C", None).unwrap().id.unwrap();
+        DependencyRepo::set(&conn, b, &[a]).unwrap();
+        DependencyRepo::set(&conn, c, &[b]).unwrap();
+
+        let err = DependencyRepo::set(&conn, a, &[c]).unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{}", err);
+        assert!(DependencyRepo::set(&conn, a, &[a]).is_err());
+        assert!(DependencyRepo::set(&conn, a, &[99]).is_err());
+        assert!(DependencyRepo::get_dependencies(&conn, a).unwrap().is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 use rusqlite::Connection;
 use serde_json;
 use anyhow::Result;
+use crate::models::{Task, TaskEvent, TaskSnapshot, Divergence, LedgerEvent};
+use crate::repo::TaskRepo;
 
 /// Event repository for recording immutable task events
 pub struct EventRepo;
@@ -13,12 +15,54 @@ pub enum EventType {
     StatusChanged,
     TagAdded,
     TagRemoved,
+    DependencyAdded,
+    DependencyRemoved,
     AnnotationAdded,
     AnnotationDeleted,
     StackAdded,
     StackRemoved,
     SessionStarted,
     SessionEnded,
+    Undone,
+    SessionAdded,
+    SessionModified,
+    SessionDeleted,
+    SessionMerged,
+    SessionPurged,
+    ExternalSent,
+    ExternalReturned,
+    ProjectRenamed,
+    ProjectMerged,
+    ProjectArchived,
+    ProjectUnarchived,
+}
+
+/// Kinds of non-task records with an audit trail in `entity_events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityType {
+    Session,
+    External,
+    Project,
+}
+
+impl EntityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityType::Session => "session",
+            EntityType::External => "external",
+            EntityType::Project => "project",
+        }
+    }
+
+    /// Entity type owning rows of a journaled table, if it is audited
+    pub fn for_table(table: &str) -> Option<Self> {
+        match table {
+            "sessions" => Some(EntityType::Session),
+            "externals" => Some(EntityType::External),
+            "projects" => Some(EntityType::Project),
+            _ => None,
+        }
+    }
 }
 
 impl EventType {
@@ -29,12 +73,26 @@ impl EventType {
             EventType::StatusChanged => "status_changed",
             EventType::TagAdded => "tag_added",
             EventType::TagRemoved => "tag_removed",
+            EventType::DependencyAdded => "dependency_added",
+            EventType::DependencyRemoved => "dependency_removed",
             EventType::AnnotationAdded => "annotation_added",
             EventType::AnnotationDeleted => "annotation_deleted",
             EventType::StackAdded => "stack_added",
             EventType::StackRemoved => "stack_removed",
             EventType::SessionStarted => "session_started",
             EventType::SessionEnded => "session_ended",
+            EventType::Undone => "undone",
+            EventType::SessionAdded => "session_added",
+            EventType::SessionModified => "session_modified",
+            EventType::SessionDeleted => "session_deleted",
+            EventType::SessionMerged => "session_merged",
+            EventType::SessionPurged => "session_purged",
+            EventType::ExternalSent => "external_sent",
+            EventType::ExternalReturned => "external_returned",
+            EventType::ProjectRenamed => "project_renamed",
+            EventType::ProjectMerged => "project_merged",
+            EventType::ProjectArchived => "project_archived",
+            EventType::ProjectUnarchived => "project_unarchived",
         }
     }
 }
@@ -47,7 +105,17 @@ impl EventRepo {
         event_type: EventType,
         payload: serde_json::Value,
     ) -> Result<()> {
-        let ts = chrono::Utc::now().timestamp();
+        Self::record_at(conn, task_id, chrono::Utc::now().timestamp(), event_type, payload)
+    }
+
+    /// Record an event with an explicit timestamp (used when importing history)
+    pub fn record_at(
+        conn: &Connection,
+        task_id: i64,
+        ts: i64,
+        event_type: EventType,
+        payload: serde_json::Value,
+    ) -> Result<()> {
         let payload_json = serde_json::to_string(&payload)?;
         
         conn.execute(
@@ -58,16 +126,127 @@ impl EventRepo {
         Ok(())
     }
 
-    /// Record task created event
+    /// Record an audit event for a session, external or project
+    pub fn record_entity(
+        conn: &Connection,
+        entity_type: EntityType,
+        entity_id: i64,
+        task_id: Option<i64>,
+        event_type: EventType,
+        payload: serde_json::Value,
+    ) -> Result<()> {
+        let payload_json = serde_json::to_string(&payload)?;
+        conn.execute(
+            "INSERT INTO entity_events (entity_type, entity_id, task_id, ts, event_type, payload_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                entity_type.as_str(),
+                entity_id,
+                task_id,
+                chrono::Utc::now().timestamp(),
+                event_type.as_str(),
+                payload_json
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Events of every entity (tasks included) from the `all_events` view, in chronological order
+    pub fn list_all(
+        conn: &Connection,
+        entity_type: Option<&str>,
+        entity_id: Option<i64>,
+        since_ts: Option<i64>,
+    ) -> Result<Vec<LedgerEvent>> {
+        let mut stmt = conn.prepare(
+            "SELECT entity_type, entity_id, task_id, ts, event_type, payload_json FROM all_events
+             WHERE (?1 IS NULL OR entity_type = ?1)
+               AND (?2 IS NULL OR entity_id = ?2)
+               AND ts >= ?3
+             ORDER BY ts, entity_type, entity_id"
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![entity_type, entity_id, since_ts.unwrap_or(i64::MIN)],
+            |row| {
+                let payload_json: Option<String> = row.get(5)?;
+                Ok(LedgerEvent {
+                    entity_type: row.get(0)?,
+                    entity_id: row.get(1)?,
+                    task_id: row.get(2)?,
+                    ts: row.get(3)?,
+                    event_type: row.get(4)?,
+                    payload: payload_json
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or(serde_json::Value::Null),
+                })
+            },
+        )?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Get a task's events in chronological order, optionally only those at or after `since_ts`
+    pub fn get_by_task(conn: &Connection, task_id: i64, since_ts: Option<i64>) -> Result<Vec<TaskEvent>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, ts, event_type, payload_json FROM task_events
+             WHERE task_id = ?1 AND ts >= ?2
+             ORDER BY ts, id"
+        )?;
+        let rows = stmt.query_map(rusqlite::params![task_id, since_ts.unwrap_or(i64::MIN)], |row| {
+            let payload_json: Option<String> = row.get(4)?;
+            Ok(TaskEvent {
+                id: Some(row.get(0)?),
+                task_id: row.get(1)?,
+                ts: row.get(2)?,
+                event_type: row.get(3)?,
+                payload: payload_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or(serde_json::Value::Null),
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Rebuild a task's attributes and tags by replaying its events, optionally only up to `at_ts`.
+    /// Returns None when the task has no events (yet).
+    pub fn replay(conn: &Connection, task_id: i64, at_ts: Option<i64>) -> Result<Option<TaskSnapshot>> {
+        let events: Vec<TaskEvent> = Self::get_by_task(conn, task_id, None)?
+            .into_iter()
+            .filter(|event| at_ts.is_none_or(|at| event.ts <= at))
+            .collect();
+        Ok(TaskSnapshot::replay(&events))
+    }
+
+    /// Compare the replayed event log of a task with its `tasks` and `task_tags` rows
+    pub fn verify(conn: &Connection, task_id: i64) -> Result<Vec<Divergence>> {
+        let task = TaskRepo::get_by_id(conn, task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+        let stored = TaskSnapshot::from_task(&task, &TaskRepo::get_tags(conn, task_id)?);
+        let replayed = Self::replay(conn, task_id, None)?.unwrap_or_default();
+        Ok(replayed.divergences(&stored))
+    }
+
+    /// Record task created event with the task's initial attributes
     pub fn record_created(
         conn: &Connection,
         task_id: i64,
-        description: &str,
-        project_id: Option<i64>,
+        task: &Task,
     ) -> Result<()> {
+        let udas = if task.udas.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::to_value(&task.udas)?
+        };
         let payload = serde_json::json!({
-            "description": description,
-            "project_id": project_id,
+            "description": task.description,
+            "status": task.status.as_str(),
+            "project_id": task.project_id,
+            "due_ts": task.due_ts,
+            "scheduled_ts": task.scheduled_ts,
+            "wait_ts": task.wait_ts,
+            "alloc_secs": task.alloc_secs,
+            "template": task.template,
+            "respawn": task.respawn,
+            "udas": udas,
         });
         Self::record(conn, task_id, EventType::Created, payload)
     }
@@ -126,6 +305,30 @@ impl EventRepo {
         Self::record(conn, task_id, EventType::TagRemoved, payload)
     }
 
+    /// Record dependency added event
+    pub fn record_dependency_added(
+        conn: &Connection,
+        task_id: i64,
+        depends_on_id: i64,
+    ) -> Result<()> {
+        let payload = serde_json::json!({
+            "depends_on": depends_on_id,
+        });
+        Self::record(conn, task_id, EventType::DependencyAdded, payload)
+    }
+
+    /// Record dependency removed event
+    pub fn record_dependency_removed(
+        conn: &Connection,
+        task_id: i64,
+        depends_on_id: i64,
+    ) -> Result<()> {
+        let payload = serde_json::json!({
+            "depends_on": depends_on_id,
+        });
+        Self::record(conn, task_id, EventType::DependencyRemoved, payload)
+    }
+
     /// Record annotation added event
     pub fn record_annotation_added(
         conn: &Connection,
@@ -213,7 +416,7 @@ impl EventRepo {
 mod tests {
     use super::*;
     use crate::db::DbConnection;
-    use crate::repo::TaskRepo;
+    use std::collections::HashMap;
 
     #[test]
     fn test_record_created() {
@@ -242,4 +445,65 @@ mod tests {
         assert_eq!(payload_value["old_status"], "pending");
         assert_eq!(payload_value["new_status"], "completed");
     }
+
+    #[test]
+    fn test_get_by_task_since() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Test task", None).unwrap();
+        let task_id = task.id.unwrap();
+        EventRepo::record_at(&conn, task_id, 100, EventType::TagAdded, serde_json::json!({"tag": "a"})).unwrap();
+
+        let events = EventRepo::get_by_task(&conn, task_id, None).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "tag_added");
+        let recent = EventRepo::get_by_task(&conn, task_id, Some(1_000)).unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].event_type, "created");
+    }
+
+    #[test]
+    fn test_verify_matches_and_detects_divergence() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let mut udas = HashMap::new();
+        udas.insert("size".to_string(), "L".to_string());
+        let task = TaskRepo::create_full(&conn, "Test task", None, Some(100), None, Some(50), Some(3600),
+            None, None, &udas, &["home".to_string()]).unwrap();
+        let task_id = task.id.unwrap();
+        TaskRepo::modify(&conn, task_id, None, None, Some(Some(200)), None, None, None, None, None,
+            &HashMap::new(), &["size".to_string()], &["work".to_string()], &[]).unwrap();
+        TaskRepo::complete(&conn, task_id).unwrap();
+        assert!(EventRepo::verify(&conn, task_id).unwrap().is_empty());
+
+        conn.execute("UPDATE tasks SET due_ts = 300 WHERE id = ?1", [task_id]).unwrap();
+        let divergences = EventRepo::verify(&conn, task_id).unwrap();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].field, "due_ts");
+        assert_eq!(divergences[0].replayed, 200);
+
+        let before = EventRepo::replay(&conn, task_id, Some(i64::MIN)).unwrap();
+        assert!(before.is_none());
+    }
+
+    #[test]
+    fn test_entity_events_appear_in_all_events() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&conn, "Test task", None).unwrap();
+        let task_id = task.id.unwrap();
+        let project = crate::repo::ProjectRepo::create(&conn, "work").unwrap();
+        crate::repo::ProjectRepo::rename(&conn, "work", "office").unwrap();
+        EventRepo::record_entity(&conn, EntityType::Session, 7, Some(task_id),
+            EventType::SessionDeleted, serde_json::json!({"start_ts": 100, "end_ts": 200})).unwrap();
+
+        let all = EventRepo::list_all(&conn, None, None, None).unwrap();
+        assert!(all.iter().any(|e| e.entity_type == "task" && e.event_type == "created"));
+
+        let renamed = EventRepo::list_all(&conn, Some("project"), project.id, None).unwrap();
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].event_type, "project_renamed");
+        assert_eq!(renamed[0].payload["new_name"], "office");
+
+        let sessions = EventRepo::list_all(&conn, Some("session"), Some(7), None).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].task_id, Some(task_id));
+    }
 }
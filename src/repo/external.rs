@@ -1,5 +1,6 @@
 use rusqlite::{Connection, OptionalExtension};
 use crate::models::External;
+use crate::repo::{EventRepo, EventType, EntityType};
 use anyhow::{Context, Result};
 
 pub struct ExternalRepo;
@@ -35,6 +36,8 @@ impl ExternalRepo {
         .with_context(|| format!("Failed to create external record for task {} to {}", task_id, recipient))?;
         
         let id = conn.last_insert_rowid();
+        EventRepo::record_entity(conn, EntityType::External, id, Some(task_id), EventType::ExternalSent,
+            serde_json::json!({ "recipient": recipient, "request": request }))?;
         let mut result = external;
         result.id = Some(id);
         Ok(result)
@@ -140,17 +143,25 @@ impl ExternalRepo {
             anyhow::bail!("External {} not found", external_id);
         }
         
+        let (task_id, recipient): (i64, String) = conn.query_row(
+            "SELECT task_id, recipient FROM externals WHERE id = ?1",
+            [external_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        EventRepo::record_entity(conn, EntityType::External, external_id, Some(task_id), EventType::ExternalReturned,
+            serde_json::json!({ "recipient": recipient }))?;
+        
         Ok(())
     }
     
     /// Mark all externals for a task as returned
     pub fn mark_all_returned_for_task(conn: &Connection, task_id: i64) -> Result<()> {
-        let now = chrono::Utc::now().timestamp();
-        conn.execute(
-            "UPDATE externals SET returned_ts = ?1, modified_ts = ?2 WHERE task_id = ?3 AND returned_ts IS NULL",
-            rusqlite::params![now, now, task_id],
-        )
-        .with_context(|| format!("Failed to mark externals for task {} as returned", task_id))?;
+        for external in Self::get_active_for_task(conn, task_id)? {
+            if let Some(id) = external.id {
+                Self::mark_returned(conn, id)
+                    .with_context(|| format!("Failed to mark externals for task {} as returned", task_id))?;
+            }
+        }
         
         Ok(())
     }
@@ -6,7 +6,11 @@ pub mod annotation;
 pub mod event;
 pub mod template;
 pub mod view;
+pub mod context;
+pub mod dependency;
 pub mod external;
+pub mod undo;
+pub mod search;
 
 pub use project::*;
 pub use task::*;
@@ -16,4 +20,8 @@ pub use annotation::*;
 pub use event::*;
 pub use template::*;
 pub use view::*;
-pub use external::*;
\ No newline at end of file
+pub use context::*;
+pub use dependency::*;
+pub use external::*;
+pub use undo::*;
+pub use search::*;
\ No newline at end of file
@@ -1,5 +1,6 @@
 use rusqlite::{Connection, OptionalExtension};
 use crate::models::Project;
+use crate::repo::{EventRepo, EventType, EntityType};
 use anyhow::{Context, Result};
 
 /// Project repository for database operations
@@ -132,6 +133,11 @@ impl ProjectRepo {
         )
         .with_context(|| format!("Failed to rename project from {} to {}", old_name, new_name))?;
         
+        if let Some(project) = Self::get_by_name(conn, new_name)? {
+            EventRepo::record_entity(conn, EntityType::Project, project.id.unwrap(), None, EventType::ProjectRenamed,
+                serde_json::json!({ "old_name": old_name, "new_name": new_name }))?;
+        }
+        
         // Update all tasks referencing this project
         conn.execute(
             "UPDATE tasks SET modified_ts = ?1 
@@ -176,10 +182,19 @@ impl ProjectRepo {
         
         // Move all tasks from old to new project
         let now = chrono::Utc::now().timestamp();
+        let moved: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT id FROM tasks WHERE project_id = ?1")?;
+            let rows = stmt.query_map([old_id], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
         tx.execute(
             "UPDATE tasks SET project_id = ?1, modified_ts = ?2 WHERE project_id = ?3",
             rusqlite::params![new_id, now, old_id],
         )?;
+        for task_id in moved {
+            EventRepo::record_modified(&tx, task_id, "project_id",
+                Some(serde_json::Value::from(old_id)), Some(serde_json::Value::from(new_id)))?;
+        }
         
         // If old is active and new is archived, keep new archived
         // If old is archived and new is active, merged project becomes active
@@ -201,6 +216,15 @@ impl ProjectRepo {
         // Delete old project
         tx.execute("DELETE FROM projects WHERE id = ?1", [old_id])?;
         
+        let payload = serde_json::json!({
+            "from_id": old_id,
+            "from_name": old_name,
+            "into_id": new_id,
+            "into_name": new_name,
+        });
+        EventRepo::record_entity(&tx, EntityType::Project, old_id, None, EventType::ProjectMerged, payload.clone())?;
+        EventRepo::record_entity(&tx, EntityType::Project, new_id, None, EventType::ProjectMerged, payload)?;
+        
         tx.commit()?;
         Ok(())
     }
@@ -218,6 +242,11 @@ impl ProjectRepo {
             anyhow::bail!("Project '{}' not found", name);
         }
         
+        if let Some(project) = Self::get_by_name(conn, name)? {
+            EventRepo::record_entity(conn, EntityType::Project, project.id.unwrap(), None, EventType::ProjectArchived,
+                serde_json::json!({ "name": name }))?;
+        }
+        
         Ok(())
     }
 
@@ -234,6 +263,11 @@ impl ProjectRepo {
             anyhow::bail!("Project '{}' not found", name);
         }
         
+        if let Some(project) = Self::get_by_name(conn, name)? {
+            EventRepo::record_entity(conn, EntityType::Project, project.id.unwrap(), None, EventType::ProjectUnarchived,
+                serde_json::json!({ "name": name }))?;
+        }
+        
         Ok(())
     }
 }
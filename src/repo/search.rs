@@ -0,0 +1,153 @@
+use rusqlite::Connection;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// A task found by full-text search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub task_id: i64,
+    /// bm25 score of the best matching text (lower is more relevant)
+    pub rank: f64,
+    /// The annotation that matched best, when it ranked above the description
+    pub note: Option<String>,
+}
+
+/// Repository for full-text search over task descriptions and annotations
+pub struct SearchRepo;
+
+impl SearchRepo {
+    /// FTS5 query requiring every word. Each word is quoted, so FTS5 operators and
+    /// punctuation in the input are searched for literally.
+    pub fn build_query(words: &[String]) -> String {
+        words.iter()
+            .flat_map(|w| w.split_whitespace())
+            .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Tasks that contain every word across their description and annotations, most
+    /// relevant first. Tasks with every word in a single text rank above tasks whose
+    /// words are spread over several. Deleted tasks are left out.
+    pub fn search(conn: &Connection, words: &[String], limit: usize) -> Result<Vec<SearchHit>> {
+        let query = Self::build_query(words);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut hits = Self::best_matches(conn, &query)?;
+
+        // Then tasks where each word is found in some text of the task, ranked by the
+        // sum of each word's best rank
+        let terms: Vec<String> = words.iter()
+            .flat_map(|w| w.split_whitespace())
+            .map(|w| Self::build_query(&[w.to_string()]))
+            .collect();
+        if terms.len() > 1 {
+            let found: HashSet<i64> = hits.iter().map(|h| h.task_id).collect();
+            let mut spread: HashMap<i64, SearchHit> = HashMap::new();
+            for (i, term) in terms.iter().enumerate() {
+                let matches: HashMap<i64, SearchHit> = Self::best_matches(conn, term)?
+                    .into_iter()
+                    .filter(|h| !found.contains(&h.task_id))
+                    .map(|h| (h.task_id, h))
+                    .collect();
+                if i == 0 {
+                    spread = matches;
+                    continue;
+                }
+                spread.retain(|task_id, _| matches.contains_key(task_id));
+                for (task_id, hit) in spread.iter_mut() {
+                    let other = &matches[task_id];
+                    hit.rank += other.rank;
+                    if hit.note.is_none() {
+                        hit.note = other.note.clone();
+                    }
+                }
+            }
+            let mut spread: Vec<SearchHit> = spread.into_values().collect();
+            spread.sort_by(|a, b| a.rank.total_cmp(&b.rank).then(a.task_id.cmp(&b.task_id)));
+            hits.extend(spread);
+        }
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// Each task's best match for an FTS5 query within a single text
+    fn best_matches(conn: &Connection, query: &str) -> Result<Vec<SearchHit>> {
+        let mut stmt = conn.prepare(
+            "SELECT m.task_id, m.rank, m.note FROM (
+                 SELECT rowid AS task_id, bm25(task_fts) AS rank, NULL AS note
+                 FROM task_fts WHERE task_fts MATCH ?1
+                 UNION ALL
+                 SELECT a.task_id, bm25(annotation_fts), a.note
+                 FROM annotation_fts JOIN task_annotations a ON a.id = annotation_fts.rowid
+                 WHERE annotation_fts MATCH ?1
+             ) m JOIN tasks t ON t.id = m.task_id
+             WHERE t.status != 'deleted'
+             ORDER BY m.rank, m.task_id"
+        )?;
+        let rows = stmt.query_map([query], |row| {
+            Ok(SearchHit { task_id: row.get(0)?, rank: row.get(1)?, note: row.get(2)? })
+        })?;
+
+        // Keep each task's best match only
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        for hit in rows {
+            let hit = hit?;
+            if seen.insert(hit.task_id) {
+                hits.push(hit);
+            }
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::db::DbConnection;
+    use crate::repo::{TaskRepo, AnnotationRepo};
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_build_query_quotes_words() {
+        assert_eq!(SearchRepo::build_query(&words("auth bug")), "\"auth\" \"bug\"");
+        assert_eq!(SearchRepo::build_query(&["say \"hi\" OR".to_string()]), "\"say\" \"\"\"hi\"\"\" \"OR\"");
+        assert_eq!(SearchRepo::build_query(&[]), "");
+    }
+
+    #[test]
+    fn test_search_descriptions_and_annotations() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let login = TaskRepo::create(&conn, "Fix login bug", None).unwrap().id.unwrap();
+        let report = TaskRepo::create(&conn, "Write quarterly report", None).unwrap().id.unwrap();
+        let trashed = TaskRepo::create(&conn, "Old login bug", None).unwrap().id.unwrap();
+        AnnotationRepo::create(&conn, report, "The login page bugs finance".to_string(), None).unwrap();
+        TaskRepo::trash(&conn, trashed, &Config::default()).unwrap();
+
+        let hits = SearchRepo::search(&conn, &words("login bug"), 10).unwrap();
+        let ids: Vec<i64> = hits.iter().map(|h| h.task_id).collect();
+        assert_eq!(ids, vec![login, report]);
+        assert_eq!(hits[0].note, None);
+        assert_eq!(hits[1].note.as_deref(), Some("The login page bugs finance"));
+
+        // Words may be split between the description and an annotation
+        AnnotationRepo::create(&conn, login, "Reported by finance".to_string(), None).unwrap();
+        let hits = SearchRepo::search(&conn, &words("login finance"), 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.task_id).collect::<Vec<_>>(), vec![report, login]);
+        assert_eq!(hits[1].note.as_deref(), Some("Reported by finance"));
+        assert!(SearchRepo::search(&conn, &words("quarterly finance signup"), 10).unwrap().is_empty());
+        assert_eq!(SearchRepo::search(&conn, &words("login finance"), 1).unwrap().len(), 1);
+
+        // The index follows edits
+        conn.execute("UPDATE tasks SET description = 'Fix signup bug' WHERE id = ?1", [login]).unwrap();
+        let hits = SearchRepo::search(&conn, &words("login"), 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.task_id).collect::<Vec<_>>(), vec![report]);
+        assert_eq!(SearchRepo::search(&conn, &words("signup"), 1).unwrap()[0].task_id, login);
+    }
+}
@@ -1,12 +1,9 @@
 use rusqlite::{Connection, OptionalExtension};
 use crate::models::Session;
-use crate::repo::EventRepo;
+use crate::repo::{EventRepo, EventType, EntityType};
+use crate::config::Config;
 use anyhow::{Context, Result};
 
-/// Micro-session threshold (30 seconds)
-/// Sessions shorter than this duration may be merged or purged based on subsequent activity
-const MICRO_SECONDS: i64 = 30;
-
 /// Session repository for database operations
 ///
 /// Manages timing sessions for tasks, including:
@@ -33,19 +30,20 @@ impl SessionRepo {
     /// # Micro-Session Rules
     ///
     /// When creating a new session, the system checks for recent micro-sessions (closed sessions
-    /// shorter than 30 seconds that ended within the last 30 seconds):
+    /// shorter than `session.micro_seconds` that ended within that window):
     ///
     /// 1. **Merge**: If a micro-session exists for the same task, it is merged into the new session
     ///    (the new session's start time is set to the micro-session's original start time)
     ///
     /// 2. **Purge**: If a micro-session exists for a different task, it is deleted
     ///
-    /// 3. **Preserve**: If no micro-session exists or it's outside the 30-second window, the
+    /// 3. **Preserve**: If no micro-session exists or it's outside the window, the
     ///    micro-session is preserved
     ///
     /// # Example
     ///
     /// ```no_run
+    /// use tatl::config::Config;
     /// use tatl::db::DbConnection;
     /// use tatl::repo::SessionRepo;
     /// use chrono::Utc;
@@ -53,18 +51,19 @@ impl SessionRepo {
     /// let conn = DbConnection::connect().unwrap();
     /// let task_id = 1;
     /// let start_ts = Utc::now().timestamp();
-    /// let session = SessionRepo::create(&conn, task_id, start_ts).unwrap();
+    /// let session = SessionRepo::create(&conn, task_id, start_ts, &Config::default()).unwrap();
     /// ```
-    pub fn create(conn: &Connection, task_id: i64, start_ts: i64) -> Result<Session> {
+    pub fn create(conn: &Connection, task_id: i64, start_ts: i64, config: &Config) -> Result<Session> {
+        let micro_seconds = config.session.micro_seconds;
         let now = chrono::Utc::now().timestamp();
         
         // Check for recent micro-session that might need merge/purge
-        if let Some(micro_session) = Self::get_recent_micro_session(conn, start_ts)? {
+        if let Some(micro_session) = Self::get_recent_micro_session(conn, start_ts, config)? {
             let micro_end_ts = micro_session.end_ts.unwrap();
             let time_since_micro_end = start_ts - micro_end_ts;
             
             // Check if within MICRO seconds of micro-session end
-            if time_since_micro_end >= 0 && time_since_micro_end <= MICRO_SECONDS {
+            if time_since_micro_end >= 0 && time_since_micro_end <= micro_seconds {
                 if micro_session.task_id == task_id {
                     // Merge: same task - merge micro-session into new session
                     let new_session_id = {
@@ -107,7 +106,7 @@ impl SessionRepo {
                         micro_session.task_id,
                         micro_end_ts - micro_session.start_ts,
                         task_id,
-                        MICRO_SECONDS);
+                        micro_seconds);
                 }
             }
         }
@@ -159,6 +158,8 @@ impl SessionRepo {
         )?;
         
         let id = conn.last_insert_rowid();
+        EventRepo::record_entity(conn, EntityType::Session, id, Some(task_id), EventType::SessionAdded,
+            serde_json::json!({ "start_ts": start_ts, "end_ts": end_ts }))?;
         Ok(Session {
             id: Some(id),
             task_id,
@@ -192,7 +193,8 @@ impl SessionRepo {
     ///
     /// # Errors
     /// Returns an error if end_ts <= start_ts (session must have positive duration)
-    pub fn close_open(conn: &Connection, end_ts: i64) -> Result<Option<Session>> {
+    pub fn close_open(conn: &Connection, end_ts: i64, config: &Config) -> Result<Option<Session>> {
+        let micro_seconds = config.session.micro_seconds;
         // Get the open session first
         let session_opt = Self::get_open(conn)?;
 
@@ -213,6 +215,7 @@ impl SessionRepo {
                 "UPDATE sessions SET end_ts = ?1 WHERE id = ?2",
                 rusqlite::params![end_ts, session_id],
             )?;
+            EventRepo::record_session_ended(conn, session.task_id, session_id, session.start_ts, end_ts)?;
             
             let closed_session = Session {
                 id: Some(session_id),
@@ -223,8 +226,8 @@ impl SessionRepo {
             };
             
             // Check if this is a micro-session and warn
-            if duration < MICRO_SECONDS {
-                eprintln!("Warning: Micro-session detected ({}s). This session may be merged or purged if another session starts within {} seconds.", duration, MICRO_SECONDS);
+            if duration < micro_seconds {
+                eprintln!("Warning: Micro-session detected ({}s). This session may be merged or purged if another session starts within {} seconds.", duration, micro_seconds);
             }
             
             // Return the closed session
@@ -236,9 +239,10 @@ impl SessionRepo {
     
     /// Get the most recent micro-session (closed within MICRO seconds)
     /// Returns the most recent closed session that ended within MICRO seconds of the given timestamp
-    pub fn get_recent_micro_session(conn: &Connection, before_ts: i64) -> Result<Option<Session>> {
+    pub fn get_recent_micro_session(conn: &Connection, before_ts: i64, config: &Config) -> Result<Option<Session>> {
+        let micro_seconds = config.session.micro_seconds;
         // Look for sessions that ended within MICRO seconds before before_ts
-        let cutoff_ts = before_ts - MICRO_SECONDS;
+        let cutoff_ts = before_ts - micro_seconds;
         
         let mut stmt = conn.prepare(
             "SELECT id, task_id, start_ts, end_ts, created_ts 
@@ -251,7 +255,7 @@ impl SessionRepo {
              LIMIT 1"
         )?;
         
-        stmt.query_row(rusqlite::params![cutoff_ts, before_ts, MICRO_SECONDS], |row| {
+        stmt.query_row(rusqlite::params![cutoff_ts, before_ts, micro_seconds], |row| {
             Ok(Session {
                 id: Some(row.get(0)?),
                 task_id: row.get(1)?,
@@ -267,6 +271,8 @@ impl SessionRepo {
     /// Merge a micro-session into an adjacent session
     /// The micro-session's start time becomes the start time of the adjacent session
     pub fn merge_micro_session(conn: &Connection, micro_session: &Session, adjacent_session_id: i64) -> Result<()> {
+        let adjacent = Self::get_by_id(conn, adjacent_session_id)?;
+        
         // Update the adjacent session to start at the micro-session's start time
         conn.execute(
             "UPDATE sessions SET start_ts = ?1 WHERE id = ?2",
@@ -279,17 +285,55 @@ impl SessionRepo {
             rusqlite::params![micro_session.id.unwrap()],
         )?;
         
+        if let Some(adjacent) = adjacent {
+            EventRepo::record_entity(conn, EntityType::Session, adjacent_session_id, Some(adjacent.task_id),
+                EventType::SessionMerged, serde_json::json!({
+                    "merged_session_id": micro_session.id,
+                    "merged_start_ts": micro_session.start_ts,
+                    "merged_end_ts": micro_session.end_ts,
+                    "old_start_ts": adjacent.start_ts,
+                    "new_start_ts": micro_session.start_ts,
+                }))?;
+        }
+        
         Ok(())
     }
     
     /// Purge (delete) a micro-session
     pub fn purge_micro_session(conn: &Connection, micro_session_id: i64) -> Result<()> {
+        let session = Self::get_by_id(conn, micro_session_id)?;
         conn.execute(
             "DELETE FROM sessions WHERE id = ?1",
             rusqlite::params![micro_session_id],
         )?;
+        if let Some(session) = session {
+            Self::record_removed(conn, &session, EventType::SessionPurged)?;
+        }
         Ok(())
     }
+    
+    /// Record a session_modified event when the interval changed
+    fn record_modified(conn: &Connection, old: &Session, start_ts: i64, end_ts: Option<i64>) -> Result<()> {
+        if old.start_ts == start_ts && old.end_ts == end_ts {
+            return Ok(());
+        }
+        EventRepo::record_entity(conn, EntityType::Session, old.id.unwrap(), Some(old.task_id),
+            EventType::SessionModified, serde_json::json!({
+                "old_start_ts": old.start_ts,
+                "old_end_ts": old.end_ts,
+                "new_start_ts": start_ts,
+                "new_end_ts": end_ts,
+            }))
+    }
+    
+    /// Record the interval of a session that was deleted or purged
+    fn record_removed(conn: &Connection, session: &Session, event_type: EventType) -> Result<()> {
+        EventRepo::record_entity(conn, EntityType::Session, session.id.unwrap(), Some(session.task_id),
+            event_type, serde_json::json!({
+                "start_ts": session.start_ts,
+                "end_ts": session.end_ts,
+            }))
+    }
 
     /// Get all sessions for a task, ordered by start time (newest first)
     pub fn get_by_task(conn: &Connection, task_id: i64) -> Result<Vec<Session>> {
@@ -319,19 +363,44 @@ impl SessionRepo {
 
     /// Amend the end time of a closed session (for overlap prevention)
     pub fn amend_end_time(conn: &Connection, session_id: i64, new_end_ts: i64) -> Result<()> {
-        conn.execute(
+        let old = Self::get_by_id(conn, session_id)?;
+        let changed = conn.execute(
             "UPDATE sessions SET end_ts = ?1 WHERE id = ?2 AND end_ts IS NOT NULL",
             rusqlite::params![new_end_ts, session_id],
         )?;
+        if let (Some(old), true) = (old, changed > 0) {
+            Self::record_modified(conn, &old, old.start_ts, Some(new_end_ts))?;
+        }
         Ok(())
     }
 
+    /// Get all sessions including those of trashed tasks, ordered by start time (newest first).
+    /// Used for overlap checks, which must not let new sessions overlap a restorable one.
+    pub fn list_including_trashed(conn: &Connection) -> Result<Vec<Session>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, start_ts, end_ts, created_ts FROM sessions ORDER BY start_ts DESC"
+        )?;
+        let sessions = stmt.query_map([], |row| {
+            Ok(Session {
+                id: Some(row.get(0)?),
+                task_id: row.get(1)?,
+                start_ts: row.get(2)?,
+                end_ts: row.get(3)?,
+                created_ts: row.get(4)?,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sessions)
+    }
+
     /// Get the most recent closed session that ends at or after the given timestamp
     /// Used for overlap prevention - find sessions that might need end time amendment
     /// Get all sessions, ordered by start time (newest first)
+    /// Sessions of tasks in the trash are left out.
     pub fn list_all(conn: &Connection) -> Result<Vec<Session>> {
         let mut stmt = conn.prepare(
-            "SELECT id, task_id, start_ts, end_ts, created_ts FROM sessions ORDER BY start_ts DESC"
+            "SELECT id, task_id, start_ts, end_ts, created_ts FROM sessions
+             WHERE task_id NOT IN (SELECT id FROM tasks WHERE status = 'deleted')
+             ORDER BY start_ts DESC"
         )?;
         
         let rows = stmt.query_map([], |row| {
@@ -418,30 +487,42 @@ impl SessionRepo {
 
     /// Modify session start time
     pub fn modify_start_time(conn: &Connection, session_id: i64, new_start_ts: i64) -> Result<()> {
+        let old = Self::get_by_id(conn, session_id)?;
         conn.execute(
             "UPDATE sessions SET start_ts = ?1 WHERE id = ?2",
             rusqlite::params![new_start_ts, session_id],
         )?;
+        if let Some(old) = old {
+            Self::record_modified(conn, &old, new_start_ts, old.end_ts)?;
+        }
         Ok(())
     }
 
     /// Modify session end time
     /// Can set to None (make open) or Some(timestamp) (set end time)
     pub fn modify_end_time(conn: &Connection, session_id: i64, new_end_ts: Option<i64>) -> Result<()> {
+        let old = Self::get_by_id(conn, session_id)?;
         conn.execute(
             "UPDATE sessions SET end_ts = ?1 WHERE id = ?2",
             rusqlite::params![new_end_ts, session_id],
         )?;
+        if let Some(old) = old {
+            Self::record_modified(conn, &old, old.start_ts, new_end_ts)?;
+        }
         Ok(())
     }
 
     /// Delete a session
     /// Annotations linked to this session will have their session_id set to NULL (via ON DELETE SET NULL)
     pub fn delete(conn: &Connection, session_id: i64) -> Result<()> {
+        let session = Self::get_by_id(conn, session_id)?;
         conn.execute(
             "DELETE FROM sessions WHERE id = ?1",
             rusqlite::params![session_id],
         )?;
+        if let Some(session) = session {
+            Self::record_removed(conn, &session, EventType::SessionDeleted)?;
+        }
         Ok(())
     }
 
@@ -466,10 +547,14 @@ impl SessionRepo {
             }
         }
 
+        let old = Self::get_by_id(conn, session_id)?;
         conn.execute(
             "UPDATE sessions SET start_ts = ?1, end_ts = ?2 WHERE id = ?3",
             rusqlite::params![start_ts, end_ts, session_id],
         )?;
+        if let Some(old) = old {
+            Self::record_modified(conn, &old, start_ts, end_ts)?;
+        }
         Ok(())
     }
 
@@ -498,28 +583,12 @@ impl SessionRepo {
     ) -> Result<Vec<Session>> {
         let mut overlapping = Vec::new();
         
-        // Get all sessions (excluding the one being modified if specified)
-        let all_sessions = if let Some(exclude_id) = exclude_session_id {
-            let mut stmt = conn.prepare(
-                "SELECT id, task_id, start_ts, end_ts, created_ts FROM sessions WHERE id != ?1"
-            )?;
-            let rows = stmt.query_map([exclude_id], |row| {
-                Ok(Session {
-                    id: Some(row.get(0)?),
-                    task_id: row.get(1)?,
-                    start_ts: row.get(2)?,
-                    end_ts: row.get(3)?,
-                    created_ts: row.get(4)?,
-                })
-            })?;
-            let mut sessions = Vec::new();
-            for row in rows {
-                sessions.push(row?);
-            }
-            sessions
-        } else {
-            Self::list_all(conn)?
-        };
+        // Sessions of trashed tasks still hold their time, so they count here:
+        // the overlap would surface once the task is restored
+        let all_sessions: Vec<Session> = Self::list_including_trashed(conn)?
+            .into_iter()
+            .filter(|session| exclude_session_id.is_none() || session.id != exclude_session_id)
+            .collect();
         
         // Check for overlaps
         for session in all_sessions {
@@ -567,7 +636,7 @@ mod tests {
         let task_id = task.id.unwrap();
         
         let start_ts = chrono::Utc::now().timestamp();
-        let session = SessionRepo::create(&conn, task_id, start_ts).unwrap();
+        let session = SessionRepo::create(&conn, task_id, start_ts, &Config::default()).unwrap();
         
         assert_eq!(session.task_id, task_id);
         assert_eq!(session.start_ts, start_ts);
@@ -582,10 +651,10 @@ mod tests {
         let task2 = TaskRepo::create(&conn, "Task 2", None).unwrap();
         
         let start_ts = chrono::Utc::now().timestamp();
-        SessionRepo::create(&conn, task1.id.unwrap(), start_ts).unwrap();
+        SessionRepo::create(&conn, task1.id.unwrap(), start_ts, &Config::default()).unwrap();
         
         // Try to create another open session - should fail
-        let result = SessionRepo::create(&conn, task2.id.unwrap(), start_ts + 100);
+        let result = SessionRepo::create(&conn, task2.id.unwrap(), start_ts + 100, &Config::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("already running"));
     }
@@ -601,7 +670,7 @@ mod tests {
         
         // Create a session
         let start_ts = chrono::Utc::now().timestamp();
-        let session = SessionRepo::create(&conn, task.id.unwrap(), start_ts).unwrap();
+        let session = SessionRepo::create(&conn, task.id.unwrap(), start_ts, &Config::default()).unwrap();
         
         // Should find the open session
         let open = SessionRepo::get_open(&conn).unwrap();
@@ -615,11 +684,11 @@ mod tests {
         let task = TaskRepo::create(&conn, "Test task", None).unwrap();
         
         let start_ts = chrono::Utc::now().timestamp();
-        SessionRepo::create(&conn, task.id.unwrap(), start_ts).unwrap();
+        SessionRepo::create(&conn, task.id.unwrap(), start_ts, &Config::default()).unwrap();
         
         // Close the session
         let end_ts = start_ts + 3600;
-        let closed = SessionRepo::close_open(&conn, end_ts).unwrap();
+        let closed = SessionRepo::close_open(&conn, end_ts, &Config::default()).unwrap();
         assert!(closed.is_some());
         assert_eq!(closed.unwrap().end_ts, Some(end_ts));
         
@@ -688,7 +757,7 @@ mod tests {
         
         // Use a specific UTC timestamp
         let start_ts = 1704067200; // 2024-01-01 00:00:00 UTC
-        let session = SessionRepo::create(&conn, task.id.unwrap(), start_ts).unwrap();
+        let session = SessionRepo::create(&conn, task.id.unwrap(), start_ts, &Config::default()).unwrap();
         
         // Verify it's stored as-is (UTC)
         let open = SessionRepo::get_open(&conn).unwrap().unwrap();
@@ -1,6 +1,6 @@
 use rusqlite::{Connection, OptionalExtension};
 use crate::models::{Stack, StackItem};
-use crate::repo::EventRepo;
+use crate::repo::{EventRepo, DependencyRepo};
 use anyhow::Result;
 
 /// Stack repository for database operations
@@ -14,6 +14,7 @@ use anyhow::Result;
 /// - Clearing the stack
 ///
 /// The default stack (name='default') is auto-created on first operation.
+/// Moving a blocked task to the top prints a warning naming the tasks it waits on.
 ///
 /// # Example
 ///
@@ -29,12 +30,10 @@ use anyhow::Result;
 pub struct StackRepo;
 
 impl StackRepo {
-    /// Get or create the default stack
-    /// Auto-creates the default stack if it doesn't exist
-    pub fn get_or_create_default(conn: &Connection) -> Result<Stack> {
-        // Try to get existing default stack
+    /// Get the default stack without creating it (safe on read-only connections)
+    pub fn get_default(conn: &Connection) -> Result<Option<Stack>> {
         let mut stmt = conn.prepare("SELECT id, name, created_ts, modified_ts FROM stacks WHERE name = 'default'")?;
-        let stack_opt = stmt.query_row([], |row| {
+        let stack = stmt.query_row([], |row| {
             Ok(Stack {
                 id: Some(row.get(0)?),
                 name: row.get(1)?,
@@ -42,8 +41,22 @@ impl StackRepo {
                 modified_ts: row.get(3)?,
             })
         }).optional()?;
-        
-        if let Some(stack) = stack_opt {
+        Ok(stack)
+    }
+
+    /// Items of the default stack, empty if it has not been created yet.
+    /// Never writes, so filters can use it on read-only connections.
+    pub fn default_items(conn: &Connection) -> Result<Vec<StackItem>> {
+        match Self::get_default(conn)? {
+            Some(stack) => Self::get_items(conn, stack.id.unwrap()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get or create the default stack
+    /// Auto-creates the default stack if it doesn't exist
+    pub fn get_or_create_default(conn: &Connection) -> Result<Stack> {
+        if let Some(stack) = Self::get_default(conn)? {
             return Ok(stack);
         }
         
@@ -334,6 +347,16 @@ impl StackRepo {
         Ok(())
     }
 
+    /// The task at the top of the stack and the pending tasks it waits on,
+    /// or None when the stack is empty or its top is not blocked
+    pub fn top_blockers(conn: &Connection, stack_id: i64) -> Result<Option<(i64, Vec<i64>)>> {
+        let Some(top) = Self::get_items(conn, stack_id)?.first().map(|item| item.task_id) else {
+            return Ok(None);
+        };
+        let blockers = DependencyRepo::get_blockers(conn, top)?;
+        Ok(if blockers.is_empty() { None } else { Some((top, blockers)) })
+    }
+
     /// Renumber stack items to ensure clean ordinals (0, 1, 2, ...)
     fn renumber(conn: &Connection, stack_id: i64) -> Result<()> {
         let items = Self::get_items(conn, stack_id)?;
@@ -518,4 +541,22 @@ mod tests {
         let items = StackRepo::get_items(&conn, stack_id).unwrap();
         assert_eq!(items[0].task_id, task1.id.unwrap());
     }
+
+    #[test]
+    fn test_top_blockers() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let stack = StackRepo::get_or_create_default(&conn).unwrap();
+        let stack_id = stack.id.unwrap();
+        assert_eq!(StackRepo::top_blockers(&conn, stack_id).unwrap(), None);
+
+        let design = TaskRepo::create(&conn, "Design", None).unwrap().id.unwrap();
+        let build = TaskRepo::create(&conn, "Build", None).unwrap().id.unwrap();
+        DependencyRepo::set(&conn, build, &[design]).unwrap();
+
+        StackRepo::enqueue(&conn, stack_id, build).unwrap();
+        assert_eq!(StackRepo::top_blockers(&conn, stack_id).unwrap(), Some((build, vec![design])));
+
+        StackRepo::push_to_top(&conn, stack_id, design).unwrap();
+        assert_eq!(StackRepo::top_blockers(&conn, stack_id).unwrap(), None);
+    }
 }
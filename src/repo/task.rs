@@ -1,6 +1,7 @@
 use rusqlite::{Connection, OptionalExtension};
 use crate::models::Task;
-use crate::repo::EventRepo;
+use crate::repo::{EventRepo, SessionRepo, StackRepo};
+use crate::config::Config;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 
@@ -83,6 +84,9 @@ impl TaskRepo {
         
         let id = conn.last_insert_rowid();
         
+        // Record created event
+        EventRepo::record_created(conn, id, &task)?;
+        
         // Add tags
         for tag in tags {
             conn.execute(
@@ -93,9 +97,6 @@ impl TaskRepo {
             EventRepo::record_tag_added(conn, id, tag)?;
         }
         
-        // Record created event
-        EventRepo::record_created(conn, id, description, project_id)?;
-        
         Ok(Task {
             id: Some(id),
             ..task
@@ -172,15 +173,22 @@ impl TaskRepo {
         Ok(tags)
     }
 
-    /// List all tasks (basic - no filtering yet)
+    /// List all tasks except those in the trash
     pub fn list_all(conn: &Connection) -> Result<Vec<(Task, Vec<String>)>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, uuid, description, status, project_id, due_ts, scheduled_ts, 
-                    wait_ts, alloc_secs, template, respawn, udas_json, created_ts, modified_ts 
-             FROM tasks WHERE status != 'deleted' ORDER BY id"
-        )?;
-        
-        let rows = stmt.query_map([], |row| {
+        Self::list_matching(conn, "1", &[])
+    }
+
+    /// List tasks outside the trash satisfying `clause`, a condition on `tasks t`
+    /// with `?` placeholders bound to `params` (see `filter::compile_filter`)
+    pub fn list_matching(conn: &Connection, clause: &str, params: &[rusqlite::types::Value]) -> Result<Vec<(Task, Vec<String>)>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.id, t.uuid, t.description, t.status, t.project_id, t.due_ts, t.scheduled_ts,
+                    t.wait_ts, t.alloc_secs, t.template, t.respawn, t.udas_json, t.created_ts, t.modified_ts
+             FROM tasks t WHERE t.status != 'deleted' AND ({}) ORDER BY t.id",
+            clause
+        ))?;
+        
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
             let id: i64 = row.get(0)?;
             let udas_json: Option<String> = row.get(11)?;
             let mut udas = HashMap::new();
@@ -208,15 +216,29 @@ impl TaskRepo {
                 modified_ts: row.get(13)?,
             })
         })?;
-        
-        let mut tasks = Vec::new();
-        for task_result in rows {
-            let task = task_result?;
-            let tags = Self::get_tags(conn, task.id.unwrap())?;
-            tasks.push((task, tags));
+        let tasks = rows.collect::<rusqlite::Result<Vec<Task>>>()?;
+        
+        // Tags of all matching tasks in one query rather than one per task
+        let mut tags_by_task: HashMap<i64, Vec<String>> = HashMap::new();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT tt.task_id, tt.tag FROM task_tags tt JOIN tasks t ON t.id = tt.task_id
+             WHERE t.status != 'deleted' AND ({}) ORDER BY tt.task_id, tt.tag",
+            clause
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (task_id, tag) = row?;
+            tags_by_task.entry(task_id).or_default().push(tag);
         }
         
-        Ok(tasks)
+        Ok(tasks.into_iter()
+            .map(|task| {
+                let tags = tags_by_task.remove(&task.id.unwrap_or(0)).unwrap_or_default();
+                (task, tags)
+            })
+            .collect())
     }
 
     /// Modify a task
@@ -352,6 +374,12 @@ impl TaskRepo {
         for key in udas_to_remove {
             task.udas.remove(key);
         }
+        if task.udas != old_task.udas {
+            let as_value = |udas: &HashMap<String, String>| -> Result<Option<serde_json::Value>> {
+                Ok(if udas.is_empty() { None } else { Some(serde_json::to_value(udas)?) })
+            };
+            EventRepo::record_modified(conn, task_id, "udas", as_value(&old_task.udas)?, as_value(&task.udas)?)?;
+        }
         
         // Serialize UDAs
         let udas_json = if task.udas.is_empty() {
@@ -504,6 +532,10 @@ impl TaskRepo {
         if old_status != new_status_str {
             EventRepo::record_status_changed(conn, task_id, old_status, new_status_str)?;
         }
+        if should_clear_wait && old_task.wait_ts.is_some() {
+            EventRepo::record_modified(conn, task_id, "wait_ts",
+                old_task.wait_ts.map(serde_json::Value::from), None)?;
+        }
 
         Ok(())
     }
@@ -523,6 +555,117 @@ impl TaskRepo {
         Self::set_status(conn, task_id, crate::models::TaskStatus::Pending)
     }
 
+    /// Move a task to the trash
+    ///
+    /// The task keeps its sessions, annotations and events but is hidden from
+    /// lists, filters, reports and the queue. A running session on the task is
+    /// stopped and the task is removed from every queue.
+    pub fn trash(conn: &Connection, task_id: i64, config: &Config) -> Result<()> {
+        let task = Self::get_by_id(conn, task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+        if task.status == crate::models::TaskStatus::Deleted {
+            anyhow::bail!("Task {} is already in the trash", task_id);
+        }
+
+        let tx = conn.unchecked_transaction()?;
+
+        if let Some(open) = SessionRepo::get_open(&tx)? {
+            if open.task_id == task_id {
+                SessionRepo::close_open(&tx, chrono::Utc::now().timestamp(), config)?;
+            }
+        }
+
+        let stack_ids: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT stack_id FROM stack_items WHERE task_id = ?1")?;
+            let rows = stmt.query_map([task_id], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for stack_id in stack_ids {
+            StackRepo::remove_task(&tx, stack_id, task_id)?;
+        }
+
+        Self::set_status(&tx, task_id, crate::models::TaskStatus::Deleted)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Fail when a task is in the trash.
+    ///
+    /// Commands that act on a task by ID call this, so a trashed task cannot be
+    /// queued, timed or edited until it is restored.
+    pub fn ensure_not_trashed(conn: &Connection, task_id: i64) -> Result<()> {
+        if let Some(task) = Self::get_by_id(conn, task_id)? {
+            if task.status == crate::models::TaskStatus::Deleted {
+                anyhow::bail!("Task {} is in the trash; restore it first", task_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take a task out of the trash, restoring the status it had before
+    pub fn restore(conn: &Connection, task_id: i64) -> Result<crate::models::TaskStatus> {
+        let task = Self::get_by_id(conn, task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+        if task.status != crate::models::TaskStatus::Deleted {
+            anyhow::bail!("Task {} is not in the trash", task_id);
+        }
+
+        let previous: Option<String> = conn.query_row(
+            "SELECT json_extract(payload_json, '$.old_status') FROM task_events
+             WHERE task_id = ?1 AND event_type = 'status_changed'
+               AND json_extract(payload_json, '$.new_status') = 'deleted'
+             ORDER BY ts DESC, id DESC LIMIT 1",
+            [task_id],
+            |row| row.get(0),
+        ).optional()?.flatten();
+        let status = previous.as_deref()
+            .and_then(crate::models::TaskStatus::from_str)
+            .filter(|s| *s != crate::models::TaskStatus::Deleted)
+            .unwrap_or(crate::models::TaskStatus::Pending);
+
+        Self::set_status(conn, task_id, status)?;
+        Ok(status)
+    }
+
+    /// List tasks in the trash with the time they were trashed, most recent first
+    pub fn list_trashed(conn: &Connection) -> Result<Vec<(Task, i64)>> {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, COALESCE(
+                 (SELECT MAX(e.ts) FROM task_events e
+                  WHERE e.task_id = t.id AND e.event_type = 'status_changed'
+                    AND json_extract(e.payload_json, '$.new_status') = 'deleted'),
+                 t.modified_ts) AS trashed_ts
+             FROM tasks t WHERE t.status = 'deleted'
+             ORDER BY trashed_ts DESC, t.id DESC"
+        )?;
+        let rows: Vec<(i64, i64)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut trashed = Vec::new();
+        for (task_id, trashed_ts) in rows {
+            if let Some(task) = Self::get_by_id(conn, task_id)? {
+                trashed.push((task, trashed_ts));
+            }
+        }
+        Ok(trashed)
+    }
+
+    /// Time a completed or closed task was finished: its last status change to
+    /// completed or closed, or its modification time if it has none (None for other statuses)
+    pub fn get_end_ts(conn: &Connection, task_id: i64) -> Result<Option<i64>> {
+        let end_ts = conn.query_row(
+            "SELECT CASE WHEN t.status IN ('completed', 'closed') THEN COALESCE(
+                 (SELECT MAX(e.ts) FROM task_events e
+                  WHERE e.task_id = t.id AND e.event_type = 'status_changed'
+                    AND json_extract(e.payload_json, '$.new_status') IN ('completed', 'closed')),
+                 t.modified_ts) END
+             FROM tasks t WHERE t.id = ?1",
+            [task_id],
+            |row| row.get(0),
+        ).optional()?.flatten();
+        Ok(end_ts)
+    }
+
     /// Permanently delete a task and all related data
     /// 
     /// This operation is atomic - all related data is deleted in a transaction.
@@ -534,35 +677,45 @@ impl TaskRepo {
     /// - Task events (CASCADE)
     /// - Respawn rules (part of task)
     /// 
+    /// Only tasks already in the trash can be purged.
+    /// 
     /// # Example
     /// 
     /// ```no_run
+    /// use tatl::config::Config;
     /// use tatl::db::DbConnection;
     /// use tatl::repo::TaskRepo;
     /// 
     /// let conn = DbConnection::connect().unwrap();
-    /// TaskRepo::delete(&conn, 5).unwrap();
+    /// TaskRepo::trash(&conn, 5, &Config::default()).unwrap();
+    /// TaskRepo::purge(&conn, 5).unwrap();
     /// ```
-    pub fn delete(conn: &Connection, task_id: i64) -> Result<()> {
-        // Verify task exists
-        let _task = Self::get_by_id(conn, task_id)?
-            .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
-        
-        // Use transaction to ensure atomicity
+    pub fn purge(conn: &Connection, task_id: i64) -> Result<()> {
         let tx = conn.unchecked_transaction()?;
         
+        // Keep the uuid so that sync does not bring the task back
+        tx.execute(
+            "INSERT OR REPLACE INTO task_tombstones (uuid, reason, ts)
+             SELECT uuid, 'purged', ?2 FROM tasks WHERE id = ?1 AND status = 'deleted'",
+            rusqlite::params![task_id, chrono::Utc::now().timestamp()],
+        )?;
+        
         // Delete the task - CASCADE will handle related data automatically
-        // But we'll also explicitly clean up to be safe and clear
         let rows_affected = tx.execute(
-            "DELETE FROM tasks WHERE id = ?1",
+            "DELETE FROM tasks WHERE id = ?1 AND status = 'deleted'",
             rusqlite::params![task_id],
         )?;
         
         if rows_affected == 0 {
-            anyhow::bail!("Task {} not found", task_id);
+            anyhow::bail!("Task {} is not in the trash", task_id);
         }
         
-        // Commit transaction
+        // Journaled commands that touched the task can no longer be undone
+        tx.execute(
+            "DELETE FROM undo_ops WHERE id IN (SELECT op_id FROM undo_journal WHERE task_id = ?1)",
+            rusqlite::params![task_id],
+        )?;
+        
         tx.commit()?;
         
         Ok(())
@@ -0,0 +1,460 @@
+// Undo journal for mutating commands
+//
+// While a journaled command runs, temporary triggers on the data tables write
+// the SQL that reverses each row change into `undo_journal`. Undoing an
+// operation replays those statements newest first. The task event log is not
+// journaled: undo appends compensating events instead of removing history.
+// Each entry also keeps the row as the command left it, so undo refuses when
+// something that is not journaled (sync, import, doctor --fix) changed it since.
+
+use rusqlite::{Connection, OptionalExtension};
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use crate::models::Task;
+use crate::repo::{DependencyRepo, EventRepo, EventType, EntityType, TaskRepo};
+
+/// Tables whose changes can be undone
+const JOURNALED_TABLES: &[&str] = &[
+    "projects", "tasks", "task_tags", "task_dependencies", "task_annotations", "stacks", "stack_items", "sessions", "externals",
+];
+
+/// Number of operations kept in the journal
+pub const UNDO_KEEP: usize = 100;
+
+/// A journaled command
+#[derive(Debug, Clone)]
+pub struct UndoOp {
+    pub id: i64,
+    pub command: String,
+    pub created_ts: i64,
+    pub undone_ts: Option<i64>,
+    /// Number of row changes recorded for the command
+    pub changes: usize,
+    /// Tasks touched by the command
+    pub task_ids: Vec<i64>,
+}
+
+/// Expression rendering a row of `table` as text, compared with the journaled post-image
+fn row_image(conn: &Connection, table: &str, row: &str) -> Result<String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA main.table_info({})", table))?;
+    let columns: Vec<String> = stmt.query_map([], |row| row.get(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns.iter()
+        .map(|c| format!("quote({}.{})", row, c))
+        .collect::<Vec<_>>()
+        .join(" || ',' || "))
+}
+
+/// Task state compared before and after an undo to derive compensating events
+struct TaskState {
+    task: Task,
+    tags: BTreeSet<String>,
+    dependencies: BTreeSet<i64>,
+    queue_position: Option<i64>,
+}
+
+fn task_state(conn: &Connection, task_id: i64) -> Result<Option<TaskState>> {
+    let Some(task) = TaskRepo::get_by_id(conn, task_id)? else { return Ok(None) };
+    let tags = TaskRepo::get_tags(conn, task_id)?.into_iter().collect();
+    let dependencies = DependencyRepo::get_dependencies(conn, task_id)?.into_iter().collect();
+    let queue_position = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM stack_items o WHERE o.stack_id = si.stack_id AND o.ordinal < si.ordinal)
+         FROM stack_items si JOIN stacks s ON s.id = si.stack_id
+         WHERE si.task_id = ?1 AND s.name = 'default'",
+        [task_id],
+        |row| row.get(0),
+    ).optional()?;
+    Ok(Some(TaskState { task, tags, dependencies, queue_position }))
+}
+
+/// Undo journal repository
+pub struct UndoRepo;
+
+impl UndoRepo {
+    /// Start journaling a command. Returns the operation id to pass to `install_triggers`.
+    /// Old operations beyond `UNDO_KEEP` are pruned.
+    pub fn begin(conn: &Connection, command: &str) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT INTO undo_ops (command, created_ts) VALUES (?1, ?2)",
+            rusqlite::params![command, now],
+        )?;
+        let op_id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM undo_journal WHERE op_id IN (SELECT id FROM undo_ops WHERE id <= ?1)",
+            [op_id - UNDO_KEEP as i64],
+        )?;
+        conn.execute("DELETE FROM undo_ops WHERE id <= ?1", [op_id - UNDO_KEEP as i64])?;
+        Ok(op_id)
+    }
+
+    /// Install temporary triggers on this connection that journal row changes under `op_id`
+    pub fn install_triggers(conn: &Connection, op_id: i64) -> Result<()> {
+        for table in JOURNALED_TABLES {
+            let mut stmt = conn.prepare(&format!("PRAGMA main.table_info({})", table))?;
+            let columns: Vec<String> = stmt.query_map([], |row| row.get(1))?
+                .collect::<Result<Vec<_>, _>>()?;
+            let task_column = match *table {
+                "tasks" => "id",
+                "projects" | "stacks" => "",
+                _ => "task_id",
+            };
+            let task_expr = |row: &str| if task_column.is_empty() {
+                "NULL".to_string()
+            } else {
+                format!("{}.{}", row, task_column)
+            };
+
+            let restore_values = columns.iter()
+                .map(|c| format!("quote(old.{})", c))
+                .collect::<Vec<_>>()
+                .join(" || ',' || ");
+            let restore_sets = columns.iter()
+                .map(|c| format!("'{c}=' || quote(old.{c})"))
+                .collect::<Vec<_>>()
+                .join(" || ',' || ");
+            let journal = |event: &str, row: &str, undo_sql: String, post_image: String| format!(
+                "CREATE TEMP TRIGGER IF NOT EXISTS undo_{table}_{event} AFTER {event} ON main.{table} BEGIN
+                    INSERT INTO undo_journal (op_id, table_name, task_id, row_id, undo_sql, post_image)
+                    VALUES ({op_id}, '{table}', {task}, {row}.rowid, {undo_sql}, {post_image});
+                 END;",
+                task = task_expr(row),
+            );
+
+            conn.execute_batch(&journal(
+                "INSERT", "new",
+                format!("'DELETE FROM {} WHERE rowid=' || new.rowid", table),
+                row_image(conn, table, "new")?,
+            ))?;
+            conn.execute_batch(&journal(
+                "UPDATE", "old",
+                format!("'UPDATE {} SET ' || {} || ' WHERE rowid=' || old.rowid", table, restore_sets),
+                row_image(conn, table, "new")?,
+            ))?;
+            conn.execute_batch(&journal(
+                "DELETE", "old",
+                format!("'INSERT INTO {} (rowid,{}) VALUES (' || old.rowid || ',' || {} || ')'",
+                    table, columns.join(","), restore_values),
+                "''".to_string(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Recent operations that changed something, newest first
+    pub fn list(conn: &Connection, limit: usize) -> Result<Vec<UndoOp>> {
+        let mut stmt = conn.prepare(
+            "SELECT o.id, o.command, o.created_ts, o.undone_ts, COUNT(j.id)
+             FROM undo_ops o JOIN undo_journal j ON j.op_id = o.id
+             GROUP BY o.id ORDER BY o.id DESC LIMIT ?1"
+        )?;
+        let ops = stmt.query_map([limit as i64], |row| {
+            Ok(UndoOp {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                created_ts: row.get(2)?,
+                undone_ts: row.get(3)?,
+                changes: row.get::<_, i64>(4)? as usize,
+                task_ids: Vec::new(),
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        ops.into_iter().map(|mut op| {
+            op.task_ids = Self::task_ids(conn, op.id)?;
+            Ok(op)
+        }).collect()
+    }
+
+    fn task_ids(conn: &Connection, op_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT task_id FROM undo_journal WHERE op_id = ?1 AND task_id IS NOT NULL ORDER BY task_id"
+        )?;
+        let ids = stmt.query_map([op_id], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// The most recent operation that has not been undone
+    pub fn last_undoable(conn: &Connection) -> Result<Option<UndoOp>> {
+        Ok(Self::list(conn, UNDO_KEEP)?.into_iter().find(|op| op.undone_ts.is_none()))
+    }
+
+    /// Fail when a row the operation changed no longer looks the way the command left it
+    fn check_unchanged(conn: &Connection, op: &UndoOp) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT j.table_name, j.row_id, j.post_image FROM undo_journal j
+             WHERE j.op_id = ?1 AND j.row_id IS NOT NULL
+               AND j.id = (SELECT MAX(l.id) FROM undo_journal l
+                           WHERE l.op_id = j.op_id AND l.table_name = j.table_name AND l.row_id = j.row_id)"
+        )?;
+        let rows: Vec<(String, i64, Option<String>)> = stmt.query_map([op.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (table, row_id, post_image) in rows {
+            // Journaled before images were kept
+            let Some(post_image) = post_image else { continue };
+            let current: Option<String> = conn.query_row(
+                &format!("SELECT {} FROM {table} WHERE rowid = ?1", row_image(conn, &table, &table)?),
+                [row_id],
+                |row| row.get(0),
+            ).optional()?;
+            if current.unwrap_or_default() != post_image {
+                return Err(anyhow!("Cannot undo '{}': the ledger has changed since (row {} of {} was modified by a command that cannot be undone)",
+                    op.command, row_id, table));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tasks the operation created: their first journaled change is an insert
+    fn created_tasks(conn: &Connection, op: &UndoOp) -> Result<BTreeSet<i64>> {
+        let mut stmt = conn.prepare(
+            "SELECT j.row_id FROM undo_journal j
+             WHERE j.op_id = ?1 AND j.table_name = 'tasks' AND j.undo_sql LIKE 'DELETE FROM tasks %'
+               AND j.id = (SELECT MIN(f.id) FROM undo_journal f
+                           WHERE f.op_id = j.op_id AND f.table_name = 'tasks' AND f.row_id = j.row_id)"
+        )?;
+        let ids = stmt.query_map([op.id], |row| row.get(0))?.collect::<Result<BTreeSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Reverse an operation. The connection must not be journaling itself.
+    ///
+    /// Every row the command changed is restored, then each affected task gets
+    /// events describing the reversal and an `undone` event naming the command.
+    /// Tasks the command created go to the trash with their history, rather than
+    /// being removed. Nothing is written if a changed row was modified since by
+    /// a command that is not journaled.
+    pub fn undo(conn: &Connection, op: &UndoOp) -> Result<()> {
+        if op.undone_ts.is_some() {
+            return Err(anyhow!("'{}' has already been undone", op.command));
+        }
+        let tx = conn.unchecked_transaction()?;
+        Self::check_unchanged(&tx, op)?;
+        let created = Self::created_tasks(&tx, op)?;
+
+        let mut before: BTreeMap<i64, TaskState> = BTreeMap::new();
+        for task_id in &op.task_ids {
+            if let Some(state) = task_state(&tx, *task_id)? {
+                before.insert(*task_id, state);
+            }
+        }
+
+        let mut stmt = tx.prepare(
+            "SELECT DISTINCT table_name, row_id, task_id FROM undo_journal
+             WHERE op_id = ?1 AND table_name IN ('sessions', 'externals', 'projects') AND row_id IS NOT NULL"
+        )?;
+        let audited: Vec<(String, i64, Option<i64>)> = stmt.query_map([op.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut stmt = tx.prepare("SELECT table_name, row_id, undo_sql FROM undo_journal WHERE op_id = ?1 ORDER BY id DESC")?;
+        let statements: Vec<(String, Option<i64>, String)> = stmt.query_map([op.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+        let now = chrono::Utc::now().timestamp();
+        for (table, row_id, sql) in &statements {
+            // A created task stays, trashed, so that its events are kept
+            if table == "tasks" && row_id.is_some_and(|id| created.contains(&id)) && sql.starts_with("DELETE") {
+                tx.execute(
+                    "UPDATE tasks SET status = 'deleted', modified_ts = ?1 WHERE id = ?2",
+                    rusqlite::params![now, row_id],
+                )?;
+                continue;
+            }
+            tx.execute_batch(sql)
+                .map_err(|e| anyhow!("Cannot undo '{}': the ledger has changed since ({})", op.command, e))?;
+        }
+
+        for task_id in &op.task_ids {
+            let Some(after) = task_state(&tx, *task_id)? else { continue };
+            if let Some(before) = before.get(task_id) {
+                Self::record_compensation(&tx, *task_id, before, &after)?;
+            }
+            EventRepo::record_at(&tx, *task_id, now, EventType::Undone, serde_json::json!({
+                "undo_op": op.id,
+                "command": op.command,
+            }))?;
+        }
+
+        // Sessions, externals and projects keep their own audit trail
+        for (table, row_id, task_id) in audited {
+            if let Some(entity_type) = EntityType::for_table(&table) {
+                EventRepo::record_entity(&tx, entity_type, row_id, task_id, EventType::Undone, serde_json::json!({
+                    "undo_op": op.id,
+                    "command": op.command,
+                }))?;
+            }
+        }
+
+        tx.execute("UPDATE undo_ops SET undone_ts = ?1 WHERE id = ?2", rusqlite::params![now, op.id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record the events that describe going from `before` to `after`
+    fn record_compensation(conn: &Connection, task_id: i64, before: &TaskState, after: &TaskState) -> Result<()> {
+        use serde_json::Value;
+        let (old, new) = (&before.task, &after.task);
+        let int = |v: Option<i64>| v.map(Value::from);
+        let text = |v: &Option<String>| v.clone().map(Value::String);
+
+        if old.description != new.description {
+            EventRepo::record_modified(conn, task_id, "description",
+                Some(Value::String(old.description.clone())), Some(Value::String(new.description.clone())))?;
+        }
+        let int_fields = [
+            ("project_id", old.project_id, new.project_id),
+            ("due_ts", old.due_ts, new.due_ts),
+            ("scheduled_ts", old.scheduled_ts, new.scheduled_ts),
+            ("wait_ts", old.wait_ts, new.wait_ts),
+            ("alloc_secs", old.alloc_secs, new.alloc_secs),
+        ];
+        for (field, old_value, new_value) in int_fields {
+            if old_value != new_value {
+                EventRepo::record_modified(conn, task_id, field, int(old_value), int(new_value))?;
+            }
+        }
+        if old.template != new.template {
+            EventRepo::record_modified(conn, task_id, "template", text(&old.template), text(&new.template))?;
+        }
+        if old.respawn != new.respawn {
+            EventRepo::record_modified(conn, task_id, "respawn", text(&old.respawn), text(&new.respawn))?;
+        }
+        if old.udas != new.udas {
+            EventRepo::record_modified(conn, task_id, "udas",
+                Some(serde_json::to_value(&old.udas)?), Some(serde_json::to_value(&new.udas)?))?;
+        }
+        if old.status != new.status {
+            EventRepo::record_status_changed(conn, task_id, old.status.as_str(), new.status.as_str())?;
+        }
+        for tag in after.tags.difference(&before.tags) {
+            EventRepo::record_tag_added(conn, task_id, tag)?;
+        }
+        for tag in before.tags.difference(&after.tags) {
+            EventRepo::record_tag_removed(conn, task_id, tag)?;
+        }
+        for depends_on_id in after.dependencies.difference(&before.dependencies) {
+            EventRepo::record_dependency_added(conn, task_id, *depends_on_id)?;
+        }
+        for depends_on_id in before.dependencies.difference(&after.dependencies) {
+            EventRepo::record_dependency_removed(conn, task_id, *depends_on_id)?;
+        }
+        if before.queue_position != after.queue_position {
+            let stack_id: Option<i64> = conn.query_row(
+                "SELECT id FROM stacks WHERE name = 'default' ORDER BY id LIMIT 1",
+                [],
+                |row| row.get(0),
+            ).optional()?;
+            if let Some(stack_id) = stack_id {
+                match after.queue_position {
+                    Some(position) => EventRepo::record_stack_added(conn, task_id, stack_id, position as i32)?,
+                    None => EventRepo::record_stack_removed(conn, task_id, stack_id)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::repo::StackRepo;
+    use std::collections::HashMap;
+
+    fn journaled(conn: &Connection, command: &str) -> i64 {
+        let op_id = UndoRepo::begin(conn, command).unwrap();
+        UndoRepo::install_triggers(conn, op_id).unwrap();
+        op_id
+    }
+
+    fn stop_journaling(conn: &Connection) {
+        for table in JOURNALED_TABLES {
+            for event in ["INSERT", "UPDATE", "DELETE"] {
+                conn.execute_batch(&format!("DROP TRIGGER IF EXISTS temp.undo_{}_{}", table, event)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo_modify_restores_fields_and_tags() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create_full(&conn, "Original", None, Some(1_000), None, None, None, None, None,
+            &HashMap::new(), &["keep".to_string()]).unwrap();
+        let task_id = task.id.unwrap();
+
+        journaled(&conn, "modify 1 Changed due=2000 +new -keep");
+        TaskRepo::modify(&conn, task_id, Some("Changed".to_string()), None, Some(Some(2_000)), None, None,
+            None, None, None, &HashMap::new(), &[], &["new".to_string()], &["keep".to_string()]).unwrap();
+        stop_journaling(&conn);
+
+        let op = UndoRepo::last_undoable(&conn).unwrap().unwrap();
+        assert_eq!(op.task_ids, vec![task_id]);
+        UndoRepo::undo(&conn, &op).unwrap();
+
+        let restored = TaskRepo::get_by_id(&conn, task_id).unwrap().unwrap();
+        assert_eq!(restored.description, "Original");
+        assert_eq!(restored.due_ts, Some(1_000));
+        assert_eq!(TaskRepo::get_tags(&conn, task_id).unwrap(), vec!["keep".to_string()]);
+
+        // History is kept and the reversal is recorded on top of it
+        let events = EventRepo::get_by_task(&conn, task_id, None).unwrap();
+        assert!(events.iter().any(|e| e.event_type == "undone"));
+        assert!(events.iter().any(|e| e.event_type == "modified" && e.payload["new_value"] == "Original"));
+        assert!(UndoRepo::last_undoable(&conn).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_restores_queue_order() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let stack_id = StackRepo::get_or_create_default(&conn).unwrap().id.unwrap();
+        let a = TaskRepo::create(&conn, "A", None).unwrap().id.unwrap();
+        let b = TaskRepo::create(&conn, "B", None).unwrap().id.unwrap();
+        StackRepo::enqueue(&conn, stack_id, a).unwrap();
+        StackRepo::enqueue(&conn, stack_id, b).unwrap();
+
+        journaled(&conn, "dequeue");
+        StackRepo::remove_task(&conn, stack_id, a).unwrap();
+        stop_journaling(&conn);
+
+        UndoRepo::undo(&conn, &UndoRepo::last_undoable(&conn).unwrap().unwrap()).unwrap();
+        let items = StackRepo::get_items(&conn, stack_id).unwrap();
+        assert_eq!(items.iter().map(|i| i.task_id).collect::<Vec<_>>(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_undo_trashes_created_tasks_and_keeps_their_history() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let done = TaskRepo::create(&conn, "Water plants", None).unwrap().id.unwrap();
+
+        journaled(&conn, "finish 1");
+        TaskRepo::complete(&conn, done).unwrap();
+        let respawned = TaskRepo::create(&conn, "Water plants", None).unwrap().id.unwrap();
+        stop_journaling(&conn);
+
+        UndoRepo::undo(&conn, &UndoRepo::last_undoable(&conn).unwrap().unwrap()).unwrap();
+        assert_eq!(TaskRepo::get_by_id(&conn, done).unwrap().unwrap().status, crate::models::TaskStatus::Pending);
+        let trashed = TaskRepo::get_by_id(&conn, respawned).unwrap().unwrap();
+        assert_eq!(trashed.status, crate::models::TaskStatus::Deleted);
+        let events = EventRepo::get_by_task(&conn, respawned, None).unwrap();
+        assert!(events.iter().any(|e| e.event_type == "created"));
+        assert!(events.iter().any(|e| e.event_type == "undone"));
+    }
+
+    #[test]
+    fn test_undo_refuses_when_rows_changed_outside_the_journal() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let task_id = TaskRepo::create(&conn, "Original", None).unwrap().id.unwrap();
+
+        journaled(&conn, "modify 1 Changed");
+        conn.execute("UPDATE tasks SET description = 'Changed' WHERE id = ?1", [task_id]).unwrap();
+        stop_journaling(&conn);
+        // e.g. sync bringing in a newer description
+        conn.execute("UPDATE tasks SET description = 'Synced' WHERE id = ?1", [task_id]).unwrap();
+
+        let err = UndoRepo::undo(&conn, &UndoRepo::last_undoable(&conn).unwrap().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("ledger has changed"), "{}", err);
+        assert_eq!(TaskRepo::get_by_id(&conn, task_id).unwrap().unwrap().description, "Synced");
+        assert!(UndoRepo::last_undoable(&conn).unwrap().is_some());
+    }
+}
@@ -0,0 +1,537 @@
+// Timewarrior and CSV session import
+//
+// Time logs from other trackers are parsed into intervals, matched to tasks by
+// description and project (creating tasks as needed), and written as closed
+// sessions. Overlaps with existing sessions are resolved by an OverlapStrategy.
+
+use rusqlite::{Connection, OptionalExtension};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use crate::repo::{SessionRepo, TaskRepo, ProjectRepo, AnnotationRepo};
+use crate::transfer::{parse_tw_date, sanitize_name};
+use crate::utils::{parse_date_expr, parse_duration};
+
+/// A time interval read from an external time log
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedInterval {
+    pub start_ts: i64,
+    /// None for intervals that were still running when the log was written
+    pub end_ts: Option<i64>,
+    pub description: String,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    /// Explicit tatl task id (CSV `task_id` column)
+    pub task_id: Option<i64>,
+}
+
+/// How to handle an interval that overlaps existing sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapStrategy {
+    /// Do not import the interval
+    Skip,
+    /// Import only the parts of the interval that do not overlap
+    Clip,
+    /// Import the interval as-is, creating overlapping sessions
+    Force,
+}
+
+impl OverlapStrategy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skip" => Some(OverlapStrategy::Skip),
+            "clip" => Some(OverlapStrategy::Clip),
+            "force" => Some(OverlapStrategy::Force),
+            _ => None,
+        }
+    }
+}
+
+/// Result of importing a set of intervals
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalImportSummary {
+    pub sessions_created: usize,
+    pub seconds_imported: i64,
+    pub tasks_matched: usize,
+    /// Descriptions of tasks created to hold imported sessions
+    pub tasks_created: Vec<String>,
+    pub skipped_overlap: usize,
+    pub clipped: usize,
+    pub forced: usize,
+    pub skipped_open: usize,
+}
+
+/// Parse a timestamp from a time log: Taskwarrior/Timewarrior, RFC 3339,
+/// `YYYY-MM-DD HH:MM[:SS]` (local time), epoch seconds or a tatl date expression
+pub fn parse_log_timestamp(value: &str) -> Result<i64> {
+    let value = value.trim();
+    if let Ok(ts) = parse_tw_date(value) {
+        return Ok(ts);
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S", "%Y/%m/%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, fmt) {
+            return Local.from_local_datetime(&dt)
+                .earliest()
+                .map(|dt| dt.timestamp())
+                .ok_or_else(|| anyhow!("Invalid local time: {}", value));
+        }
+    }
+    parse_date_expr(value).with_context(|| format!("Invalid timestamp: {}", value))
+}
+
+/// Parse a duration column: `1h30m`, `01:30[:00]` or decimal hours (`1.5`)
+fn parse_log_duration(value: &str) -> Result<i64> {
+    let value = value.trim();
+    if value.contains(':') {
+        let parts: Vec<i64> = value.split(':')
+            .map(|p| p.parse::<i64>().map_err(|_| anyhow!("Invalid duration: {}", value)))
+            .collect::<Result<_>>()?;
+        return match parts.as_slice() {
+            [h, m] => Ok(h * 3600 + m * 60),
+            [h, m, s] => Ok(h * 3600 + m * 60 + s),
+            _ => bail!("Invalid duration: {}", value),
+        };
+    }
+    if let Ok(hours) = value.parse::<f64>() {
+        return Ok((hours * 3600.0).round() as i64);
+    }
+    parse_duration(value)
+}
+
+/// Split a line into tokens, keeping double-quoted strings together
+fn split_quoted(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Build an interval from Timewarrior tags: the first tag is the task description,
+/// the remaining tags become task tags
+fn interval_from_tags(start_ts: i64, end_ts: Option<i64>, mut tags: Vec<String>, note: Option<String>) -> ImportedInterval {
+    let description = if tags.is_empty() {
+        note.clone().unwrap_or_else(|| "Imported time".to_string())
+    } else {
+        tags.remove(0)
+    };
+    ImportedInterval {
+        start_ts,
+        end_ts,
+        description,
+        project: None,
+        tags,
+        note,
+        task_id: None,
+    }
+}
+
+/// Parse Timewarrior data: either `.data` files (`inc <start> - <end> # tags # annotation`)
+/// or the JSON array written by `timew export`
+pub fn parse_timewarrior(text: &str) -> Result<Vec<ImportedInterval>> {
+    let trimmed = text.trim();
+    if trimmed.starts_with('[') {
+        let items: Vec<serde_json::Value> = serde_json::from_str(trimmed)
+            .context("Failed to parse Timewarrior export")?;
+        return items.iter().map(|item| {
+            let start = item.get("start").and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Timewarrior interval is missing 'start'"))?;
+            let end = item.get("end").and_then(|v| v.as_str()).map(parse_tw_date).transpose()?;
+            let tags = item.get("tags").and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|t| t.as_str().map(|s| s.to_string())).collect())
+                .unwrap_or_default();
+            let note = item.get("annotation").and_then(|v| v.as_str()).map(|s| s.to_string());
+            Ok(interval_from_tags(parse_tw_date(start)?, end, tags, note))
+        }).collect();
+    }
+
+    let mut intervals = Vec::new();
+    for (i, line) in trimmed.lines().enumerate() {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix("inc ") else { continue };
+        let (range, extra) = match rest.split_once(" # ") {
+            Some((range, extra)) => (range.trim(), Some(extra)),
+            None => (rest.trim_end_matches(" #").trim(), None),
+        };
+        let (start, end) = match range.split_once(" - ") {
+            Some((start, end)) => (start.trim(), Some(end.trim())),
+            None => (range, None),
+        };
+        let start_ts = parse_tw_date(start).with_context(|| format!("Line {}: invalid start", i + 1))?;
+        let end_ts = end.map(parse_tw_date).transpose().with_context(|| format!("Line {}: invalid end", i + 1))?;
+        let (tags, note) = match extra {
+            Some(extra) => match extra.split_once(" # ") {
+                Some((tags, note)) => (split_quoted(tags), Some(split_quoted(note).join(" "))),
+                None => (split_quoted(extra), None),
+            },
+            None => (Vec::new(), None),
+        };
+        intervals.push(interval_from_tags(start_ts, end_ts, tags, note.filter(|n| !n.is_empty())));
+    }
+    Ok(intervals)
+}
+
+/// Split one CSV record, honouring double quotes and doubled quote escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Parse CSV with a header row. Recognised columns (case-insensitive):
+/// `start`, `end` or `duration`, `description`/`task`/`activity`, `project`,
+/// `tags`, `note`/`annotation`/`comment`, and `task_id`.
+pub fn parse_csv(text: &str) -> Result<Vec<ImportedInterval>> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or_else(|| anyhow!("CSV file is empty"))?;
+    let columns: Vec<String> = split_csv_line(header.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .map(|c| c.to_lowercase().replace(' ', "_"))
+        .collect();
+    let find = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+
+    let start_col = find(&["start", "start_time", "begin", "from"])
+        .ok_or_else(|| anyhow!("CSV header must include a 'start' column"))?;
+    let end_col = find(&["end", "end_time", "stop", "to"]);
+    let duration_col = find(&["duration", "hours"]);
+    if end_col.is_none() && duration_col.is_none() {
+        bail!("CSV header must include an 'end' or 'duration' column");
+    }
+    let desc_col = find(&["description", "task", "activity", "title"]);
+    let project_col = find(&["project"]);
+    let tags_col = find(&["tags", "tag"]);
+    let note_col = find(&["note", "annotation", "comment", "notes"]);
+    let task_id_col = find(&["task_id", "id"]);
+
+    let mut intervals = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let row = i + 2;
+        let fields = split_csv_line(line);
+        let get = |col: Option<usize>| col.and_then(|c| fields.get(c)).filter(|v| !v.is_empty()).cloned();
+
+        let start_ts = parse_log_timestamp(&get(Some(start_col)).ok_or_else(|| anyhow!("Row {}: missing start", row))?)
+            .with_context(|| format!("Row {}", row))?;
+        let end_ts = match (get(end_col), get(duration_col)) {
+            (Some(end), _) => Some(parse_log_timestamp(&end).with_context(|| format!("Row {}", row))?),
+            (None, Some(duration)) => Some(start_ts + parse_log_duration(&duration).with_context(|| format!("Row {}", row))?),
+            (None, None) => None,
+        };
+        let task_id = match get(task_id_col) {
+            Some(id) => Some(id.parse::<i64>().map_err(|_| anyhow!("Row {}: invalid task_id '{}'", row, id))?),
+            None => None,
+        };
+        let tags = get(tags_col)
+            .map(|t| t.split(|c: char| c.is_whitespace() || c == ';' || c == ',')
+                .map(|s| s.trim_start_matches('+').to_string())
+                .filter(|s| !s.is_empty())
+                .collect())
+            .unwrap_or_default();
+        let note = get(note_col);
+        let description = get(desc_col)
+            .or_else(|| note.clone())
+            .unwrap_or_else(|| "Imported time".to_string());
+
+        intervals.push(ImportedInterval {
+            start_ts,
+            end_ts,
+            description,
+            project: get(project_col),
+            tags,
+            note,
+            task_id,
+        });
+    }
+    Ok(intervals)
+}
+
+/// Subtract occupied ranges from [start, end), returning the free pieces
+fn clip_interval(start: i64, end: i64, mut occupied: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    occupied.sort();
+    let mut pieces = Vec::new();
+    let mut cursor = start;
+    for (busy_start, busy_end) in occupied {
+        if busy_end <= cursor {
+            continue;
+        }
+        if busy_start >= end {
+            break;
+        }
+        if busy_start > cursor {
+            pieces.push((cursor, busy_start.min(end)));
+        }
+        cursor = cursor.max(busy_end);
+        if cursor >= end {
+            break;
+        }
+    }
+    if cursor < end {
+        pieces.push((cursor, end));
+    }
+    pieces
+}
+
+/// Writes imported intervals as closed sessions
+pub struct IntervalImporter;
+
+impl IntervalImporter {
+    /// Find or create the task an interval belongs to
+    fn resolve_task(
+        conn: &Connection,
+        interval: &ImportedInterval,
+        cache: &mut HashMap<(String, Option<i64>), i64>,
+        summary: &mut IntervalImportSummary,
+    ) -> Result<i64> {
+        if let Some(task_id) = interval.task_id {
+            if TaskRepo::get_by_id(conn, task_id)?.is_none() {
+                bail!("Task {} not found", task_id);
+            }
+            summary.tasks_matched += 1;
+            return Ok(task_id);
+        }
+
+        let project_id = match interval.project.as_deref().map(sanitize_name).filter(|p| !p.is_empty()) {
+            Some(name) => match ProjectRepo::get_by_name(conn, &name)? {
+                Some(project) => project.id,
+                None => ProjectRepo::create(conn, &name)?.id,
+            },
+            None => None,
+        };
+
+        let key = (interval.description.to_lowercase(), project_id);
+        if let Some(task_id) = cache.get(&key) {
+            summary.tasks_matched += 1;
+            return Ok(*task_id);
+        }
+
+        let existing: Option<i64> = conn.query_row(
+            "SELECT id FROM tasks WHERE lower(description) = ?1 AND project_id IS ?2 AND status != 'deleted'
+             ORDER BY id DESC LIMIT 1",
+            rusqlite::params![key.0, project_id],
+            |row| row.get(0),
+        ).optional()?;
+        let task_id = match existing {
+            Some(id) => {
+                summary.tasks_matched += 1;
+                id
+            }
+            None => {
+                let tags: Vec<String> = interval.tags.iter().map(|t| sanitize_name(t)).collect();
+                let task = TaskRepo::create_full(
+                    conn, &interval.description, project_id, None, None, None, None, None, None,
+                    &HashMap::new(), &tags,
+                )?;
+                summary.tasks_created.push(interval.description.clone());
+                task.id.unwrap()
+            }
+        };
+        cache.insert(key, task_id);
+        Ok(task_id)
+    }
+
+    /// Import intervals as closed sessions.
+    ///
+    /// Does not open or commit a transaction: callers wrap this in one so a dry
+    /// run can be rolled back after the summary is shown.
+    pub fn import(
+        conn: &Connection,
+        intervals: &[ImportedInterval],
+        strategy: OverlapStrategy,
+    ) -> Result<IntervalImportSummary> {
+        let mut summary = IntervalImportSummary::default();
+        let mut cache: HashMap<(String, Option<i64>), i64> = HashMap::new();
+
+        let mut ordered: Vec<&ImportedInterval> = intervals.iter().collect();
+        ordered.sort_by_key(|i| i.start_ts);
+
+        for interval in ordered {
+            let Some(end_ts) = interval.end_ts else {
+                summary.skipped_open += 1;
+                continue;
+            };
+            if end_ts <= interval.start_ts {
+                bail!("Interval '{}' ends before it starts", interval.description);
+            }
+
+            let overlapping = SessionRepo::find_overlapping_sessions(conn, 0, interval.start_ts, Some(end_ts), None)?;
+            let pieces = if overlapping.is_empty() {
+                vec![(interval.start_ts, end_ts)]
+            } else {
+                match strategy {
+                    OverlapStrategy::Skip => {
+                        summary.skipped_overlap += 1;
+                        continue;
+                    }
+                    OverlapStrategy::Force => {
+                        summary.forced += 1;
+                        vec![(interval.start_ts, end_ts)]
+                    }
+                    OverlapStrategy::Clip => {
+                        let occupied = overlapping.iter()
+                            .map(|s| (s.start_ts, s.end_ts.unwrap_or(i64::MAX)))
+                            .collect();
+                        let pieces = clip_interval(interval.start_ts, end_ts, occupied);
+                        if pieces.is_empty() {
+                            summary.skipped_overlap += 1;
+                            continue;
+                        }
+                        summary.clipped += 1;
+                        pieces
+                    }
+                }
+            };
+
+            let task_id = Self::resolve_task(conn, interval, &mut cache, &mut summary)?;
+            let mut first_session = None;
+            for (start, end) in pieces {
+                let session = SessionRepo::create_closed(conn, task_id, start, end)?;
+                first_session.get_or_insert(session.id);
+                summary.sessions_created += 1;
+                summary.seconds_imported += end - start;
+            }
+            if let Some(note) = &interval.note {
+                if note != &interval.description {
+                    AnnotationRepo::create(conn, task_id, note.clone(), first_session.flatten())?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+
+    #[test]
+    fn test_parse_timewarrior_data_file() {
+        let data = "inc 20260114T090000Z - 20260114T100000Z # \"Write report\" work # \"first draft\"\n\
+                    inc 20260114T110000Z - 20260114T113000Z # email\n\
+                    inc 20260114T140000Z # \"Write report\"\n";
+        let intervals = parse_timewarrior(data).unwrap();
+        assert_eq!(intervals.len(), 3);
+        assert_eq!(intervals[0].description, "Write report");
+        assert_eq!(intervals[0].tags, vec!["work"]);
+        assert_eq!(intervals[0].note.as_deref(), Some("first draft"));
+        assert_eq!(intervals[0].end_ts.unwrap() - intervals[0].start_ts, 3600);
+        assert_eq!(intervals[1].description, "email");
+        assert!(intervals[2].end_ts.is_none());
+    }
+
+    #[test]
+    fn test_parse_timewarrior_export_json() {
+        let json = r#"[{"id":1,"start":"20260114T090000Z","end":"20260114T093000Z","tags":["Review","work"]}]"#;
+        let intervals = parse_timewarrior(json).unwrap();
+        assert_eq!(intervals[0].description, "Review");
+        assert_eq!(intervals[0].end_ts.unwrap() - intervals[0].start_ts, 1800);
+    }
+
+    #[test]
+    fn test_parse_csv_with_duration_and_quotes() {
+        let csv = "Start,Duration,Description,Project,Tags\n\
+                   2026-01-14T09:00:00Z,1:30,\"Plan, then build\",work,\"a;b\"\n\
+                   2026-01-14T12:00:00Z,0.5,Lunch review,,\n";
+        let intervals = parse_csv(csv).unwrap();
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(intervals[0].description, "Plan, then build");
+        assert_eq!(intervals[0].project.as_deref(), Some("work"));
+        assert_eq!(intervals[0].tags, vec!["a", "b"]);
+        assert_eq!(intervals[0].end_ts.unwrap() - intervals[0].start_ts, 5400);
+        assert_eq!(intervals[1].end_ts.unwrap() - intervals[1].start_ts, 1800);
+        assert!(intervals[1].project.is_none());
+    }
+
+    #[test]
+    fn test_parse_csv_requires_start() {
+        assert!(parse_csv("end,description\n2026-01-14T09:00:00Z,x\n").is_err());
+    }
+
+    #[test]
+    fn test_clip_interval() {
+        assert_eq!(clip_interval(0, 100, vec![(20, 40), (60, 80)]), vec![(0, 20), (40, 60), (80, 100)]);
+        assert_eq!(clip_interval(0, 100, vec![(-10, 50)]), vec![(50, 100)]);
+        assert!(clip_interval(0, 100, vec![(0, i64::MAX)]).is_empty());
+    }
+
+    fn interval(desc: &str, start: i64, end: i64) -> ImportedInterval {
+        ImportedInterval {
+            start_ts: start,
+            end_ts: Some(end),
+            description: desc.to_string(),
+            project: Some("work".to_string()),
+            tags: vec![],
+            note: None,
+            task_id: None,
+        }
+    }
+
+    #[test]
+    fn test_import_matches_tasks_and_handles_overlaps() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let project = ProjectRepo::create(&conn, "work").unwrap();
+        let existing = TaskRepo::create(&conn, "Write report", project.id).unwrap();
+        SessionRepo::create_closed(&conn, existing.id.unwrap(), 1_000, 2_000).unwrap();
+
+        let intervals = vec![
+            interval("write report", 2_000, 3_000),
+            interval("New thing", 1_500, 2_500),
+        ];
+
+        let skip = IntervalImporter::import(&conn.unchecked_transaction().unwrap(), &intervals, OverlapStrategy::Skip).unwrap();
+        assert_eq!(skip.sessions_created, 1);
+        assert_eq!(skip.skipped_overlap, 1);
+        assert_eq!(skip.tasks_matched, 1);
+        assert!(skip.tasks_created.is_empty());
+
+        let clip = IntervalImporter::import(&conn.unchecked_transaction().unwrap(), &intervals, OverlapStrategy::Clip).unwrap();
+        // Intervals are imported in start order: "New thing" is clipped to 2000..2500,
+        // then "write report" is clipped to 2500..3000
+        assert_eq!(clip.sessions_created, 2);
+        assert_eq!(clip.clipped, 2);
+        assert_eq!(clip.seconds_imported, 1_000);
+
+        let force = IntervalImporter::import(&conn.unchecked_transaction().unwrap(), &intervals, OverlapStrategy::Force).unwrap();
+        assert_eq!(force.sessions_created, 2);
+        assert_eq!(force.forced, 2);
+        assert_eq!(force.tasks_created, vec!["New thing"]);
+        assert_eq!(force.seconds_imported, 2_000);
+
+        // Each run above was rolled back when its transaction dropped
+        assert_eq!(SessionRepo::list_all(&conn).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,908 @@
+// Full-ledger JSON export and import
+//
+// The export is a single versioned document containing every table in the
+// schema. Child rows reference tasks by uuid rather than integer id so that a
+// document can be imported into a ledger whose ids have diverged.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use anyhow::{anyhow, Context, Result};
+use std::collections::{HashMap, HashSet};
+use crate::db::{MigrationManager, CURRENT_VERSION};
+
+/// Format identifier written to every ledger export
+pub const LEDGER_FORMAT: &str = "tatl-ledger";
+
+/// Version of the ledger export document layout
+pub const LEDGER_FORMAT_VERSION: u32 = 1;
+
+/// Full-ledger export document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerExport {
+    pub format: String,
+    pub version: u32,
+    pub schema_version: u32,
+    pub exported_ts: i64,
+    #[serde(default)]
+    pub projects: Vec<ProjectRecord>,
+    #[serde(default)]
+    pub tasks: Vec<TaskRecord>,
+    #[serde(default)]
+    pub task_tags: Vec<TagRecord>,
+    #[serde(default)]
+    pub task_dependencies: Vec<DependencyRecord>,
+    #[serde(default)]
+    pub task_annotations: Vec<AnnotationRecord>,
+    #[serde(default)]
+    pub stacks: Vec<StackRecord>,
+    #[serde(default)]
+    pub stack_items: Vec<StackItemRecord>,
+    #[serde(default)]
+    pub sessions: Vec<SessionRecord>,
+    #[serde(default)]
+    pub task_events: Vec<EventRecord>,
+    #[serde(default)]
+    pub entity_events: Vec<EntityEventRecord>,
+    #[serde(default)]
+    pub templates: Vec<TemplateRecord>,
+    #[serde(default)]
+    pub list_views: Vec<ListViewRecord>,
+    #[serde(default)]
+    pub externals: Vec<ExternalRecord>,
+    #[serde(default)]
+    pub contexts: Vec<ContextRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRecord {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub is_archived: bool,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub uuid: String,
+    /// Task id in the exporting ledger (informational only)
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub description: String,
+    pub status: String,
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub due_ts: Option<i64>,
+    #[serde(default)]
+    pub scheduled_ts: Option<i64>,
+    #[serde(default)]
+    pub wait_ts: Option<i64>,
+    #[serde(default)]
+    pub alloc_secs: Option<i64>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub respawn: Option<String>,
+    #[serde(default)]
+    pub udas: Option<serde_json::Value>,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagRecord {
+    pub task_uuid: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyRecord {
+    pub task_uuid: String,
+    pub depends_on_uuid: String,
+    pub created_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationRecord {
+    pub task_uuid: String,
+    #[serde(default)]
+    pub session_id: Option<i64>,
+    pub note: String,
+    pub entry_ts: i64,
+    pub created_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackRecord {
+    pub id: i64,
+    pub name: String,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackItemRecord {
+    pub stack_id: i64,
+    pub task_uuid: String,
+    pub ordinal: i64,
+    pub added_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: i64,
+    pub task_uuid: String,
+    pub start_ts: i64,
+    #[serde(default)]
+    pub end_ts: Option<i64>,
+    pub created_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub task_uuid: String,
+    pub ts: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
+/// Audit event of a session, external or project. `entity_id` is the exported
+/// id of the session, external or project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityEventRecord {
+    pub entity_type: String,
+    pub entity_id: i64,
+    #[serde(default)]
+    pub task_uuid: Option<String>,
+    pub ts: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateRecord {
+    pub name: String,
+    pub payload: serde_json::Value,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListViewRecord {
+    pub name: String,
+    pub entity: String,
+    pub filter: serde_json::Value,
+    pub sort: serde_json::Value,
+    pub group: serde_json::Value,
+    #[serde(default)]
+    pub hide: Option<serde_json::Value>,
+    #[serde(default)]
+    pub color: Option<serde_json::Value>,
+    #[serde(default)]
+    pub fill: Option<serde_json::Value>,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalRecord {
+    #[serde(default)]
+    pub id: i64,
+    pub task_uuid: String,
+    pub recipient: String,
+    #[serde(default)]
+    pub request: Option<String>,
+    pub sent_ts: i64,
+    #[serde(default)]
+    pub returned_ts: Option<i64>,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextRecord {
+    pub name: String,
+    pub filter: serde_json::Value,
+    #[serde(default)]
+    pub is_active: bool,
+    pub created_ts: i64,
+    pub modified_ts: i64,
+}
+
+/// Counts of what a ledger import added and skipped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub tasks_imported: usize,
+    pub tasks_skipped: usize,
+    pub projects_created: usize,
+    pub tags: usize,
+    pub dependencies: usize,
+    pub annotations: usize,
+    pub sessions: usize,
+    pub sessions_skipped: usize,
+    pub events: usize,
+    pub stack_items: usize,
+    pub externals: usize,
+    pub templates: usize,
+    pub views: usize,
+    pub contexts: usize,
+}
+
+/// Parse a JSON column, falling back to a plain string value when it is not valid JSON
+fn json_column(text: Option<String>) -> Option<serde_json::Value> {
+    text.map(|t| serde_json::from_str(&t).unwrap_or(serde_json::Value::String(t)))
+}
+
+/// Serialize a JSON value back into column text
+fn json_text(value: &serde_json::Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+}
+
+/// Full-ledger export/import
+pub struct LedgerTransfer;
+
+impl LedgerTransfer {
+    /// Export every table in the ledger into a single document
+    pub fn export(conn: &Connection) -> Result<LedgerExport> {
+        let schema_version = MigrationManager::get_version(conn)
+            .context("Failed to read schema version")?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, is_archived, created_ts, modified_ts FROM projects ORDER BY id"
+        )?;
+        let projects = stmt.query_map([], |row| {
+            Ok(ProjectRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                is_archived: row.get::<_, i64>(2)? != 0,
+                created_ts: row.get(3)?,
+                modified_ts: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, uuid, description, status, project_id, due_ts, scheduled_ts, wait_ts,
+                    alloc_secs, template, respawn, udas_json, created_ts, modified_ts
+             FROM tasks ORDER BY id"
+        )?;
+        let tasks = stmt.query_map([], |row| {
+            Ok(TaskRecord {
+                id: Some(row.get(0)?),
+                uuid: row.get(1)?,
+                description: row.get(2)?,
+                status: row.get(3)?,
+                project_id: row.get(4)?,
+                due_ts: row.get(5)?,
+                scheduled_ts: row.get(6)?,
+                wait_ts: row.get(7)?,
+                alloc_secs: row.get(8)?,
+                template: row.get(9)?,
+                respawn: row.get(10)?,
+                udas: json_column(row.get(11)?),
+                created_ts: row.get(12)?,
+                modified_ts: row.get(13)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT t.uuid, tt.tag FROM task_tags tt JOIN tasks t ON t.id = tt.task_id
+             ORDER BY t.id, tt.tag"
+        )?;
+        let task_tags = stmt.query_map([], |row| {
+            Ok(TagRecord { task_uuid: row.get(0)?, tag: row.get(1)? })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT t.uuid, u.uuid, d.created_ts FROM task_dependencies d
+             JOIN tasks t ON t.id = d.task_id JOIN tasks u ON u.id = d.depends_on_id
+             ORDER BY t.id, u.id"
+        )?;
+        let task_dependencies = stmt.query_map([], |row| {
+            Ok(DependencyRecord { task_uuid: row.get(0)?, depends_on_uuid: row.get(1)?, created_ts: row.get(2)? })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT t.uuid, a.session_id, a.note, a.entry_ts, a.created_ts
+             FROM task_annotations a JOIN tasks t ON t.id = a.task_id
+             ORDER BY a.id"
+        )?;
+        let task_annotations = stmt.query_map([], |row| {
+            Ok(AnnotationRecord {
+                task_uuid: row.get(0)?,
+                session_id: row.get(1)?,
+                note: row.get(2)?,
+                entry_ts: row.get(3)?,
+                created_ts: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, created_ts, modified_ts FROM stacks ORDER BY id"
+        )?;
+        let stacks = stmt.query_map([], |row| {
+            Ok(StackRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_ts: row.get(2)?,
+                modified_ts: row.get(3)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT si.stack_id, t.uuid, si.ordinal, si.added_ts
+             FROM stack_items si JOIN tasks t ON t.id = si.task_id
+             ORDER BY si.stack_id, si.ordinal"
+        )?;
+        let stack_items = stmt.query_map([], |row| {
+            Ok(StackItemRecord {
+                stack_id: row.get(0)?,
+                task_uuid: row.get(1)?,
+                ordinal: row.get(2)?,
+                added_ts: row.get(3)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT s.id, t.uuid, s.start_ts, s.end_ts, s.created_ts
+             FROM sessions s JOIN tasks t ON t.id = s.task_id
+             ORDER BY s.id"
+        )?;
+        let sessions = stmt.query_map([], |row| {
+            Ok(SessionRecord {
+                id: row.get(0)?,
+                task_uuid: row.get(1)?,
+                start_ts: row.get(2)?,
+                end_ts: row.get(3)?,
+                created_ts: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT t.uuid, e.ts, e.event_type, e.payload_json
+             FROM task_events e JOIN tasks t ON t.id = e.task_id
+             ORDER BY e.id"
+        )?;
+        let task_events = stmt.query_map([], |row| {
+            Ok(EventRecord {
+                task_uuid: row.get(0)?,
+                ts: row.get(1)?,
+                event_type: row.get(2)?,
+                payload: json_column(row.get(3)?).unwrap_or(serde_json::Value::Null),
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT e.entity_type, e.entity_id, t.uuid, e.ts, e.event_type, e.payload_json
+             FROM entity_events e LEFT JOIN tasks t ON t.id = e.task_id
+             ORDER BY e.id"
+        )?;
+        let entity_events = stmt.query_map([], |row| {
+            Ok(EntityEventRecord {
+                entity_type: row.get(0)?,
+                entity_id: row.get(1)?,
+                task_uuid: row.get(2)?,
+                ts: row.get(3)?,
+                event_type: row.get(4)?,
+                payload: json_column(row.get(5)?).unwrap_or(serde_json::Value::Null),
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT name, payload_json, created_ts, modified_ts FROM templates ORDER BY name"
+        )?;
+        let templates = stmt.query_map([], |row| {
+            Ok(TemplateRecord {
+                name: row.get(0)?,
+                payload: json_column(row.get(1)?).unwrap_or(serde_json::Value::Null),
+                created_ts: row.get(2)?,
+                modified_ts: row.get(3)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT name, entity, filter_json, sort_json, group_json, hide_json, color_json, fill_json,
+                    created_ts, modified_ts
+             FROM list_views ORDER BY entity, name"
+        )?;
+        let list_views = stmt.query_map([], |row| {
+            Ok(ListViewRecord {
+                name: row.get(0)?,
+                entity: row.get(1)?,
+                filter: json_column(row.get(2)?).unwrap_or(serde_json::Value::Null),
+                sort: json_column(row.get(3)?).unwrap_or(serde_json::Value::Null),
+                group: json_column(row.get(4)?).unwrap_or(serde_json::Value::Null),
+                hide: json_column(row.get(5)?),
+                color: json_column(row.get(6)?),
+                fill: json_column(row.get(7)?),
+                created_ts: row.get(8)?,
+                modified_ts: row.get(9)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT x.id, t.uuid, x.recipient, x.request, x.sent_ts, x.returned_ts, x.created_ts, x.modified_ts
+             FROM externals x JOIN tasks t ON t.id = x.task_id
+             ORDER BY x.id"
+        )?;
+        let externals = stmt.query_map([], |row| {
+            Ok(ExternalRecord {
+                id: row.get(0)?,
+                task_uuid: row.get(1)?,
+                recipient: row.get(2)?,
+                request: row.get(3)?,
+                sent_ts: row.get(4)?,
+                returned_ts: row.get(5)?,
+                created_ts: row.get(6)?,
+                modified_ts: row.get(7)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT name, filter_json, is_active, created_ts, modified_ts FROM contexts ORDER BY name"
+        )?;
+        let contexts = stmt.query_map([], |row| {
+            Ok(ContextRecord {
+                name: row.get(0)?,
+                filter: json_column(row.get(1)?).unwrap_or(serde_json::Value::Null),
+                is_active: row.get(2)?,
+                created_ts: row.get(3)?,
+                modified_ts: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(LedgerExport {
+            format: LEDGER_FORMAT.to_string(),
+            version: LEDGER_FORMAT_VERSION,
+            schema_version,
+            exported_ts: chrono::Utc::now().timestamp(),
+            projects,
+            tasks,
+            task_tags,
+            task_dependencies,
+            task_annotations,
+            stacks,
+            stack_items,
+            sessions,
+            task_events,
+            entity_events,
+            templates,
+            list_views,
+            externals,
+            contexts,
+        })
+    }
+
+    /// Parse and validate a ledger export document
+    pub fn parse(json: &str) -> Result<LedgerExport> {
+        let doc: LedgerExport = serde_json::from_str(json)
+            .context("Failed to parse ledger export")?;
+        if doc.format != LEDGER_FORMAT {
+            return Err(anyhow!("Unsupported export format '{}' (expected '{}')", doc.format, LEDGER_FORMAT));
+        }
+        if doc.version > LEDGER_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Export format version {} is newer than this tatl supports ({})",
+                doc.version, LEDGER_FORMAT_VERSION
+            ));
+        }
+        if doc.schema_version > CURRENT_VERSION {
+            return Err(anyhow!(
+                "Export was written by schema version {}, newer than this tatl supports ({})",
+                doc.schema_version, CURRENT_VERSION
+            ));
+        }
+        Ok(doc)
+    }
+
+    /// Import a ledger export document.
+    ///
+    /// Tasks are matched on uuid: tasks that already exist are left untouched and
+    /// their related rows (tags, dependencies, annotations, sessions, events, queue
+    /// entries, externals) are skipped. A new task's dependencies may point at tasks
+    /// that already existed. Projects, stacks, templates, views and contexts are
+    /// matched by name; imported contexts are never made active. The whole import
+    /// runs in a single transaction.
+    pub fn import(conn: &Connection, doc: &LedgerExport) -> Result<ImportSummary> {
+        let tx = conn.unchecked_transaction()?;
+        let summary = Self::import_rows(&tx, doc)?;
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Write the rows of `doc` through `conn`, inside the caller's transaction
+    pub(crate) fn import_rows(tx: &Connection, doc: &LedgerExport) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+
+        // Projects: match by name, map exported id -> local id
+        let mut project_map: HashMap<i64, i64> = HashMap::new();
+        let mut created_projects: HashSet<i64> = HashSet::new();
+        for project in &doc.projects {
+            let existing: Option<i64> = tx.query_row(
+                "SELECT id FROM projects WHERE name = ?1",
+                [&project.name],
+                |row| row.get(0),
+            ).optional()?;
+            let local_id = match existing {
+                Some(id) => id,
+                None => {
+                    tx.execute(
+                        "INSERT INTO projects (name, is_archived, created_ts, modified_ts) VALUES (?1, ?2, ?3, ?4)",
+                        rusqlite::params![project.name, project.is_archived as i64, project.created_ts, project.modified_ts],
+                    ).with_context(|| format!("Failed to import project '{}'", project.name))?;
+                    summary.projects_created += 1;
+                    created_projects.insert(project.id);
+                    tx.last_insert_rowid()
+                }
+            };
+            project_map.insert(project.id, local_id);
+        }
+
+        // Tasks: match by uuid, only new tasks carry their related rows
+        let mut task_map: HashMap<String, i64> = HashMap::new();
+        for task in &doc.tasks {
+            if crate::models::TaskStatus::from_str(&task.status).is_none() {
+                return Err(anyhow!("Task {} has invalid status '{}'", task.uuid, task.status));
+            }
+            let existing: Option<i64> = tx.query_row(
+                "SELECT id FROM tasks WHERE uuid = ?1",
+                [&task.uuid],
+                |row| row.get(0),
+            ).optional()?;
+            if existing.is_some() {
+                summary.tasks_skipped += 1;
+                continue;
+            }
+            let project_id = match task.project_id {
+                Some(pid) => Some(*project_map.get(&pid).ok_or_else(|| {
+                    anyhow!("Task {} references unknown project id {}", task.uuid, pid)
+                })?),
+                None => None,
+            };
+            let udas_json = task.udas.as_ref()
+                .filter(|v| !v.is_null())
+                .map(json_text);
+            tx.execute(
+                "INSERT INTO tasks (uuid, description, status, project_id, due_ts, scheduled_ts, wait_ts,
+                                    alloc_secs, template, respawn, udas_json, created_ts, modified_ts)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                rusqlite::params![
+                    task.uuid, task.description, task.status, project_id, task.due_ts,
+                    task.scheduled_ts, task.wait_ts, task.alloc_secs, task.template,
+                    task.respawn, udas_json, task.created_ts, task.modified_ts
+                ],
+            ).with_context(|| format!("Failed to import task {}", task.uuid))?;
+            task_map.insert(task.uuid.clone(), tx.last_insert_rowid());
+            summary.tasks_imported += 1;
+        }
+
+        for tag in &doc.task_tags {
+            if let Some(task_id) = task_map.get(&tag.task_uuid) {
+                summary.tags += tx.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                    rusqlite::params![task_id, tag.tag],
+                )?;
+            }
+        }
+
+        // Dependencies of new tasks, on tasks that are new or already in the ledger
+        for dependency in &doc.task_dependencies {
+            let Some(task_id) = task_map.get(&dependency.task_uuid) else { continue };
+            let depends_on_id: Option<i64> = tx.query_row(
+                "SELECT id FROM tasks WHERE uuid = ?1",
+                [&dependency.depends_on_uuid],
+                |row| row.get(0),
+            ).optional()?;
+            if let Some(depends_on_id) = depends_on_id {
+                summary.dependencies += tx.execute(
+                    "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_ts) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![task_id, depends_on_id, dependency.created_ts],
+                )?;
+            }
+        }
+
+        // Sessions: only one open session may exist in a ledger
+        let mut session_map: HashMap<i64, i64> = HashMap::new();
+        for session in &doc.sessions {
+            let Some(task_id) = task_map.get(&session.task_uuid) else { continue };
+            if session.end_ts.is_none() {
+                let open: Option<i64> = tx.query_row(
+                    "SELECT id FROM sessions WHERE end_ts IS NULL",
+                    [],
+                    |row| row.get(0),
+                ).optional()?;
+                if open.is_some() {
+                    summary.sessions_skipped += 1;
+                    continue;
+                }
+            }
+            tx.execute(
+                "INSERT INTO sessions (task_id, start_ts, end_ts, created_ts) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![task_id, session.start_ts, session.end_ts, session.created_ts],
+            )?;
+            session_map.insert(session.id, tx.last_insert_rowid());
+            summary.sessions += 1;
+        }
+
+        for annotation in &doc.task_annotations {
+            if let Some(task_id) = task_map.get(&annotation.task_uuid) {
+                let session_id = annotation.session_id.and_then(|sid| session_map.get(&sid).copied());
+                tx.execute(
+                    "INSERT INTO task_annotations (task_id, session_id, note, entry_ts, created_ts)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![task_id, session_id, annotation.note, annotation.entry_ts, annotation.created_ts],
+                )?;
+                summary.annotations += 1;
+            }
+        }
+
+        for event in &doc.task_events {
+            if let Some(task_id) = task_map.get(&event.task_uuid) {
+                tx.execute(
+                    "INSERT INTO task_events (task_id, ts, event_type, payload_json) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![task_id, event.ts, event.event_type, json_text(&event.payload)],
+                )?;
+                summary.events += 1;
+            }
+        }
+
+        let mut external_map: HashMap<i64, i64> = HashMap::new();
+        for external in &doc.externals {
+            if let Some(task_id) = task_map.get(&external.task_uuid) {
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO externals (task_id, recipient, request, sent_ts, returned_ts, created_ts, modified_ts)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    rusqlite::params![
+                        task_id, external.recipient, external.request, external.sent_ts,
+                        external.returned_ts, external.created_ts, external.modified_ts
+                    ],
+                )?;
+                if inserted > 0 {
+                    external_map.insert(external.id, tx.last_insert_rowid());
+                }
+                summary.externals += inserted;
+            }
+        }
+
+        // Audit events follow their new task, or their new project. Events of sessions
+        // and externals that no longer exist keep their exported entity id, as in the archive.
+        for event in &doc.entity_events {
+            let task_id = event.task_uuid.as_ref().and_then(|uuid| task_map.get(uuid)).copied();
+            let entity_id = match event.entity_type.as_str() {
+                "session" if task_id.is_some() => session_map.get(&event.entity_id),
+                "external" if task_id.is_some() => external_map.get(&event.entity_id),
+                "project" if created_projects.contains(&event.entity_id) => project_map.get(&event.entity_id),
+                _ => continue,
+            };
+            tx.execute(
+                "INSERT INTO entity_events (entity_type, entity_id, task_id, ts, event_type, payload_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![event.entity_type, entity_id.copied().unwrap_or(event.entity_id),
+                    task_id, event.ts, event.event_type, json_text(&event.payload)],
+            )?;
+            summary.events += 1;
+        }
+
+        // Stacks: match by name; imported items are appended in their exported order
+        let mut stack_map: HashMap<i64, i64> = HashMap::new();
+        for stack in &doc.stacks {
+            let existing: Option<i64> = tx.query_row(
+                "SELECT id FROM stacks WHERE name = ?1 ORDER BY id LIMIT 1",
+                [&stack.name],
+                |row| row.get(0),
+            ).optional()?;
+            let local_id = match existing {
+                Some(id) => id,
+                None => {
+                    tx.execute(
+                        "INSERT INTO stacks (name, created_ts, modified_ts) VALUES (?1, ?2, ?3)",
+                        rusqlite::params![stack.name, stack.created_ts, stack.modified_ts],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            stack_map.insert(stack.id, local_id);
+        }
+        let mut items: Vec<&StackItemRecord> = doc.stack_items.iter().collect();
+        items.sort_by_key(|item| (item.stack_id, item.ordinal));
+        let mut seen: HashSet<(i64, i64)> = HashSet::new();
+        for item in items {
+            let (Some(task_id), Some(stack_id)) = (task_map.get(&item.task_uuid), stack_map.get(&item.stack_id)) else {
+                continue;
+            };
+            if !seen.insert((*stack_id, *task_id)) {
+                continue;
+            }
+            let next_ordinal: i64 = tx.query_row(
+                "SELECT COALESCE(MAX(ordinal), -1) + 1 FROM stack_items WHERE stack_id = ?1",
+                [stack_id],
+                |row| row.get(0),
+            )?;
+            tx.execute(
+                "INSERT INTO stack_items (stack_id, task_id, ordinal, added_ts) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![stack_id, task_id, next_ordinal, item.added_ts],
+            )?;
+            summary.stack_items += 1;
+        }
+
+        for template in &doc.templates {
+            summary.templates += tx.execute(
+                "INSERT OR IGNORE INTO templates (name, payload_json, created_ts, modified_ts) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![template.name, json_text(&template.payload), template.created_ts, template.modified_ts],
+            )?;
+        }
+
+        for view in &doc.list_views {
+            let exists: Option<String> = tx.query_row(
+                "SELECT name FROM list_views WHERE entity = ?1 AND name = ?2",
+                [&view.entity, &view.name],
+                |row| row.get(0),
+            ).optional()?;
+            if exists.is_some() {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO list_views (name, entity, filter_json, sort_json, group_json, hide_json,
+                                         color_json, fill_json, created_ts, modified_ts)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    view.name,
+                    view.entity,
+                    json_text(&view.filter),
+                    json_text(&view.sort),
+                    json_text(&view.group),
+                    view.hide.as_ref().map(json_text).unwrap_or_else(|| "[]".to_string()),
+                    view.color.as_ref().map(json_text),
+                    view.fill.as_ref().map(json_text),
+                    view.created_ts,
+                    view.modified_ts
+                ],
+            )?;
+            summary.views += 1;
+        }
+
+        for context in &doc.contexts {
+            summary.contexts += tx.execute(
+                "INSERT OR IGNORE INTO contexts (name, filter_json, is_active, created_ts, modified_ts)
+                 VALUES (?1, ?2, 0, ?3, ?4)",
+                rusqlite::params![context.name, json_text(&context.filter), context.created_ts, context.modified_ts],
+            )?;
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::repo::{TaskRepo, ProjectRepo, SessionRepo, StackRepo, AnnotationRepo};
+
+    fn seeded_ledger() -> Connection {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let project = ProjectRepo::create(&conn, "work").unwrap();
+        let task = TaskRepo::create(&conn, "Write report", project.id).unwrap();
+        let task_id = task.id.unwrap();
+        conn.execute("INSERT INTO task_tags (task_id, tag) VALUES (?1, 'urgent')", [task_id]).unwrap();
+        SessionRepo::create_closed(&conn, task_id, 1_000, 2_000).unwrap();
+        AnnotationRepo::create(&conn, task_id, "First draft".to_string(), None).unwrap();
+        StackRepo::enqueue(&conn, StackRepo::get_or_create_default(&conn).unwrap().id.unwrap(), task_id).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_export_contains_all_tables() {
+        let conn = seeded_ledger();
+        let doc = LedgerTransfer::export(&conn).unwrap();
+        assert_eq!(doc.format, LEDGER_FORMAT);
+        assert_eq!(doc.schema_version, CURRENT_VERSION);
+        assert_eq!(doc.projects.len(), 1);
+        assert_eq!(doc.tasks.len(), 1);
+        assert_eq!(doc.task_tags.len(), 1);
+        assert_eq!(doc.sessions.len(), 1);
+        assert_eq!(doc.task_annotations.len(), 1);
+        assert_eq!(doc.stack_items.len(), 1);
+        assert!(!doc.task_events.is_empty());
+        assert!(doc.entity_events.iter().any(|e| e.entity_type == "session"));
+    }
+
+    #[test]
+    fn test_import_round_trip_into_empty_ledger() {
+        let source = seeded_ledger();
+        let doc = LedgerTransfer::export(&source).unwrap();
+        let json = serde_json::to_string(&doc).unwrap();
+
+        let target = DbConnection::connect_in_memory().unwrap();
+        let summary = LedgerTransfer::import(&target, &LedgerTransfer::parse(&json).unwrap()).unwrap();
+        assert_eq!(summary.tasks_imported, 1);
+        assert_eq!(summary.projects_created, 1);
+        assert_eq!(summary.sessions, 1);
+        assert_eq!(summary.stack_items, 1);
+
+        let reexported = LedgerTransfer::export(&target).unwrap();
+        assert_eq!(reexported.tasks[0].uuid, doc.tasks[0].uuid);
+        assert_eq!(reexported.task_events.len(), doc.task_events.len());
+    }
+
+    #[test]
+    fn test_import_skips_existing_uuids() {
+        let conn = seeded_ledger();
+        let doc = LedgerTransfer::export(&conn).unwrap();
+        let summary = LedgerTransfer::import(&conn, &doc).unwrap();
+        assert_eq!(summary.tasks_imported, 0);
+        assert_eq!(summary.tasks_skipped, 1);
+        assert_eq!(summary.sessions, 0);
+        assert_eq!(summary.projects_created, 0);
+    }
+
+    #[test]
+    fn test_dependencies_round_trip_by_uuid() {
+        use crate::repo::DependencyRepo;
+        let source = seeded_ledger();
+        let blocked = TaskRepo::create(&source, "Send report", None).unwrap().id.unwrap();
+        DependencyRepo::set(&source, blocked, &[1]).unwrap();
+        let doc = LedgerTransfer::export(&source).unwrap();
+        assert_eq!(doc.task_dependencies.len(), 1);
+
+        let target = DbConnection::connect_in_memory().unwrap();
+        let summary = LedgerTransfer::import(&target, &doc).unwrap();
+        assert_eq!(summary.dependencies, 1);
+        assert!(DependencyRepo::is_blocked(&target, 2).unwrap());
+        assert_eq!(LedgerTransfer::import(&target, &doc).unwrap().dependencies, 0);
+    }
+
+    #[test]
+    fn test_contexts_import_inactive() {
+        use crate::repo::ContextRepo;
+        let source = seeded_ledger();
+        ContextRepo::define(&source, "work", &["project=work".to_string()]).unwrap();
+        ContextRepo::set_active(&source, Some("work")).unwrap();
+        let doc = LedgerTransfer::export(&source).unwrap();
+        assert_eq!(doc.contexts.len(), 1);
+
+        let target = DbConnection::connect_in_memory().unwrap();
+        let summary = LedgerTransfer::import(&target, &doc).unwrap();
+        assert_eq!(summary.contexts, 1);
+        assert_eq!(ContextRepo::get_by_name(&target, "work").unwrap().unwrap().filter_tokens, vec!["project=work"]);
+        assert_eq!(ContextRepo::get_active(&target).unwrap(), None);
+        assert_eq!(LedgerTransfer::import(&target, &doc).unwrap().contexts, 0);
+    }
+
+    #[test]
+    fn test_entity_events_round_trip_with_remapped_ids() {
+        let source = DbConnection::connect_in_memory().unwrap();
+        let filler = TaskRepo::create(&source, "Filler", None).unwrap().id.unwrap();
+        SessionRepo::create_closed(&source, filler, 500, 600).unwrap();
+        let seeded = LedgerTransfer::export(&seeded_ledger()).unwrap();
+        LedgerTransfer::import(&source, &seeded).unwrap();
+        ProjectRepo::archive(&source, "work").unwrap();
+        let doc = LedgerTransfer::export(&source).unwrap();
+        assert!(doc.entity_events.iter().any(|e| e.entity_type == "project"));
+
+        let target = DbConnection::connect_in_memory().unwrap();
+        ProjectRepo::create(&target, "home").unwrap();
+        LedgerTransfer::import(&target, &doc).unwrap();
+        let reexported = LedgerTransfer::export(&target).unwrap();
+        assert_eq!(reexported.entity_events.len(), doc.entity_events.len());
+
+        let session_ids: Vec<i64> = reexported.sessions.iter().map(|s| s.id).collect();
+        for event in reexported.entity_events.iter().filter(|e| e.entity_type == "session") {
+            assert!(session_ids.contains(&event.entity_id));
+        }
+        let work = ProjectRepo::get_by_name(&target, "work").unwrap().unwrap();
+        let project_event = reexported.entity_events.iter().find(|e| e.entity_type == "project").unwrap();
+        assert_eq!(project_event.entity_id, work.id.unwrap());
+
+        // Re-importing the same document does not duplicate audit rows.
+        LedgerTransfer::import(&target, &doc).unwrap();
+        assert_eq!(LedgerTransfer::export(&target).unwrap().entity_events.len(), doc.entity_events.len());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_format() {
+        let json = r#"{"format":"other","version":1,"schema_version":1,"exported_ts":0}"#;
+        assert!(LedgerTransfer::parse(json).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+pub mod ledger;
+pub mod taskwarrior;
+pub mod intervals;
+pub mod sync;
+
+pub use ledger::*;
+pub use taskwarrior::*;
+pub use intervals::*;
+pub use sync::*;
+
+/// Replace characters tatl does not allow in project names and tags
+pub(crate) fn sanitize_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '.' || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,872 @@
+// Two-way sync between ledgers
+//
+// Both ledgers are exported, tasks are matched by uuid, and each side receives
+// whatever the other side has that it lacks. Tasks that only exist on one side
+// are copied with their full history through the ledger import. For tasks that
+// exist on both sides, each attribute takes the value from the side whose most
+// recent `task_events` entry for that attribute is newer. Tasks a ledger has
+// purged or archived are recorded in its `task_tombstones` and never copied back.
+
+use rusqlite::{Connection, OptionalExtension};
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::transfer::ledger::{
+    LedgerExport, LedgerTransfer, TaskRecord, AnnotationRecord, SessionRecord, EventRecord,
+    ExternalRecord, EntityEventRecord, ImportSummary,
+};
+
+/// Task attributes merged individually, in report order
+const SYNC_FIELDS: &[&str] = &[
+    "description", "status", "project", "due_ts", "scheduled_ts", "wait_ts",
+    "alloc_secs", "template", "respawn", "udas",
+];
+
+/// A task whose attributes were overwritten by the other ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedTask {
+    pub uuid: String,
+    pub description: String,
+    pub fields: Vec<&'static str>,
+}
+
+/// What a sync changed in one ledger
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncChanges {
+    pub tasks_added: usize,
+    /// Tasks of the other ledger that this one purged or archived
+    pub tasks_tombstoned: usize,
+    pub projects_created: usize,
+    pub updated: Vec<SyncedTask>,
+    pub tags_added: usize,
+    pub tags_removed: usize,
+    pub dependencies: usize,
+    pub annotations: usize,
+    pub sessions: usize,
+    pub sessions_updated: usize,
+    pub sessions_skipped: usize,
+    pub externals: usize,
+    pub events: usize,
+    pub templates: usize,
+    pub views: usize,
+    pub contexts: usize,
+}
+
+impl SyncChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == SyncChanges::default()
+    }
+
+    fn absorb(&mut self, summary: ImportSummary) {
+        self.tasks_added += summary.tasks_imported;
+        self.projects_created += summary.projects_created;
+        self.tags_added += summary.tags;
+        self.dependencies += summary.dependencies;
+        self.annotations += summary.annotations;
+        self.sessions += summary.sessions;
+        self.sessions_skipped += summary.sessions_skipped;
+        self.externals += summary.externals;
+        self.events += summary.events;
+        self.templates += summary.templates;
+        self.views += summary.views;
+        self.contexts += summary.contexts;
+    }
+}
+
+/// Changes applied to both ledgers by a sync
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub local: SyncChanges,
+    pub remote: SyncChanges,
+}
+
+/// Row an audit event refers to, looked up again in the receiving ledger
+#[derive(Debug, Clone)]
+enum EntityKey {
+    /// Session of the same task, by start time
+    Session(i64),
+    /// External of the same task, by recipient
+    External(String),
+    /// Project, by name
+    Project(String),
+}
+
+/// Pending writes for one shared task in one ledger
+#[derive(Debug, Default)]
+struct TaskPatch {
+    uuid: String,
+    description: String,
+    fields: Vec<(&'static str, serde_json::Value)>,
+    modified_ts: i64,
+    tags_add: Vec<String>,
+    tags_remove: Vec<String>,
+    sessions_add: Vec<SessionRecord>,
+    sessions_end: Vec<(i64, Option<i64>)>,
+    /// Annotations to add, with the start time of the session they belong to
+    annotations_add: Vec<(AnnotationRecord, Option<i64>)>,
+    externals_put: Vec<ExternalRecord>,
+    events_add: Vec<EventRecord>,
+    entity_events_add: Vec<(EntityEventRecord, Option<EntityKey>)>,
+}
+
+impl TaskPatch {
+    fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.tags_add.is_empty() && self.tags_remove.is_empty()
+            && self.sessions_add.is_empty() && self.sessions_end.is_empty()
+            && self.annotations_add.is_empty() && self.externals_put.is_empty()
+            && self.events_add.is_empty() && self.entity_events_add.is_empty()
+    }
+}
+
+/// Rows of one ledger export grouped by task uuid
+struct LedgerIndex<'a> {
+    tasks: HashMap<&'a str, &'a TaskRecord>,
+    projects: HashMap<i64, &'a str>,
+    tags: HashMap<&'a str, BTreeSet<&'a str>>,
+    annotations: HashMap<&'a str, Vec<&'a AnnotationRecord>>,
+    sessions: HashMap<&'a str, Vec<&'a SessionRecord>>,
+    events: HashMap<&'a str, Vec<&'a EventRecord>>,
+    externals: HashMap<&'a str, Vec<&'a ExternalRecord>>,
+    entity_events: HashMap<&'a str, Vec<&'a EntityEventRecord>>,
+}
+
+impl<'a> LedgerIndex<'a> {
+    fn new(doc: &'a LedgerExport) -> Self {
+        let mut index = LedgerIndex {
+            tasks: doc.tasks.iter().map(|t| (t.uuid.as_str(), t)).collect(),
+            projects: doc.projects.iter().map(|p| (p.id, p.name.as_str())).collect(),
+            tags: HashMap::new(),
+            annotations: HashMap::new(),
+            sessions: HashMap::new(),
+            events: HashMap::new(),
+            externals: HashMap::new(),
+            entity_events: HashMap::new(),
+        };
+        for tag in &doc.task_tags {
+            index.tags.entry(tag.task_uuid.as_str()).or_default().insert(tag.tag.as_str());
+        }
+        for annotation in &doc.task_annotations {
+            index.annotations.entry(annotation.task_uuid.as_str()).or_default().push(annotation);
+        }
+        for session in &doc.sessions {
+            index.sessions.entry(session.task_uuid.as_str()).or_default().push(session);
+        }
+        for event in &doc.task_events {
+            index.events.entry(event.task_uuid.as_str()).or_default().push(event);
+        }
+        for external in &doc.externals {
+            index.externals.entry(external.task_uuid.as_str()).or_default().push(external);
+        }
+        for event in &doc.entity_events {
+            if let Some(uuid) = &event.task_uuid {
+                index.entity_events.entry(uuid.as_str()).or_default().push(event);
+            }
+        }
+        index
+    }
+
+    fn field_value(&self, task: &TaskRecord, field: &str) -> serde_json::Value {
+        use serde_json::Value;
+        let opt_int = |v: Option<i64>| v.map(Value::from).unwrap_or(Value::Null);
+        let opt_str = |v: &Option<String>| v.clone().map(Value::String).unwrap_or(Value::Null);
+        match field {
+            "description" => Value::String(task.description.clone()),
+            "status" => Value::String(task.status.clone()),
+            "project" => task.project_id
+                .and_then(|id| self.projects.get(&id))
+                .map(|name| Value::String(name.to_string()))
+                .unwrap_or(Value::Null),
+            "due_ts" => opt_int(task.due_ts),
+            "scheduled_ts" => opt_int(task.scheduled_ts),
+            "wait_ts" => opt_int(task.wait_ts),
+            "alloc_secs" => opt_int(task.alloc_secs),
+            "template" => opt_str(&task.template),
+            "respawn" => opt_str(&task.respawn),
+            "udas" => match &task.udas {
+                Some(Value::Object(map)) if map.is_empty() => Value::Null,
+                Some(value) => value.clone(),
+                None => Value::Null,
+            },
+            _ => Value::Null,
+        }
+    }
+
+    /// Timestamp of the latest event that changed `field` (0 if it never changed)
+    fn field_ts(&self, uuid: &str, field: &str) -> i64 {
+        let event_field = if field == "project" { "project_id" } else { field };
+        self.events.get(uuid).into_iter().flatten()
+            .filter(|e| match e.event_type.as_str() {
+                "status_changed" => field == "status",
+                "modified" => e.payload.get("field").and_then(|f| f.as_str()) == Some(event_field),
+                _ => false,
+            })
+            .map(|e| e.ts)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Latest tag event for `tag`: (ts, was_added)
+    fn tag_event(&self, uuid: &str, tag: &str) -> Option<(i64, bool)> {
+        self.events.get(uuid).into_iter().flatten()
+            .filter(|e| e.payload.get("tag").and_then(|t| t.as_str()) == Some(tag))
+            .filter_map(|e| match e.event_type.as_str() {
+                "tag_added" => Some((e.ts, true)),
+                "tag_removed" => Some((e.ts, false)),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Timestamp of the latest event that closed the session starting at `start_ts`
+    fn session_end_ts(&self, uuid: &str, start_ts: i64) -> i64 {
+        self.events.get(uuid).into_iter().flatten()
+            .filter(|e| e.event_type == "session_ended"
+                && e.payload.get("start_ts").and_then(|s| s.as_i64()) == Some(start_ts))
+            .map(|e| e.ts)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn session_start(&self, uuid: &str, session_id: Option<i64>) -> Option<i64> {
+        let session_id = session_id?;
+        self.sessions.get(uuid)?.iter().find(|s| s.id == session_id).map(|s| s.start_ts)
+    }
+
+    /// The session or external a task's audit event refers to, if it still exists
+    fn entity_key(&self, uuid: &str, event: &EntityEventRecord) -> Option<EntityKey> {
+        match event.entity_type.as_str() {
+            "session" => self.session_start(uuid, Some(event.entity_id)).map(EntityKey::Session),
+            "external" => self.externals.get(uuid)?.iter()
+                .find(|x| x.id == event.entity_id)
+                .map(|x| EntityKey::External(x.recipient.clone())),
+            _ => None,
+        }
+    }
+}
+
+fn event_key(event: &EventRecord) -> (i64, String, String) {
+    (event.ts, event.event_type.clone(), serde_json::to_string(&event.payload).unwrap_or_default())
+}
+
+fn entity_event_key(event: &EntityEventRecord) -> (String, i64, String, String) {
+    (event.entity_type.clone(), event.ts, event.event_type.clone(),
+        serde_json::to_string(&event.payload).unwrap_or_default())
+}
+
+/// Uuids of the tasks a ledger has purged or archived
+fn tombstones(conn: &Connection) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT uuid FROM task_tombstones")?;
+    let uuids = stmt.query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<HashSet<String>>>()?;
+    Ok(uuids)
+}
+
+/// The part of `doc` that the ledger with `tombstones` should import: tasks it
+/// never removed, with their task-bound audit events. Project events are merged
+/// separately, since the import only carries them for projects it creates.
+fn importable(doc: &LedgerExport, tombstones: &HashSet<String>) -> (LedgerExport, usize) {
+    let mut doc = doc.clone();
+    let before = doc.tasks.len();
+    doc.tasks.retain(|t| !tombstones.contains(&t.uuid));
+    doc.entity_events.retain(|e| e.task_uuid.is_some());
+    let skipped = before - doc.tasks.len();
+    (doc, skipped)
+}
+
+/// Two-way ledger sync
+pub struct LedgerSync;
+
+impl LedgerSync {
+    /// Merge `local` and `remote` so that both end up with the same tasks.
+    ///
+    /// Queue order stays per-ledger: stacks are neither copied nor merged.
+    /// Each ledger is written in a single transaction, and both are committed
+    /// only once every write succeeded; on error neither ledger changes.
+    pub fn sync(local: &Connection, remote: &Connection) -> Result<SyncReport> {
+        let local_tx = local.unchecked_transaction()?;
+        let remote_tx = remote.unchecked_transaction()?;
+
+        let mut local_doc = LedgerTransfer::export(&local_tx)?;
+        let mut remote_doc = LedgerTransfer::export(&remote_tx)?;
+        for doc in [&mut local_doc, &mut remote_doc] {
+            doc.stacks.clear();
+            doc.stack_items.clear();
+        }
+
+        let (local_patches, remote_patches) = Self::plan(&local_doc, &remote_doc);
+        let local_project_events = Self::plan_project_events(&remote_doc, &local_doc);
+        let remote_project_events = Self::plan_project_events(&local_doc, &remote_doc);
+        let mut report = SyncReport::default();
+
+        // Tasks present on one side only travel with their whole history,
+        // unless the other side purged or archived them
+        let (to_local, skipped) = importable(&remote_doc, &tombstones(&local_tx)?);
+        report.local.tasks_tombstoned = skipped;
+        report.local.absorb(LedgerTransfer::import_rows(&local_tx, &to_local)?);
+        let (to_remote, skipped) = importable(&local_doc, &tombstones(&remote_tx)?);
+        report.remote.tasks_tombstoned = skipped;
+        report.remote.absorb(LedgerTransfer::import_rows(&remote_tx, &to_remote)?);
+
+        Self::apply(&local_tx, &local_patches, &mut report.local)?;
+        Self::apply(&remote_tx, &remote_patches, &mut report.remote)?;
+        for (event, key) in &local_project_events {
+            Self::write_entity_event(&local_tx, None, event, key.as_ref())?;
+            report.local.events += 1;
+        }
+        for (event, key) in &remote_project_events {
+            Self::write_entity_event(&remote_tx, None, event, key.as_ref())?;
+            report.remote.events += 1;
+        }
+
+        local_tx.commit()?;
+        remote_tx.commit()?;
+        Ok(report)
+    }
+
+    /// Project audit events of `from` that `to` lacks, with the project's name
+    fn plan_project_events(from: &LedgerExport, to: &LedgerExport) -> Vec<(EntityEventRecord, Option<EntityKey>)> {
+        let known: HashSet<_> = to.entity_events.iter()
+            .filter(|e| e.task_uuid.is_none())
+            .map(entity_event_key)
+            .collect();
+        from.entity_events.iter()
+            .filter(|e| e.task_uuid.is_none() && !known.contains(&entity_event_key(e)))
+            .map(|e| {
+                let name = from.projects.iter().find(|p| p.id == e.entity_id).map(|p| EntityKey::Project(p.name.clone()));
+                (e.clone(), name)
+            })
+            .collect()
+    }
+
+    /// Work out the writes each side needs for the tasks both ledgers share
+    fn plan(local_doc: &LedgerExport, remote_doc: &LedgerExport) -> (Vec<TaskPatch>, Vec<TaskPatch>) {
+        let local = LedgerIndex::new(local_doc);
+        let remote = LedgerIndex::new(remote_doc);
+        let mut local_patches = Vec::new();
+        let mut remote_patches = Vec::new();
+
+        for task_l in &local_doc.tasks {
+            let Some(task_r) = remote.tasks.get(task_l.uuid.as_str()) else { continue };
+            let uuid = task_l.uuid.as_str();
+            let modified_ts = task_l.modified_ts.max(task_r.modified_ts);
+            let mut to_local = TaskPatch { uuid: uuid.to_string(), modified_ts, ..Default::default() };
+            let mut to_remote = TaskPatch { uuid: uuid.to_string(), modified_ts, ..Default::default() };
+
+            // Attributes: the newest change wins, ties fall back to the task's modified time
+            for field in SYNC_FIELDS {
+                let value_l = local.field_value(task_l, field);
+                let value_r = remote.field_value(task_r, field);
+                if value_l == value_r {
+                    continue;
+                }
+                let key_l = (local.field_ts(uuid, field), task_l.modified_ts);
+                let key_r = (remote.field_ts(uuid, field), task_r.modified_ts);
+                if key_r > key_l {
+                    to_local.fields.push((field, value_r));
+                } else {
+                    to_remote.fields.push((field, value_l));
+                }
+            }
+            let winner_description = |patch: &TaskPatch, fallback: &str| {
+                patch.fields.iter()
+                    .find(|(f, _)| *f == "description")
+                    .and_then(|(_, v)| v.as_str().map(String::from))
+                    .unwrap_or_else(|| fallback.to_string())
+            };
+            to_local.description = winner_description(&to_local, &task_l.description);
+            to_remote.description = winner_description(&to_remote, &task_r.description);
+
+            // Tags: a tag missing on one side is added there unless its latest event is a removal
+            let empty = BTreeSet::new();
+            let tags_l = local.tags.get(uuid).unwrap_or(&empty);
+            let tags_r = remote.tags.get(uuid).unwrap_or(&empty);
+            for tag in tags_l.symmetric_difference(tags_r) {
+                let latest = local.tag_event(uuid, tag).max(remote.tag_event(uuid, tag));
+                let removed = matches!(latest, Some((_, false)));
+                match (tags_l.contains(tag), removed) {
+                    (true, false) => to_remote.tags_add.push(tag.to_string()),
+                    (true, true) => to_local.tags_remove.push(tag.to_string()),
+                    (false, false) => to_local.tags_add.push(tag.to_string()),
+                    (false, true) => to_remote.tags_remove.push(tag.to_string()),
+                }
+            }
+
+            // Sessions: matched by start time; a differing end takes the latest close
+            let sessions_l = local.sessions.get(uuid).cloned().unwrap_or_default();
+            let sessions_r = remote.sessions.get(uuid).cloned().unwrap_or_default();
+            for session_l in &sessions_l {
+                match sessions_r.iter().find(|s| s.start_ts == session_l.start_ts) {
+                    None => to_remote.sessions_add.push((*session_l).clone()),
+                    Some(session_r) if session_r.end_ts != session_l.end_ts => {
+                        let key_l = (local.session_end_ts(uuid, session_l.start_ts), session_l.end_ts);
+                        let key_r = (remote.session_end_ts(uuid, session_r.start_ts), session_r.end_ts);
+                        if key_r > key_l {
+                            to_local.sessions_end.push((session_l.start_ts, session_r.end_ts));
+                        } else {
+                            to_remote.sessions_end.push((session_r.start_ts, session_l.end_ts));
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+            for session_r in &sessions_r {
+                if !sessions_l.iter().any(|s| s.start_ts == session_r.start_ts) {
+                    to_local.sessions_add.push((*session_r).clone());
+                }
+            }
+
+            // Annotations: matched by entry time and text
+            let annotations_l = local.annotations.get(uuid).cloned().unwrap_or_default();
+            let annotations_r = remote.annotations.get(uuid).cloned().unwrap_or_default();
+            let same = |a: &AnnotationRecord, b: &AnnotationRecord| a.entry_ts == b.entry_ts && a.note == b.note;
+            for annotation in &annotations_l {
+                if !annotations_r.iter().any(|a| same(a, annotation)) {
+                    let start = local.session_start(uuid, annotation.session_id);
+                    to_remote.annotations_add.push(((*annotation).clone(), start));
+                }
+            }
+            for annotation in &annotations_r {
+                if !annotations_l.iter().any(|a| same(a, annotation)) {
+                    let start = remote.session_start(uuid, annotation.session_id);
+                    to_local.annotations_add.push(((*annotation).clone(), start));
+                }
+            }
+
+            // Externals: matched by recipient, the most recently modified row wins
+            let externals_l = local.externals.get(uuid).cloned().unwrap_or_default();
+            let externals_r = remote.externals.get(uuid).cloned().unwrap_or_default();
+            let differs = |a: &ExternalRecord, b: &ExternalRecord| {
+                a.request != b.request || a.sent_ts != b.sent_ts || a.returned_ts != b.returned_ts
+            };
+            for external_l in &externals_l {
+                match externals_r.iter().find(|x| x.recipient == external_l.recipient) {
+                    None => to_remote.externals_put.push((*external_l).clone()),
+                    Some(external_r) if differs(external_l, external_r) => {
+                        if external_r.modified_ts > external_l.modified_ts {
+                            to_local.externals_put.push((*external_r).clone());
+                        } else {
+                            to_remote.externals_put.push((*external_l).clone());
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+            for external_r in &externals_r {
+                if !externals_l.iter().any(|x| x.recipient == external_r.recipient) {
+                    to_local.externals_put.push((*external_r).clone());
+                }
+            }
+
+            // Events: union of both histories
+            let events_l = local.events.get(uuid).cloned().unwrap_or_default();
+            let events_r = remote.events.get(uuid).cloned().unwrap_or_default();
+            let keys_l: BTreeSet<_> = events_l.iter().map(|e| event_key(e)).collect();
+            let keys_r: BTreeSet<_> = events_r.iter().map(|e| event_key(e)).collect();
+            to_remote.events_add = events_l.iter().filter(|e| !keys_r.contains(&event_key(e))).map(|e| (*e).clone()).collect();
+            to_local.events_add = events_r.iter().filter(|e| !keys_l.contains(&event_key(e))).map(|e| (*e).clone()).collect();
+
+            // Session and external audit events: union, pointed at the matching row
+            let audit_l = local.entity_events.get(uuid).cloned().unwrap_or_default();
+            let audit_r = remote.entity_events.get(uuid).cloned().unwrap_or_default();
+            let keys_l: BTreeSet<_> = audit_l.iter().map(|e| entity_event_key(e)).collect();
+            let keys_r: BTreeSet<_> = audit_r.iter().map(|e| entity_event_key(e)).collect();
+            to_remote.entity_events_add = audit_l.iter()
+                .filter(|e| !keys_r.contains(&entity_event_key(e)))
+                .map(|e| ((*e).clone(), local.entity_key(uuid, e)))
+                .collect();
+            to_local.entity_events_add = audit_r.iter()
+                .filter(|e| !keys_l.contains(&entity_event_key(e)))
+                .map(|e| ((*e).clone(), remote.entity_key(uuid, e)))
+                .collect();
+
+            if !to_local.is_empty() {
+                local_patches.push(to_local);
+            }
+            if !to_remote.is_empty() {
+                remote_patches.push(to_remote);
+            }
+        }
+        (local_patches, remote_patches)
+    }
+
+    /// Write the planned changes into one ledger, inside the sync's transaction
+    fn apply(tx: &Connection, patches: &[TaskPatch], changes: &mut SyncChanges) -> Result<()> {
+        for patch in patches {
+            let task_id: i64 = tx.query_row(
+                "SELECT id FROM tasks WHERE uuid = ?1",
+                [&patch.uuid],
+                |row| row.get(0),
+            )?;
+
+            for (field, value) in &patch.fields {
+                Self::write_field(tx, task_id, field, value, changes)?;
+            }
+            if !patch.fields.is_empty() {
+                tx.execute(
+                    "UPDATE tasks SET modified_ts = ?1 WHERE id = ?2",
+                    rusqlite::params![patch.modified_ts, task_id],
+                )?;
+                changes.updated.push(SyncedTask {
+                    uuid: patch.uuid.clone(),
+                    description: patch.description.clone(),
+                    fields: patch.fields.iter().map(|(f, _)| *f).collect(),
+                });
+            }
+
+            for tag in &patch.tags_add {
+                changes.tags_added += tx.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                    rusqlite::params![task_id, tag],
+                )?;
+            }
+            for tag in &patch.tags_remove {
+                changes.tags_removed += tx.execute(
+                    "DELETE FROM task_tags WHERE task_id = ?1 AND tag = ?2",
+                    rusqlite::params![task_id, tag],
+                )?;
+            }
+
+            for (start_ts, end_ts) in &patch.sessions_end {
+                changes.sessions_updated += tx.execute(
+                    "UPDATE sessions SET end_ts = ?1 WHERE task_id = ?2 AND start_ts = ?3",
+                    rusqlite::params![end_ts, task_id, start_ts],
+                )?;
+            }
+            for session in &patch.sessions_add {
+                // Only one open session may exist in a ledger
+                if session.end_ts.is_none() {
+                    let open: Option<i64> = tx.query_row(
+                        "SELECT id FROM sessions WHERE end_ts IS NULL",
+                        [],
+                        |row| row.get(0),
+                    ).optional()?;
+                    if open.is_some() {
+                        changes.sessions_skipped += 1;
+                        continue;
+                    }
+                }
+                tx.execute(
+                    "INSERT INTO sessions (task_id, start_ts, end_ts, created_ts) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![task_id, session.start_ts, session.end_ts, session.created_ts],
+                )?;
+                changes.sessions += 1;
+            }
+
+            for (annotation, session_start) in &patch.annotations_add {
+                let session_id: Option<i64> = match session_start {
+                    Some(start_ts) => tx.query_row(
+                        "SELECT id FROM sessions WHERE task_id = ?1 AND start_ts = ?2",
+                        rusqlite::params![task_id, start_ts],
+                        |row| row.get(0),
+                    ).optional()?,
+                    None => None,
+                };
+                tx.execute(
+                    "INSERT INTO task_annotations (task_id, session_id, note, entry_ts, created_ts)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![task_id, session_id, annotation.note, annotation.entry_ts, annotation.created_ts],
+                )?;
+                changes.annotations += 1;
+            }
+
+            for external in &patch.externals_put {
+                tx.execute(
+                    "INSERT INTO externals (task_id, recipient, request, sent_ts, returned_ts, created_ts, modified_ts)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT (task_id, recipient) DO UPDATE SET
+                        request = excluded.request, sent_ts = excluded.sent_ts,
+                        returned_ts = excluded.returned_ts, modified_ts = excluded.modified_ts",
+                    rusqlite::params![
+                        task_id, external.recipient, external.request, external.sent_ts,
+                        external.returned_ts, external.created_ts, external.modified_ts
+                    ],
+                )?;
+                changes.externals += 1;
+            }
+
+            for event in &patch.events_add {
+                tx.execute(
+                    "INSERT INTO task_events (task_id, ts, event_type, payload_json) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![
+                        task_id, event.ts, event.event_type,
+                        serde_json::to_string(&event.payload).unwrap_or_else(|_| "null".to_string())
+                    ],
+                )?;
+                changes.events += 1;
+            }
+
+            for (event, key) in &patch.entity_events_add {
+                Self::write_entity_event(tx, Some(task_id), event, key.as_ref())?;
+                changes.events += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert an audit event, pointing it at the receiving ledger's row when that
+    /// row exists; otherwise the event keeps its entity id, as in an import
+    fn write_entity_event(
+        conn: &Connection,
+        task_id: Option<i64>,
+        event: &EntityEventRecord,
+        key: Option<&EntityKey>,
+    ) -> Result<()> {
+        let entity_id: Option<i64> = match (key, task_id) {
+            (Some(EntityKey::Session(start_ts)), Some(task_id)) => conn.query_row(
+                "SELECT id FROM sessions WHERE task_id = ?1 AND start_ts = ?2",
+                rusqlite::params![task_id, start_ts],
+                |row| row.get(0),
+            ).optional()?,
+            (Some(EntityKey::External(recipient)), Some(task_id)) => conn.query_row(
+                "SELECT id FROM externals WHERE task_id = ?1 AND recipient = ?2",
+                rusqlite::params![task_id, recipient],
+                |row| row.get(0),
+            ).optional()?,
+            (Some(EntityKey::Project(name)), _) => conn.query_row(
+                "SELECT id FROM projects WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            ).optional()?,
+            _ => None,
+        };
+        conn.execute(
+            "INSERT INTO entity_events (entity_type, entity_id, task_id, ts, event_type, payload_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                event.entity_type, entity_id.unwrap_or(event.entity_id), task_id, event.ts, event.event_type,
+                serde_json::to_string(&event.payload).unwrap_or_else(|_| "null".to_string())
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn write_field(
+        conn: &Connection,
+        task_id: i64,
+        field: &str,
+        value: &serde_json::Value,
+        changes: &mut SyncChanges,
+    ) -> Result<()> {
+        match field {
+            "project" => {
+                let project_id = match value.as_str() {
+                    Some(name) => {
+                        let existing: Option<i64> = conn.query_row(
+                            "SELECT id FROM projects WHERE name = ?1",
+                            [name],
+                            |row| row.get(0),
+                        ).optional()?;
+                        match existing {
+                            Some(id) => Some(id),
+                            None => {
+                                let now = chrono::Utc::now().timestamp();
+                                conn.execute(
+                                    "INSERT INTO projects (name, is_archived, created_ts, modified_ts) VALUES (?1, 0, ?2, ?2)",
+                                    rusqlite::params![name, now],
+                                )?;
+                                changes.projects_created += 1;
+                                Some(conn.last_insert_rowid())
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                conn.execute(
+                    "UPDATE tasks SET project_id = ?1 WHERE id = ?2",
+                    rusqlite::params![project_id, task_id],
+                )?;
+            }
+            "status" => {
+                let status = value.as_str().unwrap_or("pending");
+                conn.execute(
+                    "UPDATE tasks SET status = ?1 WHERE id = ?2",
+                    rusqlite::params![status, task_id],
+                )?;
+                // Only pending tasks may sit in a queue
+                if status != crate::models::TaskStatus::Pending.as_str() {
+                    conn.execute("DELETE FROM stack_items WHERE task_id = ?1", [task_id])?;
+                }
+            }
+            "udas" => {
+                let text = (!value.is_null()).then(|| serde_json::to_string(value)).transpose()?;
+                conn.execute(
+                    "UPDATE tasks SET udas_json = ?1 WHERE id = ?2",
+                    rusqlite::params![text, task_id],
+                )?;
+            }
+            "description" | "template" | "respawn" => {
+                let column = field;
+                conn.execute(
+                    &format!("UPDATE tasks SET {} = ?1 WHERE id = ?2", column),
+                    rusqlite::params![value.as_str(), task_id],
+                )?;
+            }
+            _ => {
+                conn.execute(
+                    &format!("UPDATE tasks SET {} = ?1 WHERE id = ?2", field),
+                    rusqlite::params![value.as_i64(), task_id],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+    use crate::repo::{TaskRepo, SessionRepo, EventRepo};
+    use std::collections::HashMap as Map;
+
+    fn copy_of(conn: &Connection) -> Connection {
+        let copy = DbConnection::connect_in_memory().unwrap();
+        LedgerTransfer::import(&copy, &LedgerTransfer::export(conn).unwrap()).unwrap();
+        copy
+    }
+
+    fn task_by_uuid(conn: &Connection, uuid: &str) -> crate::models::Task {
+        let id: i64 = conn.query_row("SELECT id FROM tasks WHERE uuid = ?1", [uuid], |r| r.get(0)).unwrap();
+        TaskRepo::get_by_id(conn, id).unwrap().unwrap()
+    }
+
+    fn modify_description(conn: &Connection, task_id: i64, description: &str, ts: i64) {
+        conn.execute(
+            "UPDATE tasks SET description = ?1, modified_ts = ?2 WHERE id = ?3",
+            rusqlite::params![description, ts, task_id],
+        ).unwrap();
+        EventRepo::record_at(conn, task_id, ts, crate::repo::EventType::Modified, serde_json::json!({
+            "field": "description", "old_value": null, "new_value": description,
+        })).unwrap();
+    }
+
+    #[test]
+    fn test_sync_copies_new_tasks_both_ways() {
+        let local = DbConnection::connect_in_memory().unwrap();
+        let remote = DbConnection::connect_in_memory().unwrap();
+        let a = TaskRepo::create(&local, "Laptop task", None).unwrap();
+        let b = TaskRepo::create(&remote, "Desktop task", None).unwrap();
+        SessionRepo::create_closed(&remote, b.id.unwrap(), 1_000, 2_000).unwrap();
+
+        let report = LedgerSync::sync(&local, &remote).unwrap();
+        assert_eq!(report.local.tasks_added, 1);
+        assert_eq!(report.local.sessions, 1);
+        assert_eq!(report.remote.tasks_added, 1);
+        assert_eq!(task_by_uuid(&remote, &a.uuid).description, "Laptop task");
+        assert_eq!(task_by_uuid(&local, &b.uuid).description, "Desktop task");
+
+        // A second sync has nothing left to do
+        let again = LedgerSync::sync(&local, &remote).unwrap();
+        assert!(again.local.is_empty() && again.remote.is_empty(), "{:?}", again);
+    }
+
+    #[test]
+    fn test_newer_field_change_wins() {
+        let local = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&local, "Original", None).unwrap();
+        let remote = copy_of(&local);
+        let local_id = task.id.unwrap();
+        let remote_id = task_by_uuid(&remote, &task.uuid).id.unwrap();
+
+        modify_description(&local, local_id, "Edited on laptop", 5_000);
+        modify_description(&remote, remote_id, "Edited on desktop", 6_000);
+        TaskRepo::modify(&local, local_id, None, None, Some(Some(9_999)), None, None, None, None, None,
+            &Map::new(), &[], &[], &[]).unwrap();
+
+        let report = LedgerSync::sync(&local, &remote).unwrap();
+        assert_eq!(report.local.updated.len(), 1);
+        assert_eq!(report.local.updated[0].fields, vec!["description"]);
+        assert_eq!(report.remote.updated[0].fields, vec!["due_ts"]);
+        for conn in [&local, &remote] {
+            let merged = task_by_uuid(conn, &task.uuid);
+            assert_eq!(merged.description, "Edited on desktop");
+            assert_eq!(merged.due_ts, Some(9_999));
+        }
+    }
+
+    #[test]
+    fn test_tag_removal_propagates() {
+        let local = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create_full(&local, "Tagged", None, None, None, None, None, None, None,
+            &Map::new(), &["urgent".to_string()]).unwrap();
+        let remote = copy_of(&local);
+        let remote_id = task_by_uuid(&remote, &task.uuid).id.unwrap();
+        let later = chrono::Utc::now().timestamp() + 60;
+        remote.execute("DELETE FROM task_tags WHERE task_id = ?1", [remote_id]).unwrap();
+        remote.execute("INSERT INTO task_tags (task_id, tag) VALUES (?1, 'later')", [remote_id]).unwrap();
+        EventRepo::record_at(&remote, remote_id, later, crate::repo::EventType::TagRemoved,
+            serde_json::json!({"tag": "urgent"})).unwrap();
+        EventRepo::record_at(&remote, remote_id, later, crate::repo::EventType::TagAdded,
+            serde_json::json!({"tag": "later"})).unwrap();
+
+        let report = LedgerSync::sync(&local, &remote).unwrap();
+        assert_eq!(report.local.tags_removed, 1);
+        assert_eq!(report.local.tags_added, 1);
+        let local_id = task_by_uuid(&local, &task.uuid).id.unwrap();
+        assert_eq!(TaskRepo::get_tags(&local, local_id).unwrap(), vec!["later".to_string()]);
+    }
+
+    #[test]
+    fn test_purged_task_is_not_copied_back() {
+        let local = DbConnection::connect_in_memory().unwrap();
+        let task = TaskRepo::create(&local, "Done with", None).unwrap();
+        let remote = copy_of(&local);
+        TaskRepo::trash(&local, task.id.unwrap(), &crate::config::Config::default()).unwrap();
+        TaskRepo::purge(&local, task.id.unwrap()).unwrap();
+
+        let report = LedgerSync::sync(&local, &remote).unwrap();
+        assert_eq!(report.local.tasks_added, 0);
+        assert_eq!(report.local.tasks_tombstoned, 1);
+        let count: i64 = local.query_row("SELECT COUNT(*) FROM tasks", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 0);
+        assert_eq!(task_by_uuid(&remote, &task.uuid).description, "Done with");
+    }
+
+    #[test]
+    fn test_audit_events_follow_their_rows() {
+        use crate::repo::ProjectRepo;
+        let local = DbConnection::connect_in_memory().unwrap();
+        let project = ProjectRepo::create(&local, "work").unwrap();
+        let task = TaskRepo::create(&local, "Shared", project.id).unwrap();
+        // Offset the ids so that copying them unchanged would be caught
+        let remote = DbConnection::connect_in_memory().unwrap();
+        ProjectRepo::create(&remote, "home").unwrap();
+        let other = TaskRepo::create(&remote, "Other", None).unwrap();
+        SessionRepo::create_closed(&remote, other.id.unwrap(), 100, 200).unwrap();
+        LedgerSync::sync(&local, &remote).unwrap();
+
+        let remote_id = task_by_uuid(&remote, &task.uuid).id.unwrap();
+        SessionRepo::create_closed(&remote, remote_id, 1_000, 2_000).unwrap();
+        ProjectRepo::archive(&remote, "work").unwrap();
+        let report = LedgerSync::sync(&local, &remote).unwrap();
+        assert_eq!(report.local.sessions, 1);
+        assert_eq!(report.local.events, 2);
+
+        let local_doc = LedgerTransfer::export(&local).unwrap();
+        let session_id = local_doc.sessions.iter().find(|s| s.start_ts == 1_000).unwrap().id;
+        let session_event = local_doc.entity_events.iter()
+            .find(|e| e.entity_type == "session" && e.task_uuid.as_deref() == Some(task.uuid.as_str())).unwrap();
+        assert_eq!(session_event.entity_id, session_id);
+        let project_event = local_doc.entity_events.iter().find(|e| e.entity_type == "project").unwrap();
+        assert_eq!(project_event.entity_id, project.id.unwrap());
+
+        let again = LedgerSync::sync(&local, &remote).unwrap();
+        assert!(again.local.is_empty() && again.remote.is_empty(), "{:?}", again);
+    }
+
+    #[test]
+    fn test_failed_sync_changes_neither_ledger() {
+        let local = DbConnection::connect_in_memory().unwrap();
+        let remote = DbConnection::connect_in_memory().unwrap();
+        TaskRepo::create(&local, "Laptop task", None).unwrap();
+        TaskRepo::create(&remote, "Desktop task", None).unwrap();
+        remote.execute_batch(
+            "CREATE TRIGGER refuse BEFORE INSERT ON tasks BEGIN SELECT RAISE(ABORT, 'refused'); END;",
+        ).unwrap();
+
+        assert!(LedgerSync::sync(&local, &remote).is_err());
+        for conn in [&local, &remote] {
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |r| r.get(0)).unwrap();
+            assert_eq!(count, 1);
+        }
+    }
+}
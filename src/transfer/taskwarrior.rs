@@ -0,0 +1,526 @@
+// Taskwarrior JSON import and export
+//
+// Reads the output of `task export` (a JSON array, or one object per line) and
+// writes the same shape back out so Taskwarrior tooling can read tatl data.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{Map, Value};
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use crate::repo::{TaskRepo, ProjectRepo, AnnotationRepo, EventRepo, EventType, DependencyRepo};
+use crate::respawn::RespawnRule;
+use crate::utils::parse_duration;
+use crate::transfer::{sanitize_name, LedgerTransfer};
+
+/// Taskwarrior attributes that are consumed by the importer (everything else becomes a UDA)
+const KNOWN_KEYS: &[&str] = &[
+    "id", "uuid", "description", "status", "project", "tags", "annotations",
+    "due", "scheduled", "wait", "recur", "respawn", "alloc", "entry", "modified",
+    "end", "urgency", "mask", "imask", "parent", "tatl_status", "depends",
+];
+
+/// Counts of what a Taskwarrior import added and skipped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskwarriorImportSummary {
+    pub tasks_imported: usize,
+    pub tasks_skipped: usize,
+    pub recurring_templates_skipped: usize,
+    pub projects_created: usize,
+    pub annotations: usize,
+    /// Recurrence values that could not be mapped to a respawn rule (kept as the `recur` UDA)
+    pub unmapped_recur: Vec<String>,
+}
+
+/// Parse a Taskwarrior date (`20260114T093000Z`) or RFC 3339 timestamp
+pub fn parse_tw_date(value: &str) -> Result<i64> {
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(Utc.from_utc_datetime(&dt).timestamp());
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.timestamp());
+    }
+    value.parse::<i64>()
+        .map_err(|_| anyhow!("Invalid Taskwarrior date: {}", value))
+}
+
+/// Format a timestamp as a Taskwarrior date
+pub fn format_tw_date(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .map(|dt| dt.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+/// Map a Taskwarrior `recur` value onto a respawn rule string
+pub fn recur_to_respawn(recur: &str) -> Option<String> {
+    let recur = recur.trim().to_lowercase();
+    let mapped = match recur.as_str() {
+        "daily" | "day" | "1d" | "1day" | "p1d" => "daily".to_string(),
+        "weekly" | "week" | "1w" | "1wk" | "1week" | "p1w" => "weekly".to_string(),
+        "biweekly" | "fortnight" => "2w".to_string(),
+        "monthly" | "month" | "1mo" | "1month" | "p1m" => "monthly".to_string(),
+        "quarterly" | "quarter" => "3m".to_string(),
+        "semiannual" => "6m".to_string(),
+        "yearly" | "annual" | "year" | "1y" | "1yr" | "p1y" => "yearly".to_string(),
+        "weekdays" => "mon,tue,wed,thu,fri".to_string(),
+        _ => {
+            let digits: String = recur.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let unit = &recur[digits.len()..];
+            let n: i32 = digits.parse().ok()?;
+            let suffix = match unit {
+                "d" | "day" | "days" => "d",
+                "w" | "wk" | "wks" | "week" | "weeks" => "w",
+                "mo" | "mos" | "month" | "months" => "m",
+                "y" | "yr" | "yrs" | "year" | "years" => "y",
+                _ => return None,
+            };
+            format!("{}{}", n, suffix)
+        }
+    };
+    RespawnRule::parse(&mapped).ok().map(|_| mapped)
+}
+
+/// Map a respawn rule string onto a Taskwarrior `recur` value, if Taskwarrior can express it
+pub fn respawn_to_recur(respawn: &str) -> Option<String> {
+    use crate::respawn::RespawnPattern;
+    let rule = RespawnRule::parse(respawn).ok()?;
+    match rule.pattern {
+        RespawnPattern::Daily => Some("daily".to_string()),
+        RespawnPattern::Weekly => Some("weekly".to_string()),
+        RespawnPattern::Monthly => Some("monthly".to_string()),
+        RespawnPattern::Yearly => Some("yearly".to_string()),
+        RespawnPattern::EveryDays(n) => Some(format!("{}d", n)),
+        RespawnPattern::EveryWeeks(n) => Some(format!("{}wk", n)),
+        RespawnPattern::EveryMonths(n) => Some(format!("{}mo", n)),
+        RespawnPattern::EveryYears(n) => Some(format!("{}y", n)),
+        RespawnPattern::Weekdays(ref days) if days == &[0, 1, 2, 3, 4] => Some("weekdays".to_string()),
+        _ => None,
+    }
+}
+
+/// Format seconds as a compact duration accepted by `parse_duration`
+fn format_alloc(secs: i64) -> String {
+    let mut out = String::new();
+    let (d, h, m, s) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60, secs % 60);
+    for (n, unit) in [(d, 'd'), (h, 'h'), (m, 'm'), (s, 's')] {
+        if n > 0 {
+            out.push_str(&format!("{}{}", n, unit));
+        }
+    }
+    if out.is_empty() {
+        out.push_str("0s");
+    }
+    out
+}
+
+/// Render a UDA value as the string tatl stores
+fn uda_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(uda_value).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn optional_date(obj: &Map<String, Value>, key: &str) -> Result<Option<i64>> {
+    match obj.get(key).and_then(|v| v.as_str()) {
+        Some(s) => parse_tw_date(s).map(Some).with_context(|| format!("Invalid '{}' value", key)),
+        None => Ok(None),
+    }
+}
+
+/// Taskwarrior import/export
+pub struct TaskwarriorTransfer;
+
+impl TaskwarriorTransfer {
+    /// Parse `task export` output: a JSON array or one JSON object per line
+    pub fn parse(json: &str) -> Result<Vec<Map<String, Value>>> {
+        let trimmed = json.trim();
+        if trimmed.starts_with('[') {
+            let items: Vec<Value> = serde_json::from_str(trimmed)
+                .context("Failed to parse Taskwarrior export")?;
+            return items.into_iter()
+                .map(|v| match v {
+                    Value::Object(map) => Ok(map),
+                    _ => Err(anyhow!("Taskwarrior export must contain task objects")),
+                })
+                .collect();
+        }
+        json.lines()
+            .enumerate()
+            .map(|(i, l)| (i, l.trim().trim_end_matches(',')))
+            .filter(|(_, l)| !l.is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str::<Map<String, Value>>(line)
+                    .with_context(|| format!("Failed to parse Taskwarrior task on line {}", i + 1))
+            })
+            .collect()
+    }
+
+    /// Import Taskwarrior tasks. Tasks whose uuid already exists are skipped.
+    pub fn import(conn: &Connection, items: &[Map<String, Value>]) -> Result<TaskwarriorImportSummary> {
+        let tx = conn.unchecked_transaction()?;
+        let mut summary = TaskwarriorImportSummary::default();
+
+        // Recurring parents are templates for their children; import the children instead
+        let parents: HashSet<&str> = items.iter()
+            .filter_map(|obj| obj.get("parent").and_then(|v| v.as_str()))
+            .collect();
+        // Dependencies are linked once every task is in, as they may point forward
+        let mut pending_depends: Vec<(i64, Vec<String>)> = Vec::new();
+
+        for obj in items {
+            let description = obj.get("description").and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Taskwarrior task is missing a description"))?;
+            let uuid = obj.get("uuid").and_then(|v| v.as_str()).map(|s| s.to_string());
+            let tw_status = obj.get("status").and_then(|v| v.as_str()).unwrap_or("pending");
+
+            if let Some(ref uuid) = uuid {
+                if tw_status == "recurring" && parents.contains(uuid.as_str()) {
+                    summary.recurring_templates_skipped += 1;
+                    continue;
+                }
+                let existing: Option<i64> = tx.query_row(
+                    "SELECT id FROM tasks WHERE uuid = ?1", [uuid], |row| row.get(0),
+                ).optional()?;
+                if existing.is_some() {
+                    summary.tasks_skipped += 1;
+                    continue;
+                }
+            }
+
+            let status = match obj.get("tatl_status").and_then(|v| v.as_str()).unwrap_or(tw_status) {
+                "pending" | "waiting" | "recurring" => "pending",
+                "completed" => "completed",
+                "closed" => "closed",
+                "deleted" => "deleted",
+                other => return Err(anyhow!("Task '{}' has unknown status '{}'", description, other)),
+            };
+
+            let project_id = match obj.get("project").and_then(|v| v.as_str()) {
+                Some(name) if !name.trim().is_empty() => {
+                    let name = sanitize_name(name);
+                    match ProjectRepo::get_by_name(&tx, &name)? {
+                        Some(project) => project.id,
+                        None => {
+                            summary.projects_created += 1;
+                            ProjectRepo::create(&tx, &name)?.id
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            let mut tags: Vec<String> = obj.get("tags")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|t| t.as_str()).map(sanitize_name).filter(|t| !t.is_empty()).collect())
+                .unwrap_or_default();
+            tags.sort();
+            tags.dedup();
+
+            let mut udas: HashMap<String, String> = HashMap::new();
+            for (key, value) in obj {
+                if !KNOWN_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    udas.insert(sanitize_name(key), uda_value(value));
+                }
+            }
+
+            let respawn = match (obj.get("respawn").and_then(|v| v.as_str()), obj.get("recur").and_then(|v| v.as_str())) {
+                (Some(rule), _) if RespawnRule::parse(rule).is_ok() => Some(rule.to_string()),
+                (_, Some(recur)) => match recur_to_respawn(recur) {
+                    Some(rule) => Some(rule),
+                    None => {
+                        summary.unmapped_recur.push(recur.to_string());
+                        udas.insert("recur".to_string(), recur.to_string());
+                        None
+                    }
+                },
+                _ => None,
+            };
+
+            let alloc_secs = match obj.get("alloc").and_then(|v| v.as_str()) {
+                Some(alloc) => match parse_duration(alloc) {
+                    Ok(secs) => Some(secs),
+                    Err(_) => {
+                        udas.insert("alloc".to_string(), alloc.to_string());
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            let task = TaskRepo::create_full(
+                &tx,
+                description,
+                project_id,
+                optional_date(obj, "due")?,
+                optional_date(obj, "scheduled")?,
+                optional_date(obj, "wait")?,
+                alloc_secs,
+                None,
+                respawn,
+                &udas,
+                &tags,
+            )?;
+            let task_id = task.id.unwrap();
+
+            let entry_ts = optional_date(obj, "entry")?.unwrap_or(task.created_ts);
+            let modified_ts = optional_date(obj, "modified")?.unwrap_or(entry_ts);
+            tx.execute(
+                "UPDATE tasks SET uuid = ?1, status = ?2, created_ts = ?3, modified_ts = ?4 WHERE id = ?5",
+                rusqlite::params![uuid.unwrap_or(task.uuid), status, entry_ts, modified_ts, task_id],
+            ).with_context(|| format!("Failed to import task '{}'", description))?;
+            // Date the created and tag events at the Taskwarrior entry time so replay sees them first
+            tx.execute(
+                "UPDATE task_events SET ts = ?1 WHERE task_id = ?2",
+                rusqlite::params![entry_ts, task_id],
+            )?;
+
+            if status != "pending" {
+                let end_ts = optional_date(obj, "end")?.unwrap_or(modified_ts);
+                EventRepo::record_at(&tx, task_id, end_ts, EventType::StatusChanged, serde_json::json!({
+                    "old_status": "pending",
+                    "new_status": status,
+                }))?;
+            }
+
+            if let Some(annotations) = obj.get("annotations").and_then(|v| v.as_array()) {
+                for annotation in annotations {
+                    let Some(note) = annotation.get("description").and_then(|v| v.as_str()) else { continue };
+                    let created = AnnotationRepo::create(&tx, task_id, note.to_string(), None)?;
+                    if let Some(entry) = annotation.get("entry").and_then(|v| v.as_str()) {
+                        tx.execute(
+                            "UPDATE task_annotations SET entry_ts = ?1 WHERE id = ?2",
+                            rusqlite::params![parse_tw_date(entry)?, created.id],
+                        )?;
+                    }
+                    summary.annotations += 1;
+                }
+            }
+
+            // Taskwarrior writes `depends` as an array of uuids, older versions as a comma-separated string
+            let depends: Vec<String> = match obj.get("depends") {
+                Some(Value::Array(uuids)) => uuids.iter().filter_map(|u| u.as_str()).map(|u| u.to_string()).collect(),
+                Some(Value::String(uuids)) => uuids.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect(),
+                _ => Vec::new(),
+            };
+            if !depends.is_empty() {
+                pending_depends.push((task_id, depends));
+            }
+
+            summary.tasks_imported += 1;
+        }
+
+        for (task_id, uuids) in pending_depends {
+            let mut depends_on = Vec::new();
+            for uuid in uuids {
+                let found: Option<i64> = tx.query_row(
+                    "SELECT id FROM tasks WHERE uuid = ?1", [&uuid], |row| row.get(0),
+                ).optional()?;
+                if let Some(id) = found {
+                    if !DependencyRepo::would_create_cycle(&tx, task_id, id)? {
+                        depends_on.push(id);
+                    }
+                }
+            }
+            DependencyRepo::set(&tx, task_id, &depends_on)?;
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Export every task in the ledger as Taskwarrior task objects
+    pub fn export(conn: &Connection) -> Result<Vec<Value>> {
+        let ledger = LedgerTransfer::export(conn)?;
+        let projects: HashMap<i64, &str> = ledger.projects.iter()
+            .map(|p| (p.id, p.name.as_str()))
+            .collect();
+
+        let mut tags: HashMap<&str, Vec<&str>> = HashMap::new();
+        for tag in &ledger.task_tags {
+            tags.entry(tag.task_uuid.as_str()).or_default().push(tag.tag.as_str());
+        }
+        let mut depends: HashMap<&str, Vec<&str>> = HashMap::new();
+        for dependency in &ledger.task_dependencies {
+            depends.entry(dependency.task_uuid.as_str()).or_default().push(dependency.depends_on_uuid.as_str());
+        }
+        let mut annotations: HashMap<&str, Vec<Value>> = HashMap::new();
+        for annotation in &ledger.task_annotations {
+            annotations.entry(annotation.task_uuid.as_str()).or_default().push(serde_json::json!({
+                "entry": format_tw_date(annotation.entry_ts),
+                "description": annotation.note,
+            }));
+        }
+        let mut ended: HashMap<&str, i64> = HashMap::new();
+        for event in ledger.task_events.iter().filter(|e| e.event_type == "status_changed") {
+            ended.insert(event.task_uuid.as_str(), event.ts);
+        }
+
+        let mut out = Vec::new();
+        for task in &ledger.tasks {
+            let mut obj = Map::new();
+            if task.status == "pending" {
+                if let Some(id) = task.id {
+                    obj.insert("id".to_string(), Value::from(id));
+                }
+            }
+            let tw_status = match task.status.as_str() {
+                "closed" => "deleted",
+                other => other,
+            };
+            obj.insert("uuid".to_string(), Value::from(task.uuid.clone()));
+            obj.insert("description".to_string(), Value::from(task.description.clone()));
+            obj.insert("status".to_string(), Value::from(tw_status));
+            if task.status == "closed" {
+                obj.insert("tatl_status".to_string(), Value::from("closed"));
+            }
+            obj.insert("entry".to_string(), Value::from(format_tw_date(task.created_ts)));
+            obj.insert("modified".to_string(), Value::from(format_tw_date(task.modified_ts)));
+            if task.status != "pending" {
+                let end = ended.get(task.uuid.as_str()).copied().unwrap_or(task.modified_ts);
+                obj.insert("end".to_string(), Value::from(format_tw_date(end)));
+            }
+            if let Some(name) = task.project_id.and_then(|id| projects.get(&id)) {
+                obj.insert("project".to_string(), Value::from(*name));
+            }
+            for (key, ts) in [("due", task.due_ts), ("scheduled", task.scheduled_ts), ("wait", task.wait_ts)] {
+                if let Some(ts) = ts {
+                    obj.insert(key.to_string(), Value::from(format_tw_date(ts)));
+                }
+            }
+            if let Some(ref respawn) = task.respawn {
+                match respawn_to_recur(respawn) {
+                    Some(recur) => { obj.insert("recur".to_string(), Value::from(recur)); }
+                    None => { obj.insert("respawn".to_string(), Value::from(respawn.clone())); }
+                }
+            }
+            if let Some(secs) = task.alloc_secs {
+                obj.insert("alloc".to_string(), Value::from(format_alloc(secs)));
+            }
+            if let Some(task_tags) = tags.get(task.uuid.as_str()) {
+                obj.insert("tags".to_string(), Value::from(task_tags.clone()));
+            }
+            if let Some(task_depends) = depends.get(task.uuid.as_str()) {
+                obj.insert("depends".to_string(), Value::from(task_depends.clone()));
+            }
+            if let Some(task_annotations) = annotations.remove(task.uuid.as_str()) {
+                obj.insert("annotations".to_string(), Value::Array(task_annotations));
+            }
+            if let Some(Value::Object(udas)) = &task.udas {
+                for (key, value) in udas {
+                    obj.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            out.push(Value::Object(obj));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConnection;
+
+    const SAMPLE: &str = r#"[
+        {"id":1,"description":"Pay rent","entry":"20260101T090000Z","modified":"20260102T090000Z",
+         "status":"pending","uuid":"11111111-1111-4111-8111-111111111111","project":"Home.Bills",
+         "tags":["money","home"],"due":"20260201T170000Z","recur":"monthly","priority":"H",
+         "annotations":[{"entry":"20260101T100000Z","description":"Autopay failed"}],"urgency":9.5},
+        {"id":0,"description":"Old report","entry":"20251201T090000Z","end":"20251205T120000Z",
+         "status":"completed","uuid":"22222222-2222-4222-8222-222222222222"}
+    ]"#;
+
+    #[test]
+    fn test_parse_tw_date() {
+        assert_eq!(parse_tw_date("20260101T000000Z").unwrap(), 1767225600);
+        assert_eq!(format_tw_date(1767225600), "20260101T000000Z");
+        assert!(parse_tw_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_recur_mapping() {
+        assert_eq!(recur_to_respawn("weekly").as_deref(), Some("weekly"));
+        assert_eq!(recur_to_respawn("3d").as_deref(), Some("3d"));
+        assert_eq!(recur_to_respawn("2wk").as_deref(), Some("2w"));
+        assert_eq!(recur_to_respawn("quarterly").as_deref(), Some("3m"));
+        assert_eq!(recur_to_respawn("fortnightly-ish"), None);
+        assert_eq!(respawn_to_recur("2w").as_deref(), Some("2wk"));
+        assert_eq!(respawn_to_recur("2nd-tue"), None);
+    }
+
+    #[test]
+    fn test_parse_accepts_json_lines() {
+        let lines = "{\"description\":\"a\",\"status\":\"pending\"}\n{\"description\":\"b\",\"status\":\"pending\"}\n";
+        assert_eq!(TaskwarriorTransfer::parse(lines).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors_report_the_line_in_the_file() {
+        let lines = "{\"description\":\"a\",\"status\":\"pending\"}\n\nnot json\n";
+        let err = TaskwarriorTransfer::parse(lines).unwrap_err();
+        assert!(err.to_string().contains("on line 3"), "{}", err);
+    }
+
+    #[test]
+    fn test_import_maps_fields() {
+        let conn = DbConnection::connect_in_memory().unwrap();
+        let items = TaskwarriorTransfer::parse(SAMPLE).unwrap();
+        let summary = TaskwarriorTransfer::import(&conn, &items).unwrap();
+        assert_eq!(summary.tasks_imported, 2);
+        assert_eq!(summary.projects_created, 1);
+        assert_eq!(summary.annotations, 1);
+
+        let task = TaskRepo::get_by_id(&conn, 1).unwrap().unwrap();
+        assert_eq!(task.uuid, "11111111-1111-4111-8111-111111111111");
+        assert_eq!(task.respawn.as_deref(), Some("monthly"));
+        assert_eq!(task.udas.get("priority").map(|s| s.as_str()), Some("H"));
+        assert!(!task.udas.contains_key("urgency"));
+        assert_eq!(ProjectRepo::get_by_id(&conn, task.project_id.unwrap()).unwrap().unwrap().name, "Home.Bills");
+        assert_eq!(TaskRepo::get_tags(&conn, 1).unwrap(), vec!["home", "money"]);
+
+        let done = TaskRepo::get_by_id(&conn, 2).unwrap().unwrap();
+        assert_eq!(done.status, crate::models::TaskStatus::Completed);
+
+        // Re-importing the same export skips existing uuids
+        let again = TaskwarriorTransfer::import(&conn, &items).unwrap();
+        assert_eq!(again.tasks_imported, 0);
+        assert_eq!(again.tasks_skipped, 2);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let source = DbConnection::connect_in_memory().unwrap();
+        TaskwarriorTransfer::import(&source, &TaskwarriorTransfer::parse(SAMPLE).unwrap()).unwrap();
+        let exported = TaskwarriorTransfer::export(&source).unwrap();
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0]["project"], "Home.Bills");
+        assert_eq!(exported[0]["recur"], "monthly");
+        assert_eq!(exported[0]["priority"], "H");
+        assert_eq!(exported[1]["end"], "20251205T120000Z");
+
+        let json = serde_json::to_string(&exported).unwrap();
+        let target = DbConnection::connect_in_memory().unwrap();
+        let summary = TaskwarriorTransfer::import(&target, &TaskwarriorTransfer::parse(&json).unwrap()).unwrap();
+        assert_eq!(summary.tasks_imported, 2);
+    }
+
+    #[test]
+    fn test_depends_round_trip() {
+        let items = TaskwarriorTransfer::parse(r#"[
+            {"description":"Ship","status":"pending","uuid":"33333333-3333-4333-8333-333333333333",
+             "depends":["44444444-4444-4444-8444-444444444444","99999999-9999-4999-8999-999999999999"]},
+            {"description":"Build","status":"pending","uuid":"44444444-4444-4444-8444-444444444444"}
+        ]"#).unwrap();
+        let conn = DbConnection::connect_in_memory().unwrap();
+        TaskwarriorTransfer::import(&conn, &items).unwrap();
+        assert_eq!(DependencyRepo::get_dependencies(&conn, 1).unwrap(), vec![2]);
+        assert!(!TaskRepo::get_by_id(&conn, 1).unwrap().unwrap().udas.contains_key("depends"));
+
+        let exported = TaskwarriorTransfer::export(&conn).unwrap();
+        assert_eq!(exported[0]["depends"], serde_json::json!(["44444444-4444-4444-8444-444444444444"]));
+        assert!(exported[1].get("depends").is_none());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod date;
 pub mod duration;
 pub mod fuzzy;
+pub mod priority;
 
 pub use date::*;
 pub use duration::*;
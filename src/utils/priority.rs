@@ -2,26 +2,29 @@
 
 use crate::models::Task;
 use crate::repo::TaskRepo;
+use crate::config::Config;
 use rusqlite::Connection;
 use anyhow::Result;
 use chrono::Utc;
 
 /// Calculate priority/urgency score for a task (Taskwarrior-style)
 /// 
-/// Priority is calculated using a polynomial with configurable coefficients:
+/// Priority is calculated using a polynomial with coefficients from the
+/// `urgency.*` configuration keys:
 /// - Due date proximity (higher urgency for tasks due soon or overdue)
 /// - Allocation (tasks with less allocation remaining get higher urgency)
 /// - Age (older tasks get slightly higher urgency)
 /// - Status (pending tasks only)
 /// 
 /// Returns a floating-point urgency score (higher = more urgent)
-pub fn calculate_priority(task: &Task, conn: &Connection) -> Result<f64> {
+pub fn calculate_priority(task: &Task, conn: &Connection, config: &Config) -> Result<f64> {
+    let coefficients = &config.urgency;
     let mut urgency = 0.0;
     let now = Utc::now().timestamp();
     
     // Base urgency for pending tasks
     if task.status == crate::models::TaskStatus::Pending {
-        urgency += 1.0;
+        urgency += coefficients.pending;
     }
     
     // Due date urgency (Taskwarrior-style)
@@ -30,20 +33,20 @@ pub fn calculate_priority(task: &Task, conn: &Connection) -> Result<f64> {
         
         if days_until_due < 0.0 {
             // Overdue - high urgency that increases with lateness
-            // Formula: 15.0 - (days overdue * 0.5), minimum 1.0
-            urgency += (15.0 - (days_until_due.abs() * 0.5)).max(1.0);
+            // Formula: overdue - (days overdue * 0.5), minimum 1.0
+            urgency += (coefficients.overdue - (days_until_due.abs() * 0.5)).max(1.0);
         } else if days_until_due <= 7.0 {
             // Due within a week - urgency increases as deadline approaches
-            // Formula: 12.0 - days_until_due
-            urgency += (12.0 - days_until_due).max(1.0);
+            // Formula: due_week - days_until_due
+            urgency += (coefficients.due_week - days_until_due).max(1.0);
         } else if days_until_due <= 30.0 {
             // Due within a month - moderate urgency
-            // Formula: 5.0 - (days_until_due / 10.0)
-            urgency += (5.0 - (days_until_due / 10.0)).max(0.5);
+            // Formula: due_month - (days_until_due / 10.0)
+            urgency += (coefficients.due_month - (days_until_due / 10.0)).max(0.5);
         } else {
             // Due far in the future - low urgency
-            // Formula: 2.0 / (1.0 + days_until_due / 30.0)
-            urgency += 2.0 / (1.0 + days_until_due / 30.0);
+            // Formula: due_later / (1.0 + days_until_due / 30.0)
+            urgency += coefficients.due_later / (1.0 + days_until_due / 30.0);
         }
     }
     
@@ -57,13 +60,13 @@ pub fn calculate_priority(task: &Task, conn: &Connection) -> Result<f64> {
                     
                     if remaining_secs < alloc_secs / 4 {
                         // Less than 25% allocation remaining - high urgency
-                        urgency += 3.0;
+                        urgency += coefficients.alloc_critical;
                     } else if remaining_secs < alloc_secs / 2 {
                         // Less than 50% allocation remaining - moderate urgency
-                        urgency += 1.5;
+                        urgency += coefficients.alloc_low;
                     } else {
                         // More than 50% remaining - low urgency
-                        urgency += 0.5;
+                        urgency += coefficients.alloc_ok;
                     }
                 }
             }
@@ -74,7 +77,7 @@ pub fn calculate_priority(task: &Task, conn: &Connection) -> Result<f64> {
     let age_days = (now - task.created_ts) as f64 / 86400.0;
     if age_days > 30.0 {
         // Tasks older than 30 days get a small boost
-        urgency += (age_days / 30.0).min(2.0) * 0.1;
+        urgency += (age_days / 30.0).min(2.0) * coefficients.age;
     }
     
     Ok(urgency)
@@ -85,6 +88,7 @@ pub fn get_top_priority_tasks(
     conn: &Connection,
     exclude_task_ids: &[i64],
     limit: usize,
+    config: &Config,
 ) -> Result<Vec<(Task, Vec<String>, f64)>> {
     let all_tasks = TaskRepo::list_all(conn)?;
     
@@ -96,7 +100,7 @@ pub fn get_top_priority_tasks(
             task.status == crate::models::TaskStatus::Pending
         })
         .filter_map(|(task, tags)| {
-            if let Ok(priority) = calculate_priority(&task, conn) {
+            if let Ok(priority) = calculate_priority(&task, conn, config) {
                 Some((task, tags, priority))
             } else {
                 None
@@ -6,6 +6,7 @@ use tempfile::TempDir;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock, MutexGuard};
+use tatl::config::Config;
 use tatl::db::DbConnection;
 use tatl::repo::{TaskRepo, ProjectRepo, StackRepo, SessionRepo};
 use chrono::{Local, TimeZone};
@@ -166,7 +167,7 @@ impl<'a> GivenBuilder<'a> {
     /// Given: clock is running on task since time
     pub fn clock_running_on_task_since(&self, task_id: i64, time_str: &str) -> &Self {
         let start_ts = parse_time(time_str);
-        SessionRepo::create(self.ctx.db(), task_id, start_ts).unwrap();
+        SessionRepo::create(self.ctx.db(), task_id, start_ts, &Config::default()).unwrap();
         self
     }
     
@@ -174,7 +175,7 @@ impl<'a> GivenBuilder<'a> {
     pub fn no_running_session(&self) -> &Self {
         // Close any open session
         if let Ok(Some(_session)) = SessionRepo::get_open(self.ctx.db()) {
-            SessionRepo::close_open(self.ctx.db(), chrono::Utc::now().timestamp()).unwrap();
+            SessionRepo::close_open(self.ctx.db(), chrono::Utc::now().timestamp(), &Config::default()).unwrap();
         }
         self
     }
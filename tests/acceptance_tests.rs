@@ -3,6 +3,7 @@
 
 mod acceptance_framework;
 use acceptance_framework::*;
+use tatl::config::Config;
 use tatl::repo::{StackRepo, SessionRepo, TaskRepo};
 use tatl::filter::parser::parse_filter;
 use tatl::filter::evaluator::filter_tasks;
@@ -329,11 +330,11 @@ fn acceptance_micro_purge_on_rapid_switch() {
     // Close task 11 session after 20 seconds (micro-session)
     // Use a timestamp that's 20 seconds after the start
     let start_ts = date::parse_date_expr("2026-01-10T09:00").unwrap();
-    SessionRepo::close_open(ctx.db(), start_ts + 20).unwrap();
+    SessionRepo::close_open(ctx.db(), start_ts + 20, &Config::default()).unwrap();
     
     // Start task 10 session at 09:00:25 (within 30s of micro-session end)
     // This should trigger purge of task 11's micro-session
-    SessionRepo::create(ctx.db(), task10, start_ts + 25).unwrap();
+    SessionRepo::create(ctx.db(), task10, start_ts + 25, &Config::default()).unwrap();
     
     // Verify task 11's micro-session was purged
     let sessions = SessionRepo::get_by_task(ctx.db(), task11).unwrap();
@@ -368,6 +369,7 @@ fn acceptance_micro_merge_on_bounce_back_to_same_task() {
         ctx.db(),
         task11,
         start_ts + 25,
+        &Config::default(),
     ).unwrap();
     
     // Verify the session starts at the micro-session's start time (merged)
@@ -404,6 +406,7 @@ fn acceptance_micro_session_preserved_if_no_rule_triggers() {
         ctx.db(),
         task11,
         start_ts + 65, // 20s session + 45s gap = 65s from start
+        &Config::default(),
     ).unwrap();
     
     // Verify both sessions exist (micro-session preserved)
@@ -453,7 +456,7 @@ fn acceptance_filter_and_or_not() {
     
     // Test: +urgent or +important
     let filter_expr = parse_filter(vec!["+urgent".to_string(), "or".to_string(), "+important".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task_a), "Task A should match");
     assert!(matching_ids.contains(&task_b), "Task B should match");
@@ -461,7 +464,7 @@ fn acceptance_filter_and_or_not() {
     
     // Test: not +urgent
     let filter_expr = parse_filter(vec!["not".to_string(), "+urgent".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(!matching_ids.contains(&task_a), "Task A should not match");
     assert!(matching_ids.contains(&task_b), "Task B should match");
@@ -506,7 +509,7 @@ fn acceptance_waiting_derived() {
     
     // Test waiting filter
     let filter_expr = parse_filter(vec!["waiting".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task10), "Task 10 should appear in waiting list");
     
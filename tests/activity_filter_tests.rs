@@ -0,0 +1,93 @@
+use assert_cmd::Command;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn open_db(temp_dir: &TempDir) -> rusqlite::Connection {
+    rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap()
+}
+
+fn list(temp_dir: &TempDir, filter: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).arg("list").args(filter).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// Two finished tasks, one of them 20 days ago, and two pending tasks last worked on
+/// in 2024 and an hour ago
+fn setup_activity(temp_dir: &TempDir) {
+    get_task_cmd(temp_dir).args(&["add", "Closed recently"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Closed long ago"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Idle task"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Busy task"]).assert().success();
+    get_task_cmd(temp_dir).args(&["finish", "1", "-y"]).assert().success();
+    get_task_cmd(temp_dir).args(&["close", "2", "-y"]).assert().success();
+    get_task_cmd(temp_dir).args(&["onoff", "2024-03-04T09:00..2024-03-04T10:00", "3"]).assert().success();
+
+    let conn = open_db(temp_dir);
+    conn.execute("UPDATE task_events SET ts = strftime('%s', 'now', '-20 days')
+                  WHERE task_id = 2 AND event_type = 'status_changed'", []).unwrap();
+    conn.execute("INSERT INTO sessions (task_id, start_ts, end_ts, created_ts)
+                  VALUES (4, strftime('%s', 'now', '-2 hours'), strftime('%s', 'now', '-1 hours'), strftime('%s', 'now'))", []).unwrap();
+}
+
+#[test]
+fn test_filter_finished_in_last_days() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_activity(&temp_dir);
+
+    let out = list(&temp_dir, &["status=completed,closed", "end>=-7d"]);
+    assert!(out.contains("Closed recently") && !out.contains("Closed long ago"), "{}", out);
+
+    let out = list(&temp_dir, &["status=completed,closed", "end<-7d"]);
+    assert!(out.contains("Closed long ago") && !out.contains("Closed recently"), "{}", out);
+
+    // Pending tasks have no end time
+    let out = list(&temp_dir, &["end=none"]);
+    assert!(out.contains("Idle task") && !out.contains("Closed recently"), "{}", out);
+}
+
+#[test]
+fn test_filter_untouched_tasks() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_activity(&temp_dir);
+
+    let out = list(&temp_dir, &["lastactive<-30d"]);
+    assert!(out.contains("Idle task") && !out.contains("Busy task"), "{}", out);
+
+    let out = list(&temp_dir, &["lastactive=today"]);
+    assert!(out.contains("Busy task") && !out.contains("Idle task"), "{}", out);
+}
+
+#[test]
+fn test_filter_created_and_modified() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_activity(&temp_dir);
+    open_db(&temp_dir).execute("UPDATE tasks SET created_ts = strftime('%s', '2024-01-02'),
+                                modified_ts = strftime('%s', '2024-01-02') WHERE id = 3", []).unwrap();
+
+    let out = list(&temp_dir, &["created=2024-01-02"]);
+    assert!(out.contains("Idle task") && !out.contains("Busy task"), "{}", out);
+
+    let out = list(&temp_dir, &["modified>=today"]);
+    assert!(out.contains("Busy task") && !out.contains("Idle task"), "{}", out);
+}
@@ -0,0 +1,126 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn open_db(temp_dir: &TempDir) -> rusqlite::Connection {
+    rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap()
+}
+
+/// Two finished tasks with a logged hour each, and one pending task
+fn setup_finished_work(temp_dir: &TempDir) {
+    get_task_cmd(temp_dir).args(&["add", "Old report", "project=work"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Old cleanup"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Still open"]).assert().success();
+    get_task_cmd(temp_dir).args(&["onoff", "2024-03-04T09:00..2024-03-04T10:00", "1"]).assert().success();
+    get_task_cmd(temp_dir).args(&["onoff", "2024-03-05T09:00..2024-03-05T10:00", "2"]).assert().success();
+    get_task_cmd(temp_dir).args(&["annotate", "1", "Sent to finance"]).assert().success();
+    get_task_cmd(temp_dir).args(&["finish", "1", "-y"]).assert().success();
+    get_task_cmd(temp_dir).args(&["close", "2", "-y"]).assert().success();
+    open_db(temp_dir).execute("UPDATE tasks SET modified_ts = strftime('%s', '2024-03-10') WHERE id IN (1, 2)", []).unwrap();
+}
+
+#[test]
+fn test_archive_moves_finished_tasks() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_finished_work(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2025-01-01", "--dry-run"]).assert().success()
+        .stdout(predicate::str::contains("Would archive 2 task(s)"));
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2024-01-01", "-y"]).assert().success()
+        .stdout(predicate::str::contains("No completed, closed or deleted tasks"));
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2025-01-01", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Archived 2 task(s) (2 session(s), 1 annotation(s)"));
+
+    assert!(temp_dir.path().join("test.archive.db").exists());
+    let conn = open_db(&temp_dir);
+    let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0)).unwrap();
+    assert_eq!(remaining, 1);
+    let sessions: i64 = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0)).unwrap();
+    assert_eq!(sessions, 0);
+
+    let archive = rusqlite::Connection::open(temp_dir.path().join("test.archive.db")).unwrap();
+    let description: String = archive.query_row(
+        "SELECT t.description FROM tasks t JOIN projects p ON p.id = t.project_id WHERE p.name = 'work'",
+        [], |row| row.get(0)).unwrap();
+    assert_eq!(description, "Old report");
+    let events: i64 = archive.query_row("SELECT COUNT(*) FROM task_events", [], |row| row.get(0)).unwrap();
+    assert!(events > 0);
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Still open")
+            .and(predicate::str::contains("Old report").not()));
+}
+
+#[test]
+fn test_sessions_report_include_archive() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_finished_work(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(&["sessions", "report", "--include-archive", "2024-03-01..2024-03-31"]).assert().failure()
+        .stderr(predicate::str::contains("No archive found"));
+
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2025-01-01", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["sessions", "report", "2024-03-01..2024-03-31"]).assert().success()
+        .stdout(predicate::str::contains("No sessions found"));
+    get_task_cmd(&temp_dir).args(&["sessions", "report", "--include-archive", "2024-03-01..2024-03-31"]).assert().success()
+        .stdout(predicate::str::contains("work")
+            .and(predicate::str::contains("Sessions: 2")));
+    get_task_cmd(&temp_dir).args(&["sessions", "report", "--include-archive", "2024-03-01..2024-03-31", "project=work"]).assert().success()
+        .stdout(predicate::str::contains("Sessions: 1"));
+    // Options go before the report arguments rather than being read as filter words
+    get_task_cmd(&temp_dir).args(&["sessions", "report", "2024-03-01..2024-03-31", "--include-archive"]).assert().failure()
+        .stderr(predicate::str::contains("options go before the period and filter"));
+}
+
+#[test]
+fn test_report_include_archive() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_finished_work(&temp_dir);
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2025-01-01", "-y"]).assert().success();
+    // Move the archived work into this year
+    rusqlite::Connection::open(temp_dir.path().join("test.archive.db")).unwrap().execute_batch(
+        "UPDATE sessions SET start_ts = strftime('%s', 'now') - 7200, end_ts = strftime('%s', 'now') - 3600;
+         UPDATE tasks SET modified_ts = strftime('%s', 'now') - 3600;").unwrap();
+
+    get_task_cmd(&temp_dir).args(&["report", "--period=year"]).assert().success()
+        .stdout(predicate::str::contains("By project:").not());
+    get_task_cmd(&temp_dir).args(&["report", "--period=year", "--include-archive"]).assert().success()
+        .stdout(predicate::str::contains("By project:")
+            .and(predicate::str::contains("work"))
+            .and(predicate::str::contains("Tasks completed:  2")));
+}
+
+#[test]
+fn test_archive_records_dependencies_on_remaining_tasks() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_finished_work(&temp_dir);
+    get_task_cmd(&temp_dir).args(&["modify", "3", "depends=1", "-y"]).assert().success();
+    open_db(&temp_dir).execute("UPDATE tasks SET modified_ts = strftime('%s', '2024-03-10') WHERE id = 1", []).unwrap();
+
+    get_task_cmd(&temp_dir).args(&["archive", "--before", "2025-01-01", "-y"]).assert().success();
+    let removed: i64 = open_db(&temp_dir).query_row(
+        "SELECT COUNT(*) FROM task_events WHERE task_id = 3 AND event_type = 'dependency_removed'",
+        [], |row| row.get(0)).unwrap();
+    assert_eq!(removed, 1);
+}
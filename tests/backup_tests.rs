@@ -0,0 +1,116 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+use tatl::db::migrations::{MigrationManager, CURRENT_VERSION};
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+fn snapshots(temp_dir: &TempDir) -> Vec<String> {
+    let dir = temp_dir.path().join(".tatl").join("backups");
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let mut names: Vec<String> = fs::read_dir(dir).unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_backup_creates_snapshot_and_rotates() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Keep me safe"]).assert().success();
+
+    for _ in 0..3 {
+        get_task_cmd(&temp_dir).args(&["backup", "--keep", "2"]).assert().success()
+            .stdout(predicate::str::contains("Created snapshot"));
+    }
+    assert_eq!(snapshots(&temp_dir).len(), 2);
+
+    get_task_cmd(&temp_dir).args(&["backup", "--list"]).assert().success()
+        .stdout(predicate::str::contains("ledger-").and(predicate::str::contains(format!("v{}", CURRENT_VERSION))));
+}
+
+#[test]
+fn test_restore_latest_replaces_ledger() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Before backup"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["backup"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "After backup"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["restore", "latest", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Restored").and(predicate::str::contains("pre-restore")));
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Before backup").and(predicate::str::contains("After backup").not()));
+    assert!(snapshots(&temp_dir).iter().any(|n| n.contains("pre-restore")));
+}
+
+#[test]
+fn test_restore_rotates_its_own_snapshots_with_backup_keep() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["config", "set", "backup.keep", "1"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Before backup"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["backup"]).assert().success();
+    let manual = snapshots(&temp_dir)[0].clone();
+
+    for _ in 0..3 {
+        get_task_cmd(&temp_dir).args(&["restore", &manual, "-y"]).assert().success();
+    }
+    let names = snapshots(&temp_dir);
+    assert_eq!(names.iter().filter(|n| n.contains("pre-restore")).count(), 1);
+    assert!(names.contains(&manual));
+}
+
+#[test]
+fn test_restore_rejects_non_ledger_file() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("other.db");
+    rusqlite::Connection::open(&path).unwrap().execute("CREATE TABLE t (x)", []).unwrap();
+
+    get_task_cmd(&temp_dir).args(&["restore", path.to_str().unwrap(), "-y"]).assert().failure()
+        .stderr(predicate::str::contains("not a tatl ledger"));
+}
+
+#[test]
+fn test_connect_snapshots_before_pending_migration() {
+    let (temp_dir, _guard) = setup_test_env();
+    // A ledger left at schema v6, so every later migration is pending
+    let conn = rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap();
+    MigrationManager::migrate_to(&conn, 6).unwrap();
+    conn.execute(
+        "INSERT INTO tasks (uuid, description, status, created_ts, modified_ts)
+         VALUES ('00000000-0000-4000-8000-000000000001', 'Old schema task', 'pending', 0, 0)",
+        [],
+    ).unwrap();
+    drop(conn);
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Old schema task"))
+        .stderr(predicate::str::contains(format!("Upgrading ledger schema v6 -> v{}", CURRENT_VERSION)));
+    let label = format!("pre-migration-v{}", CURRENT_VERSION);
+    assert!(snapshots(&temp_dir).iter().any(|n| n.contains(&label)));
+
+    // No further snapshot once the schema is current
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success();
+    assert_eq!(snapshots(&temp_dir).len(), 1);
+}
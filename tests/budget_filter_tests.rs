@@ -0,0 +1,94 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for filtering on allocated and logged time
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn list(temp_dir: &TempDir, filter: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).arg("list").args(filter).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// A 30m task that took 1h, a 2h task with 1h logged, a 3h task never started and
+/// an unestimated task with 45m logged
+fn setup_budgets(temp_dir: &TempDir) {
+    get_task_cmd(temp_dir).args(["add", "Underestimated", "allocation=30m"]).assert().success();
+    get_task_cmd(temp_dir).args(["add", "Halfway", "allocation=2h"]).assert().success();
+    get_task_cmd(temp_dir).args(["add", "Untouched", "allocation=3h"]).assert().success();
+    get_task_cmd(temp_dir).args(["add", "Unestimated"]).assert().success();
+    for (interval, id) in [
+        ("2024-03-04T09:00..2024-03-04T10:00", "1"),
+        ("2024-03-04T10:00..2024-03-04T11:00", "2"),
+        ("2024-03-04T11:00..2024-03-04T11:45", "4"),
+    ] {
+        get_task_cmd(temp_dir).args(["onoff", interval, id]).assert().success();
+    }
+}
+
+#[test]
+fn test_filter_alloc_and_logged() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_budgets(&temp_dir);
+
+    let out = list(&temp_dir, &["alloc>1h"]);
+    assert!(out.contains("Halfway") && out.contains("Untouched") && !out.contains("Underestimated"), "{}", out);
+
+    let out = list(&temp_dir, &["alloc=none"]);
+    assert!(out.contains("Unestimated") && !out.contains("Halfway"), "{}", out);
+
+    let out = list(&temp_dir, &["logged>=45m"]);
+    assert!(out.contains("Underestimated") && out.contains("Unestimated") && !out.contains("Untouched"), "{}", out);
+
+    let out = list(&temp_dir, &["logged=0"]);
+    assert!(out.contains("Untouched") && !out.contains("Halfway"), "{}", out);
+}
+
+#[test]
+fn test_filter_remaining_and_overbudget() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_budgets(&temp_dir);
+
+    // Tasks without an allocation have no remaining time
+    let out = list(&temp_dir, &["remaining<0"]);
+    assert!(out.contains("Underestimated") && !out.contains("Halfway") && !out.contains("Unestimated"), "{}", out);
+
+    let out = list(&temp_dir, &["+OVERBUDGET"]);
+    assert!(out.contains("Underestimated") && !out.contains("Halfway") && !out.contains("Unestimated"), "{}", out);
+
+    // Almost finished: allocated, started, and at most an hour to go
+    let out = list(&temp_dir, &["remaining<=1h", "-OVERBUDGET", "logged>0"]);
+    assert!(out.contains("Halfway") && !out.contains("Underestimated") && !out.contains("Untouched"), "{}", out);
+}
+
+#[test]
+fn test_filter_duration_errors() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir)
+        .args(["list", "alloc>2x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration '2x' in alloc filter"));
+}
@@ -0,0 +1,107 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+fn rc(temp_dir: &TempDir) -> String {
+    fs::read_to_string(temp_dir.path().join(".tatl/rc")).unwrap()
+}
+
+#[test]
+fn test_config_set_get_unset() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir).args(&["config", "get", "session.micro_seconds"]).assert().success()
+        .stdout("30\n");
+    get_task_cmd(&temp_dir).args(&["config", "set", "session.micro_seconds", "45"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["config", "get", "session.micro_seconds"]).assert().success()
+        .stdout("45\n");
+    assert!(rc(&temp_dir).contains("data.location="));
+    assert!(rc(&temp_dir).contains("session.micro_seconds=45"));
+
+    get_task_cmd(&temp_dir).args(&["config", "list"]).assert().success()
+        .stdout(predicate::str::contains("session.micro_seconds=45    # default: 30")
+            .and(predicate::str::contains("urgency.overdue=15.0")));
+
+    get_task_cmd(&temp_dir).args(&["config", "unset", "session.micro_seconds"]).assert().success()
+        .stdout(predicate::str::contains("default: 30"));
+    assert!(!rc(&temp_dir).contains("session.micro_seconds"));
+}
+
+#[test]
+fn test_config_set_validates_value() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir).args(&["config", "set", "color.kanban.queued", "chartreuse"]).assert().failure()
+        .stderr(predicate::str::contains("Invalid value for 'color.kanban.queued'"));
+    get_task_cmd(&temp_dir).args(&["config", "set", "list.colour", "x"]).assert().failure()
+        .stderr(predicate::str::contains("Unknown configuration key 'list.colour'"));
+    assert!(!rc(&temp_dir).contains("color.kanban"));
+}
+
+#[test]
+fn test_invalid_rc_names_the_key() {
+    let (temp_dir, _guard) = setup_test_env();
+    let mut text = rc(&temp_dir);
+    text.push_str("urgency.overdue=lots\n");
+    fs::write(temp_dir.path().join(".tatl/rc"), text).unwrap();
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().failure()
+        .stderr(predicate::str::contains("line 2").and(predicate::str::contains("'urgency.overdue'")));
+
+    // The config commands still work so the file can be repaired
+    get_task_cmd(&temp_dir).args(&["config", "unset", "urgency.overdue"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success();
+}
+
+#[test]
+fn test_unknown_rc_key_is_ignored_with_a_warning() {
+    let (temp_dir, _guard) = setup_test_env();
+    let mut text = rc(&temp_dir);
+    text.push_str("foo.bar=1\n");
+    fs::write(temp_dir.path().join(".tatl/rc"), text).unwrap();
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stderr(predicate::str::contains("Warning").and(predicate::str::contains("foo.bar")));
+
+    // config set stays strict, and unset can remove the unknown key
+    get_task_cmd(&temp_dir).args(&["config", "set", "foo.bar", "2"]).assert().failure()
+        .stderr(predicate::str::contains("Unknown configuration key 'foo.bar'"));
+    get_task_cmd(&temp_dir).args(&["config", "unset", "foo.bar"]).assert().success();
+    assert!(!rc(&temp_dir).contains("foo.bar"));
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stderr(predicate::str::contains("foo.bar").not());
+}
+
+#[test]
+fn test_config_drives_list_and_dates() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report", "project=work", "due=2030-03-15"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["config", "set", "list.columns", "project,due"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["config", "set", "date.format", "%d/%m/%Y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Project")
+            .and(predicate::str::contains("Kanban").not())
+            .and(predicate::str::contains("15/03/2030")));
+}
@@ -0,0 +1,151 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for persistent filter contexts
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn run(temp_dir: &TempDir, args: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// Two work tasks, an on-call task and a home task, with a work context active
+fn setup_work_context(temp_dir: &TempDir) {
+    for args in [
+        vec!["add", "-y", "Write report", "project=work"],
+        vec!["add", "Page triage", "+oncall"],
+        vec!["add", "-y", "Fix sink", "project=home"],
+        vec!["add", "Review PR", "project=work"],
+    ] {
+        get_task_cmd(temp_dir).args(&args).assert().success();
+    }
+    get_task_cmd(temp_dir)
+        .args(["context", "define", "work", "project=work", "or", "+oncall"])
+        .assert()
+        .success();
+    get_task_cmd(temp_dir)
+        .args(["context", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to context 'work'"));
+}
+
+#[test]
+fn test_context_restricts_list() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_work_context(&temp_dir);
+
+    let out = run(&temp_dir, &["list"]);
+    assert!(out.contains("Write report") && out.contains("Page triage") && !out.contains("Fix sink"), "{}", out);
+    assert!(out.contains("Context: work (project=work or +oncall)"), "{}", out);
+
+    // ANDed with the command's own filter, so the context's 'or' stays grouped
+    let out = run(&temp_dir, &["list", "+oncall"]);
+    assert!(out.contains("Page triage") && !out.contains("Write report"), "{}", out);
+
+    // Explicit IDs are not restricted
+    let out = run(&temp_dir, &["list", "3"]);
+    assert!(out.contains("Fix sink"), "{}", out);
+
+    let out = run(&temp_dir, &["context", "list"]);
+    assert!(out.contains("* work"), "{}", out);
+}
+
+#[test]
+fn test_no_context_flag_and_clearing() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_work_context(&temp_dir);
+
+    let out = run(&temp_dir, &["--no-context", "list"]);
+    assert!(out.contains("Fix sink") && !out.contains("Context:"), "{}", out);
+    let out = run(&temp_dir, &["list", "project=home", "--no-context"]);
+    assert!(out.contains("Fix sink"), "{}", out);
+
+    // The bypass lasts a single command
+    let out = run(&temp_dir, &["list"]);
+    assert!(!out.contains("Fix sink"), "{}", out);
+
+    run(&temp_dir, &["context", "none"]);
+    let out = run(&temp_dir, &["list"]);
+    assert!(out.contains("Fix sink") && !out.contains("Context:"), "{}", out);
+    let out = run(&temp_dir, &["context"]);
+    assert!(out.contains("No context is active"), "{}", out);
+}
+
+#[test]
+fn test_context_restricts_modify_and_finish() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_work_context(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(["add", "Buy paint", "+errand", "project=home"]).assert().success();
+    get_task_cmd(&temp_dir).args(["add", "Print slides", "+errand", "project=work"]).assert().success();
+
+    // Only the work errand is inside the context
+    get_task_cmd(&temp_dir).args(["modify", "+errand", "+today", "-y"]).assert().success();
+    let out = run(&temp_dir, &["--no-context", "list", "+today"]);
+    assert!(out.contains("Print slides") && !out.contains("Buy paint"), "{}", out);
+
+    get_task_cmd(&temp_dir).args(["finish", "+errand", "-y"]).assert().success();
+    let out = run(&temp_dir, &["--no-context", "list", "+errand", "status=pending"]);
+    assert!(out.contains("Buy paint") && !out.contains("Print slides"), "{}", out);
+}
+
+#[test]
+fn test_context_in_reports_and_sessions() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_work_context(&temp_dir);
+    get_task_cmd(&temp_dir).args(["onoff", "2024-03-04T09:00..2024-03-04T10:00", "1"]).assert().success();
+    get_task_cmd(&temp_dir).args(["onoff", "2024-03-04T10:00..2024-03-04T11:00", "3"]).assert().success();
+
+    let out = run(&temp_dir, &["sessions", "list"]);
+    assert!(out.contains("Write report") && !out.contains("Fix sink"), "{}", out);
+
+    let out = run(&temp_dir, &["sessions", "report", "2024-03-04", "2024-03-05"]);
+    assert!(out.contains("Context: work") && out.contains("work") && !out.contains("home"), "{}", out);
+
+    let out = run(&temp_dir, &["report"]);
+    assert!(out.contains("Context: work"), "{}", out);
+}
+
+#[test]
+fn test_context_errors() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir)
+        .args(["context", "work"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Context 'work' not found"));
+    get_task_cmd(&temp_dir)
+        .args(["context", "define", "none", "+x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("reserved"));
+    get_task_cmd(&temp_dir)
+        .args(["context", "define", "work", "(project=work"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Filter parse error"));
+}
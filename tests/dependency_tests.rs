@@ -0,0 +1,143 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for task dependencies and the blocked kanban status
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn run(temp_dir: &TempDir, args: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// Design (1) <- Build (2) <- Ship (3), plus an unrelated task (4)
+fn setup_chain(temp_dir: &TempDir) {
+    run(temp_dir, &["add", "Design"]);
+    let out = run(temp_dir, &["add", "Build", "depends=1"]);
+    assert!(out.contains("Task 2 is blocked by task(s) 1"), "{}", out);
+    run(temp_dir, &["add", "Ship", "depends=2"]);
+    run(temp_dir, &["add", "Unrelated"]);
+}
+
+#[test]
+fn test_blocked_filters() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    let out = run(&temp_dir, &["list", "+BLOCKED"]);
+    assert!(out.contains("Build") && out.contains("Ship") && !out.contains("Design"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "+BLOCKING"]);
+    assert!(out.contains("Design") && out.contains("Build") && !out.contains("Ship"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "kanban=blocked"]);
+    assert!(out.contains("Build") && !out.contains("Unrelated"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "depends=none"]);
+    assert!(out.contains("Design") && out.contains("Unrelated") && !out.contains("Ship"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "depends=2"]);
+    assert!(out.contains("Ship") && !out.contains("Build"), "{}", out);
+}
+
+#[test]
+fn test_finishing_unblocks_dependents() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    get_task_cmd(&temp_dir)
+        .args(["finish", "1", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Unblocked task 2: Build"));
+
+    let out = run(&temp_dir, &["list", "+BLOCKED"]);
+    assert!(out.contains("Ship") && !out.contains("Build"), "{}", out);
+}
+
+#[test]
+fn test_cycles_and_missing_tasks_are_rejected() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    get_task_cmd(&temp_dir)
+        .args(["modify", "1", "depends=3", "-y"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cycle"));
+
+    get_task_cmd(&temp_dir)
+        .args(["add", "Orphan", "depends=99"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("99"));
+
+    let out = run(&temp_dir, &["list", "depends=any"]);
+    assert!(!out.contains("Design") && !out.contains("Orphan"), "{}", out);
+}
+
+#[test]
+fn test_show_lists_upstream_and_downstream() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    let out = run(&temp_dir, &["show", "2"]);
+    assert!(out.contains("Depends on:") && out.contains("Design"), "{}", out);
+    assert!(out.contains("Required by:") && out.contains("Ship"), "{}", out);
+}
+
+#[test]
+fn test_clearing_dependencies() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    run(&temp_dir, &["modify", "3", "depends=none", "-y"]);
+    let out = run(&temp_dir, &["list", "+BLOCKED"]);
+    assert!(out.contains("Build") && !out.contains("Ship"), "{}", out);
+}
+
+#[test]
+fn test_blocked_task_at_top_of_queue_warns() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    get_task_cmd(&temp_dir)
+        .args(["enqueue", "2"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Task 2 at the top of the queue is blocked by task(s) 1"));
+}
+
+#[test]
+fn test_closing_the_top_task_warns_about_a_blocked_successor() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_chain(&temp_dir);
+
+    run(&temp_dir, &["enqueue", "4,3"]);
+    get_task_cmd(&temp_dir)
+        .args(["close", "4", "-y"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Task 3 at the top of the queue is blocked by task(s) 2"));
+}
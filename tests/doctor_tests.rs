@@ -0,0 +1,117 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+/// Corrupt the ledger behind tatl's back
+fn corrupt(temp_dir: &TempDir, sql: &str) {
+    let conn = rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap();
+    conn.execute_batch(sql).unwrap();
+}
+
+#[test]
+fn test_doctor_clean_ledger() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report"]).assert().success();
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found."));
+}
+
+#[test]
+fn test_doctor_reports_and_fixes() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["enqueue", "1"]).assert().success();
+    corrupt(&temp_dir, "UPDATE tasks SET status = 'completed', respawn = 'sometimes' WHERE id = 1;");
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor"])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::contains("stack_item_not_pending")
+            .and(predicate::str::contains("respawn_invalid"))
+            .and(predicate::str::contains("tatl doctor --fix")));
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fixed 2 issue(s)"));
+
+    let backups = fs::read_dir(temp_dir.path().join(".tatl/backups")).unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains("pre-doctor"))
+        .count();
+    assert_eq!(backups, 1);
+
+    get_task_cmd(&temp_dir).args(&["doctor"]).assert().success();
+}
+
+#[test]
+fn test_doctor_json_report() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["projects", "add", "someday"]).assert().success();
+    corrupt(&temp_dir, "INSERT INTO sessions (task_id, start_ts, end_ts, created_ts) VALUES (1, 2000, 1000, 0);");
+
+    let output = get_task_cmd(&temp_dir).args(&["doctor", "--json"]).assert().code(1);
+    let report: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["errors"], 1);
+    assert_eq!(report["info"], 1);
+    let findings = report["findings"].as_array().unwrap();
+    assert_eq!(findings[0]["check"], "session_inverted");
+    assert_eq!(findings[0]["fix"]["action"], "swap_session_times");
+    assert_eq!(findings[1]["check"], "project_empty");
+    assert_eq!(findings[1]["fix"]["name"], "someday");
+}
+
+#[test]
+fn test_doctor_fix_leaves_info_findings_unless_asked() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["projects", "add", "someday"]).assert().success();
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("project_empty")
+            .and(predicate::str::contains("Fixed").not())
+            .and(predicate::str::contains("tatl doctor --fix --include-info")));
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor", "--include-info"])
+        .assert()
+        .stderr(predicate::str::contains("--fix"));
+
+    get_task_cmd(&temp_dir)
+        .args(&["doctor", "--fix", "--include-info"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fixed 1 issue(s)")
+            .and(predicate::str::contains("No problems found.")));
+}
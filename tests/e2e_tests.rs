@@ -3,6 +3,7 @@
 
 mod acceptance_framework;
 use acceptance_framework::*;
+use tatl::config::Config;
 use tatl::repo::{TaskRepo, ProjectRepo, StackRepo, SessionRepo, AnnotationRepo, TemplateRepo};
 use tatl::filter::{parse_filter, filter_tasks};
 use tatl::utils::date;
@@ -189,7 +190,7 @@ fn e2e_complex_filter_scenarios() {
     
     // Test 1: Filter by project
     let filter_expr = parse_filter(vec!["project=work".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task1));
     assert!(matching_ids.contains(&task2));
@@ -197,7 +198,7 @@ fn e2e_complex_filter_scenarios() {
     
     // Test 2: Filter by tag (OR)
     let filter_expr = parse_filter(vec!["+urgent".to_string(), "or".to_string(), "+important".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task1)); // Has both
     assert!(matching_ids.contains(&task2)); // Has important
@@ -205,7 +206,7 @@ fn e2e_complex_filter_scenarios() {
     
     // Test 3: Filter by project AND tag
     let filter_expr = parse_filter(vec!["project=work".to_string(), "+urgent".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task1)); // work + urgent
     assert!(!matching_ids.contains(&task2)); // work but not urgent
@@ -213,7 +214,7 @@ fn e2e_complex_filter_scenarios() {
     
     // Test 4: Filter by NOT
     let filter_expr = parse_filter(vec!["not".to_string(), "project=work".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(!matching_ids.contains(&task1));
     assert!(!matching_ids.contains(&task2));
@@ -221,7 +222,7 @@ fn e2e_complex_filter_scenarios() {
     
     // Test 5: Filter by due date
     let filter_expr = parse_filter(vec!["due=tomorrow".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task1));
     assert!(!matching_ids.contains(&task2));
@@ -251,7 +252,7 @@ fn e2e_complex_filter_with_nested_projects() {
     
     // Test: Filter by prefix "admin" should match admin, admin.email, admin.email.inbox
     let filter_expr = parse_filter(vec!["project=admin".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(matching_ids.contains(&task1));
     assert!(matching_ids.contains(&task2));
@@ -261,7 +262,7 @@ fn e2e_complex_filter_with_nested_projects() {
     
     // Test: Filter by prefix "admin.email" should match admin.email and admin.email.inbox
     let filter_expr = parse_filter(vec!["project=admin.email".to_string()]).unwrap();
-    let matching = filter_tasks(ctx.db(), &filter_expr).unwrap();
+    let matching = filter_tasks(ctx.db(), &filter_expr, &Config::default()).unwrap();
     let matching_ids: Vec<i64> = matching.iter().map(|(t, _)| t.id.unwrap()).collect();
     assert!(!matching_ids.contains(&task1));
     assert!(matching_ids.contains(&task2));
@@ -2,6 +2,7 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use tempfile::TempDir;
 use std::fs;
+use tatl::config::Config;
 use tatl::db::DbConnection;
 use tatl::repo::{TaskRepo, EventRepo, StackRepo, SessionRepo, AnnotationRepo};
 use rusqlite::Connection;
@@ -116,7 +117,7 @@ fn test_event_session_started() {
     let task_id = task.id.unwrap();
     
     let now = chrono::Utc::now().timestamp();
-    SessionRepo::create(&conn, task_id, now).unwrap();
+    SessionRepo::create(&conn, task_id, now, &Config::default()).unwrap();
     
     // Verify session_started event was recorded
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM task_events WHERE task_id = ?1 AND event_type = 'session_started'").unwrap();
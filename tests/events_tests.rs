@@ -0,0 +1,122 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn open_db(temp_dir: &TempDir) -> rusqlite::Connection {
+    rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap()
+}
+
+#[test]
+fn test_events_verify_matches_and_reports_divergence() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir)
+        .args(&["add", "-y", "Alpha", "project=work", "+home", "due=2030-01-20", "uda.size=L"])
+        .assert()
+        .success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta", "wait=2030-01-01"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["modify", "1", "due=2030-01-25", "-home", "+work"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["finish", "2", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "2", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["events", "verify"]).assert().success()
+        .stdout(predicate::str::contains("Checked 2 task(s): all match the event log"));
+
+    open_db(&temp_dir).execute("UPDATE tasks SET due_ts = NULL WHERE id = 1", []).unwrap();
+    get_task_cmd(&temp_dir).args(&["events", "verify"]).assert().failure()
+        .stdout(predicate::str::contains("Task 1: Alpha")
+            .and(predicate::str::contains("due: events say 2030-01-25 00:00:00, ledger has none")));
+    get_task_cmd(&temp_dir).args(&["events", "verify", "2", "--json"]).assert().success()
+        .stdout(predicate::str::contains("\"checked\": 1"));
+}
+
+#[test]
+fn test_events_snapshot_at_past_moment() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha", "due=2030-01-20"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["modify", "1", "Alpha revised", "due=2030-01-25"]).assert().success();
+
+    // Backdate the history: created in January, revised in February
+    let conn = open_db(&temp_dir);
+    conn.execute("UPDATE task_events SET ts = strftime('%s', '2026-01-05') WHERE event_type = 'created'", []).unwrap();
+    conn.execute("UPDATE task_events SET ts = strftime('%s', '2026-02-05') WHERE event_type = 'modified'", []).unwrap();
+
+    get_task_cmd(&temp_dir).args(&["events", "snapshot", "--at", "2026-01-15", "1"]).assert().success()
+        .stdout(predicate::str::contains("description: \"Alpha\"\n")
+            .and(predicate::str::contains("2030-01-20 00:00:00")));
+    get_task_cmd(&temp_dir).args(&["events", "snapshot", "--at", "2026-03-01"]).assert().success()
+        .stdout(predicate::str::contains("(1 task(s))")
+            .and(predicate::str::contains("Alpha revised")));
+    get_task_cmd(&temp_dir).args(&["events", "snapshot", "--at", "2025-12-01"]).assert().success()
+        .stdout(predicate::str::contains("(0 task(s))"));
+    get_task_cmd(&temp_dir).args(&["events", "snapshot", "--at", "2025-12-01", "1"]).assert().success()
+        .stdout(predicate::str::contains("did not exist yet"));
+}
+
+#[test]
+fn test_events_log_audits_session_edits() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["onoff", "2026-01-05T09:00..2026-01-05T10:00", "1"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["sessions", "modify", "1", "--yes", "end=2026-01-05T11:00"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["sessions", "delete", "1", "--yes"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["events", "log", "--entity", "session", "--id", "1"]).assert().success()
+        .stdout(predicate::str::contains("session_added")
+            .and(predicate::str::contains("session_modified"))
+            .and(predicate::str::contains("session_deleted"))
+            .and(predicate::str::contains("undone")));
+
+    let output = get_task_cmd(&temp_dir).args(&["events", "log", "--entity", "session", "--json"]).assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    let modified = json.as_array().unwrap().iter()
+        .find(|e| e["event_type"] == "session_modified").unwrap();
+    assert_eq!(modified["task_id"], 1);
+    assert_ne!(modified["payload"]["old_end_ts"], modified["payload"]["new_end_ts"]);
+}
+
+#[test]
+fn test_events_log_externals_and_projects() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha", "project=work"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["send", "1", "colleague", "please", "review"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["collect", "1"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["projects", "rename", "work", "office"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["projects", "archive", "office"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["events", "log", "--entity", "external"]).assert().success()
+        .stdout(predicate::str::contains("external_sent")
+            .and(predicate::str::contains("external_returned")));
+    get_task_cmd(&temp_dir).args(&["events", "log", "--entity", "project"]).assert().success()
+        .stdout(predicate::str::contains("project_renamed")
+            .and(predicate::str::contains("project_archived")));
+    // The unfiltered log interleaves task and entity events
+    get_task_cmd(&temp_dir).args(&["events", "log"]).assert().success()
+        .stdout(predicate::str::contains("created")
+            .and(predicate::str::contains("project_renamed")));
+
+    get_task_cmd(&temp_dir).args(&["events", "log", "--id", "1"]).assert().failure()
+        .stderr(predicate::str::contains("--entity"));
+    get_task_cmd(&temp_dir).args(&["events", "log", "--entity", "widget"]).assert().failure();
+}
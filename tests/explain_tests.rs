@@ -0,0 +1,118 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for --explain on filter-taking commands
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn run(temp_dir: &TempDir, args: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn setup_tasks(temp_dir: &TempDir) {
+    for args in [
+        vec!["add", "-y", "Write report", "project=work"],
+        vec!["add", "-y", "Inbox zero", "project=work.email", "+urgent"],
+        vec!["add", "Page triage", "+oncall"],
+    ] {
+        get_task_cmd(temp_dir).args(&args).assert().success();
+    }
+}
+
+#[test]
+fn test_explain_list_shows_tree_and_counts() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    let out = run(&temp_dir, &["list", "project=work", "+urgent", "or", "due<2026-01-15", "sort:due", "--explain"]);
+    assert!(out.contains("Filter: project=work +urgent or due<2026-01-15"), "{}", out);
+    assert!(out.contains("sort:due") && out.contains("display option"), "{}", out);
+    assert!(out.contains("'work' covers work, work.email"), "{}", out);
+    assert!(out.contains("due before 2026-01-15 00:00:00"), "{}", out);
+    assert!(out.contains("Result: 1 task"), "{}", out);
+    // The explanation replaces the task table
+    assert!(!out.contains("Inbox zero"), "{}", out);
+}
+
+#[test]
+fn test_explain_flags_terms_that_match_nothing() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    let out = run(&temp_dir, &["--explain", "list", "due<nextfryday", "project=home"]);
+    assert!(out.contains("'nextfryday' is not a date expression"), "{}", out);
+    assert!(out.contains("No project is named 'home'"), "{}", out);
+    assert!(out.contains("Result: 0 tasks"), "{}", out);
+}
+
+#[test]
+fn test_explain_modify_flags_arguments_and_changes_nothing() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    let out = run(&temp_dir, &["modify", "project=work", "+later", "Rewrite", "--explain"]);
+    assert!(out.contains("+later") && out.contains("modification"), "{}", out);
+    assert!(out.contains("Rewrite") && out.contains("part of the new description"), "{}", out);
+    assert!(out.contains("No tasks were modified"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "+later"]);
+    assert!(out.contains("No tasks found"), "{}", out);
+}
+
+#[test]
+fn test_explain_includes_context() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+    run(&temp_dir, &["context", "define", "work", "project=work", "or", "+oncall"]);
+    run(&temp_dir, &["context", "work"]);
+
+    let out = run(&temp_dir, &["finish", "+urgent", "--explain"]);
+    assert!(out.contains("context 'work': or"), "{}", out);
+    assert!(out.contains("No tasks were finished"), "{}", out);
+    let out = run(&temp_dir, &["list", "status=pending"]);
+    assert!(out.contains("Inbox zero"), "{}", out);
+}
+
+#[test]
+fn test_explain_rejected_for_other_commands() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir)
+        .args(["--explain", "add", "Something"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--explain works with commands that take a filter"));
+}
+
+#[test]
+fn test_flags_after_a_description_are_text() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir).args(["add", "fix", "--explain", "output"]).assert().success();
+    get_task_cmd(&temp_dir).args(["add", "pass", "--no-context", "through"]).assert().success();
+    let out = run(&temp_dir, &["list"]);
+    assert!(out.contains("fix --explain output"), "{}", out);
+    assert!(out.contains("pass --no-context through"), "{}", out);
+}
@@ -0,0 +1,151 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    (new_ledger_home(), guard)
+}
+
+fn new_ledger_home() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    temp_dir
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+fn seed(temp_dir: &TempDir) {
+    get_task_cmd(temp_dir).args(&["add", "-y", "Write report", "project=work", "+urgent"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Call plumber"]).assert().success();
+    get_task_cmd(temp_dir).args(&["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"]).assert().success();
+    get_task_cmd(temp_dir).args(&["annotate", "1", "Outline done"]).assert().success();
+    get_task_cmd(temp_dir).args(&["enqueue", "2"]).assert().success();
+}
+
+#[test]
+fn test_export_writes_versioned_document() {
+    let (temp_dir, _guard) = setup_test_env();
+    seed(&temp_dir);
+
+    let output = get_task_cmd(&temp_dir).args(&["export"]).assert().success();
+    let doc: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(doc["format"], "tatl-ledger");
+    assert_eq!(doc["version"], 1);
+    assert_eq!(doc["tasks"].as_array().unwrap().len(), 2);
+    assert_eq!(doc["projects"].as_array().unwrap().len(), 1);
+    assert_eq!(doc["sessions"].as_array().unwrap().len(), 1);
+    assert_eq!(doc["task_annotations"].as_array().unwrap().len(), 1);
+    assert_eq!(doc["stack_items"].as_array().unwrap().len(), 1);
+    assert!(!doc["task_events"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_import_restores_into_empty_ledger() {
+    let (source, _guard) = setup_test_env();
+    seed(&source);
+    let export_path = source.path().join("ledger.json");
+    get_task_cmd(&source)
+        .args(&["export", "--output", export_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let target = new_ledger_home();
+    get_task_cmd(&target)
+        .args(&["import", export_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 task(s)"));
+
+    get_task_cmd(&target).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Write report").and(predicate::str::contains("Call plumber")));
+    get_task_cmd(&target).args(&["show", "1"]).assert().success()
+        .stdout(predicate::str::contains("Outline done"));
+    get_task_cmd(&target).args(&["sessions", "list"]).assert().success()
+        .stdout(predicate::str::contains("Write report"));
+}
+
+#[test]
+fn test_import_is_idempotent_by_uuid() {
+    let (temp_dir, _guard) = setup_test_env();
+    seed(&temp_dir);
+    let export_path = temp_dir.path().join("ledger.json");
+    get_task_cmd(&temp_dir)
+        .args(&["export", "-o", export_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    get_task_cmd(&temp_dir)
+        .args(&["import", export_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 0 task(s) (2 already present, skipped)"));
+
+    let output = get_task_cmd(&temp_dir).args(&["list", "--json"]).assert().success();
+    let tasks: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(tasks.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_import_rejects_unknown_document() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("bogus.json");
+    fs::write(&path, r#"{"format":"something-else","version":1,"schema_version":1,"exported_ts":0}"#).unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["import", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported export format"));
+}
+
+#[test]
+fn test_import_from_taskwarrior() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("tw.json");
+    fs::write(&path, r#"[
+        {"id":1,"description":"Water plants","entry":"20260101T090000Z","status":"pending",
+         "uuid":"33333333-3333-4333-8333-333333333333","project":"Home.Garden","tags":["chores"],
+         "recur":"weekly","due":"20260110T090000Z","priority":"M",
+         "annotations":[{"entry":"20260102T090000Z","description":"Use rain water"}]}
+    ]"#).unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["import", "--from", "taskwarrior", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 task(s) from Taskwarrior"));
+
+    get_task_cmd(&temp_dir).args(&["show", "1"]).assert().success()
+        .stdout(predicate::str::contains("Water plants")
+            .and(predicate::str::contains("Home.Garden"))
+            .and(predicate::str::contains("weekly"))
+            .and(predicate::str::contains("Use rain water")));
+}
+
+#[test]
+fn test_export_to_taskwarrior() {
+    let (temp_dir, _guard) = setup_test_env();
+    seed(&temp_dir);
+
+    let output = get_task_cmd(&temp_dir).args(&["export", "--to", "taskwarrior"]).assert().success();
+    let tasks: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    let tasks = tasks.as_array().unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0]["project"], "work");
+    assert_eq!(tasks[0]["tags"][0], "urgent");
+    assert_eq!(tasks[0]["status"], "pending");
+    assert!(tasks[0]["entry"].as_str().unwrap().ends_with('Z'));
+    assert_eq!(tasks[0]["annotations"][0]["description"], "Outline done");
+}
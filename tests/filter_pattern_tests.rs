@@ -386,3 +386,42 @@ fn test_desc_filter_combined_with_other_filters() {
     assert!(stdout.contains("Work meeting"), "Should find matching task");
     assert!(!stdout.contains("Personal meeting"), "Should not find non-matching task");
 }
+
+#[test]
+fn test_filter_grouping_and_xor() {
+    let (temp_dir, _guard) = setup_test_env();
+    new_cmd(&temp_dir).args(&["add", "-y", "Work urgent", "project=work", "+urgent"]).assert().success();
+    new_cmd(&temp_dir).args(&["add", "-y", "Home urgent", "project=home", "+urgent"]).assert().success();
+    new_cmd(&temp_dir).args(&["add", "-y", "Gym urgent", "project=gym", "+urgent"]).assert().success();
+    new_cmd(&temp_dir).args(&["add", "-y", "Work later", "project=work", "+later"]).assert().success();
+
+    let output = new_cmd(&temp_dir)
+        .args(&["list", "(project=work", "or", "project=home)", "and", "+urgent"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("Work urgent") && stdout.contains("Home urgent"));
+    assert!(!stdout.contains("Gym urgent") && !stdout.contains("Work later"));
+
+    // Exactly one of the two conditions
+    let output = new_cmd(&temp_dir)
+        .args(&["list", "project=work", "xor", "+urgent"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("Work later") && stdout.contains("Home urgent") && stdout.contains("Gym urgent"));
+    assert!(!stdout.contains("Work urgent"));
+}
+
+#[test]
+fn test_filter_unbalanced_parentheses_error() {
+    let (temp_dir, _guard) = setup_test_env();
+    new_cmd(&temp_dir).args(&["add", "Task"]).assert().success();
+
+    new_cmd(&temp_dir)
+        .args(&["list", "(project=work", "or", "+urgent"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing ')' to close this '('")
+            .and(predicate::str::contains("( project=work or +urgent\n  ^")));
+}
@@ -0,0 +1,69 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+#[test]
+fn test_history_shows_field_diffs() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report", "due=2030-01-20", "+draft"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["modify", "1", "due=2030-01-22", "-draft"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["finish", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["history", "1"]).assert().success()
+        .stdout(predicate::str::contains("Task 1: Write report")
+            .and(predicate::str::contains("due: 2030-01-20 00:00:00 → 2030-01-22 00:00:00"))
+            .and(predicate::str::contains("-draft"))
+            .and(predicate::str::contains("status: pending → completed")));
+}
+
+#[test]
+fn test_history_json_and_since() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "First", "project=work", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Second", "project=work"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["modify", "2", "alloc=1h"]).assert().success();
+
+    let output = get_task_cmd(&temp_dir).args(&["history", "project=work", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tasks = doc.as_array().unwrap();
+    assert_eq!(tasks.len(), 2);
+    let change = tasks[1]["events"].as_array().unwrap().iter()
+        .flat_map(|e| e["changes"].as_array().unwrap().clone())
+        .find(|c| c["field"] == "alloc_secs")
+        .unwrap();
+    assert!(change["old_value"].is_null());
+    assert_eq!(change["new_value"], 3600);
+
+    get_task_cmd(&temp_dir).args(&["history", "2", "--since", "2099-01-01"]).assert().success()
+        .stdout(predicate::str::contains("No events since"));
+    get_task_cmd(&temp_dir).args(&["history", "2", "--since", "-1d"]).assert().success()
+        .stdout(predicate::str::contains("alloc: none → 1h"));
+}
+
+#[test]
+fn test_history_no_match() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["history", "project=nowhere"]).assert().failure()
+        .stderr(predicate::str::contains("No matching tasks"));
+}
@@ -0,0 +1,111 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn add_work_ledger(temp_dir: &TempDir) {
+    let work_db = temp_dir.path().join("work.db");
+    get_task_cmd(temp_dir)
+        .args(&["ledger", "add", "work", work_db.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added ledger 'work'"));
+}
+
+#[test]
+fn test_ledger_flag_selects_profile() {
+    let (temp_dir, _guard) = setup_test_env();
+    add_work_ledger(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(&["--ledger", "work", "add", "Client task"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Personal task"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["list", "--ledger=work"]).assert().success()
+        .stdout(predicate::str::contains("Client task").and(predicate::str::contains("Personal task").not()));
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Personal task").and(predicate::str::contains("Client task").not()));
+    assert!(temp_dir.path().join("work.db").exists());
+}
+
+#[test]
+fn test_ledger_switch_and_env_precedence() {
+    let (temp_dir, _guard) = setup_test_env();
+    add_work_ledger(&temp_dir);
+    get_task_cmd(&temp_dir).args(&["add", "Personal task"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["ledger", "switch", "work"]).assert().success()
+        .stdout(predicate::str::contains("Switched to ledger 'work'"));
+    get_task_cmd(&temp_dir).args(&["add", "Client task"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["ledger", "list"]).assert().success()
+        .stdout(predicate::str::contains("* work"));
+
+    // TATL_LEDGER overrides the switched ledger
+    get_task_cmd(&temp_dir).env("TATL_LEDGER", "default").args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Personal task").and(predicate::str::contains("Client task").not()));
+
+    get_task_cmd(&temp_dir).args(&["ledger", "switch", "default"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Personal task"));
+}
+
+#[test]
+fn test_unknown_ledger_is_rejected() {
+    let (temp_dir, _guard) = setup_test_env();
+
+    get_task_cmd(&temp_dir).args(&["--ledger", "nope", "list"]).assert().failure()
+        .stderr(predicate::str::contains("Unknown ledger 'nope'"));
+    get_task_cmd(&temp_dir).args(&["ledger", "switch", "nope"]).assert().failure()
+        .stderr(predicate::str::contains("Unknown ledger 'nope'"));
+    get_task_cmd(&temp_dir).args(&["ledger", "add", "default", "x.db"]).assert().failure()
+        .stderr(predicate::str::contains("reserved"));
+}
+
+#[test]
+fn test_reports_show_active_ledger() {
+    let (temp_dir, _guard) = setup_test_env();
+    add_work_ledger(&temp_dir);
+    get_task_cmd(&temp_dir).args(&["--ledger", "work", "add", "Client task"]).assert().success();
+    get_task_cmd(&temp_dir)
+        .args(&["--ledger", "work", "onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"])
+        .assert()
+        .success();
+
+    get_task_cmd(&temp_dir).args(&["--ledger", "work", "report"]).assert().success()
+        .stdout(predicate::str::contains("Ledger: work").and(predicate::str::contains("via --ledger")));
+    get_task_cmd(&temp_dir).env("TATL_LEDGER", "work")
+        .args(&["sessions", "report", "2026-01-14..2026-01-15"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ledger: work").and(predicate::str::contains("via TATL_LEDGER")));
+}
+
+#[test]
+fn test_ledger_path_selector() {
+    let (temp_dir, _guard) = setup_test_env();
+    let scratch = temp_dir.path().join("scratch.db");
+
+    get_task_cmd(&temp_dir).args(&["--ledger", scratch.to_str().unwrap(), "add", "Scratch task"]).assert().success();
+    assert!(scratch.exists());
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Scratch task").not());
+}
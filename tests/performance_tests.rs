@@ -1,6 +1,7 @@
 // Performance and Optimization Tests
 // Verify indexes are created and used, and queries are optimized
 
+use tatl::config::Config;
 use tatl::db::DbConnection;
 use tatl::repo::{TaskRepo, ProjectRepo, StackRepo, SessionRepo};
 use tatl::filter::{parse_filter, filter_tasks};
@@ -173,7 +174,7 @@ fn test_filter_performance_with_indexes() {
     // Test filter by tag (should use index)
     let start = Instant::now();
     let filter_expr = parse_filter(vec!["+even".to_string()]).unwrap();
-    let matching = filter_tasks(&conn, &filter_expr).unwrap();
+    let matching = filter_tasks(&conn, &filter_expr, &Config::default()).unwrap();
     let filter_time = start.elapsed();
     
     // Should find approximately half the tasks
@@ -302,3 +303,80 @@ fn test_stack_operations_performance() {
     
     println!("Enqueued {} tasks in {:?}, retrieved in {:?}", num_tasks, enqueue_time, get_items_time);
 }
+
+#[test]
+fn test_filter_performance_large_dataset() {
+    // Filters are compiled to SQL, so their cost should not grow with per-task queries
+    let conn = DbConnection::connect_in_memory().unwrap();
+    let num_tasks: usize = 20_000;
+
+    let start = Instant::now();
+    let tx = conn.unchecked_transaction().unwrap();
+    let work = ProjectRepo::create(&tx, "work").unwrap();
+    let work_email = ProjectRepo::create(&tx, "work.email").unwrap();
+    let stack = StackRepo::get_or_create_default(&tx).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    for i in 0..num_tasks {
+        let project_id = match i % 4 {
+            0 => work.id,
+            1 => work_email.id,
+            _ => None,
+        };
+        let mut tags = vec![];
+        if i % 10 == 0 {
+            tags.push("rare".to_string());
+        }
+        if i % 2 == 0 {
+            tags.push("even".to_string());
+        }
+        let due_ts = if i % 5 == 0 { Some(now + (i as i64) * 60) } else { None };
+        let task = TaskRepo::create_full(
+            &tx,
+            &format!("Task {} {}", i, if i % 100 == 0 { "invoice" } else { "routine" }),
+            project_id,
+            due_ts,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &std::collections::HashMap::new(),
+            &tags,
+        ).unwrap();
+        let task_id = task.id.unwrap();
+        if i % 50 == 0 {
+            StackRepo::enqueue(&tx, stack.id.unwrap(), task_id).unwrap();
+        } else if i % 50 == 1 {
+            SessionRepo::create_closed(&tx, task_id, now - 7200, now - 3600).unwrap();
+        }
+    }
+    tx.commit().unwrap();
+    println!("Created {} tasks in {:?}", num_tasks, start.elapsed());
+
+    let cases: &[(&str, usize)] = &[
+        ("+rare", num_tasks / 10),
+        ("-even", num_tasks / 2),
+        ("project=work", num_tasks / 2),
+        ("project=work.email +even", 0),
+        ("kanban=queued", num_tasks / 50),
+        ("kanban=stalled", num_tasks / 50),
+        ("desc=invoice", num_tasks / 100),
+        ("due=any +rare", num_tasks / 10),
+        ("+rare or kanban=queued", num_tasks / 10),
+    ];
+    for (filter, expected) in cases {
+        let start = Instant::now();
+        let tokens = filter.split_whitespace().map(|t| t.to_string()).collect();
+        let filter_expr = parse_filter(tokens).unwrap();
+        let matching = filter_tasks(&conn, &filter_expr, &Config::default()).unwrap();
+        let filter_time = start.elapsed();
+
+        assert_eq!(matching.len(), *expected, "filter '{}'", filter);
+        assert!(
+            filter_time.as_millis() < 1000,
+            "Filter '{}' over {} tasks took {}ms, expected < 1000ms",
+            filter, num_tasks, filter_time.as_millis()
+        );
+        println!("Filtered '{}' ({} matches) in {:?}", filter, matching.len(), filter_time);
+    }
+}
@@ -0,0 +1,94 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for filtering on priority score and queue position
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn list(temp_dir: &TempDir, filter: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).arg("list").args(filter).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// Four queued tasks and an overdue one outside the queue
+fn setup_queue(temp_dir: &TempDir) {
+    for description in ["First", "Second", "Third", "Fourth"] {
+        get_task_cmd(temp_dir).args(["add", description]).assert().success();
+    }
+    get_task_cmd(temp_dir).args(["add", "Overdue", "due=-3d"]).assert().success();
+    get_task_cmd(temp_dir).args(["enqueue", "1,2,3,4"]).assert().success();
+}
+
+#[test]
+fn test_filter_queue_position() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_queue(&temp_dir);
+
+    let out = list(&temp_dir, &["queue<3"]);
+    assert!(out.contains("First") && out.contains("Third") && !out.contains("Fourth"), "{}", out);
+
+    let out = list(&temp_dir, &["queue=0"]);
+    assert!(out.contains("First") && !out.contains("Second"), "{}", out);
+
+    let out = list(&temp_dir, &["queue=none"]);
+    assert!(out.contains("Overdue") && !out.contains("First"), "{}", out);
+
+    let out = list(&temp_dir, &["queue=any"]);
+    assert!(out.contains("Fourth") && !out.contains("Overdue"), "{}", out);
+}
+
+#[test]
+fn test_finish_top_of_queue() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_queue(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(["finish", "queue<3", "-y"]).assert().success();
+
+    let out = list(&temp_dir, &["status=pending"]);
+    assert!(out.contains("Fourth") && out.contains("Overdue") && !out.contains("First") && !out.contains("Third"), "{}", out);
+    // The remaining task moved to the top
+    let out = list(&temp_dir, &["queue=0"]);
+    assert!(out.contains("Fourth"), "{}", out);
+}
+
+#[test]
+fn test_filter_priority() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_queue(&temp_dir);
+
+    // Urgent things not yet queued
+    let out = list(&temp_dir, &["priority>5", "queue=none"]);
+    assert!(out.contains("Overdue") && !out.contains("First"), "{}", out);
+
+    // Finished tasks have no priority
+    get_task_cmd(&temp_dir).args(["finish", "5", "-y"]).assert().success();
+    let out = list(&temp_dir, &["priority>5"]);
+    assert!(!out.contains("Overdue"), "{}", out);
+
+    get_task_cmd(&temp_dir)
+        .args(["list", "priority>high"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Priority filter needs a number"));
+}
@@ -0,0 +1,87 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn run(temp_dir: &TempDir, args: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn setup_tasks(temp_dir: &TempDir) {
+    get_task_cmd(temp_dir).args(&["add", "Fix auth token bug"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Quarterly report"]).assert().success();
+    get_task_cmd(temp_dir).args(&["add", "Bug in authorization cache"]).assert().success();
+    get_task_cmd(temp_dir).args(&["annotate", "2", "Sent to finance for review"]).assert().success();
+}
+
+#[test]
+fn test_search_descriptions_and_annotations() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    let out = run(&temp_dir, &["search", "bugs"]);
+    assert!(out.contains("Fix auth token bug") && out.contains("Bug in authorization cache"), "{}", out);
+    assert!(!out.contains("Quarterly report"), "{}", out);
+
+    let out = run(&temp_dir, &["search", "finance", "review"]);
+    assert!(out.contains("Quarterly report") && out.contains("note: Sent to finance for review"), "{}", out);
+
+    get_task_cmd(&temp_dir).args(&["search", "nothing-like-this"]).assert().success()
+        .stdout(predicate::str::contains("No tasks match"));
+}
+
+#[test]
+fn test_search_follows_edits_and_deletes() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    get_task_cmd(&temp_dir).args(&["modify", "1", "Fix login token bug"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "3", "-y"]).assert().success();
+
+    let out = run(&temp_dir, &["search", "auth"]);
+    assert!(!out.contains("Fix") && !out.contains("authorization"), "{}", out);
+    let out = run(&temp_dir, &["search", "login", "--json"]);
+    let hits: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["description"], "Fix login token bug");
+}
+
+#[test]
+fn test_regex_and_note_filters() {
+    let (temp_dir, _guard) = setup_test_env();
+    setup_tasks(&temp_dir);
+
+    let out = run(&temp_dir, &["list", "desc~/auth.*bug/i"]);
+    assert!(out.contains("Fix auth token bug") && !out.contains("authorization cache"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "note~finance"]);
+    assert!(out.contains("Quarterly report") && !out.contains("auth"), "{}", out);
+
+    let out = run(&temp_dir, &["list", "not", "note~/^sent/i"]);
+    assert!(!out.contains("Quarterly report") && out.contains("Fix auth token bug"), "{}", out);
+
+    get_task_cmd(&temp_dir).args(&["list", "desc~/(unclosed/"]).assert().failure()
+        .stderr(predicate::str::contains("Invalid regular expression"));
+}
@@ -0,0 +1,145 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+use chrono::TimeZone;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd
+}
+
+fn session_count(temp_dir: &TempDir) -> usize {
+    let output = get_task_cmd(temp_dir).args(&["sessions", "list", "--json"]).assert().success();
+    let sessions: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    sessions.as_array().unwrap().len()
+}
+
+#[test]
+fn test_sessions_import_timewarrior_creates_tasks_and_sessions() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("2026-01.data");
+    fs::write(&path, "inc 20260114T090000Z - 20260114T100000Z # \"Write report\" work\n\
+                      inc 20260114T110000Z - 20260114T113000Z # \"Write report\"\n\
+                      inc 20260114T140000Z # email\n").unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap(), "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sessions to create: 2")
+            .and(predicate::str::contains("+ Write report"))
+            .and(predicate::str::contains("Skipped (still running): 1"))
+            .and(predicate::str::contains("Imported 2 session(s).")));
+
+    assert_eq!(session_count(&temp_dir), 2);
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Write report"));
+}
+
+#[test]
+fn test_sessions_import_dry_run_writes_nothing() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("hours.csv");
+    fs::write(&path, "start,end,description,project\n\
+                      2026-01-14T09:00:00Z,2026-01-14T10:00:00Z,Planning,work\n").unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap(), "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sessions to create: 1").and(predicate::str::contains("Dry run")));
+
+    assert_eq!(session_count(&temp_dir), 0);
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Planning").not());
+}
+
+#[test]
+fn test_sessions_import_csv_overlap_strategies() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Existing"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["onoff", "2026-01-14T10:00..2026-01-14T11:00", "1", "-y"]).assert().success();
+
+    let start = chrono::Local.with_ymd_and_hms(2026, 1, 14, 10, 30, 0).unwrap().timestamp();
+    let path = temp_dir.path().join("hours.csv");
+    fs::write(&path, format!("start,duration,description\n{},1h,Overlapping work\n", start)).unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap(), "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped (overlap): 1").and(predicate::str::contains("Nothing to import")));
+    assert_eq!(session_count(&temp_dir), 1);
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap(), "--overlap", "clip", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Clipped to avoid overlap: 1").and(predicate::str::contains("(30m0s)")));
+    assert_eq!(session_count(&temp_dir), 2);
+}
+
+#[test]
+fn test_sessions_import_rejects_unknown_overlap_strategy() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("hours.csv");
+    fs::write(&path, "start,duration\n2026-01-14T09:00:00Z,1h\n").unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap(), "--overlap", "merge"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid overlap strategy"));
+}
+
+#[test]
+fn test_sessions_import_from_stdin_needs_yes_or_dry_run() {
+    let (temp_dir, _guard) = setup_test_env();
+    let csv = "start,end,description\n2026-01-14T09:00:00Z,2026-01-14T10:00:00Z,Planning\n";
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", "-", "--from", "csv"])
+        .write_stdin(csv)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass -y"));
+    assert_eq!(session_count(&temp_dir), 0);
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", "-", "--from", "csv", "-y"])
+        .write_stdin(csv)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 session(s)."));
+    assert_eq!(session_count(&temp_dir), 1);
+}
+
+#[test]
+fn test_sessions_import_confirmation_imports_after_prompt() {
+    let (temp_dir, _guard) = setup_test_env();
+    let path = temp_dir.path().join("hours.csv");
+    fs::write(&path, "start,end,description\n2026-01-14T09:00:00Z,2026-01-14T10:00:00Z,Planning\n").unwrap();
+
+    get_task_cmd(&temp_dir)
+        .args(&["sessions", "import", path.to_str().unwrap()])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 session(s)."));
+    assert_eq!(session_count(&temp_dir), 1);
+}
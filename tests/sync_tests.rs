@@ -0,0 +1,91 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+/// Command running against the "other device" ledger file
+fn other_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = get_task_cmd(temp_dir);
+    cmd.env("TATL_LEDGER", temp_dir.path().join("other.db"));
+    cmd
+}
+
+fn other_path(temp_dir: &TempDir) -> String {
+    temp_dir.path().join("other.db").display().to_string()
+}
+
+#[test]
+fn test_sync_merges_tasks_both_ways() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Laptop task", "+home"]).assert().success();
+    other_cmd(&temp_dir).args(&["add", "Desktop task", "project=work"]).assert().success();
+    other_cmd(&temp_dir)
+        .args(&["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"])
+        .assert()
+        .success();
+
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir)]).assert().success()
+        .stdout(predicate::str::contains("1 task(s) added").and(predicate::str::contains("1 session(s) added")));
+
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Laptop task").and(predicate::str::contains("Desktop task")));
+    other_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Laptop task").and(predicate::str::contains("Desktop task")));
+
+    // Nothing left to exchange
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir)]).assert().success()
+        .stdout(predicate::str::contains("No changes").count(2));
+}
+
+#[test]
+fn test_sync_takes_latest_change_and_dry_run_writes_nothing() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Write report"]).assert().success();
+    other_cmd(&temp_dir).args(&["list"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir)]).assert().success();
+
+    other_cmd(&temp_dir).args(&["modify", "1", "Write final report"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir), "--dry-run"]).assert().success()
+        .stdout(predicate::str::contains("Write final report: description")
+            .and(predicate::str::contains("Dry run")));
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Write final report").not());
+
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir)]).assert().success();
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Write final report"));
+    assert!(fs::read_dir(temp_dir.path().join(".tatl/backups")).unwrap()
+        .any(|e| e.unwrap().file_name().to_string_lossy().contains("pre-sync")));
+}
+
+#[test]
+fn test_sync_rejects_missing_or_same_ledger() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Task"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["sync", &other_path(&temp_dir)]).assert().failure()
+        .stderr(predicate::str::contains("Ledger not found"));
+    let own = temp_dir.path().join("test.db").display().to_string();
+    get_task_cmd(&temp_dir).args(&["sync", &own]).assert().failure()
+        .stderr(predicate::str::contains("with itself"));
+}
@@ -0,0 +1,150 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+#[test]
+fn test_delete_moves_task_to_trash() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["enqueue", "1,2"]).assert().success();
+    get_task_cmd(&temp_dir)
+        .args(&["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"])
+        .assert()
+        .success();
+
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Deleted task 1: Alpha"));
+
+    // Hidden from lists, the queue and session reports
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").not()
+            .and(predicate::str::is_match(r"0\s+2\s+Beta").unwrap()));
+    get_task_cmd(&temp_dir).args(&["sessions", "list"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").not());
+
+    get_task_cmd(&temp_dir).args(&["trash", "list"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").and(predicate::str::contains("Beta").not()));
+    // The audit log survives
+    get_task_cmd(&temp_dir).args(&["history", "1"]).assert().success()
+        .stdout(predicate::str::contains("status: pending → deleted"));
+}
+
+#[test]
+fn test_trash_restore_brings_back_previous_status() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir)
+        .args(&["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"])
+        .assert()
+        .success();
+    get_task_cmd(&temp_dir).args(&["close", "1", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["trash", "restore", "1"]).assert().success()
+        .stdout(predicate::str::contains("Restored task 1: Alpha (closed)"));
+    get_task_cmd(&temp_dir).args(&["trash", "list"]).assert().success()
+        .stdout(predicate::str::contains("Trash is empty"));
+    get_task_cmd(&temp_dir).args(&["sessions", "list", "1"]).assert().success()
+        .stdout(predicate::str::contains("1h"));
+
+    get_task_cmd(&temp_dir).args(&["trash", "restore", "1"]).assert().failure()
+        .stderr(predicate::str::contains("not in the trash"));
+}
+
+#[test]
+fn test_trash_purge_respects_age() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["trash", "purge", "--older-than", "90d", "-y"]).assert().success()
+        .stdout(predicate::str::contains("No tasks in the trash older than 90d"));
+    get_task_cmd(&temp_dir).args(&["trash", "list"]).assert().success()
+        .stdout(predicate::str::contains("Alpha"));
+
+    get_task_cmd(&temp_dir).args(&["trash", "purge", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Purged 1 task(s)"));
+    get_task_cmd(&temp_dir).args(&["trash", "list"]).assert().success()
+        .stdout(predicate::str::contains("Trash is empty"));
+    get_task_cmd(&temp_dir).args(&["show", "1"]).assert().failure();
+}
+
+#[test]
+fn test_trashed_task_cannot_be_acted_on_by_id() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success();
+
+    for args in [
+        vec!["enqueue", "1"],
+        vec!["on", "1"],
+        vec!["modify", "1", "+x", "-y"],
+        vec!["finish", "1", "-y"],
+        vec!["close", "1", "-y"],
+        vec!["annotate", "1", "note"],
+        vec!["send", "1", "alice"],
+        vec!["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"],
+    ] {
+        get_task_cmd(&temp_dir).args(&args).assert().failure()
+            .stderr(predicate::str::contains("Task 1 is in the trash; restore it first"));
+    }
+
+    get_task_cmd(&temp_dir).args(&["trash", "restore", "1"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["enqueue", "1"]).assert().success();
+}
+
+#[test]
+fn test_deleting_a_trashed_task_says_so() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success()
+        .stderr(predicate::str::contains("Task 1 is already in the trash, skipping"));
+    get_task_cmd(&temp_dir).args(&["delete", "1"]).write_stdin("y\n").assert().failure()
+        .stderr(predicate::str::contains("Task 1 is already in the trash"));
+    get_task_cmd(&temp_dir).args(&["delete", "1,2", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Deleted task 2: Beta"))
+        .stderr(predicate::str::contains("Task 1 is already in the trash, skipping"));
+}
+
+#[test]
+fn test_trashed_sessions_still_count_for_overlaps() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta"]).assert().success();
+    get_task_cmd(&temp_dir)
+        .args(&["onoff", "2026-01-14T09:00..2026-01-14T10:00", "1", "-y"])
+        .assert()
+        .success();
+    get_task_cmd(&temp_dir).args(&["delete", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir)
+        .args(&["onoff", "2026-01-14T09:30..2026-01-14T10:30", "2", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Session 1 (task 1): Alpha"));
+}
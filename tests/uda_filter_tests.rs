@@ -0,0 +1,147 @@
+mod test_env;
+// Tests for filtering on user-defined attributes
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+
+/// Helper to create a temporary database, with extra rc lines
+fn setup_test_env(rc: &str) -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n{}", db_path.display(), rc)).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn new_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path()).env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn add_tasks(temp_dir: &TempDir) {
+    for args in [
+        ["add", "Alpha call", "uda.client=acme", "uda.points=3"],
+        ["add", "Beta review", "uda.client=globex", "uda.points=10"],
+        ["add", "Gamma fix", "uda.ticket=T-9", "uda.points=2"],
+    ] {
+        new_cmd(temp_dir).args(args).assert().success();
+    }
+}
+
+fn list(temp_dir: &TempDir, filter: &[&str]) -> String {
+    let output = new_cmd(temp_dir).arg("list").args(filter).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_uda_equality_presence_and_pattern() {
+    let (temp_dir, _guard) = setup_test_env("");
+    add_tasks(&temp_dir);
+
+    let out = list(&temp_dir, &["uda.client=acme"]);
+    assert!(out.contains("Alpha call") && !out.contains("Beta review"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.ticket=none"]);
+    assert!(out.contains("Alpha call") && out.contains("Beta review") && !out.contains("Gamma fix"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.ticket=any"]);
+    assert!(out.contains("Gamma fix") && !out.contains("Alpha call"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.client~GLOB"]);
+    assert!(out.contains("Beta review") && !out.contains("Alpha call"), "{}", out);
+}
+
+#[test]
+fn test_uda_numbers_compare_numerically() {
+    let (temp_dir, _guard) = setup_test_env("");
+    add_tasks(&temp_dir);
+
+    // As strings "10" < "3"; as numbers it is the largest
+    let out = list(&temp_dir, &["uda.points>=3"]);
+    assert!(out.contains("Alpha call") && out.contains("Beta review") && !out.contains("Gamma fix"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.points>5", "or", "uda.client=acme"]);
+    assert!(out.contains("Alpha call") && out.contains("Beta review") && !out.contains("Gamma fix"), "{}", out);
+}
+
+#[test]
+fn test_uda_declared_type_overrides_inference() {
+    let (temp_dir, _guard) = setup_test_env("uda.points.type=string\n");
+    add_tasks(&temp_dir);
+
+    // Compared as strings: "3" >= "3", "2" < "3" and "10" < "3"
+    let out = list(&temp_dir, &["uda.points>=3"]);
+    assert!(out.contains("Alpha call") && !out.contains("Beta review") && !out.contains("Gamma fix"), "{}", out);
+}
+
+#[test]
+fn test_uda_dates_compare_as_dates() {
+    let (temp_dir, _guard) = setup_test_env("");
+    new_cmd(&temp_dir).args(["add", "Renew contract", "uda.renewal=2026-03-01"]).assert().success();
+    new_cmd(&temp_dir).args(["add", "Renew domain", "uda.renewal=2026-11-15"]).assert().success();
+
+    let out = list(&temp_dir, &["uda.renewal<2026-06-01"]);
+    assert!(out.contains("Renew contract") && !out.contains("Renew domain"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.renewal=2026-11-15"]);
+    assert!(out.contains("Renew domain") && !out.contains("Renew contract"), "{}", out);
+}
+
+#[test]
+fn test_uda_filter_in_modify() {
+    let (temp_dir, _guard) = setup_test_env("");
+    add_tasks(&temp_dir);
+
+    new_cmd(&temp_dir)
+        .args(["modify", "uda.points>=3", "--yes", "+big"])
+        .assert()
+        .success();
+
+    let out = list(&temp_dir, &["+big"]);
+    assert!(out.contains("Alpha call") && out.contains("Beta review") && !out.contains("Gamma fix"), "{}", out);
+}
+
+#[test]
+fn test_uda_filter_errors() {
+    let (temp_dir, _guard) = setup_test_env("");
+
+    new_cmd(&temp_dir)
+        .args(["list", "due~today"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported on desc, note and UDA values"));
+
+    new_cmd(&temp_dir)
+        .args(["config", "set", "uda.points.type", "integer"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("numeric"));
+}
+
+#[test]
+fn test_uda_values_that_do_not_parse_only_match_not_equal() {
+    let (temp_dir, _guard) = setup_test_env("");
+    for args in [
+        ["add", "Odd one", "uda.points=abc"],
+        ["add", "Big one", "uda.points=5"],
+        ["add", "Small one", "uda.points=1"],
+    ] {
+        new_cmd(&temp_dir).args(args).assert().success();
+    }
+
+    // Not compared as strings ("abc" >= "3")
+    let out = list(&temp_dir, &["uda.points>=3"]);
+    assert!(out.contains("Big one") && !out.contains("Odd one") && !out.contains("Small one"), "{}", out);
+
+    let out = list(&temp_dir, &["uda.points!=5"]);
+    assert!(out.contains("Odd one") && out.contains("Small one") && !out.contains("Big one"), "{}", out);
+}
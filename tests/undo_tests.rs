@@ -0,0 +1,81 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+fn setup_test_env() -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n", db_path.display())).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+#[test]
+fn test_undo_modify_restores_fields_and_tags() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha", "+home"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta", "+home"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["modify", "-y", "+home", "+work", "-home", "due=2030-01-20"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["list", "+work"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").and(predicate::str::contains("Beta")));
+
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Undid 'modify"));
+    get_task_cmd(&temp_dir).args(&["list", "+home"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").and(predicate::str::contains("Beta")));
+    get_task_cmd(&temp_dir).args(&["list", "+work"]).assert().success()
+        .stdout(predicate::str::contains("Alpha").not());
+
+    // The reversal is appended to the history rather than erasing it
+    get_task_cmd(&temp_dir).args(&["history", "1"]).assert().success()
+        .stdout(predicate::str::contains("due: 2030-01-20 00:00:00 → none")
+            .and(predicate::str::contains("reverted 'modify")));
+}
+
+#[test]
+fn test_undo_finish_returns_task_to_queue() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["add", "Beta"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["enqueue", "1,2"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["finish", "1", "-y"]).assert().success();
+
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["show", "1"]).assert().success()
+        .stdout(predicate::str::contains("pending"));
+    get_task_cmd(&temp_dir).args(&["list"]).assert().success()
+        .stdout(predicate::str::is_match(r"(?s)0\s+1\s+Alpha.*1\s+2\s+Beta").unwrap());
+}
+
+#[test]
+fn test_undo_list_and_nothing_to_undo() {
+    let (temp_dir, _guard) = setup_test_env();
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Nothing to undo"));
+
+    get_task_cmd(&temp_dir).args(&["add", "Alpha"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["modify", "1", "Alpha renamed"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["undo", "--list"]).assert().success()
+        .stdout(predicate::str::contains("modify 1 Alpha renamed").and(predicate::str::contains("(undone)").not()));
+
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(&["undo", "--list"]).assert().success()
+        .stdout(predicate::str::contains("(undone)"));
+    // Adding a task is not journaled, so there is nothing left to undo
+    get_task_cmd(&temp_dir).args(&["undo", "-y"]).assert().success()
+        .stdout(predicate::str::contains("Nothing to undo"));
+}
@@ -0,0 +1,116 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use std::fs;
+mod test_env;
+
+// Tests for virtual tags derived from task state
+
+fn setup_test_env(rc: &str) -> (TempDir, std::sync::MutexGuard<'static, ()>) {
+    let guard = test_env::lock_test_env();
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let config_dir = temp_dir.path().join(".tatl");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_file = config_dir.join("rc");
+    fs::write(&config_file, format!("data.location={}\n{}", db_path.display(), rc)).unwrap();
+    std::env::set_var("HOME", temp_dir.path().to_str().unwrap());
+    (temp_dir, guard)
+}
+
+fn get_task_cmd(temp_dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("tatl").unwrap();
+    cmd.env("HOME", temp_dir.path());
+    cmd.env_remove("TATL_LEDGER");
+    cmd
+}
+
+fn list(temp_dir: &TempDir, filter: &[&str]) -> String {
+    let output = get_task_cmd(temp_dir).arg("list").args(filter).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// One task per kind of derived state
+fn setup_states(temp_dir: &TempDir) {
+    for args in [
+        vec!["add", "Late report", "due=-2d"],
+        vec!["add", "Weekly sync", "due=+3d"],
+        vec!["add", "Standup", "respawn=daily"],
+        vec!["add", "Client call", "uda.client=acme"],
+        vec!["add", "Plain task"],
+        vec!["add", "Handed over"],
+    ] {
+        get_task_cmd(temp_dir).args(&args).assert().success();
+    }
+    get_task_cmd(temp_dir).args(["enqueue", "2"]).assert().success();
+    get_task_cmd(temp_dir).args(["on", "1"]).assert().success();
+    get_task_cmd(temp_dir).args(["annotate", "1", "Waiting for figures"]).assert().success();
+    get_task_cmd(temp_dir).args(["send", "6", "colleague"]).assert().success();
+}
+
+#[test]
+fn test_virtual_tags_filter_derived_state() {
+    let (temp_dir, _guard) = setup_test_env("");
+    setup_states(&temp_dir);
+
+    let out = list(&temp_dir, &["+OVERDUE"]);
+    assert!(out.contains("Late report") && !out.contains("Weekly sync"), "{}", out);
+
+    let out = list(&temp_dir, &["+DUEWEEK"]);
+    assert!(out.contains("Weekly sync") && !out.contains("Late report"), "{}", out);
+
+    let out = list(&temp_dir, &["+ACTIVE", "+ANNOTATED"]);
+    assert!(out.contains("Late report") && !out.contains("Plain task"), "{}", out);
+
+    let out = list(&temp_dir, &["+RESPAWNING", "or", "+UDA"]);
+    assert!(out.contains("Standup") && out.contains("Client call") && !out.contains("Plain task"), "{}", out);
+
+    let out = list(&temp_dir, &["+EXTERNAL"]);
+    assert!(out.contains("Handed over") && !out.contains("Plain task"), "{}", out);
+
+    let out = list(&temp_dir, &["-QUEUED", "-ACTIVE", "-EXTERNAL", "-UDA", "-RESPAWNING"]);
+    assert!(out.contains("Plain task") && !out.contains("Weekly sync") && !out.contains("Late report"), "{}", out);
+}
+
+#[test]
+fn test_virtual_tags_are_read_only() {
+    let (temp_dir, _guard) = setup_test_env("");
+
+    get_task_cmd(&temp_dir)
+        .args(["add", "Sneaky", "+OVERDUE"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("virtual tag"));
+}
+
+#[test]
+fn test_tags_column_shows_virtual_tags_when_enabled() {
+    let (temp_dir, _guard) = setup_test_env("");
+    setup_states(&temp_dir);
+    let out = list(&temp_dir, &["2", "--full"]);
+    assert!(!out.contains("+QUEUED"), "{}", out);
+
+    get_task_cmd(&temp_dir).args(["config", "set", "list.virtual_tags", "true"]).assert().success();
+    let out = list(&temp_dir, &["2", "--full"]);
+    assert!(out.contains("+DUEWEEK +QUEUED"), "{}", out);
+}
+
+#[test]
+fn test_stored_tags_with_virtual_names_can_be_removed_and_renamed() {
+    let (temp_dir, _guard) = setup_test_env("");
+    get_task_cmd(&temp_dir).args(["add", "Old task"]).assert().success();
+    get_task_cmd(&temp_dir).args(["add", "Older task"]).assert().success();
+    let conn = rusqlite::Connection::open(temp_dir.path().join("test.db")).unwrap();
+    conn.execute_batch("INSERT INTO task_tags (task_id, tag) VALUES (1, 'ACTIVE'), (2, 'QUEUED');").unwrap();
+    drop(conn);
+
+    get_task_cmd(&temp_dir).args(["modify", "1", "-ACTIVE", "-y"]).assert().success();
+    get_task_cmd(&temp_dir).args(["modify", "1", "+ACTIVE", "-y"]).assert().failure()
+        .stderr(predicate::str::contains("virtual tag"));
+
+    get_task_cmd(&temp_dir).args(["doctor"]).assert().code(1)
+        .stdout(predicate::str::contains("tag_reserved").and(predicate::str::contains("'QUEUED'")));
+    get_task_cmd(&temp_dir).args(["doctor", "--fix"]).assert().success();
+    assert!(list(&temp_dir, &["+queued"]).contains("Older task"));
+}